@@ -1,6 +1,15 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use std::fmt::{Display, Formatter, Result, Write};
+use serde::Serialize;
+use std::{
+    fmt::{Display, Formatter, Result, Write},
+    path::PathBuf,
+};
 use yarte::Template;
+use yarte_parser::{
+    parse,
+    source_map::{clean, get_cursor},
+    Arena,
+};
 
 criterion_group!(benches, functions);
 criterion_main!(benches);
@@ -12,6 +21,98 @@ fn functions(c: &mut Criterion) {
     c.bench_function("Big table", |b| big_table(b, 100));
     c.bench_function("Big table Unescaped", |b| big_table_display(b, 100));
     c.bench_function("Formatter Big table", |b| big_table_fmt(b, 100));
+    c.bench_function("Parse big template", |b| parse_big(b, 5000));
+    // `call` of html templates writes in the `String`, `Display` through the formatter
+    c.bench_function("Teams Display", |b| {
+        display(
+            b,
+            &Teams {
+                year: 2015,
+                teams: build_teams(),
+            },
+        )
+    });
+    c.bench_function("Big table Display", |b| {
+        display(
+            b,
+            &BigTable {
+                table: build_big_table(100),
+            },
+        )
+    });
+    c.bench_function("Escaped text", |b| escaped(b, 1000));
+    c.bench_function("Escaped text Display", |b| {
+        display(
+            b,
+            &EscapedText {
+                items: build_text(1000),
+            },
+        )
+    });
+    // `call_bytes` of json templates against `serde_json` serializing the same context
+    c.bench_function("Teams JSON", teams_json);
+    c.bench_function("Teams JSON serde_json", |b| {
+        let teams = teams_json_context();
+        b.iter(|| serde_json::to_vec(&teams).unwrap());
+    });
+    c.bench_function("Escaped JSON", |b| escaped_json(b, 1000));
+    c.bench_function("Escaped JSON serde_json", |b| {
+        let items = build_text(1000);
+        b.iter(|| serde_json::to_vec(&items).unwrap());
+    });
+}
+
+/// Render through the formatter of `Display`, as `call` before writing in the `String`
+fn display<T: Template>(b: &mut criterion::Bencher, t: &T) {
+    b.iter(|| {
+        let mut buf = String::with_capacity(T::size_hint());
+        write!(buf, "{}", t).map(|_| buf).unwrap()
+    });
+}
+
+fn build_text(size: usize) -> Vec<String> {
+    (0..size)
+        .map(|i| format!("item {} with some longer text & <b>tags</b>", i))
+        .collect()
+}
+
+fn escaped(b: &mut criterion::Bencher, size: usize) {
+    let t = EscapedText {
+        items: build_text(size),
+    };
+    b.iter(|| t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{#each items }}<p>{{ this }}</p>{{/each }}", mode = "html")]
+struct EscapedText {
+    items: Vec<String>,
+}
+
+/// Machine generated template with `size` blocks of helpers and expressions
+fn build_big_template(size: usize) -> String {
+    let mut src = String::new();
+    for i in 0..size {
+        writeln!(
+            src,
+            "<p>{{{{#if a{} }}}}{{{{ b.c }}}}{{{{#each items }}}}<li>{{{{ this }}}}</li>{{{{/each}}}}{{{{/if}}}}</p>",
+            i
+        )
+        .unwrap();
+    }
+
+    src
+}
+
+fn parse_big(b: &mut criterion::Bencher, size: usize) {
+    let src = build_big_template(size);
+    let path = PathBuf::from("big.hbs");
+    b.iter(|| {
+        let arena = Arena::new();
+        let nodes = parse(get_cursor(&path, &src), &arena);
+        clean();
+        nodes.len()
+    });
 }
 
 fn build_big_table(size: usize) -> Vec<Vec<usize>> {
@@ -104,6 +205,7 @@ impl Display for BigTableFmt {
     }
 }
 
+#[derive(Serialize)]
 struct Team {
     name: String,
     score: u8,
@@ -124,6 +226,49 @@ struct Teams {
     teams: Vec<Team>,
 }
 
+fn teams_json_context() -> TeamsJson {
+    TeamsJson {
+        year: 2015,
+        teams: build_teams(),
+    }
+}
+
+fn teams_json(b: &mut criterion::Bencher) {
+    let teams = teams_json_context();
+    assert_eq!(
+        teams.call_bytes().unwrap(),
+        serde_json::to_vec(&teams).unwrap()
+    );
+    b.iter(|| teams.call_bytes().unwrap());
+}
+
+#[derive(Template, Serialize)]
+#[template(
+    src = "{\"year\":{{ year }},\"teams\":[{{#each teams }}{{#if index0 > 0 }},{{/if }}\
+           {\"name\":\"{{ name }}\",\"score\":{{ score }}}{{/each }}]}",
+    mode = "json"
+)]
+struct TeamsJson {
+    year: u16,
+    teams: Vec<Team>,
+}
+
+fn escaped_json(b: &mut criterion::Bencher, size: usize) {
+    let t = EscapedJson {
+        items: build_text(size),
+    };
+    b.iter(|| t.call_bytes().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "[{{#each items }}{{#if index0 > 0 }},{{/if }}\"{{ this }}\"{{/each }}]",
+    mode = "json"
+)]
+struct EscapedJson {
+    items: Vec<String>,
+}
+
 fn teams_display(b: &mut criterion::Bencher) {
     let teams = TeamsDisplay {
         year: 2015,
@@ -14,16 +14,32 @@ async fn index(query: web::Query<HashMap<String, String>>) -> impl Responder {
     IndexTemplate { query }
 }
 
+#[derive(Template)]
+#[template(path = "index.hbs", error_template = "error.hbs")]
+struct StrictTemplate {
+    query: web::Query<HashMap<String, String>>,
+}
+
+#[get("/strict")]
+async fn strict(query: web::Query<HashMap<String, String>>) -> impl Responder {
+    StrictTemplate { query }
+}
+
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "actix_web=info");
     env_logger::init();
 
     // start http server
-    HttpServer::new(move || App::new().wrap(Logger::default()).service(index))
-        .bind("127.0.0.1:8080")?
-        .run()
-        .await
+    HttpServer::new(move || {
+        App::new()
+            .wrap(Logger::default())
+            .service(index)
+            .service(strict)
+    })
+    .bind("127.0.0.1:8080")?
+    .run()
+    .await
 }
 
 #[cfg(test)]
@@ -111,4 +127,30 @@ mod test {
             )
         );
     }
+
+    #[actix_rt::test]
+    async fn test_error_template() {
+        let mut app = atest::init_service(App::new().service(strict)).await;
+
+        let req = atest::TestRequest::with_uri("/strict?name=foo").to_request();
+        let resp = atest::call_service(&mut app, req).await;
+
+        assert!(resp.status().is_server_error());
+
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+
+        let bytes = atest::read_body(resp).await;
+        assert_eq!(
+            bytes,
+            Bytes::from_static(
+                "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Error</title></\
+                 head><body><h1>Something went wrong</h1><p>an error occurred when formatting \
+                 an argument</p></body></html>"
+                    .as_ref()
+            )
+        );
+    }
 }
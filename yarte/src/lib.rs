@@ -10,15 +10,43 @@
 //! [Yarte book](https://yarte.netlify.com)
 //!
 
-pub use yarte_derive::Template;
+pub use yarte_derive::{collected, Template};
 #[cfg(any(not(target_arch = "wasm32"), debug_assertions))]
-pub use yarte_helpers::{helpers::Render, Error, Result};
+pub use yarte_helpers::{
+    builtins,
+    helpers::{
+        concat, join, lazy, negotiate, Buffer, BytesWriter, Concat, CustomBlockHelper,
+        CustomConditionHelper, Join, Lazy, Render, RenderJson, Safe, SafeDisplay, SafeStr, Scalar,
+    },
+    Error, RenderError, Result,
+};
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub use yarte_template::RenderInto;
+#[cfg(all(feature = "bytes-buf", not(target_arch = "wasm32")))]
+pub use yarte_template::TemplateBytes;
 #[cfg(not(target_arch = "wasm32"))]
-pub use yarte_template::Template;
+pub use yarte_template::{Displayable, OnceRender, Template};
 #[cfg(target_arch = "wasm32")]
 pub use yarte_wasm_app::{Addr, App as Template};
 
+#[cfg(feature = "context")]
+pub use yarte_helpers::context;
+#[cfg(feature = "coverage")]
+pub use yarte_helpers::coverage;
+#[cfg(feature = "hook")]
+pub use yarte_helpers::hook;
+#[cfg(feature = "instrument")]
+pub use yarte_helpers::instrument;
+#[cfg(feature = "integrity")]
+pub use yarte_helpers::integrity;
+#[cfg(feature = "profile")]
+pub use yarte_helpers::profile;
+#[cfg(feature = "sanitize")]
+pub use yarte_helpers::sanitize::{sanitize, Policy};
+
 pub mod recompile;
+#[cfg(all(feature = "testsuite", not(target_arch = "wasm32")))]
+pub mod testsuite;
 
 #[cfg(all(feature = "with-actix-web", not(target_arch = "wasm32")))]
 pub mod aw {
@@ -34,11 +62,7 @@ pub mod serde_json {
 }
 
 #[cfg(target_arch = "wasm32")]
-pub mod wasm {
-    pub use serde_json::from_str;
-    pub use wasm_bindgen::JsCast;
-    pub use web_sys as web;
-}
+pub mod wasm;
 
 #[cfg(target_arch = "wasm32")]
 pub use self::wasm::*;
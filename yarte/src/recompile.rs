@@ -1,6 +1,8 @@
 use std::fs;
 
-use yarte_config::{config_file_path, read_config_file, Config};
+use yarte_config::{
+    config_file_path, env_vars, read_config_file, workspace_config_file_path, Config,
+};
 
 /// Recompile when changed. Put me on your `build.rs`
 pub fn when_changed() {
@@ -9,11 +11,35 @@ pub fn when_changed() {
         "cargo:rerun-if-changed={}",
         config_file_path().to_str().unwrap()
     );
+    let mut paths = vec![config_file_path()];
+    if let Some(path) = workspace_config_file_path() {
+        println!("cargo:rerun-if-changed={}", path.to_str().unwrap());
+        paths.push(path);
+    }
+    // rerun when a variable of the config change
+    for path in paths {
+        for var in env_vars(&fs::read_to_string(path).unwrap_or_default()) {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+    }
 
     let file = read_config_file();
     let config = Config::new(&file);
 
-    let mut stack = vec![config.get_dir().clone()];
+    // rerun when the template archive change
+    if let Some(archive) = &config.archive {
+        println!(
+            "cargo:rerun-if-changed={}",
+            archive.path().to_str().unwrap()
+        );
+    }
+
+    let mut stack = vec![];
+    // the templates directory can be missing with an archive
+    if config.archive.is_none() || config.get_dir().is_dir() {
+        stack.push(config.get_dir().clone());
+    }
+    stack.extend(config.alias_dirs().filter(|dir| dir.is_dir()));
     while let Some(dir) = stack.pop() {
         // rerun when dir change
         println!("cargo:rerun-if-changed={}", dir.to_str().unwrap());
@@ -0,0 +1,714 @@
+//! Golden test corpus runner
+//!
+//! A corpus is a directory tree, each directory with a `context.json` is a case with the
+//! template `template.<ext>` and its expected output `expected.out`. The cases are rendered
+//! at runtime by an interpreter of the templates over the JSON context, so a corpus doesn't
+//! depend on the Rust types of the templates:
+//!
+//! ```text
+//! tests/golden/
+//! ├── list/
+//! │   ├── template.hbs
+//! │   ├── context.json
+//! │   └── expected.out
+//! └── shared/
+//!     └── card.hbs
+//! ```
+//!
+//! The interpreter covers the language agnostic part of the templates: expressions of
+//! paths, literals and operators, `if`, `unless`, `each` with `zip` and block parameters,
+//! `with`, partials, raw blocks,
+//! comments and whitespace control. Templates with html extensions are escaped but not
+//! minified, as in `html` mode. Partials resolve relative to the including template.
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::Write,
+    fs, panic,
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use quote::quote;
+use serde_json::{Map, Number, Value};
+use syn::{BinOp, Expr, Lit, Member, UnOp};
+use v_htmlescape::escape;
+
+use yarte_config::{Config, Whitespace};
+use yarte_helpers::helpers::read;
+use yarte_parser::{
+    find_inline, html_comment, parse, set_unicode_whitespace,
+    source_map::{clean, get_cursor},
+    Arena, Arg, Builtin, BuiltinArg, Helper, Node, Partial, SArgs, SExpr, SNode, Ws, PARTIAL_BLOCK,
+};
+
+/// Template extensions in html mode, as in the derive
+static HTML_EXTENSIONS: [&str; 6] = ["hbs", "htm", "xml", "html", "handlebars", "mustache"];
+
+/// Render each case of the corpus in `dir` and compare it with its expected output
+///
+/// Returns the number of cases, panics with the failed cases. It reads the partials as
+/// the derive, so it must run with `cargo test`.
+pub fn run<P: AsRef<Path>>(dir: P) -> usize {
+    let dir = dir.as_ref();
+    let mut cases = vec![];
+    find_cases(dir, &mut cases);
+    assert!(!cases.is_empty(), "no golden cases in {:?}", dir);
+
+    let mut failures = String::new();
+    for case in &cases {
+        if let Err(message) = check(case) {
+            writeln!(failures, "{}:\n{}\n", case.display(), message).unwrap();
+        }
+    }
+    if !failures.is_empty() {
+        panic!("golden cases failed\n\n{}", failures);
+    }
+
+    cases.len()
+}
+
+fn find_cases(dir: &Path, cases: &mut Vec<PathBuf>) {
+    if dir.join("context.json").is_file() {
+        cases.push(dir.to_owned());
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|_| panic!("unable to read directory {:?}", dir))
+        .map(|entry| entry.expect("valid directory entry").path())
+        .filter(|path| path.is_dir())
+        .collect();
+    entries.sort();
+    for entry in entries {
+        find_cases(&entry, cases);
+    }
+}
+
+fn check(case: &Path) -> Result<()> {
+    let template = fs::read_dir(case)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|x| x.path()))
+        .find(|path| path.is_file() && path.file_stem().map_or(false, |x| x == "template"))
+        .ok_or_else(|| "missing template".to_owned())?;
+    let context = fs::read_to_string(case.join("context.json")).map_err(|e| e.to_string())?;
+    let context: Value =
+        serde_json::from_str(&context).map_err(|e| format!("invalid context.json: {}", e))?;
+    let mut expected = fs::read_to_string(case.join("expected.out"))
+        .map_err(|e| format!("missing expected.out: {}", e))?;
+    if expected.ends_with('\n') {
+        expected.pop();
+    }
+
+    let out = panic::catch_unwind(|| render(&template, context));
+    clean();
+    let out = out.map_err(|_| "problems parsing the template".to_owned())??;
+
+    if out == expected {
+        Ok(())
+    } else {
+        Err(format!("expected:\n{}\nfound:\n{}", expected, out))
+    }
+}
+
+fn render(template: &Path, context: Value) -> Result<String> {
+    let config = Config::new("");
+    set_unicode_whitespace(config.whitespace == Whitespace::Unicode);
+    let sources = read(template.to_owned(), config.get_source(template), &config);
+    let arena = Arena::new();
+    let nodes: BTreeMap<_, _> = sources
+        .iter()
+        .map(|(path, src)| (path, parse(get_cursor(path, src), &arena)))
+        .collect();
+
+    let html = template
+        .extension()
+        .and_then(|x| x.to_str())
+        .map_or(false, |x| HTML_EXTENSIONS.contains(&x));
+    let mut interpreter = Interpreter {
+        config: &config,
+        nodes: &nodes,
+        path: template.to_owned(),
+        html,
+        out: String::new(),
+        next: None,
+        skip: false,
+        scopes: vec![Scope::new(On::Root, context)],
+        blocks: vec![],
+        partials: vec![&nodes[&template.to_owned()]],
+    };
+    interpreter.handle(&nodes[&template.to_owned()])?;
+
+    Ok(interpreter.out)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum On {
+    Root,
+    Each(usize),
+    With,
+    /// Partial arguments over the current scope
+    Partial,
+}
+
+struct Scope {
+    on: On,
+    this: Value,
+    args: Map<String, Value>,
+}
+
+impl Scope {
+    fn new(on: On, this: Value) -> Scope {
+        Scope {
+            on,
+            this,
+            args: Map::new(),
+        }
+    }
+}
+
+struct Interpreter<'a> {
+    config: &'a Config<'a>,
+    nodes: &'a BTreeMap<&'a PathBuf, Vec<SNode<'a>>>,
+    path: PathBuf,
+    html: bool,
+    out: String,
+    /// Pending whitespace, with the same rules as the lowering of `yarte_hir`
+    next: Option<&'a str>,
+    skip: bool,
+    scopes: Vec<Scope>,
+    /// Blocks of the partial blocks being rendered
+    blocks: Vec<(PathBuf, &'a [SNode<'a>])>,
+    /// Partials being rendered, for `recursion` of `[limits]`
+    partials: Vec<&'a [SNode<'a>]>,
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+impl<'a> Interpreter<'a> {
+    fn handle(&mut self, nodes: &'a [SNode<'a>]) -> Result<()> {
+        for n in nodes {
+            match n.t() {
+                Node::Lit(l, lit, r) => self.lit(l, lit.t(), r),
+                Node::Expr(ws, e) | Node::Safe(ws, e) => {
+                    self.handle_ws(*ws);
+                    let value = self.eval(e.t())?;
+                    let value = display(&value).ok_or_else(|| {
+                        format!("not displayable value of `{}`: {}", source(e), value)
+                    })?;
+                    if self.html {
+                        if let Node::Expr(..) = n.t() {
+                            write!(self.out, "{}", escape(&value)).unwrap();
+                            continue;
+                        }
+                    }
+                    self.out.push_str(&value);
+                }
+                Node::Helper(h) => self.helper(h)?,
+                Node::Partial(Partial(ws, path, args)) if *path.t() == PARTIAL_BLOCK => {
+                    let block = self
+                        .blocks
+                        .pop()
+                        .ok_or_else(|| format!("`{}` out of a partial block", PARTIAL_BLOCK))?;
+                    self.partial(*ws, block.clone(), args)?;
+                    self.blocks.push(block);
+                }
+                Node::Partial(Partial(ws, path, args)) => {
+                    let source = self.find_partial(path.t());
+                    let source = source.unwrap_or_else(|| {
+                        let path = self.config.resolve_partial(&self.path, path.t());
+                        let body = &self.nodes[&path][..];
+                        (path, body)
+                    });
+                    self.partial(*ws, source, args)?;
+                }
+                Node::PartialBlock(ws, path, args, block) => match self.find_partial(path.t()) {
+                    Some(source) => {
+                        self.blocks.push((self.path.clone(), &block[..]));
+                        self.partial(((ws.0).0, (ws.1).1), source, args)?;
+                        self.blocks.pop();
+                    }
+                    None => {
+                        self.flush_ws(ws.0);
+                        self.block(ws.0, ws.1, block)?;
+                        self.end(ws.1);
+                    }
+                },
+                Node::Raw(ws, l, v, r) => {
+                    self.handle_ws(ws.0);
+                    self.lit(l, v.t(), r);
+                    self.handle_ws(ws.1);
+                }
+                Node::Comment(_) => self.skip_ws(),
+                Node::Inline(ws, ..) => self.handle_ws(((ws.0).0, (ws.1).1)),
+                Node::Keep(text) => {
+                    if !self.html {
+                        return Err("kept comments aren't available in text mode".into());
+                    }
+                    self.handle_ws((false, false));
+                    self.out.push_str(&html_comment(text));
+                }
+                Node::Local(_) => return Err("`let` isn't available in the interpreter".into()),
+                Node::Const(_) => {
+                    return Err("constants aren't available in the interpreter".into())
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn helper(&mut self, h: &'a Helper<'a>) -> Result<()> {
+        match h {
+            Helper::If((pws, cond, block), ifs, els)
+            | Helper::Unless((pws, cond, block), ifs, els) => {
+                let unless = match h {
+                    Helper::Unless(..) => true,
+                    _ => false,
+                };
+                let mut branches = vec![(pws.0, Some(cond), block)];
+                branches.extend(ifs.iter().map(|(ws, cond, block)| (*ws, Some(cond), block)));
+                branches.extend(els.iter().map(|(ws, block)| (*ws, None, block)));
+
+                self.flush_ws(pws.0);
+                for (i, (open, cond, block)) in branches.iter().enumerate() {
+                    let take = match cond {
+                        Some(cond) => truthy(&self.eval(cond.t())?) != (unless && i == 0),
+                        None => true,
+                    };
+                    if take {
+                        let close = branches.get(i + 1).map_or(pws.1, |x| x.0);
+                        self.block(*open, close, block)?;
+                        break;
+                    }
+                }
+                self.end(pws.1);
+            }
+            Helper::Each(ws, args, modifiers, params, block, els) => {
+                if !modifiers.is_empty() {
+                    return Err("each modifiers aren't available in the interpreter".into());
+                }
+                if let Some((_, Some(_), _)) = els {
+                    return Err("each error arms aren't available in the interpreter".into());
+                }
+                let items = match self.eval(args.t())? {
+                    Value::Array(items) => items,
+                    Value::Object(items) => items.into_iter().map(|(_, x)| x).collect(),
+                    value => return Err(format!("not iterable `{}`: {}", source(args), value)),
+                };
+
+                self.flush_ws(ws.0);
+                let close = els.as_ref().map_or(ws.1, |(ws, _, _)| *ws);
+                if let (true, Some((open, _, els))) = (items.is_empty(), els) {
+                    self.block(*open, ws.1, els)?;
+                }
+                for (i, item) in items.into_iter().enumerate() {
+                    let mut scope = Scope::new(On::Each(i), item);
+                    if let Some(index) = &params.index {
+                        scope
+                            .args
+                            .insert(index.to_string(), Value::Number(i.into()));
+                    }
+                    let params = &params.item;
+                    if let [param] = params.as_slice() {
+                        scope.args.insert(param.to_string(), scope.this.clone());
+                    } else if !params.is_empty() {
+                        match &scope.this {
+                            Value::Array(values) if values.len() == params.len() => {
+                                for (param, value) in params.iter().zip(values) {
+                                    scope.args.insert(param.to_string(), value.clone());
+                                }
+                            }
+                            value => {
+                                return Err(format!(
+                                    "expected {} block parameters, found {}",
+                                    params.len(),
+                                    value
+                                ))
+                            }
+                        }
+                    }
+                    self.scopes.push(scope);
+                    self.block(ws.0, close, block)?;
+                    self.scopes.pop();
+                }
+                self.end(ws.1);
+            }
+            Helper::With(ws, args, params, block) => {
+                let this = self.eval(args.t())?;
+
+                self.flush_ws(ws.0);
+                let mut scope = Scope::new(On::With, this);
+                match (params.item.as_slice(), &params.index) {
+                    ([], None) => (),
+                    ([param], None) => {
+                        scope.args.insert(param.to_string(), scope.this.clone());
+                    }
+                    _ => return Err("`with` takes one block parameter".into()),
+                }
+                self.scopes.push(scope);
+                self.block(ws.0, ws.1, block)?;
+                self.scopes.pop();
+                self.end(ws.1);
+            }
+            Helper::Portal(..) => return Err("`portal` is a helper of wasm applications".into()),
+            Helper::Defined(_, name, ..) => {
+                return Err(format!("unknown helper `{}`", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn eval(&self, e: &Expr) -> Result<Value> {
+        Ok(match e {
+            Expr::Path(syn::ExprPath {
+                path, qself: None, ..
+            }) => {
+                let idents: Vec<_> = path.segments.iter().map(|x| x.ident.to_string()).collect();
+                let (name, supers) = idents.split_last().expect("some segment");
+                if supers.iter().any(|x| x != "super") {
+                    return Err(format!("unknown path `{}`", quote!(#path)));
+                }
+                self.resolve(name, supers.len())?
+            }
+            Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+                Lit::Str(s) => Value::String(s.value()),
+                Lit::Int(i) => {
+                    Value::Number(i.base10_parse::<i64>().map_err(|e| e.to_string())?.into())
+                }
+                Lit::Float(f) => float(f.base10_parse::<f64>().map_err(|e| e.to_string())?)?,
+                Lit::Bool(b) => Value::Bool(b.value),
+                _ => return Err(unavailable(e)),
+            },
+            Expr::Paren(syn::ExprParen { expr, .. })
+            | Expr::Group(syn::ExprGroup { expr, .. })
+            | Expr::Reference(syn::ExprReference { expr, .. }) => self.eval(expr)?,
+            Expr::Field(syn::ExprField { base, member, .. }) => {
+                let base = self.eval(base)?;
+                match member {
+                    Member::Named(name) => base.get(name.to_string()).cloned(),
+                    Member::Unnamed(i) => base.get(i.index as usize).cloned(),
+                }
+                .ok_or_else(|| format!("unknown field `{}`", quote!(#e)))?
+            }
+            Expr::Index(syn::ExprIndex { expr, index, .. }) => {
+                let base = self.eval(expr)?;
+                match self.eval(index)? {
+                    Value::Number(i) => i.as_u64().and_then(|i| base.get(i as usize)).cloned(),
+                    Value::String(key) => base.get(key).cloned(),
+                    _ => None,
+                }
+                .ok_or_else(|| format!("index out of bounds `{}`", quote!(#e)))?
+            }
+            Expr::MethodCall(syn::ExprMethodCall {
+                receiver,
+                method,
+                args,
+                ..
+            }) if args.is_empty() => {
+                let len = match self.eval(receiver)? {
+                    Value::String(s) => s.len(),
+                    Value::Array(a) => a.len(),
+                    Value::Object(o) => o.len(),
+                    _ => return Err(unavailable(e)),
+                };
+                if method == "len" {
+                    Value::Number(len.into())
+                } else if method == "is_empty" {
+                    Value::Bool(len == 0)
+                } else {
+                    return Err(unavailable(e));
+                }
+            }
+            Expr::Unary(syn::ExprUnary { op, expr, .. }) => {
+                let value = self.eval(expr)?;
+                match op {
+                    UnOp::Not(_) => Value::Bool(!truthy(&value)),
+                    UnOp::Neg(_) => match value.as_i64() {
+                        Some(i) => Value::Number((-i).into()),
+                        None => float(-number(&value)?)?,
+                    },
+                    UnOp::Deref(_) => value,
+                }
+            }
+            Expr::Binary(syn::ExprBinary {
+                left, op, right, ..
+            }) => {
+                let left = self.eval(left)?;
+                match op {
+                    BinOp::And(_) => {
+                        return Ok(Value::Bool(truthy(&left) && truthy(&self.eval(right)?)))
+                    }
+                    BinOp::Or(_) => {
+                        return Ok(Value::Bool(truthy(&left) || truthy(&self.eval(right)?)))
+                    }
+                    _ => (),
+                }
+                let right = self.eval(right)?;
+                match op {
+                    BinOp::Eq(_) => Value::Bool(equal(&left, &right)),
+                    BinOp::Ne(_) => Value::Bool(!equal(&left, &right)),
+                    BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_) => {
+                        let ord = match (&left, &right) {
+                            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+                            _ => number(&left)?.partial_cmp(&number(&right)?),
+                        };
+                        Value::Bool(match (op, ord) {
+                            (_, None) => false,
+                            (BinOp::Lt(_), Some(o)) => o == Ordering::Less,
+                            (BinOp::Le(_), Some(o)) => o != Ordering::Greater,
+                            (BinOp::Gt(_), Some(o)) => o == Ordering::Greater,
+                            (_, Some(o)) => o != Ordering::Less,
+                        })
+                    }
+                    _ => arithmetic(op, &left, &right).ok_or_else(|| unavailable(e))?,
+                }
+            }
+            Expr::Verbatim(tokens) => match syn::parse2::<Builtin>(tokens.clone()) {
+                Ok(Builtin { ident, args }) if ident == "zip" => {
+                    let mut lists = vec![];
+                    for arg in &args {
+                        match arg {
+                            BuiltinArg::Expr(arg) => match self.eval(arg)? {
+                                Value::Array(list) => lists.push(list),
+                                value => return Err(format!("not iterable {}", value)),
+                            },
+                            _ => return Err(unavailable(e)),
+                        }
+                    }
+                    let len = lists.iter().map(Vec::len).min().unwrap_or(0);
+                    Value::Array(
+                        (0..len)
+                            .map(|i| Value::Array(lists.iter().map(|x| x[i].clone()).collect()))
+                            .collect(),
+                    )
+                }
+                _ => return Err(unavailable(e)),
+            },
+            _ => return Err(unavailable(e)),
+        })
+    }
+
+    /// Value of a name in the scope `level` of each and with helpers above the current
+    fn resolve(&self, name: &str, level: usize) -> Result<Value> {
+        if name == "self" {
+            let root = self.scopes.iter().rev().find(|x| x.on == On::Root);
+            return Ok(root.expect("some root scope").this.clone());
+        }
+
+        let mut level = level;
+        for scope in self.scopes.iter().rev() {
+            if level == 0 {
+                if let Some(value) = scope.args.get(name) {
+                    return Ok(value.clone());
+                }
+            }
+            if scope.on == On::Partial {
+                continue;
+            }
+            if level > 0 {
+                level -= 1;
+                continue;
+            }
+
+            return match (scope.on, name) {
+                (_, "this") => Ok(scope.this.clone()),
+                (On::Each(i), "index") => Ok(Value::Number((i + 1).into())),
+                (On::Each(i), "index0") => Ok(Value::Number(i.into())),
+                (On::Each(i), "first") => Ok(Value::Bool(i == 0)),
+                _ => scope
+                    .this
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("unknown field `{}`", name)),
+            };
+        }
+
+        Err(format!("use of super without parent `{}`", name))
+    }
+
+    /// Partial of the source, an inline partial, a partial file or the block of a partial block,
+    /// rendered in the scope of its arguments up to the recursion limit
+    fn partial(
+        &mut self,
+        ws: Ws,
+        (path, body): (PathBuf, &'a [SNode<'a>]),
+        args: &'a SArgs,
+    ) -> Result<()> {
+        let mut scope = Scope::new(On::Partial, Value::Null);
+        for arg in args.t() {
+            match arg.t() {
+                Arg::Named(ident, e) => {
+                    scope.args.insert(ident.to_string(), self.eval(e)?);
+                }
+                Arg::Positional(e) => {
+                    scope.on = On::Root;
+                    scope.this = self.eval(e)?;
+                }
+            }
+        }
+
+        self.flush_ws(ws);
+        // As the derive, the call past the limit renders nothing
+        let recursion = self.config.limits.recursion;
+        let calls = self.partials.iter().filter(|x| ptr::eq(**x, body)).count();
+        if calls > recursion {
+            if recursion == 0 {
+                return Err(format!("recursive partial {:?}", path));
+            }
+            self.skip = ws.1;
+            return Ok(());
+        }
+
+        let parent = std::mem::replace(&mut self.path, path);
+        self.partials.push(body);
+        self.scopes.push(scope);
+        self.handle(body)?;
+        self.scopes.pop();
+        self.partials.pop();
+        self.path = parent;
+        self.skip = ws.1;
+
+        Ok(())
+    }
+
+    /// Inline partial or existing partial file `name` of the current template
+    fn find_partial(&self, name: &str) -> Option<(PathBuf, &'a [SNode<'a>])> {
+        let nodes = self.nodes;
+        match find_inline(&nodes[&self.path], name) {
+            Some((_, body)) => Some((self.path.clone(), body)),
+            None => self.config.find_partial(&self.path, name).map(|path| {
+                let body = &nodes[&path][..];
+                (path, body)
+            }),
+        }
+    }
+
+    /// Block of a helper, after the pending whitespace of its opening node
+    fn block(&mut self, open: Ws, close: Ws, nodes: &'a [SNode<'a>]) -> Result<()> {
+        self.next = None;
+        self.skip = open.1;
+        self.handle(nodes)?;
+        self.flush_ws(close);
+
+        Ok(())
+    }
+
+    /// After the closing node of a helper
+    fn end(&mut self, close: Ws) {
+        self.next = None;
+        self.skip = close.1;
+    }
+
+    fn lit(&mut self, lws: &'a str, lit: &'a str, rws: &'a str) {
+        if !lws.is_empty() {
+            if self.skip {
+                self.skip = false;
+            } else if lit.is_empty() {
+                self.next = Some(lws);
+            } else {
+                self.out.push_str(lws);
+            }
+        }
+        self.out.push_str(lit);
+        if !rws.is_empty() {
+            self.next = Some(rws);
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        self.next = None;
+        self.skip = true;
+    }
+
+    fn handle_ws(&mut self, ws: Ws) {
+        self.flush_ws(ws);
+        self.skip = ws.1;
+    }
+
+    fn flush_ws(&mut self, ws: Ws) {
+        if let Some(next) = self.next.take() {
+            if !ws.0 {
+                self.out.push_str(next);
+            }
+        }
+    }
+}
+
+fn source(e: &SExpr) -> String {
+    let e = e.t();
+    quote!(#e).to_string()
+}
+
+fn unavailable(e: &Expr) -> String {
+    format!(
+        "expression isn't available in the interpreter `{}`",
+        quote!(#e)
+    )
+}
+
+fn display(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s.clone()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Handlebars truthiness: `false`, `null`, zero, empty strings and empty arrays are false
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().map_or(false, |x| x != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn number(value: &Value) -> Result<f64> {
+    value
+        .as_f64()
+        .ok_or_else(|| format!("expected a number, found {}", value))
+}
+
+fn float(f: f64) -> Result<Value> {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .ok_or_else(|| format!("not finite number {}", f))
+}
+
+fn arithmetic(op: &BinOp, a: &Value, b: &Value) -> Option<Value> {
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        let value = match op {
+            BinOp::Add(_) => a.checked_add(b),
+            BinOp::Sub(_) => a.checked_sub(b),
+            BinOp::Mul(_) => a.checked_mul(b),
+            BinOp::Div(_) => a.checked_div(b),
+            BinOp::Rem(_) => a.checked_rem(b),
+            _ => None,
+        };
+        return value.map(|x| Value::Number(x.into()));
+    }
+
+    let (a, b) = (a.as_f64()?, b.as_f64()?);
+    let value = match op {
+        BinOp::Add(_) => a + b,
+        BinOp::Sub(_) => a - b,
+        BinOp::Mul(_) => a * b,
+        BinOp::Div(_) => a / b,
+        BinOp::Rem(_) => a % b,
+        _ => return None,
+    };
+    float(value).ok()
+}
@@ -0,0 +1,56 @@
+pub use serde_json::from_str;
+pub use wasm_bindgen::JsCast;
+use wasm_bindgen::{closure::Closure, UnwrapThrowExt};
+pub use web_sys as web;
+pub use yarte_wasm_app::after_render;
+
+/// Focus the element of `selector` after the render of the pending messages
+///
+/// Called from the update code, the element is focused once the DOM is patched, when it exists
+///
+/// ```ignore
+/// fn open(app: &mut App, _addr: &Addr<App>) {
+///     app.modal = true;
+///     yarte::wasm::focus("#modal input");
+/// }
+/// ```
+pub fn focus(selector: &str) {
+    let selector = selector.to_owned();
+    after_render(move || {
+        let element = web::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.query_selector(&selector).ok().flatten())
+            .and_then(|e| e.dyn_into::<web::HtmlElement>().ok());
+        if let Some(element) = element {
+            let _ = element.focus();
+        }
+    });
+}
+
+/// Listen `event` once at `root`, calling `f` with the index of the attribute `attr` of the
+/// closest element to the target
+///
+/// Event attributes of templates, as `onclick={{ Msg::Clear }}`, are delegated with it
+pub fn delegate<F: Fn(usize) + 'static>(
+    root: &web::Element,
+    event: &str,
+    attr: &'static str,
+    f: F,
+) {
+    let selector = format!("[{}]", attr);
+    let listener = Closure::wrap(Box::new(move |event: web::Event| {
+        let index = event
+            .target()
+            .and_then(|t| t.dyn_into::<web::Element>().ok())
+            .and_then(|t| t.closest(&selector).ok().flatten())
+            .and_then(|t| t.get_attribute(attr))
+            .and_then(|i| i.parse().ok());
+        if let Some(index) = index {
+            f(index);
+        }
+    }) as Box<dyn Fn(web::Event)>);
+    root.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+        .unwrap_throw();
+    // Listens while the application lives
+    listener.forget();
+}
@@ -18,8 +18,8 @@ fn big_table() {
 
     assert_eq!(
         table.call().unwrap(),
-        "<table><tr><td>0</td><td>1</td><td>2</td></tr><tr><td>0</td><td>1</td><td>2</td></\
-         tr><tr><td>0</td><td>1</td><td>2</td></tr></table>"
+        "<table><tbody><tr><td>0</td><td>1</td><td>2</td></tr><tr><td>0</td><td>1</td><td>2</\
+         td></tr><tr><td>0</td><td>1</td><td>2</td></tr></tbody></table>"
     );
 }
 
@@ -0,0 +1,56 @@
+use std::rc::Rc;
+
+use yarte::Template;
+
+struct Nav {
+    links: Vec<&'static str>,
+}
+
+#[derive(Template)]
+#[template(
+    src = "<nav>{{#each nav.links }}<a>{{ this }}</a>{{/each }}</nav><h1>{{ title }}</h1>{{ user }}",
+    mode = "html",
+    bind = "nav"
+)]
+struct Page<'a> {
+    title: &'a str,
+    nav: &'a Nav,
+    user: &'a str,
+}
+
+#[test]
+fn test_bind() {
+    let nav = Nav {
+        links: vec!["home", "blog"],
+    };
+    let page = Page::with_nav(&nav);
+    assert_eq!(
+        page("Home", "alice").call().unwrap(),
+        "<nav><a>home</a><a>blog</a></nav><h1>Home</h1>alice"
+    );
+    assert_eq!(
+        page("Blog", "bob").call().unwrap(),
+        "<nav><a>home</a><a>blog</a></nav><h1>Blog</h1>bob"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ footer }} {{ lang }}: {{ body }}",
+    ext = "txt",
+    bind = "lang, footer"
+)]
+struct Shared {
+    body: String,
+    footer: Rc<String>,
+    lang: &'static str,
+}
+
+#[test]
+fn test_bind_shared() {
+    let footer = Rc::new("(c) 2020".to_owned());
+    let shared = Shared::with_lang_footer("en", footer.clone());
+    assert_eq!(shared("a".into()).call().unwrap(), "(c) 2020 en: a");
+    assert_eq!(shared("b".into()).call().unwrap(), "(c) 2020 en: b");
+    assert_eq!(Rc::strong_count(&footer), 2);
+}
@@ -0,0 +1,426 @@
+use std::fmt::{self, Display, Formatter};
+
+use yarte::{
+    builtins::{Money, Page},
+    Template,
+};
+
+fn page_url(page: usize) -> String {
+    format!("/posts?page={}&order=asc", page)
+}
+
+#[derive(Template)]
+#[template(src = "{{ @paginate current total url_fn }}")]
+struct PaginateTemplate {
+    current: usize,
+    total: usize,
+    url_fn: fn(usize) -> String,
+}
+
+#[test]
+fn test_paginate() {
+    let t = PaginateTemplate {
+        current: 1,
+        total: 2,
+        url_fn: page_url,
+    };
+    assert_eq!(
+        "<nav><ul class=\"pagination\">\
+         <li class=\"disabled\"><span>&laquo;</span></li>\
+         <li class=\"disabled\"><span>&lsaquo;</span></li>\
+         <li class=\"active\"><span>1</span></li>\
+         <li><a href=\"&#x2f;posts?page=2&amp;order=asc\">2</a></li>\
+         <li><a href=\"&#x2f;posts?page=2&amp;order=asc\">&rsaquo;</a></li>\
+         <li><a href=\"&#x2f;posts?page=2&amp;order=asc\">&raquo;</a></li>\
+         </ul></nav>",
+        t.call().unwrap()
+    );
+
+    let t = PaginateTemplate {
+        current: 1,
+        total: 0,
+        url_fn: page_url,
+    };
+    assert_eq!("", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{ @paginate current 10 (|p| p * 10) window = 1 }}")]
+struct PaginateWindowTemplate {
+    current: usize,
+}
+
+#[test]
+fn test_paginate_window() {
+    let t = PaginateWindowTemplate { current: 5 };
+    assert_eq!(
+        "<nav><ul class=\"pagination\">\
+         <li><a href=\"10\">&laquo;</a></li>\
+         <li><a href=\"40\">&lsaquo;</a></li>\
+         <li><a href=\"40\">4</a></li>\
+         <li class=\"active\"><span>5</span></li>\
+         <li><a href=\"60\">6</a></li>\
+         <li><a href=\"60\">&rsaquo;</a></li>\
+         <li><a href=\"100\">&raquo;</a></li>\
+         </ul></nav>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(src = "{{#*inline \"link\"}}\
+           {{#if let Some(url) = &url }}<li><a class=\"link\" href=\"{{ url }}\">{{ text }}</a></li>\
+           {{~ else }}<li><span>{{ text }}</span></li>{{/if }}\
+           {{/inline}}\
+           {{ @paginate current total url_fn window = 0 partial = \"link\" }}")]
+struct PaginatePartialTemplate {
+    current: usize,
+    total: usize,
+    url_fn: fn(usize) -> String,
+}
+
+#[test]
+fn test_paginate_partial() {
+    let t = PaginatePartialTemplate {
+        current: 2,
+        total: 3,
+        url_fn: page_url,
+    };
+    assert_eq!(
+        "<nav><ul class=\"pagination\">\
+         <li><a class=\"link\" href=\"&#x2f;posts?page=1&amp;order=asc\">\u{ab}</a></li>\
+         <li><a class=\"link\" href=\"&#x2f;posts?page=1&amp;order=asc\">\u{2039}</a></li>\
+         <li><span>2</span></li>\
+         <li><a class=\"link\" href=\"&#x2f;posts?page=3&amp;order=asc\">\u{203a}</a></li>\
+         <li><a class=\"link\" href=\"&#x2f;posts?page=3&amp;order=asc\">\u{bb}</a></li>\
+         </ul></nav>",
+        t.call().unwrap()
+    );
+
+    let t = PaginatePartialTemplate {
+        current: 1,
+        total: 0,
+        url_fn: page_url,
+    };
+    assert_eq!("", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each yarte::builtins::pages(current, total, 1) }}{{#if let Page::Number(n) = this }}[{{ n }}]{{/if }}{{/each }}",
+    ext = "txt"
+)]
+struct PagesTemplate {
+    current: usize,
+    total: usize,
+}
+
+#[test]
+fn test_pages() {
+    let t = PagesTemplate {
+        current: 3,
+        total: 3,
+    };
+    assert_eq!("[2]", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each rows }}<tr class=\"{{ @cycle \"odd\" \"even\" }}\">{{#each this }}{{ @cycle 'a' 'b' 'c' }}{{/each }}</tr>{{/each }}"
+)]
+struct CycleTemplate {
+    rows: Vec<Vec<usize>>,
+}
+
+#[test]
+fn test_cycle() {
+    let t = CycleTemplate {
+        rows: vec![vec![1, 2, 3, 4], vec![1], vec![]],
+    };
+    assert_eq!(
+        "<tr class=\"odd\">abca</tr><tr class=\"even\">a</tr><tr class=\"odd\"></tr>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each [1, 2, 3] }}{{#with this }}{{ @cycle 0 1 }}{{/with }}{{/each }}",
+    ext = "txt"
+)]
+struct CycleConstTemplate;
+
+#[test]
+fn test_cycle_const() {
+    assert_eq!("010", CycleConstTemplate.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{ @meta title=title description=\"Fast & safe\" image=image }}")]
+struct MetaTemplate<'a> {
+    title: &'a str,
+    image: &'a str,
+}
+
+#[test]
+fn test_meta() {
+    let t = MetaTemplate {
+        title: "<Yarte>",
+        image: "https://example.com/a.png",
+    };
+    assert_eq!(
+        "<title>&lt;Yarte&gt;</title>\
+         <meta name=\"description\" content=\"Fast &amp; safe\">\
+         <meta property=\"og:type\" content=\"website\">\
+         <meta property=\"og:title\" content=\"&lt;Yarte&gt;\">\
+         <meta property=\"og:description\" content=\"Fast &amp; safe\">\
+         <meta property=\"og:image\" content=\"https:&#x2f;&#x2f;example.com&#x2f;a.png\">\
+         <meta name=\"twitter:card\" content=\"summary_large_image\">\
+         <meta name=\"twitter:title\" content=\"&lt;Yarte&gt;\">\
+         <meta name=\"twitter:description\" content=\"Fast &amp; safe\">\
+         <meta name=\"twitter:image\" content=\"https:&#x2f;&#x2f;example.com&#x2f;a.png\">",
+        t.call().unwrap()
+    );
+}
+
+struct EuroFormatter;
+
+impl yarte::builtins::Formatter for EuroFormatter {
+    fn fmt_money(value: &dyn Display, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} €", Money(value).to_string().replace('.', ","))
+    }
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ @money price }} {{ @date day }} [{{ @money (@money 1) }}]",
+    formatter = "EuroFormatter"
+)]
+struct EuroTemplate {
+    price: f64,
+    day: &'static str,
+}
+
+#[test]
+fn test_formatter() {
+    let t = EuroTemplate {
+        price: 3.5,
+        day: "<today>",
+    };
+    assert_eq!("3,50 € &lt;today&gt; [1,00 € €]", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{ @money price }}", ext = "txt")]
+struct MoneyTemplate {
+    price: f32,
+}
+
+#[test]
+fn test_money() {
+    assert_eq!("0.10", MoneyTemplate { price: 0.1 }.call().unwrap());
+}
+
+#[test]
+fn test_money_values() {
+    let money = |value: &dyn Display| Money(value).to_string();
+    assert_eq!("3.00", money(&3));
+    assert_eq!("-3.00", money(&-3i64));
+    assert_eq!("2.57", money(&2.567));
+    assert_eq!("-0.50", money(&-0.5));
+    assert_eq!("100000000000000000000.00", money(&1e20));
+    assert_eq!("18446744073709551615.00", money(&u64::max_value()));
+    assert_eq!("free", money(&"free"));
+    assert_eq!("1,50 €", money(&"1,50 €"));
+    assert_eq!("12.5%", money(&"12.5%"));
+    assert_eq!("inf", money(&f64::INFINITY));
+    assert_eq!("NaN", money(&f64::NAN));
+}
+
+#[derive(Template)]
+#[template(src = "<p dir=\"{{ @dir text }}\">{{ @bidi text }}: {{ @bidi (@money 1.5) }}</p>")]
+struct BidiTemplate {
+    text: &'static str,
+}
+
+#[test]
+fn test_bidi() {
+    let t = BidiTemplate {
+        text: "<שלום> world",
+    };
+    assert_eq!(
+        "<p dir=\"rtl\">\u{2068}&lt;שלום&gt; world\u{2069}: \u{2068}1.50\u{2069}</p>",
+        t.call().unwrap()
+    );
+    let t = BidiTemplate { text: "1. hello" };
+    assert_eq!(
+        "<p dir=\"ltr\">\u{2068}1. hello\u{2069}: \u{2068}1.50\u{2069}</p>",
+        t.call().unwrap()
+    );
+    let t = BidiTemplate { text: "42" };
+    assert!(t.call().unwrap().starts_with("<p dir=\"auto\">"));
+}
+
+#[derive(Template)]
+#[template(
+    src = "<label for=\"{{ @id \"name\" }}\"></label><input id=\"{{ @id \"name\" }}\">\
+           {{#each rows }}{{#each this }}<i id=\"{{ @id \"cell\" }}\"></i>{{/each }}{{/each }}"
+)]
+struct IdTemplate {
+    rows: Vec<Vec<usize>>,
+}
+
+#[test]
+fn test_id() {
+    let t = IdTemplate {
+        rows: vec![vec![1, 2], vec![3]],
+    };
+    let out = t.call().unwrap();
+    assert_eq!(out, t.call().unwrap());
+
+    let ids: Vec<&str> = out.split('"').skip(1).step_by(2).collect();
+    assert_eq!(ids.len(), 5);
+    assert_eq!(ids[0], ids[1]);
+    assert!(ids[0].starts_with("name-"));
+    let key = &ids[0]["name-".len()..];
+    assert_eq!(key.len(), 8);
+    for (id, i) in ids[2..].iter().zip(&["0-0", "0-1", "1-0"]) {
+        assert_eq!(*id, format!("cell-{}-{}", key, i));
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{> id-partial }}{{> id-partial }}")]
+struct IdPartialTemplate;
+
+#[test]
+fn test_id_partial() {
+    let out = IdPartialTemplate.call().unwrap();
+
+    let ids: Vec<&str> = out.split('"').skip(1).step_by(2).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids[0].starts_with("field-"));
+    assert!(ids[1].starts_with("field-"));
+    assert_ne!(ids[0], ids[1]);
+}
+
+#[derive(Template)]
+#[template(src = "<a href=\"/search?{{ @query q=q page=page order=order }}\">{{ @query q=q }}</a>")]
+struct QueryTemplate<'a> {
+    q: &'a str,
+    page: Option<usize>,
+    order: Option<&'a str>,
+}
+
+#[test]
+fn test_query() {
+    let t = QueryTemplate {
+        q: "rust & \"wasm\"/ñ",
+        page: Some(2),
+        order: None,
+    };
+    assert_eq!(
+        "<a href=\"/search?q=rust%20%26%20%22wasm%22%2F%C3%B1&amp;page=2\">\
+         q=rust%20%26%20%22wasm%22%2F%C3%B1</a>",
+        t.call().unwrap()
+    );
+    let t = QueryTemplate {
+        q: "a-b_c.d~",
+        page: None,
+        order: Some("asc"),
+    };
+    assert_eq!(
+        "<a href=\"/search?q=a-b_c.d~&amp;order=asc\">q=a-b_c.d~</a>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<a href=\"/users/{{ @pathseg name }}?next={{ @urlencode next }}\">{{ name }}</a>"
+)]
+struct UrlTemplate<'a> {
+    name: &'a str,
+    next: &'a str,
+}
+
+#[test]
+fn test_url_encoding() {
+    let t = UrlTemplate {
+        name: "a/b?c#d%e f:g@h&i'",
+        next: "/home?a=1&b=ñ",
+    };
+    assert_eq!(
+        "<a href=\"/users/a%2Fb%3Fc%23d%25e%20f:g@h&amp;i&#x27;?next=%2Fhome%3Fa%3D1%26b%3D%C3%B1\">\
+         a&#x2f;b?c#d%e f:g@h&amp;i&#x27;</a>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(src = "/users/{{ @pathseg name }}/edit", ext = "txt")]
+struct PathSegTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_pathseg_dots() {
+    for (name, expected) in &[
+        (".", "/users/%2E/edit"),
+        ("..", "/users/%2E%2E/edit"),
+        ("...", "/users/.../edit"),
+        ("..a", "/users/..a/edit"),
+        ("a..", "/users/a../edit"),
+        ("", "/users//edit"),
+    ] {
+        assert_eq!(*expected, PathSegTemplate { name }.call().unwrap());
+    }
+}
+
+#[derive(Template)]
+#[template(src = "<button>{{ @inline_svg \"icons/check.svg\" }}</button>\
+           <button>{{ @inline_svg \"icons/check.svg\" ids=\"ok\" }}</button>")]
+struct InlineSvgTemplate;
+
+#[test]
+fn test_inline_svg() {
+    let svg = |ids: &str| {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 24 24\"><defs>\
+             <linearGradient id=\"{0}fill\"><stop offset=\"0\" stop-color=\"#0a0\"></stop>\
+             </linearGradient></defs><a><path id=\"{0}mark\" d=\"M5 12l5 5L20 7\" \
+             fill=\"url(#{0}fill)\"></path></a><use href=\"#{0}mark\"></use></svg>",
+            ids
+        )
+    };
+    assert_eq!(
+        format!(
+            "<button>{}</button><button>{}</button>",
+            svg(""),
+            svg("ok-")
+        ),
+        InlineSvgTemplate.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ @img \"hero.jpg\" widths=[480, 960, 1920] sizes=\"(max-width: 960px) 100vw, 50vw\" \
+                  alt=title }}{{ @img \"hero.jpg\" widths=[480] }}"
+)]
+struct ImgTemplate<'a> {
+    title: &'a str,
+}
+
+#[test]
+fn test_img() {
+    let t = ImgTemplate { title: "\"Hero\"" };
+    assert_eq!(
+        "<img src=\"&#x2f;assets&#x2f;hero-1920.c07d52.jpg\" srcset=\"&#x2f;assets&#x2f;hero-480.3f2a1c.jpg 480w, \
+         &#x2f;assets&#x2f;hero-960.9b41e0.jpg 960w, &#x2f;assets&#x2f;hero-1920.c07d52.jpg 1920w\" \
+         sizes=\"(max-width: 960px) 100vw, 50vw\" alt=\"&quot;Hero&quot;\">\
+         <img src=\"&#x2f;assets&#x2f;hero-480.3f2a1c.jpg\" srcset=\"&#x2f;assets&#x2f;hero-480.3f2a1c.jpg 480w\" \
+         sizes=\"100vw\" alt=\"\">",
+        t.call().unwrap()
+    );
+}
@@ -0,0 +1,45 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "<style>.alert { color: red }</style><script>init()</script>\
+           <p class=\"alert\">{{ message }}</p>",
+    scoped = true
+)]
+struct AlertTemplate {
+    message: &'static str,
+}
+
+#[derive(Template)]
+#[template(
+    src = "<style>.badge { padding: 0 }</style><script>init()</script>\
+           <span class=\"badge\">{{ count }}</span>",
+    scoped = true
+)]
+struct BadgeTemplate {
+    count: usize,
+}
+
+#[derive(Template)]
+#[template(src = "<style>.plain { margin: 0 }</style><p class=\"plain\">{{ text }}</p>")]
+struct PlainTemplate {
+    text: &'static str,
+}
+
+yarte::collected!("tests/collected.rs");
+
+#[test]
+fn test_collected() {
+    assert_eq!(AlertTemplate::SCRIPT, "init()");
+    assert_eq!(
+        collected::STYLES,
+        format!("{}\n{}", AlertTemplate::STYLE, BadgeTemplate::STYLE)
+    );
+    assert_eq!(collected::SCRIPTS, "init()");
+
+    let t = PlainTemplate { text: "a" };
+    assert_eq!(
+        "<style>.plain { margin: 0 }</style><p class=\"plain\">a</p>",
+        t.call().unwrap()
+    );
+}
@@ -0,0 +1,48 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{{! const PAGE_SIZE = 2 !}}{{! const SEP: &str = \", \" !}}\
+           {{ items[..PAGE_SIZE].join(SEP) }}\n{{ items[PAGE_SIZE..].join(SEP) }}\n\
+           {{ items.len() / PAGE_SIZE }} pages of {{ PAGE_SIZE }}",
+    ext = "txt"
+)]
+struct Pages<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_constants() {
+    let t = Pages {
+        items: &["a", "b", "c", "d"],
+    };
+    assert_eq!(t.call().unwrap(), "a, b\nc, d\n2 pages of 2");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{! const MAX = 3 !}}\n<ul>{{#each items.get(..MAX).unwrap_or(items) }}<li>{{ this }}</li>{{/each }}</ul>",
+    mode = "html-min"
+)]
+struct Html<'a> {
+    items: &'a [&'a str],
+}
+
+// Shadowed by the constant of the template
+#[allow(dead_code)]
+const MAX: usize = 1;
+
+#[test]
+fn test_constants_html() {
+    let t = Html {
+        items: &["a", "<b>", "c", "d"],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<ul><li>a</li><li>&lt;b&gt;</li><li>c</li></ul>"
+    );
+    assert_eq!(
+        t.to_string(),
+        "<ul><li>a</li><li>&lt;b&gt;</li><li>c</li></ul>"
+    );
+}
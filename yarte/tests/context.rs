@@ -0,0 +1,39 @@
+#![cfg(feature = "context")]
+
+use yarte::{context, Template};
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items }}{{#if *this == 0 }}{{ fail()? }}{{/if }}{{/each }}{{ fail()? }}",
+    ext = "txt"
+)]
+struct ContextTemplate {
+    items: Vec<usize>,
+}
+
+fn fail() -> Result<&'static str, std::fmt::Error> {
+    Err(std::fmt::Error)
+}
+
+#[test]
+fn test_region() {
+    let t = ContextTemplate {
+        items: vec![1, 0, 2],
+    };
+    let e = t.try_render().unwrap_err();
+    if cfg!(debug_assertions) {
+        let context = e.context().unwrap();
+        assert!(context.template.ends_with("ContextTemplate"));
+        assert_eq!(context.region.map(|(i, _)| i), Some(1));
+        assert!(context.region.unwrap().1.starts_with("if at "));
+        assert!(e.to_string().contains(", if at "));
+    }
+
+    // The regions left before the error aren't its context
+    let t = ContextTemplate { items: vec![1, 2] };
+    let e = t.try_render().unwrap_err();
+    if cfg!(debug_assertions) {
+        assert_eq!(e.context().unwrap().region, None);
+    }
+    assert_eq!(context::current(), None);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "coverage")]
+
+use yarte::{coverage, Template};
+
+#[derive(Template)]
+#[template(
+    src = "{{#if admin }}admin{{ else if guest }}guest{{ else }}user{{/if }}\n\
+           {{#each items }}{{ this }}{{/each }}\n\
+           {{#unless admin }}{{> hello }}{{/unless }}",
+    ext = "txt"
+)]
+struct CoverageTemplate<'a> {
+    admin: bool,
+    guest: bool,
+    items: Vec<&'a str>,
+    name: &'a str,
+}
+
+#[test]
+fn test_dry_run() {
+    let t = CoverageTemplate {
+        admin: false,
+        guest: true,
+        items: vec!["a", "b"],
+        name: "world",
+    };
+
+    let report: Vec<_> = coverage::dry_run(&t)
+        .into_iter()
+        .map(|x| (x.label, x.hits))
+        .collect();
+    assert_eq!(
+        report,
+        vec![
+            ("if at templates/CoverageTemplate.txt:1", 0),
+            ("else if at templates/CoverageTemplate.txt:1", 1),
+            ("else at templates/CoverageTemplate.txt:1", 0),
+            ("each at templates/CoverageTemplate.txt:2", 2),
+            ("unless at templates/CoverageTemplate.txt:3", 1),
+            ("partial `hello` at templates/CoverageTemplate.txt:3", 1),
+        ]
+    );
+    assert_eq!(coverage::uncovered().len(), 2);
+    assert_eq!("guest\nab\nHello, world!", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "<table>{{#each rows }}<tr><td>{{ this }}</td></tr>{{/each }}</table>",
+    ext = "html"
+)]
+struct TableTemplate {
+    rows: Vec<usize>,
+}
+
+#[test]
+fn test_table_rows() {
+    let t = TableTemplate { rows: vec![1, 2] };
+    assert_eq!(
+        "<table><tbody><tr><td>1</td></tr><tr><td>2</td></tr></tbody></table>",
+        t.call().unwrap()
+    );
+}
@@ -54,3 +54,261 @@ fn test_for_range() {
     let s = ForRangeTemplate { init: -1, end: 1 };
     assert_eq!(s.call().unwrap(), "foo\nfoo\nbar\nbar\nfoo\nbar\nbar\n");
 }
+
+#[derive(Template)]
+#[template(path = "each-chunks.hbs")]
+struct ChunksTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_chunks() {
+    let items: &[&str] = &["a", "b", "c"];
+    let s = ChunksTemplate { items };
+    assert_eq!(
+        s.call().unwrap(),
+        "<tr><td>a</td><td>b</td></tr><tr><td>c</td></tr>"
+    );
+}
+
+struct Post {
+    title: &'static str,
+    category: String,
+}
+
+#[derive(Template)]
+#[template(path = "each-group-by.hbs")]
+struct GroupByTemplate {
+    posts: Vec<Post>,
+}
+
+#[test]
+fn test_group_by() {
+    let post = |title, category: &str| Post {
+        title,
+        category: category.to_string(),
+    };
+    let s = GroupByTemplate {
+        posts: vec![
+            post("foo", "news"),
+            post("bar", "blog"),
+            post("baz", "news"),
+        ],
+    };
+    assert_eq!(
+        s.call().unwrap(),
+        "<h2>news</h2><p>1. foo</p><p>2. baz</p><h2>blog</h2><p>1. bar</p>"
+    );
+}
+
+#[derive(Template)]
+#[template(path = "each-sort-by.hbs")]
+struct SortByTemplate {
+    posts: Vec<Post>,
+}
+
+#[test]
+fn test_sort_by() {
+    let post = |title| Post {
+        title,
+        category: String::new(),
+    };
+    let s = SortByTemplate {
+        posts: vec![post("c"), post("a"), post("d"), post("b")],
+    };
+    assert_eq!(s.call().unwrap(), "1. c2. b");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each 0..10 reverse limit=3 }}{{ this }}{{/each }}",
+    ext = "txt"
+)]
+struct ReverseRangeTemplate;
+
+#[test]
+fn test_reverse_range() {
+    assert_eq!(ReverseRangeTemplate.call().unwrap(), "987");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items }}{{#if @first }}[{{/if }}{{ this }}{{#if @nth 2 }}|{{/if }}{{#if @last }}]{{ else }},{{/if }}{{/each }}",
+    ext = "txt"
+)]
+struct LoopStateTemplate {
+    items: Vec<usize>,
+}
+
+#[test]
+fn test_loop_state() {
+    let t = LoopStateTemplate {
+        items: vec![1, 2, 3, 4, 5],
+    };
+    assert_eq!(t.call().unwrap(), "[1,2|,3,4|,5]");
+
+    let t = LoopStateTemplate { items: vec![] };
+    assert_eq!(t.call().unwrap(), "");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each rows }}{{ @index }}:{{#each this }}{{ @index }}{{# if @last }};{{/if }}{{/each }}{{/each }}\
+           {{#each [7, 8] }} {{ @index }}={{ this }}{{/each }}",
+    ext = "txt"
+)]
+struct LoopIndexTemplate {
+    rows: Vec<Vec<usize>>,
+}
+
+#[test]
+fn test_loop_index() {
+    let t = LoopIndexTemplate {
+        rows: vec![vec![5, 5], vec![], vec![5]],
+    };
+    assert_eq!(t.call().unwrap(), "0:01;1:2:0; 0=7 1=8");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each [1, 2, 3] }}{{ this }}{{#if @last }}.{{ else }}, {{/if }}{{/each }}",
+    ext = "txt"
+)]
+struct LoopStateConstTemplate;
+
+#[test]
+fn test_loop_state_const() {
+    assert_eq!(LoopStateConstTemplate.call().unwrap(), "1, 2, 3.");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each (zip names ages) as |name, age| }}{{ name }}: {{ age }}{{#if @last }}.{{ else }}, {{/if }}{{/each }}\
+           {{#each pairs reverse as |pair| }} {{ pair.0 }}{{ pair.1 }}{{/each }}\
+           {{#each (zip names self.ages self.names) }} {{ this.2 }}{{ index }}{{/each }}",
+    ext = "txt"
+)]
+struct ZipTemplate<'a> {
+    names: Vec<&'a str>,
+    ages: [usize; 3],
+    pairs: Vec<(char, usize)>,
+}
+
+#[test]
+fn test_zip() {
+    let t = ZipTemplate {
+        names: vec!["Tom", "Ana"],
+        ages: [30, 25, 40],
+        pairs: vec![('a', 1), ('b', 2)],
+    };
+    assert_eq!(t.call().unwrap(), "Tom: 30, Ana: 25. b2 a1 Tom1 Ana2");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each rows }}[{{ this }}{{#if @last }}]{{ else }}] {{/if }}\
+           {{~ else err }} error: {{ err }}{{/each }}\
+           {{#each numbers as |n| }}{{ n + 1 }}{{ else _e }}!{{/each }}",
+    ext = "txt"
+)]
+struct TryEachTemplate {
+    rows: Vec<Result<usize, String>>,
+    numbers: Vec<Option<u8>>,
+}
+
+#[test]
+fn test_each_else() {
+    let t = TryEachTemplate {
+        rows: vec![Ok(1), Ok(2)],
+        numbers: vec![Some(1), Some(2)],
+    };
+    assert_eq!(t.call().unwrap(), "[1] [2]23");
+
+    let t = TryEachTemplate {
+        rows: vec![Ok(1), Err("broken row".into()), Ok(3)],
+        numbers: vec![Some(1), None, Some(3)],
+    };
+    assert_eq!(t.call().unwrap(), "[1] error: broken row2!");
+}
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each rows }}<li>{{ index }}: {{ this }}</li>{{ else err }}<li class=\"error\">{{ err }}</li>{{/each }}</ul>",
+    mode = "html-min"
+)]
+struct TryEachHtmlTemplate {
+    rows: Vec<Result<&'static str, &'static str>>,
+}
+
+#[test]
+fn test_each_else_html() {
+    let t = TryEachHtmlTemplate {
+        rows: vec![Ok("<a>"), Err("<b>")],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<ul><li>1: &lt;a&gt;</li><li class=\"error\">&lt;b&gt;</li></ul>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<table><thead><tr><th>n</th></tr></thead>{{#each rows }}<tr><td>{{ this }}</td></tr>{{/each }}</table>\
+           <table>\n<tbody>\n{{#each rows }}\n<tr><td>{{ this }}</td></tr>\n{{/each }}\n</tbody>\n</table>",
+    mode = "html-min"
+)]
+struct TableRowsTemplate {
+    rows: Vec<usize>,
+}
+
+// The rows written in the `<table>` are in the `<tbody>` of the browser
+#[test]
+fn test_each_table_rows() {
+    let t = TableRowsTemplate { rows: vec![1, 2] };
+    assert_eq!(
+        t.call().unwrap(),
+        "<table><thead><tr><th>n</th></tr></thead><tbody><tr><td>1</td></tr><tr><td>2</td></tr>\
+         </tbody></table><table><tbody><tr><td>1</td></tr><tr><td>2</td></tr></tbody></table>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each items skip=1 }}<li>{{ this }}</li>{{ else }}<li>Empty list</li>{{/each }}</ul>",
+    mode = "html-min"
+)]
+struct EmptyEachTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_each_empty() {
+    let t = EmptyEachTemplate {
+        items: &["header", "a", "b"],
+    };
+    assert_eq!(t.call().unwrap(), "<ul><li>a</li><li>b</li></ul>");
+
+    // Without items after the modifiers
+    let t = EmptyEachTemplate { items: &["header"] };
+    assert_eq!(t.call().unwrap(), "<ul><li>Empty list</li></ul>");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items as |item i| }}{{ i }}={{ item }} {{/each }}\
+           {{#each pairs as |c, n i| }}[{{ i }}{{ c }}{{ n }}]{{/each }}",
+    ext = "txt"
+)]
+struct IndexParamTemplate<'a> {
+    items: &'a [&'a str],
+    pairs: Vec<(char, usize)>,
+}
+
+#[test]
+fn test_index_param() {
+    let t = IndexParamTemplate {
+        items: &["a", "b"],
+        pairs: vec![('c', 1), ('d', 2)],
+    };
+    assert_eq!(t.call().unwrap(), "0=a 1=b [0c1][1d2]");
+}
@@ -449,3 +449,29 @@ fn test_call() {
     let t = CallTemplate;
     assert_eq!(t.call().unwrap(), "Hello, &amp;!");
 }
+
+fn upper<T: std::fmt::Display>(s: T) -> String {
+    s.to_string().to_uppercase()
+}
+
+fn greet<T: std::fmt::Display, M: std::fmt::Display>(name: T, mark: M) -> String {
+    format!("hello {}{}", name, mark)
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ upper (greet name \"!\") }} {{ greet (upper name) (@money 1.5) }} {{ @bidi (upper name) }}",
+    ext = "txt"
+)]
+struct SubexpressionTemplate {
+    name: &'static str,
+}
+
+#[test]
+fn test_subexpression() {
+    let t = SubexpressionTemplate { name: "world" };
+    assert_eq!(
+        "HELLO WORLD! hello WORLD1.50 \u{2068}WORLD\u{2069}",
+        t.call().unwrap()
+    );
+}
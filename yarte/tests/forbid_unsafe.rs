@@ -0,0 +1,60 @@
+//! Templates compile in crates forbidding unsafe code
+#![forbid(unsafe_code)]
+
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items reverse }}{{#if @first }}<b>{{ this }}</b>{{ else }}{{ this }}{{/if }}{{/each }}\
+           {{ #once name.to_uppercase() }}{{ #once name.to_uppercase() }}{{{ name }}}",
+    mode = "html-min"
+)]
+struct HtmlMin<'a> {
+    items: &'a [&'a str],
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items }}{{ index }}{{ this }}{{/each }}{{ name }}",
+    mode = "html"
+)]
+struct Html<'a> {
+    items: &'a [&'a str],
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items }}{{ this }}{{/each }} {{ #once name.len() }}",
+    ext = "txt"
+)]
+struct Text<'a> {
+    items: &'a [&'a str],
+    name: &'a str,
+}
+
+#[test]
+fn test_forbid_unsafe() {
+    let items = ["a", "<b>"];
+
+    let t = HtmlMin {
+        items: &items,
+        name: "yarte",
+    };
+    assert_eq!(t.call().unwrap(), "<b>&lt;b&gt;</b>aYARTEYARTEyarte");
+
+    let t = Html {
+        items: &items,
+        name: "<i>",
+    };
+    assert_eq!(t.call().unwrap(), "1a2&lt;b&gt;&lt;i&gt;");
+    let mut buf = [0; 64];
+    assert_eq!(t.write_to(&mut buf).unwrap(), "1a2&lt;b&gt;&lt;i&gt;");
+
+    let t = Text {
+        items: &items,
+        name: "yarte",
+    };
+    assert_eq!(t.call().unwrap(), "a<b> 5");
+}
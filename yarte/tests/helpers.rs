@@ -0,0 +1,82 @@
+use yarte::{CustomBlockHelper, CustomConditionHelper, Template};
+
+pub struct Repeat;
+
+impl<'a> CustomBlockHelper<'a, usize> for Repeat {
+    type Blocks = std::ops::Range<usize>;
+
+    fn blocks(times: &'a usize) -> Self::Blocks {
+        0..*times
+    }
+}
+
+pub struct Present;
+
+impl<'a, T: 'a> CustomConditionHelper<'a, Option<T>> for Present {
+    type Block = &'a T;
+
+    fn block(value: &'a Option<T>) -> Option<Self::Block> {
+        value.as_ref()
+    }
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#repeat times }}{{#if @first }}{{ super::name }}:{{/if }}[{{ @index }}={{ this }}]{{/repeat }}\
+           {{#present nick as |nick| }} aka {{ nick }}{{/present }}",
+    mode = "text"
+)]
+struct HelpersTemplate {
+    times: usize,
+    name: &'static str,
+    nick: Option<&'static str>,
+}
+
+#[test]
+fn test_custom_helpers() {
+    let t = HelpersTemplate {
+        times: 2,
+        name: "foo",
+        nick: Some("bar"),
+    };
+    assert_eq!(t.call().unwrap(), "foo:[0=0][1=1] aka bar");
+
+    let t = HelpersTemplate {
+        times: 0,
+        name: "foo",
+        nick: None,
+    };
+    assert_eq!(t.call().unwrap(), "");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#repeat times as |n i| }}[{{ i }}={{ n * 2 }}]{{/repeat }}",
+    mode = "text"
+)]
+struct ParamsTemplate {
+    times: usize,
+}
+
+#[test]
+fn test_custom_helpers_params() {
+    let t = ParamsTemplate { times: 2 };
+    assert_eq!(t.call().unwrap(), "[0=0][1=2]");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each names }}{{#present this as |nick| }}{{ @index }}:{{ nick }}{{/present }}{{/each }}",
+    mode = "text"
+)]
+struct ConditionTemplate {
+    names: Vec<Option<&'static str>>,
+}
+
+#[test]
+fn test_custom_conditions() {
+    let t = ConditionTemplate {
+        names: vec![Some("foo"), None, Some("bar")],
+    };
+    assert_eq!(t.call().unwrap(), "0:foo2:bar");
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "hook")]
+
+use std::{cell::RefCell, rc::Rc};
+
+use yarte::{hook::RenderHook, Template};
+
+#[derive(Clone, Default)]
+struct Recorder(Rc<RefCell<Vec<(&'static str, &'static str, usize)>>>);
+
+impl RenderHook for Recorder {
+    fn start(&self, template: &'static str) {
+        self.0.borrow_mut().push(("start", template, 0));
+    }
+
+    fn end(&self, template: &'static str, bytes: usize) {
+        self.0.borrow_mut().push(("end", template, bytes));
+    }
+}
+
+#[derive(Template)]
+#[template(src = "Hello, {{ name }}!", ext = "txt")]
+struct HookTemplate<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each items }}<li>{{ this }}</li>{{/each }}</ul>",
+    ext = "html"
+)]
+struct HookHtmlTemplate {
+    items: Vec<usize>,
+}
+
+#[test]
+fn test_hook() {
+    let recorder = Recorder::default();
+    yarte::hook::install(recorder.clone());
+
+    assert_eq!(
+        HookTemplate { name: "world" }.call().unwrap(),
+        "Hello, world!"
+    );
+    let t = HookHtmlTemplate { items: vec![1, 2] };
+    assert_eq!(t.call().unwrap(), "<ul><li>1</li><li>2</li></ul>");
+    assert_eq!(
+        *recorder.0.borrow(),
+        vec![
+            ("start", "HookTemplate", 0),
+            ("end", "HookTemplate", 13),
+            ("start", "HookHtmlTemplate", 0),
+            ("end", "HookHtmlTemplate", 29),
+        ]
+    );
+
+    yarte::hook::uninstall();
+    HookTemplate { name: "world" }.call().unwrap();
+    assert_eq!(recorder.0.borrow().len(), 4);
+}
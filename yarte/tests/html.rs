@@ -28,9 +28,9 @@ fn test_fortune() {
         ],
     };
     assert_eq!(
-        "<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table><tr><th>id</\
-         th><th>message</th></tr><tr><td>0</td><td>foo</td></tr><tr><td>1</td><td>bar</td></tr></\
-         table></body></html>",
+        "<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table><tbody><tr><th>\
+         id</th><th>message</th></tr><tr><td>0</td><td>foo</td></tr><tr><td>1</td><td>bar</td></\
+         tr></tbody></table></body></html>",
         t.call().unwrap()
     );
 }
@@ -175,3 +175,392 @@ fn test_noscript() {
         "<noscript><meta><style></style><link></noscript>"
     )
 }
+
+#[derive(Template)]
+#[template(
+    src = "<form><label for=\"q\">Search</label><input id=\"q\" value=\"{{ q }}\">\
+           <input type=\"image\" src=\"go.png\" alt=\"Go\"></form>",
+    ext = "html",
+    strict_html = true
+)]
+struct StrictHtml<'a> {
+    q: &'a str,
+}
+
+#[test]
+fn test_strict_html() {
+    assert_eq!(
+        StrictHtml { q: "yarte" }.call().unwrap(),
+        "<form><label for=\"q\">Search</label><input id=\"q\" value=\"yarte\">\
+         <input type=\"image\" src=\"go.png\" alt=\"Go\"></form>"
+    )
+}
+
+#[derive(Template)]
+#[template(
+    src = "<my-widget submit-label=\"{{ label }}\" maxItems=\"3\">\
+           <span slot=\"title\">Hi</span></my-widget>",
+    ext = "html"
+)]
+struct CustomElement<'a> {
+    label: &'a str,
+}
+
+#[test]
+fn test_custom_element() {
+    assert_eq!(
+        CustomElement {
+            label: "Send & close"
+        }
+        .call()
+        .unwrap(),
+        "<my-widget submit-label=\"Send &amp; close\" maxItems=\"3\">\
+         <span slot=\"title\">Hi</span></my-widget>"
+    )
+}
+
+#[derive(Template)]
+#[template(
+    src = "<svg viewBox=\"0 0 10 10\" xmlns:xlink=\"http://www.w3.org/1999/xlink\">\
+           <linearGradient id=\"g\" gradientUnits=\"userSpaceOnUse\"></linearGradient>\
+           <use xlink:href=\"{{ href }}\"/><foreignObject><p>a</p></foreignObject></svg>\
+           <math definitionURL=\"x\"><mi>x</mi></math>",
+    ext = "html"
+)]
+struct ForeignTemplate<'a> {
+    href: &'a str,
+}
+
+#[test]
+fn test_foreign() {
+    assert_eq!(
+        ForeignTemplate { href: "#a" }.call().unwrap(),
+        "<svg viewBox=\"0 0 10 10\" xmlns:xlink=\"http://www.w3.org/1999/xlink\">\
+         <linearGradient id=\"g\" gradientUnits=\"userSpaceOnUse\"></linearGradient>\
+         <use xlink:href=\"#a\"></use><foreignObject><p>a</p></foreignObject></svg>\
+         <math definitionURL=\"x\"><mi>x</mi></math>"
+    )
+}
+
+#[derive(Template)]
+#[template(path = "html/email.hbs", mode = "email", css = "html/email.css")]
+struct EmailTemplate {
+    title: &'static str,
+    kind: &'static str,
+    items: Vec<&'static str>,
+}
+
+#[test]
+fn test_email() {
+    let t = EmailTemplate {
+        title: "Hi",
+        kind: "primary",
+        items: vec!["a", "b"],
+    };
+
+    assert_eq!(
+        "<!DOCTYPE html><html><head><style>@media (max-width: 600px) { p { font-size: 16px } }\
+         </style></head><body><table><tbody><tr><td><p style=\"margin:0;font-size:14px\">a</p></td>\
+         </tr><tr><td><p style=\"margin:0;font-size:14px\">b</p></td></tr></tbody></table>\
+         <h1 id=\"title\" style=\"font-weight:bold;color: red\">Hi</h1>\
+         <a class=\"button primary\" href=\"/\" style=\"color:white;background:blue\">Go</a>\
+         </body></html>",
+        t.call().unwrap()
+    );
+    assert_eq!("a\n\nb\n\nHi\n\nGo (/)", t.render_text_alt());
+}
+
+#[derive(Template)]
+#[template(
+    src = "<style>.card { padding: 0 } .card > .title { margin: 0 }</style>\
+           <div class=\"card {{ kind }}\"><h2 class=\"title global\">{{ title }}</h2></div>",
+    scoped = true
+)]
+struct CardTemplate {
+    title: &'static str,
+    kind: &'static str,
+}
+
+#[test]
+fn test_scoped() {
+    let t = CardTemplate {
+        title: "Hi",
+        kind: "card",
+    };
+
+    assert_eq!(
+        ".card-51e3f0 { padding: 0 } .card-51e3f0 > .title-51e3f0 { margin: 0 }",
+        CardTemplate::STYLE
+    );
+    assert_eq!(
+        "<div class=\"card-51e3f0 card\"><h2 class=\"title-51e3f0 global\">Hi</h2></div>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<style>.card { padding: 0 }</style>\
+           <my-card class=\"card\"><template shadowrootmode=\"open\">\
+           <style>.card { margin: 0 }</style><p class=\"card\"><slot></slot></p></template>\
+           {{ title }}</my-card>",
+    scoped = true
+)]
+struct ShadowTemplate {
+    title: &'static str,
+}
+
+#[test]
+fn test_shadow_root() {
+    let t = ShadowTemplate { title: "<Hi>" };
+
+    // The styles of the shadow root are scoped by the browser
+    assert_eq!(".card-6195d6 { padding: 0 }", ShadowTemplate::STYLE);
+    assert_eq!(
+        "<my-card class=\"card-6195d6\"><template shadowrootmode=\"open\">\
+         <style>.card { margin: 0 }</style><p class=\"card\"><slot></slot></p></template>\
+         &lt;Hi&gt;</my-card>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<head><meta http-equiv=\"Content-Security-Policy\" content=\"{{ @csp }}\">\
+           <style>p { margin: 0 }</style><script src=\"/app.js\"></script></head>\
+           <body>{{# each items }}<script>go({{ this }})</script>{{/each }}</body>",
+    csp = true
+)]
+struct CspTemplate {
+    nonce: &'static str,
+    items: Vec<usize>,
+}
+
+impl yarte::builtins::SecurityContext for CspTemplate {
+    fn nonce(&self) -> &str {
+        self.nonce
+    }
+
+    fn report_uri(&self) -> Option<&str> {
+        Some("/csp")
+    }
+}
+
+#[test]
+fn test_csp() {
+    let t = CspTemplate {
+        nonce: "r4nd\"",
+        items: vec![1, 2],
+    };
+
+    assert_eq!(
+        "<head><meta http-equiv=\"Content-Security-Policy\" content=\"script-src \
+         &#x27;nonce-r4nd&quot;&#x27; &#x27;strict-dynamic&#x27;; style-src \
+         &#x27;nonce-r4nd&quot;&#x27;; object-src &#x27;none&#x27;; base-uri &#x27;none&#x27;; \
+         report-uri &#x2f;csp\"><style nonce=\"r4nd&quot;\">p { margin: 0 }</style>\
+         <script src=\"/app.js\"></script></head><body><script nonce=\"r4nd&quot;\">go(1)\
+         </script><script nonce=\"r4nd&quot;\">go(2)</script></body>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(src = "<p>a {{!keep build --> 42 !}} {{ b }}</p>", mode = "html")]
+struct KeepTemplate {
+    b: &'static str,
+}
+
+#[derive(Template)]
+#[template(src = "<p>a {{!keep build --> 42 !}} {{ b }}</p>", ext = "html")]
+struct KeepMinTemplate {
+    b: &'static str,
+}
+
+#[derive(Template)]
+#[template(src = "a {{!--keep build --!}}", ext = "txt", keep_comments = true)]
+struct KeepTextTemplate;
+
+#[test]
+fn test_keep_comment() {
+    assert_eq!(
+        "<p>a <!-- build - -> 42 --> b</p>",
+        KeepTemplate { b: "b" }.call().unwrap()
+    );
+    assert_eq!(
+        "<p>a <!-- build - -> 42 --> b</p>",
+        KeepMinTemplate { b: "b" }.call().unwrap()
+    );
+    assert_eq!("a <!-- build -->", KeepTextTemplate.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each items }}<li class=\"{{ class }}\">{{ name }}: {{{ raw }}} {{ n }}</li>{{/each }}</ul>",
+    ext = "html"
+)]
+struct DirectTemplate {
+    items: Vec<DirectItem>,
+}
+
+struct DirectItem {
+    class: &'static str,
+    name: String,
+    raw: &'static str,
+    n: f64,
+}
+
+#[test]
+fn test_call_display() {
+    let t = DirectTemplate {
+        items: vec![
+            DirectItem {
+                class: "a\"b",
+                name: "<foo> & 'bar'/".into(),
+                raw: "<b>raw</b>",
+                n: 1.5,
+            },
+            DirectItem {
+                class: "c",
+                name: "baz".into(),
+                raw: "",
+                n: -2.0,
+            },
+        ],
+    };
+
+    let expected =
+        "<ul><li class=\"a&quot;b\">&lt;foo&gt; &amp; &#x27;bar&#x27;&#x2f;: <b>raw</b> \
+                    1.5</li><li class=\"c\">baz:  -2</li></ul>";
+    assert_eq!(t.call().unwrap(), expected);
+    assert_eq!(t.to_string(), expected);
+}
+
+#[derive(Template)]
+#[template(
+    src = "<p title=\"{{ title }}\">{{ n }} {{{ raw }}}{{#each items }}<i>{{ this }}</i>{{/each }}</p>",
+    ext = "html"
+)]
+struct BytesTemplate<'a> {
+    title: String,
+    n: usize,
+    raw: &'a str,
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_write_bytes() {
+    let t = BytesTemplate {
+        title: "a\"b".to_string(),
+        n: 1,
+        raw: "<b>",
+        items: &["<", "c/d"],
+    };
+    let expected = "<p title=\"a&quot;b\">1 <b><i>&lt;</i><i>c&#x2f;d</i></p>";
+    assert_eq!(t.call().unwrap(), expected);
+    assert_eq!(t.call_bytes().unwrap(), expected.as_bytes());
+
+    // Appended to the reused buffer
+    let mut buf = b"<!DOCTYPE html>".to_vec();
+    t.write_bytes(&mut buf).unwrap();
+    assert_eq!(buf, format!("<!DOCTYPE html>{}", expected).as_bytes());
+}
+
+#[cfg(feature = "bytes-buf")]
+#[test]
+fn test_call_bytes_mut() {
+    use yarte::TemplateBytes;
+
+    let t = BytesTemplate {
+        title: "a\"b".to_string(),
+        n: 1,
+        raw: "<b>",
+        items: &["<", "c/d"],
+    };
+    let expected = "<p title=\"a&quot;b\">1 <b><i>&lt;</i><i>c&#x2f;d</i></p>";
+    assert_eq!(TemplateBytes::call_bytes(&t, 8).unwrap(), expected.as_bytes());
+
+    let mut buf = bytes::BytesMut::from(&b"<!DOCTYPE html>"[..]);
+    t.write_bytes(&mut buf).unwrap();
+    assert_eq!(&buf[..], format!("<!DOCTYPE html>{}", expected).as_bytes());
+}
+
+#[derive(Template)]
+#[template(
+    src = "<p title=\"{{ title }}\">{{ body }}{{#each items }}<i>{{ this }}</i>{{/each }}</p>",
+    ext = "html",
+    escape = "scalar"
+)]
+struct ScalarTemplate<'a> {
+    title: &'a str,
+    body: Option<String>,
+    items: &'a [&'a str],
+}
+
+#[derive(Template)]
+#[template(
+    src = "<p title=\"{{ title }}\">{{ body }}{{#each items }}<i>{{ this }}</i>{{/each }}</p>",
+    ext = "html",
+    escape = "simd"
+)]
+struct SimdTemplate<'a> {
+    title: &'a str,
+    body: Option<String>,
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_escape() {
+    let expected =
+        "<p title=\"a&quot;b\">&lt;b&gt; &amp; &#x27;c&#x27;<i>1 &lt; 2</i><i>d&#x2f;e</i></p>";
+    let t = ScalarTemplate {
+        title: "a\"b",
+        body: Some("<b> & 'c'".to_string()),
+        items: &["1 < 2", "d/e"],
+    };
+    assert_eq!(t.call().unwrap(), expected);
+    assert_eq!(t.call_bytes().unwrap(), expected.as_bytes());
+    assert_eq!(t.to_string(), expected);
+
+    let t = SimdTemplate {
+        title: "a\"b",
+        body: Some("<b> & 'c'".to_string()),
+        items: &["1 < 2", "d/e"],
+    };
+    assert_eq!(t.call().unwrap(), expected);
+    assert_eq!(t.call_bytes().unwrap(), expected.as_bytes());
+    assert_eq!(t.to_string(), expected);
+}
+
+struct Markup;
+
+impl std::fmt::Display for Markup {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("<b>markup</b>")
+    }
+}
+
+#[derive(Template)]
+#[template(
+    src = "<div>\n  {{{ owned }}} {{{ borrowed }}} {{{ n }}} {{{ markup }}}\n</div>",
+    mode = "html-min"
+)]
+struct SafeTemplate<'a> {
+    owned: String,
+    borrowed: &'a str,
+    n: f32,
+    markup: Markup,
+}
+
+#[test]
+fn test_safe() {
+    let t = SafeTemplate {
+        owned: "<i>a</i>".to_string(),
+        borrowed: "<u>&</u>",
+        n: 1.5,
+        markup: Markup,
+    };
+    let expected = "<div><i>a</i> <u>&</u> 1.5 <b>markup</b></div>";
+    assert_eq!(t.call().unwrap(), expected);
+    assert_eq!(t.call_bytes().unwrap(), expected.as_bytes());
+    assert_eq!(t.to_string(), expected);
+}
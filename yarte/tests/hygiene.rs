@@ -0,0 +1,112 @@
+//! Templates derived in a scope shadowing the names of the generated code
+#![allow(dead_code, unused_macros, clippy::into_iter_on_ref)]
+
+mod hostile {
+    use ::yarte::Template;
+
+    mod core {}
+    mod std {}
+    mod yarte {}
+
+    struct Render;
+    struct Display;
+    struct Formatter;
+    struct Option;
+    struct Result;
+    struct String;
+    struct Ok;
+    struct Some;
+    struct Err;
+    struct None;
+
+    macro_rules! format_args {
+        ($($t:tt)*) => {
+            compile_error!("generated code uses a `format_args` in scope")
+        };
+    }
+
+    /// Methods of every type with the names of the methods of the generated code
+    trait Hostile {
+        fn write_str(self, _: &str);
+        fn push_str(self, _: &str);
+        fn enumerate(self);
+        fn rev(self);
+        fn take(self, _: usize);
+        fn skip(self, _: usize);
+        fn call(&self);
+        fn mime(&self);
+    }
+
+    impl<T> Hostile for T {
+        fn write_str(self, _: &str) {}
+        fn push_str(self, _: &str) {}
+        fn enumerate(self) {}
+        fn rev(self) {}
+        fn take(self, _: usize) {}
+        fn skip(self, _: usize) {}
+        fn call(&self) {}
+        fn mime(&self) {}
+    }
+
+    #[derive(Template)]
+    #[template(
+        src = "<ul>{{#each items reverse skip=1 limit=2 }}<li>{{ index }} {{ this }}</li>{{/each }}</ul>\
+               {{#if f }}<p>{{ buf }}</p>{{ else }}<p>{{{ buf }}}</p>{{/if }}",
+        mode = "html-min"
+    )]
+    struct HtmlMin<'a> {
+        items: &'a [&'a str],
+        f: bool,
+        buf: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(
+        src = "{{#each items }}{{ index }}:{{ this }}{{#if @last }}.{{ else }},{{/if }}{{/each }} {{ buf }}",
+        mode = "html"
+    )]
+    struct Html<'a> {
+        items: &'a [&'a str],
+        buf: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(src = "{{#each items }}{{ this }}{{/each }} {{ f }}", ext = "txt")]
+    struct Text<'a> {
+        items: &'a [&'a str],
+        f: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(src = "constant", ext = "txt")]
+    struct Constant;
+
+    #[test]
+    fn test_hostile_scope() {
+        let items = ["a", "b", "c", "d"];
+
+        let t = HtmlMin {
+            items: &items,
+            f: true,
+            buf: "<b>",
+        };
+        assert_eq!(
+            ::yarte::Template::call(&t).unwrap(),
+            "<ul><li>1 c</li><li>2 b</li></ul><p>&lt;b&gt;</p>"
+        );
+
+        let t = Html {
+            items: &items[..2],
+            buf: "<b>",
+        };
+        assert_eq!(::yarte::Template::call(&t).unwrap(), "1:a,2:b. &lt;b&gt;");
+
+        let t = Text {
+            items: &items,
+            f: "<b>",
+        };
+        assert_eq!(::yarte::Template::call(&t).unwrap(), "abcd <b>");
+
+        assert_eq!(::yarte::Template::call(&Constant).unwrap(), "constant");
+    }
+}
@@ -1,5 +1,5 @@
 use std::fmt::{self, Display, Formatter};
-use yarte::{Render, Template};
+use yarte::{concat, join, lazy, Lazy, Render, Template};
 
 struct Rendered;
 
@@ -23,3 +23,99 @@ fn test_impl_render() {
     let s = RenderTemplate { rendered: Rendered };
     assert_eq!(HELLO, s.call().unwrap());
 }
+
+fn tag(name: &str) -> Lazy<impl Fn(&mut Formatter) -> fmt::Result + '_> {
+    lazy(move |f| write!(f, "<{}>", name))
+}
+
+#[derive(Template)]
+#[template(src = "{{ tag(name) }} {{{ tag(name) }}}")]
+struct LazyTemplate {
+    name: &'static str,
+}
+
+#[test]
+fn test_lazy() {
+    let t = LazyTemplate { name: "&" };
+    assert_eq!("&lt;&amp;&gt; <&>", t.call().unwrap());
+}
+
+struct Arguments;
+
+impl Display for Arguments {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        format_args!("{}<{}>", 1, "&").render(f)
+    }
+}
+
+#[test]
+fn test_arguments() {
+    assert_eq!("1&lt;&amp;&gt;", Arguments.to_string());
+}
+
+#[derive(Template)]
+#[template(src = "[{{ name }}][{{ title }}]{{#each items }}[{{ this }}]{{/each }}")]
+struct OptionTemplate {
+    name: Option<&'static str>,
+    title: Option<String>,
+    items: Vec<Option<u8>>,
+}
+
+#[test]
+fn test_option() {
+    let t = OptionTemplate {
+        name: Some("<a>"),
+        title: None,
+        items: vec![Some(1), None],
+    };
+    assert_eq!("[&lt;a&gt;][][1][]", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{ value }}")]
+struct ResultTemplate {
+    value: Result<&'static str, ()>,
+}
+
+#[test]
+fn test_result() {
+    let t = ResultTemplate { value: Ok("&") };
+    assert_eq!("&amp;", t.call().unwrap());
+
+    let t = ResultTemplate { value: Err(()) };
+    assert!(t.call().is_err());
+}
+
+#[derive(Template)]
+#[template(src = "{{ concat(tags) }} {{{ concat(tags) }}}")]
+struct ConcatTemplate {
+    tags: &'static [&'static str],
+}
+
+#[test]
+fn test_concat() {
+    let t = ConcatTemplate {
+        tags: &["<a>", "<b>"],
+    };
+    assert_eq!("&lt;a&gt;&lt;b&gt; <a><b>", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ join(tags, \", \") }} {{{ join(tags, \" & \") }}} [{{ join(empty, \", \") }}]"
+)]
+struct JoinTemplate {
+    tags: &'static [&'static str],
+    empty: &'static [usize],
+}
+
+#[test]
+fn test_join() {
+    let t = JoinTemplate {
+        tags: &["<a>", "<b>", "c"],
+        empty: &[],
+    };
+    let expected = "&lt;a&gt;, &lt;b&gt;, c <a> & <b> & c []";
+    assert_eq!(expected, t.call().unwrap());
+    assert_eq!(expected, t.to_string());
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "instrument")]
+
+use yarte::{instrument, Template};
+
+#[derive(Template)]
+#[template(src = "<ul>{{#each items }}<li>{{ this }}</li>{{/each }}</ul>{{ name }}")]
+struct ListTemplate<'a> {
+    items: Vec<&'a str>,
+    name: &'a str,
+}
+
+#[test]
+fn test_instrument() {
+    instrument::reset();
+    let t = ListTemplate {
+        items: vec!["foo", "bar"],
+        name: "a&b",
+    };
+    assert_eq!(
+        "<ul><li>foo</li><li>bar</li></ul>a&amp;b",
+        t.call().unwrap()
+    );
+
+    let report = instrument::report();
+    let region = |region: usize| {
+        report
+            .iter()
+            .find(|e| e.template == "ListTemplate" && e.region == region)
+            .map(|e| (e.bytes, e.hits))
+    };
+    // <ul>
+    assert_eq!(region(0), Some((4, 1)));
+    // each items
+    assert_eq!(region(1), Some((24, 1)));
+    // <li>
+    assert_eq!(region(2), Some((8, 2)));
+    // this
+    assert_eq!(region(3), Some((6, 2)));
+    // name
+    assert_eq!(region(6), Some((7, 1)));
+    assert_eq!(report[0].region, 1);
+    assert!(report[0].label.starts_with("each"));
+
+    instrument::reset();
+    assert!(instrument::report().is_empty());
+}
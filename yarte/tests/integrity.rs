@@ -0,0 +1,104 @@
+#![cfg(all(feature = "integrity", debug_assertions))]
+
+use std::fmt;
+
+use yarte::{integrity::Tag, Render, Template};
+
+#[derive(Template)]
+#[template(
+    src = "<ul class=\"{{ class }}\">{{#each items }}<li>{{ this }}</li>{{/each }}</ul>\
+           <script>var a = \"{{ class }}\";</script>{{{ raw }}}",
+    mode = "html"
+)]
+struct ListTemplate<'a> {
+    class: &'a str,
+    items: &'a [&'a str],
+    raw: &'a str,
+}
+
+#[test]
+fn test_integrity() {
+    let t = ListTemplate {
+        class: "\"><b>",
+        items: &["<li>a</li>", "</ul><script>"],
+        raw: "<hr>",
+    };
+    assert!(t.call().unwrap().ends_with("<hr>"));
+}
+
+/// Render without escaping, a bug of the user types
+struct Unescaped(&'static str);
+
+impl fmt::Display for Unescaped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Render for Unescaped {
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[derive(Template)]
+#[template(src = "<p>{{ text }}</p>", mode = "html-min")]
+struct UnescapedTemplate {
+    text: Unescaped,
+}
+
+#[test]
+#[should_panic(expected = "integrity of `UnescapedTemplate`: expected </p> found <b> at tag 1")]
+fn test_integrity_unescaped() {
+    let t = UnescapedTemplate {
+        text: Unescaped("<b>bold</b>"),
+    };
+    t.call().unwrap();
+}
+
+fn page_url(page: usize) -> String {
+    format!("/posts?page={}", page)
+}
+
+#[derive(Template)]
+#[template(src = "<nav>{{ @paginate current 5 url }}</nav>", mode = "html")]
+struct PaginateTemplate {
+    current: usize,
+    url: fn(usize) -> String,
+}
+
+#[test]
+fn test_integrity_builtins() {
+    let t = PaginateTemplate {
+        current: 2,
+        url: page_url,
+    };
+    assert!(t.call().unwrap().contains("<li"));
+}
+
+#[test]
+fn test_tags() {
+    assert_eq!(
+        yarte::integrity::tags("<!DOCTYPE html><a b=\"1\" a><br/></a><script>\"<i>\"</script>"),
+        vec![
+            Tag::Doctype(Some("html".into())),
+            Tag::Start {
+                name: "a".into(),
+                attrs: vec!["a".into(), "b".into()],
+                self_closing: false,
+            },
+            Tag::Start {
+                name: "br".into(),
+                attrs: vec![],
+                self_closing: true,
+            },
+            Tag::End("a".into()),
+            Tag::Start {
+                name: "script".into(),
+                attrs: vec![],
+                self_closing: false,
+            },
+            Tag::End("script".into()),
+        ]
+    );
+}
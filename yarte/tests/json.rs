@@ -0,0 +1,47 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{\"name\": \"{{ name }}\", \"age\": {{ age }}, \"admin\": {{ admin }}, \
+           \"tags\": [{{#each tags }}{{#if index0 > 0 }},{{/if }}\"{{ this }}\"{{/each }}], \
+           \"raw\": {{{ raw }}}, \"lit\": \"{{ \"a\\\"b\" }}\"}",
+    mode = "json"
+)]
+struct UserTemplate<'a> {
+    name: &'a str,
+    age: u32,
+    admin: bool,
+    tags: &'a [String],
+    raw: &'a str,
+}
+
+#[test]
+fn test_json() {
+    let tags = vec![
+        "a\"b".to_owned(),
+        "c\\d\n\u{1}".to_owned(),
+        "ñ €".to_owned(),
+    ];
+    let t = UserTemplate {
+        name: "</script> \"quoted\"\t",
+        age: 42,
+        admin: true,
+        tags: &tags,
+        raw: "{\"x\": 1}",
+    };
+    let expected = r#"{"name": "</script> \"quoted\"\t", "age": 42, "admin": true, "tags": ["a\"b","c\\d\n\u0001","ñ €"], "raw": {"x": 1}, "lit": "a\"b"}"#;
+    assert_eq!(t.call().unwrap(), expected);
+    assert_eq!(t.call_bytes().unwrap(), expected.as_bytes());
+}
+
+#[derive(Template)]
+#[template(src = "{\"total\": \"{{ total }}\"}", mode = "text")]
+struct TextTemplate {
+    total: &'static str,
+}
+
+#[test]
+fn test_call_bytes() {
+    let t = TextTemplate { total: "\"1\"" };
+    assert_eq!(t.call_bytes().unwrap(), b"{\"total\": \"\"1\"\"}");
+}
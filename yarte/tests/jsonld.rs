@@ -0,0 +1,41 @@
+#![cfg(feature = "json")]
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ @jsonld breadcrumb_list(&items) }}")]
+struct BreadcrumbTemplate {
+    items: Vec<(&'static str, &'static str)>,
+}
+
+#[test]
+fn test_breadcrumb_list() {
+    let t = BreadcrumbTemplate {
+        items: vec![
+            ("Home", "https://example.com/"),
+            ("</script>", "https://example.com/a&b"),
+        ],
+    };
+    assert_eq!(
+        "<script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\
+         \"@type\":\"BreadcrumbList\",\"itemListElement\":[\
+         {\"@type\":\"ListItem\",\"item\":\"https://example.com/\",\"name\":\"Home\",\"position\":1},\
+         {\"@type\":\"ListItem\",\"item\":\"https://example.com/a\\u0026b\",\
+         \"name\":\"\\u003c/script\\u003e\",\"position\":2}]}</script>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(src = "{{ @jsonld this }}", ext = "txt")]
+struct ValueTemplate {
+    this: Vec<usize>,
+}
+
+#[test]
+fn test_value() {
+    let t = ValueTemplate { this: vec![1, 2] };
+    assert_eq!(
+        "<script type=\"application/ld+json\">[1,2]</script>",
+        t.call().unwrap()
+    );
+}
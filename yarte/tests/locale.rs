@@ -0,0 +1,167 @@
+#![cfg(feature = "locale")]
+use yarte::{
+    builtins::{LanguageTag, Localized},
+    Template,
+};
+
+#[derive(Template)]
+#[template(
+    src = "{{ @num total }} {{ @plural count one = \"file\" few = \"files\" other = \"files\" }}"
+)]
+struct LocaleTemplate<'a> {
+    locale: &'a str,
+    total: f64,
+    count: usize,
+}
+
+impl<'a> Localized for LocaleTemplate<'a> {
+    fn locale(&self) -> &str {
+        self.locale
+    }
+}
+
+#[test]
+fn test_num() {
+    let num = |locale, total| {
+        LocaleTemplate {
+            locale,
+            total,
+            count: 0,
+        }
+        .call()
+        .unwrap()
+        .split(' ')
+        .next()
+        .unwrap()
+        .to_owned()
+    };
+    assert_eq!(num("en-US", 1234567.5), "1,234,567.5");
+    assert_eq!(num("de", -1234.0), "-1.234");
+    assert_eq!(num("es", 1234.0), "1234");
+    assert_eq!(num("es_ES", 12345.0), "12.345");
+    assert_eq!(num("fr", 1234.25), "1\u{202f}234,25");
+    assert_eq!(num("de-CH", 1234567.0), "1\u{2019}234\u{2019}567");
+    assert_eq!(num("de-Latn-CH", 1234.0), "1\u{2019}234");
+    assert_eq!(num("es-419", 12345.0), "12.345");
+    assert_eq!(num("en", std::f64::NAN), "NaN");
+}
+
+#[test]
+fn test_unknown_locale() {
+    for locale in &["xx", "fil-PH", "", "e", "en--US", "en-US-\u{e9}"] {
+        let t = LocaleTemplate {
+            locale,
+            total: 1234.0,
+            count: 1,
+        };
+        assert!(t.call().is_err(), "{}", locale);
+    }
+}
+
+#[test]
+fn test_language_tag() {
+    let tag = |language: &str, script, region| LanguageTag {
+        language: language.to_owned(),
+        script,
+        region,
+    };
+    assert_eq!(LanguageTag::parse("EN"), Some(tag("en", None, None)));
+    assert_eq!(
+        LanguageTag::parse("zh-Hant-TW"),
+        Some(tag("zh", Some("Hant"), Some("TW")))
+    );
+    assert_eq!(
+        LanguageTag::parse("pt_BR"),
+        Some(tag("pt", None, Some("BR")))
+    );
+    assert_eq!(
+        LanguageTag::parse("fil-PH"),
+        Some(tag("fil", None, Some("PH")))
+    );
+    assert_eq!(
+        LanguageTag::parse("zh-yue-HK"),
+        Some(tag("zh", None, Some("HK")))
+    );
+    assert_eq!(
+        LanguageTag::parse("es-419"),
+        Some(tag("es", None, Some("419")))
+    );
+    assert_eq!(
+        LanguageTag::parse("en-US-u-ca-gregory"),
+        Some(tag("en", None, Some("US")))
+    );
+    assert_eq!(LanguageTag::parse("en--US"), None);
+    assert_eq!(LanguageTag::parse("e"), None);
+    assert_eq!(LanguageTag::parse("en-US-toolongvariant"), None);
+    assert!(LanguageTag::parse("de-CH").unwrap().is_supported());
+    assert!(!LanguageTag::parse("fil-PH").unwrap().is_supported());
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ @plural n zero = \"zero\" one = \"one\" two = \"two\" few = \"few\" many = \"many\" other = \"other\" }}"
+)]
+struct PluralTemplate<'a, N: std::fmt::Display> {
+    locale: &'a str,
+    n: N,
+}
+
+impl<'a, N: std::fmt::Display> Localized for PluralTemplate<'a, N> {
+    fn locale(&self) -> &str {
+        self.locale
+    }
+}
+
+fn plural<N: std::fmt::Display>(locale: &str, ns: &[N]) -> Vec<String> {
+    ns.iter()
+        .map(|n| PluralTemplate { locale, n }.call().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_plural() {
+    assert_eq!(plural("en", &[0, 1, 2]), ["other", "one", "other"]);
+    assert_eq!(plural("en", &[1.5]), ["other"]);
+    assert_eq!(plural("en", &[1_000_001, 2_000_000]), ["other", "other"]);
+    assert_eq!(plural("en", &[u128::max_value()]), ["other"]);
+    assert_eq!(plural("fr", &[0, 1, 2]), ["one", "one", "other"]);
+    assert_eq!(plural("ja", &[1]), ["other"]);
+    assert_eq!(
+        plural("ru", &[1, 2, 5, 11, 21, 22, 112]),
+        ["one", "few", "many", "many", "one", "few", "many"]
+    );
+    assert_eq!(
+        plural(
+            "ru",
+            &[1_000_001, 1_000_012, 100_000_000_000_000_000_000_u128]
+        ),
+        ["one", "many", "many"]
+    );
+    assert_eq!(plural("ru", &[1.5]), ["other"]);
+    assert_eq!(plural("pl", &[1, 3, 21]), ["one", "few", "many"]);
+    assert_eq!(plural("cs", &[1, 3, 5]), ["one", "few", "other"]);
+    assert_eq!(plural("cs", &[1_000_001, 1_000_003]), ["other", "other"]);
+    assert_eq!(
+        plural("ro", &[1, 0, 19, 20, 102]),
+        ["one", "few", "few", "other", "few"]
+    );
+    assert_eq!(
+        plural("sl", &[1, 2, 3, 5, 101]),
+        ["one", "two", "few", "other", "one"]
+    );
+    assert_eq!(plural("hr", &[1, 3, 5, 21]), ["one", "few", "other", "one"]);
+    assert_eq!(
+        plural("ar", &[0, 1, 2, 105, 11, 100]),
+        ["zero", "one", "two", "few", "many", "other"]
+    );
+}
+
+#[test]
+fn test_plural_other() {
+    let t = LocaleTemplate {
+        locale: "ru",
+        total: 0.,
+        count: 3,
+    };
+    assert_eq!(t.call().unwrap(), "0 files");
+}
@@ -0,0 +1,50 @@
+use std::cell::Cell;
+
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{{ #once self.total() }} {{ #once self.total() }} {{ self.total() }}\
+           {{#each items }} {{ #once self.total() + this }}{{ #once self.total() + this }}{{/each }}"
+)]
+struct OnceTemplate {
+    calls: Cell<usize>,
+    items: Vec<usize>,
+}
+
+impl OnceTemplate {
+    fn total(&self) -> usize {
+        self.calls.set(self.calls.get() + 1);
+        self.calls.get()
+    }
+}
+
+#[test]
+fn test_once() {
+    let t = OnceTemplate {
+        calls: Cell::new(0),
+        items: vec![10, 20],
+    };
+
+    assert_eq!(t.call().unwrap(), "1 1 2 1313 2424");
+    assert_eq!(t.calls.get(), 4);
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#if show }}{{ #once name.to_uppercase() }}{{/if }}{{ #once name.to_uppercase() }}"
+)]
+struct OnceBranchTemplate {
+    show: bool,
+    name: &'static str,
+}
+
+#[test]
+fn test_once_branch() {
+    let t = OnceBranchTemplate {
+        show: false,
+        name: "yarte",
+    };
+
+    assert_eq!(t.call().unwrap(), "YARTE");
+}
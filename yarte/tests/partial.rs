@@ -273,3 +273,98 @@ fn test_with_partial_compile_cond() {
 
     assert_eq!(t.call().unwrap(), "foofoofoobarfalsefoobartruebar");
 }
+
+#[derive(Template)]
+#[template(src = "{{> cards/ title = \"a\" }}{{> cards title = \"b\" }}")]
+struct PartialIndex;
+
+#[test]
+fn test_partial_index() {
+    let t = PartialIndex;
+
+    assert_eq!(
+        t.call().unwrap(),
+        "<div class=\"card\">a</div><div class=\"card\">b</div>"
+    );
+}
+
+struct User {
+    name: &'static str,
+}
+
+#[derive(Template)]
+#[template(src = "{{#*inline \"item\"}}<li>{{ name }}{{ tag }}</li>{{/inline}}\
+           <ul>{{#each users}}{{> item tag = \"!\" }}{{/each}}</ul>")]
+struct PartialInline {
+    users: Vec<User>,
+}
+
+#[test]
+fn test_partial_inline() {
+    let t = PartialInline {
+        users: vec![User { name: "foo" }, User { name: "bar" }],
+    };
+
+    assert_eq!(t.call().unwrap(), "<ul><li>foo!</li><li>bar!</li></ul>");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#> block-layout title = \"a\" }}{{ name }}{{/block-layout }}\
+           {{#> block-missing }}{{ name }}{{/block-missing }}",
+    ext = "txt"
+)]
+struct PartialBlock<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_partial_block() {
+    let t = PartialBlock { name: "foo" };
+
+    assert_eq!(t.call().unwrap(), "a[foo]foo");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#*inline \"outer\"}}({{> @partial-block }}){{/inline}}\
+           {{#> outer }}{{#> block-layout title = \"b\" }}{{ name }}{{/block-layout }}{{/outer }}",
+    ext = "txt"
+)]
+struct PartialBlockNested<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_partial_block_nested() {
+    let t = PartialBlockNested { name: "foo" };
+
+    assert_eq!(t.call().unwrap(), "(b[foo])");
+}
+
+struct Node {
+    name: &'static str,
+    children: Vec<Node>,
+}
+
+#[derive(Template)]
+#[template(path = "tree.hbs")]
+struct PartialRecursive {
+    name: &'static str,
+    children: Vec<Node>,
+}
+
+#[test]
+fn test_partial_recursive() {
+    let node = |name, children| Node { name, children };
+    let t = PartialRecursive {
+        name: "a",
+        children: vec![
+            node("b", vec![node("c", vec![node("d", vec![])])]),
+            node("e", vec![]),
+        ],
+    };
+
+    // `recursion = 2` of `[limits]`, `d` is past the limit
+    assert_eq!(t.call().unwrap(), "a(b(c()))(e)");
+}
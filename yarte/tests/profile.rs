@@ -0,0 +1,68 @@
+#![cfg(all(feature = "profile", debug_assertions))]
+
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{{#if admin }}admin{{ else }}user{{/if }}\n\
+           {{#each items }}{{ this }}{{/each }}\n\
+           {{> hello }}",
+    ext = "txt"
+)]
+struct ProfileTemplate<'a> {
+    admin: bool,
+    items: Vec<&'a str>,
+    name: &'a str,
+}
+
+#[test]
+fn test_last_render_profile() {
+    let mut t = ProfileTemplate {
+        admin: false,
+        items: vec!["a", "b", "c"],
+        name: "world",
+    };
+    t.call().unwrap();
+
+    let report: Vec<_> = ProfileTemplate::last_render_profile()
+        .into_iter()
+        .map(|x| (x.label, x.calls))
+        .collect();
+    assert_eq!(
+        report,
+        vec![
+            ("else at templates/ProfileTemplate.txt:1", 1),
+            ("each at templates/ProfileTemplate.txt:2", 3),
+            ("partial `hello` at templates/ProfileTemplate.txt:3", 1),
+        ]
+    );
+
+    t.items.clear();
+    t.call().unwrap();
+    let calls: Vec<_> = ProfileTemplate::last_render_profile()
+        .into_iter()
+        .map(|x| x.calls)
+        .collect();
+    assert_eq!(calls, vec![1, 1]);
+}
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each items }}<li>{{ this }}</li>{{/each }}</ul>",
+    ext = "html"
+)]
+struct ProfileHtmlTemplate {
+    items: Vec<usize>,
+}
+
+#[test]
+fn test_html() {
+    let t = ProfileHtmlTemplate { items: vec![1, 2] };
+    assert_eq!(t.call().unwrap(), "<ul><li>1</li><li>2</li></ul>");
+
+    let calls: Vec<_> = ProfileHtmlTemplate::last_render_profile()
+        .into_iter()
+        .map(|x| x.calls)
+        .collect();
+    assert_eq!(calls, vec![2]);
+}
@@ -0,0 +1,46 @@
+use yarte::{OnceRender, Template};
+
+#[derive(Template)]
+#[template(
+    src = "{{ title }}:{{#each items }} {{ this }}{{/each }}{{#each lines }} {{ this }}{{/each }}",
+    ext = "txt",
+    once = true
+)]
+struct OnceTemplate<I: Iterator<Item = usize>> {
+    title: String,
+    items: I,
+    lines: Vec<String>,
+}
+
+#[test]
+fn test_render_once() {
+    let t = OnceTemplate {
+        title: "squares".into(),
+        items: (1..4).map(|x| x * x),
+        lines: vec!["a".into(), "b".into()],
+    };
+
+    assert_eq!(t.render_once().unwrap(), "squares: 1 4 9 a b");
+}
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each items }}<li>{{ this }}</li>{{/each }}</ul>",
+    ext = "html",
+    once = true
+)]
+struct OnceHtmlTemplate {
+    items: std::vec::IntoIter<&'static str>,
+}
+
+#[test]
+fn test_render_once_html() {
+    let t = OnceHtmlTemplate {
+        items: vec!["<a>", "b"].into_iter(),
+    };
+
+    assert_eq!(
+        t.render_once().unwrap(),
+        "<ul><li>&lt;a&gt;</li><li>b</li></ul>"
+    );
+}
@@ -0,0 +1,59 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    html = "representations/list.hbs",
+    json = "representations/list.json.hbs",
+    text = "representations/list.txt"
+)]
+struct List<'a> {
+    title: &'a str,
+    items: &'a [&'a str],
+}
+
+const LIST: List = List {
+    title: "Fruits",
+    items: &["apple", "<pear>"],
+};
+
+#[test]
+fn test_representations() {
+    let html = "<ul><li>apple</li><li>&lt;pear&gt;</li></ul>";
+    let json = r#"{"title": "Fruits", "items": ["apple", "<pear>"]}"#;
+    let text = "Fruits: - apple - <pear>";
+    assert_eq!(LIST.call().unwrap(), html);
+    assert_eq!(LIST.render_html().unwrap(), html);
+    assert_eq!(LIST.render_json().unwrap(), json);
+    assert_eq!(LIST.render_text().unwrap(), text);
+}
+
+#[test]
+fn test_render_for() {
+    let render = |accept| {
+        LIST.render_for(accept)
+            .map(|(mime, body)| (mime, body.unwrap()))
+    };
+    assert_eq!(
+        render("application/json").unwrap().0,
+        "application/json; charset=utf-8"
+    );
+    assert_eq!(
+        render("text/html,application/xhtml+xml,*/*;q=0.8")
+            .unwrap()
+            .0,
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(
+        render("text/*;q=0.5, text/plain, application/json;q=0.4").unwrap(),
+        (
+            "text/plain; charset=utf-8",
+            "Fruits: - apple - <pear>".to_owned()
+        )
+    );
+    assert_eq!(render("").unwrap().0, "text/html; charset=utf-8");
+    assert_eq!(
+        render("*/*;q=0.2, text/html;q=0").unwrap().0,
+        "application/json; charset=utf-8"
+    );
+    assert!(render("image/png").is_none());
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "sanitize")]
+
+use yarte::{sanitize, Policy, Template};
+
+#[test]
+fn test_sanitize() {
+    let basic = Policy::basic();
+    assert_eq!(
+        sanitize("<p onclick=\"x()\">Hi <b>all</b><br/>", &basic),
+        "<p>Hi <b>all</b><br></p>"
+    );
+    assert_eq!(
+        sanitize(
+            "a<script>alert('<b>')</script>b<style>p {}</style>c",
+            &basic
+        ),
+        "abc"
+    );
+    assert_eq!(
+        sanitize("<div><span>text</span></div></em> 1 < 2", &basic),
+        "text 1 &lt; 2"
+    );
+    assert_eq!(
+        sanitize("<ul><li>a<li>b</ul></p>", &basic),
+        "<ul><li>a<li>b</li></li></ul>"
+    );
+    assert_eq!(
+        sanitize(
+            "<template><b>x</b><template>y</template>z</template>w",
+            &basic
+        ),
+        "w"
+    );
+}
+
+#[test]
+fn test_sanitize_urls() {
+    let basic = Policy::basic();
+    assert_eq!(
+        sanitize(
+            "<a href=\"https://example.com/?a=1&amp;b\" title='\"t\"'>x</a>",
+            &basic
+        ),
+        "<a href=\"https:&#x2f;&#x2f;example.com&#x2f;?a=1&amp;b\" title=\"&quot;t&quot;\">x</a>"
+    );
+    assert_eq!(
+        sanitize("<a href=\" java\tscript:alert(1)\">x</a>", &basic),
+        "<a>x</a>"
+    );
+    assert_eq!(
+        sanitize("<img src=\"data:image/png;base64,AA\" alt=\"a\">", &basic),
+        "<img alt=\"a\">"
+    );
+    assert_eq!(
+        sanitize("<a href=\"/users?next=a:b\">x</a>", &basic),
+        "<a href=\"&#x2f;users?next=a:b\">x</a>"
+    );
+}
+
+#[test]
+fn test_sanitize_policy() {
+    assert_eq!(
+        sanitize("<b>a</b> &amp; <i>b</i>", &Policy::text()),
+        "a &amp; b"
+    );
+
+    let policy = Policy::text()
+        .allow_tag("a", &["href", "rel"])
+        .allow_scheme("tel");
+    assert_eq!(
+        sanitize(
+            "<a href=\"tel:+1\" rel=\"nofollow\" class=\"x\">call</a>",
+            &policy
+        ),
+        "<a href=\"tel:+1\" rel=\"nofollow\">call</a>"
+    );
+    assert_eq!(
+        sanitize("<a href=\"https://example.com\">x</a>", &policy),
+        "<a>x</a>"
+    );
+}
+
+#[derive(Template)]
+#[template(src = "<div class=\"comment\">{{{ sanitize(body, &Policy::basic()) }}}</div>")]
+struct CommentTemplate<'a> {
+    body: &'a str,
+}
+
+#[test]
+fn test_sanitize_template() {
+    let t = CommentTemplate {
+        body: "<p>Hi<img src=x onerror=alert(1)>",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<div class=\"comment\"><p>Hi<img src=\"x\"></p></div>"
+    );
+}
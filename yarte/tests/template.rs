@@ -75,3 +75,193 @@ fn test_whit_simple_iterator() {
         t.call().unwrap()
     );
 }
+
+#[derive(Template)]
+#[template(src = "<span class=\"badge\">{{ count }}</span>", ext = "html")]
+struct Badge {
+    count: usize,
+}
+
+#[test]
+fn test_write_to() {
+    let mut buf = [0; 32];
+    assert_eq!(
+        Badge { count: 42 }.write_to(&mut buf).unwrap(),
+        "<span class=\"badge\">42</span>"
+    );
+
+    let mut buf = [0; 16];
+    assert!(Badge { count: 42 }.write_to(&mut buf).is_err());
+}
+
+#[derive(Template)]
+#[template(src = "<span class=\"badge\">new</span>", mode = "html")]
+struct ConstBadge;
+
+#[test]
+fn test_call_cow() {
+    use std::borrow::Cow;
+
+    match ConstBadge.call_cow().unwrap() {
+        Cow::Borrowed(s) => assert_eq!(s, "<span class=\"badge\">new</span>"),
+        Cow::Owned(_) => panic!("constant template allocated"),
+    }
+    match (Badge { count: 1 }).call_cow().unwrap() {
+        Cow::Owned(s) => assert_eq!(s, "<span class=\"badge\">1</span>"),
+        Cow::Borrowed(_) => panic!("borrowed dynamic template"),
+    }
+}
+
+#[test]
+fn test_render_display() {
+    use std::fmt::Write;
+
+    let badge = Badge { count: 7 };
+    assert_eq!(
+        format!("[{}]", badge.render_display()),
+        "[<span class=\"badge\">7</span>]"
+    );
+    let mut buf = String::new();
+    write!(buf, "{}", ConstBadge.render_display()).unwrap();
+    assert_eq!(buf, "<span class=\"badge\">new</span>");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each rows }}<tr><td>{{ this }}</td></tr>{{/each }}",
+    ext = "html"
+)]
+struct Rows {
+    rows: Vec<usize>,
+}
+
+/// Writer keeping the size of each write
+struct Chunks(Vec<usize>, Vec<u8>);
+
+impl std::io::Write for Chunks {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if 1 << 16 < self.1.len() {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "full"));
+        }
+        self.0.push(buf.len());
+        self.1.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_render_into() {
+    let t = Rows {
+        rows: (0..1000).collect(),
+    };
+    let mut w = Chunks(vec![], vec![]);
+    t.render_into(&mut w).unwrap();
+    assert_eq!(w.1, t.call_bytes().unwrap());
+    assert!(2 < w.0.len());
+    assert!(w.0.iter().all(|n| *n < 8 * 1024 + 64));
+
+    let t = Rows {
+        rows: (0..10_000).collect(),
+    };
+    match t.render_into(Chunks(vec![], vec![])).unwrap_err().kind() {
+        yarte::RenderError::Io(e) => assert_eq!(e.to_string(), "full"),
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+/// Async writer keeping the size of each write, pending at every other poll
+#[cfg(feature = "async")]
+struct AsyncChunks(Vec<usize>, Vec<u8>, bool);
+
+#[cfg(feature = "async")]
+impl futures_io::AsyncWrite for AsyncChunks {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.2 = !self.2;
+        if self.2 {
+            cx.waker().wake_by_ref();
+            return std::task::Poll::Pending;
+        }
+        if 1 << 16 < self.1.len() {
+            let e = std::io::Error::new(std::io::ErrorKind::Other, "full");
+            return std::task::Poll::Ready(Err(e));
+        }
+        self.0.push(buf.len());
+        self.1.extend_from_slice(buf);
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_render_into_async() {
+    use futures_executor::block_on;
+
+    let t = Rows {
+        rows: (0..1000).collect(),
+    };
+    let mut w = AsyncChunks(vec![], vec![], false);
+    block_on(t.render_into_async(&mut w)).unwrap();
+    assert_eq!(w.1, t.call_bytes().unwrap());
+    assert!(2 < w.0.len());
+    assert!(w.0.iter().all(|n| *n <= 8 * 1024));
+
+    let t = Rows {
+        rows: (0..10_000).collect(),
+    };
+    let w = AsyncChunks(vec![], vec![], false);
+    match block_on(t.render_into_async(w)).unwrap_err().kind() {
+        yarte::RenderError::Io(e) => assert_eq!(e.to_string(), "full"),
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{ a? }}")]
+struct TryRender {
+    a: Result<usize, std::fmt::Error>,
+}
+
+#[test]
+fn test_try_render() {
+    let t = TryRender { a: Ok(1) };
+    assert_eq!(t.try_render().unwrap(), "1");
+
+    let t = TryRender {
+        a: Err(std::fmt::Error),
+    };
+    assert_eq!(t.call(), Err(yarte::Error));
+    let e = t.try_render().unwrap_err();
+    assert!(matches_fmt(e.kind()));
+    if cfg!(debug_assertions) {
+        assert!(e.context().unwrap().template.ends_with("TryRender"));
+    }
+}
+
+fn matches_fmt(e: &yarte::RenderError) -> bool {
+    match e {
+        yarte::RenderError::Fmt => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,6 @@
+#![cfg(feature = "testsuite")]
+
+#[test]
+fn test_golden() {
+    assert_eq!(yarte::testsuite::run("tests/golden"), 5);
+}
@@ -0,0 +1,17 @@
+//! Compile errors of the templates, the rendered diagnostics are part of the public api
+//!
+//! The diagnostics change between compilers, so the expected output is of the pinned
+//! toolchain of CI, 1.95.0, and they only run with `YARTE_UI_TESTS=1`. Regenerate it with
+//! `YARTE_UI_TESTS=1 TRYBUILD=overwrite cargo +1.95.0 test --test ui`
+#![cfg(not(target_arch = "wasm32"))]
+
+#[test]
+fn ui() {
+    if std::env::var_os("YARTE_UI_TESTS").map_or(true, |x| x != "1") {
+        return;
+    }
+    // Diagnostics without colors
+    std::env::set_var("NO_COLOR", "1");
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ a + }}")]
+struct BadExpression {
+    a: usize,
+}
+
+fn main() {}
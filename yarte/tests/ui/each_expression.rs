@@ -0,0 +1,10 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#each a + b }}{{ this }}{{/each }}")]
+struct EachExpression {
+    a: usize,
+    b: usize,
+}
+
+fn main() {}
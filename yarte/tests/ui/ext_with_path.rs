@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(path = "hello.hbs", ext = "txt")]
+struct ExtWithPath<'a> {
+    name: &'a str,
+}
+
+fn main() {}
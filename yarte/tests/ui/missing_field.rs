@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "Hello, {{ nme }}!")]
+struct MissingField<'a> {
+    name: &'a str,
+}
+
+fn main() {}
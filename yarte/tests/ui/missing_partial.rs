@@ -0,0 +1,7 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{> missing }}")]
+struct MissingPartial;
+
+fn main() {}
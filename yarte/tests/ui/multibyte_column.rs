@@ -0,0 +1,10 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<p>¡Hola 😀!</p>\n<p>中文 {{#each a + b }}{{ this }}{{/each }}</p>")]
+struct MultibyteColumn {
+    a: usize,
+    b: usize,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ async { name } }}")]
+struct NotAvailableExpression<'a> {
+    name: &'a str,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<p>{{ name }}</p>", mode = "html", scoped = "true")]
+struct NotBooleanAttribute<'a> {
+    name: &'a str,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{> @partial-block }}")]
+struct PartialBlockOut;
+
+fn main() {}
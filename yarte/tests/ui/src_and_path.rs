@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ name }}", path = "hello.hbs")]
+struct SrcAndPath<'a> {
+    name: &'a str,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#if cond }}<p>{{ name }}</p>")]
+struct UnclosedBlock<'a> {
+    cond: bool,
+    name: &'a str,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ name }}", escaping = "none")]
+struct UnknownAttribute<'a> {
+    name: &'a str,
+}
+
+fn main() {}
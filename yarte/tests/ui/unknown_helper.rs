@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#foo bar }}{{/foo }}")]
+struct UnknownHelper {
+    bar: bool,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ name }}", mode = "xml")]
+struct UnknownMode<'a> {
+    name: &'a str,
+}
+
+fn main() {}
@@ -21,6 +21,29 @@ fn test_unless() {
     );
 }
 
+#[derive(Template)]
+#[template(
+    src = "{{#unless a }}A{{else if b }}B{{else }}C{{/unless }}\
+           {{#unless true }}D{{else }}E{{/unless }}",
+    ext = "txt"
+)]
+struct UnlessElseTemplate {
+    a: bool,
+    b: bool,
+}
+
+#[test]
+fn test_unless_else() {
+    let t = UnlessElseTemplate { a: false, b: true };
+    assert_eq!("AE", t.call().unwrap());
+
+    let t = UnlessElseTemplate { a: true, b: true };
+    assert_eq!("BE", t.call().unwrap());
+
+    let t = UnlessElseTemplate { a: true, b: false };
+    assert_eq!("CE", t.call().unwrap());
+}
+
 #[derive(Template)]
 #[template(path = "people-template.hbs")]
 struct PeopleTemplate<'a> {
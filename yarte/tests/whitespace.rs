@@ -0,0 +1,70 @@
+//! Golden tests of the whitespace of each mode, see `yarte_hir::whitespace`
+use yarte::Template;
+
+macro_rules! golden {
+    ($($name:ident $src:literal => $text:literal $html:literal $html_min:literal;)*) => {
+        $(
+            // Not every template writes `b`
+            #[allow(non_snake_case, dead_code)]
+            mod $name {
+                use super::*;
+
+                #[derive(Template)]
+                #[template(src = $src, ext = "txt")]
+                struct Text {
+                    b: &'static str,
+                }
+
+                #[derive(Template)]
+                #[template(src = $src, mode = "html")]
+                struct Html {
+                    b: &'static str,
+                }
+
+                #[derive(Template)]
+                #[template(src = $src, ext = "html")]
+                struct HtmlMin {
+                    b: &'static str,
+                }
+
+                #[test]
+                fn test() {
+                    let b = "b";
+                    assert_eq!(Text { b }.call().unwrap(), $text, "text");
+                    assert_eq!(Html { b }.call().unwrap(), $html, "html");
+                    assert_eq!(HtmlMin { b }.call().unwrap(), $html_min, "html-min");
+                }
+            }
+        )*
+    };
+}
+
+golden! {
+    expr "a {{ b }} c" => "a b c" "a b c" "a b c";
+    expr_left "a {{~ b }} c" => "ab c" "ab c" "ab c";
+    expr_right "a {{ b ~}} c" => "a bc" "a bc" "a bc";
+    expr_both "a {{~ b ~}} c" => "abc" "abc" "abc";
+    lines "<p>\n  {{ b }}\n</p>" => "<p>\n  b\n</p>" "<p>\n  b\n</p>" "<p>b</p>";
+    inline "<p><i>a</i> {{ b }} <i>c</i></p>" =>
+        "<p><i>a</i> b <i>c</i></p>"
+        "<p><i>a</i> b <i>c</i></p>"
+        "<p><i>a</i> b <i>c</i></p>";
+    inline_trim "<p><i>a</i> {{~ b ~}} <i>c</i></p>" =>
+        "<p><i>a</i>b<i>c</i></p>"
+        "<p><i>a</i>b<i>c</i></p>"
+        "<p><i>a</i>b<i>c</i></p>";
+    block "<p> {{# if true }} x {{/if }} </p>" => "<p>  x  </p>" "<p>  x  </p>" "<p>x</p>";
+    block_trim "<p> {{~# if true ~}} x {{~/if ~}} </p>" => "<p>x</p>" "<p>x</p>" "<p>x</p>";
+    comment "a {{! c !}} {{ b }}" => "ab" "ab" "ab";
+    raw "a {{R}} {{ x }} {{/R}} b" => "a  {{ x }}  b" "a  {{ x }}  b" "a  {{ x }}  b";
+    blank "{{ b }}\n\n{{ b }}" => "b\n\nb" "b\n\nb" "b\n\nb";
+    pre "<pre>\n  {{ b }}  </pre>" =>
+        "<pre>\n  b  </pre>"
+        "<pre>\n  b  </pre>"
+        "<pre>\n  b  </pre>";
+    nbsp "a\u{a0}{{~ b ~}}\u{a0}c" => "a\u{a0}b\u{a0}c" "a\u{a0}b\u{a0}c" "a&nbsp;b&nbsp;c";
+    nbsp_entity "<p>&nbsp;{{ b }}&nbsp;</p>" =>
+        "<p>&nbsp;b&nbsp;</p>"
+        "<p>&nbsp;b&nbsp;</p>"
+        "<p>&nbsp;b&nbsp;</p>";
+}
@@ -42,3 +42,22 @@ fn test_with_each() {
     };
     assert_eq!("0 1", hello.call().unwrap());
 }
+
+#[derive(Template)]
+#[template(
+    src = "{{#with hold as |h| }}{{ foo }} {{ h.bar }}{{#each super::items }} {{ h.foo }}{{ this }}{{/each }}{{/with }}",
+    ext = "txt"
+)]
+struct WithParamTemplate {
+    hold: Holder,
+    items: Vec<usize>,
+}
+
+#[test]
+fn test_with_param() {
+    let t = WithParamTemplate {
+        hold: Holder { foo: 0, bar: 1 },
+        items: vec![2, 3],
+    };
+    assert_eq!("0 1 02 03", t.call().unwrap());
+}
@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+
+use syn::{Expr, Local};
+
+use crate::{Const, Helper};
+
+/// Arena of the helpers, constants, expressions and locals of the nodes of templates
+///
+/// The nodes are allocated in chunks, instead of a box each, and live as long as the
+/// arena, so it's created before the templates are parsed and kept with their sources.
+///
+/// ```ignore
+/// let arena = Arena::new();
+/// let nodes = parse(get_cursor(&path, &src), &arena);
+/// ```
+#[derive(Default)]
+pub struct Arena<'a> {
+    helpers: Chunks<Helper<'a>>,
+    consts: Chunks<Const>,
+    exprs: Chunks<Expr>,
+    locals: Chunks<Local>,
+}
+
+impl<'a> Arena<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn helper(&self, helper: Helper<'a>) -> &Helper<'a> {
+        self.helpers.alloc(helper)
+    }
+
+    pub fn constant(&self, constant: Const) -> &Const {
+        self.consts.alloc(constant)
+    }
+
+    pub fn expr(&self, expr: Expr) -> &Expr {
+        self.exprs.alloc(expr)
+    }
+
+    pub fn local(&self, local: Local) -> &Local {
+        self.locals.alloc(local)
+    }
+}
+
+/// Capacity of the first chunk, the next ones double it up to `MAX_CHUNK`
+const CHUNK: usize = 16;
+const MAX_CHUNK: usize = 1024;
+
+struct Chunks<T>(RefCell<Vec<Vec<T>>>);
+
+impl<T> Default for Chunks<T> {
+    fn default() -> Self {
+        Chunks(RefCell::new(vec![]))
+    }
+}
+
+impl<T> Chunks<T> {
+    fn alloc(&self, value: T) -> &T {
+        let mut chunks = self.0.borrow_mut();
+        let next = match chunks.last() {
+            Some(chunk) if chunk.len() < chunk.capacity() => None,
+            Some(chunk) => Some((chunk.capacity() * 2).min(MAX_CHUNK)),
+            None => Some(CHUNK),
+        };
+        if let Some(capacity) = next {
+            chunks.push(Vec::with_capacity(capacity));
+        }
+        let chunk = chunks.last_mut().expect("some chunk");
+        chunk.push(value);
+        let value: *const T = chunk.last().expect("some value");
+
+        // Safety: a chunk is never pushed over its capacity, so it doesn't reallocate, and
+        // the chunks are only dropped with the arena, so the value isn't moved or dropped
+        // while `self` is borrowed
+        unsafe { &*value }
+    }
+}
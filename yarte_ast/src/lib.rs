@@ -0,0 +1,110 @@
+//! Ast of the yarte templates
+//!
+//! The data model of the templates without the parser, for the formatters, linters and
+//! back-ends working on templates parsed by `yarte_parser`, that re-exports it. Expressions
+//! are `syn` expressions, only with its data structures, and the nodes are spanned in the
+//! source map of the thread of the parser. The helpers, constants, expressions and locals of
+//! the nodes are references into an `Arena` of the templates.
+use syn::{Expr, Ident, Local, Type};
+
+mod arena;
+pub mod source_map;
+
+pub use crate::arena::Arena;
+use crate::source_map::S;
+
+/// Partial of the block of the partial block being rendered
+pub const PARTIAL_BLOCK: &str = "@partial-block";
+
+pub type Ws = (bool, bool);
+
+pub type SExpr<'a> = S<&'a Expr>;
+pub type SLocal<'a> = S<&'a Local>;
+pub type SNode<'a> = S<Node<'a>>;
+pub type SStr<'a> = S<&'a str>;
+pub type SArgs = S<Vec<SArg>>;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Partial<'a>(pub Ws, pub SStr<'a>, pub SArgs);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Node<'a> {
+    Comment(&'a str),
+    Const(&'a Const),
+    Expr(Ws, SExpr<'a>),
+    Helper(&'a Helper<'a>),
+    Inline((Ws, Ws), SStr<'a>, Vec<SNode<'a>>),
+    Keep(&'a str),
+    Lit(&'a str, SStr<'a>, &'a str),
+    Local(SLocal<'a>),
+    Partial(Partial<'a>),
+    PartialBlock((Ws, Ws), SStr<'a>, SArgs, Vec<SNode<'a>>),
+    Raw((Ws, Ws), &'a str, SStr<'a>, &'a str),
+    Safe(Ws, SExpr<'a>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Helper<'a> {
+    Each(
+        (Ws, Ws),
+        SExpr<'a>,
+        Vec<Modifier>,
+        BlockParams,
+        Vec<SNode<'a>>,
+        Option<(Ws, Option<Ident>, Vec<SNode<'a>>)>,
+    ),
+    If(
+        ((Ws, Ws), SExpr<'a>, Vec<SNode<'a>>),
+        Vec<(Ws, SExpr<'a>, Vec<SNode<'a>>)>,
+        Option<(Ws, Vec<SNode<'a>>)>,
+    ),
+    With((Ws, Ws), SExpr<'a>, BlockParams, Vec<SNode<'a>>),
+    /// Block rendered by the wasm client in the element of its selector
+    Portal((Ws, Ws), SExpr<'a>, BlockParams, Vec<SNode<'a>>),
+    Unless(
+        ((Ws, Ws), SExpr<'a>, Vec<SNode<'a>>),
+        Vec<(Ws, SExpr<'a>, Vec<SNode<'a>>)>,
+        Option<(Ws, Vec<SNode<'a>>)>,
+    ),
+    // TODO:
+    Defined((Ws, Ws), &'a str, SExpr<'a>, BlockParams, Vec<SNode<'a>>),
+}
+
+/// Block parameters of a block helper `as |item index|`
+///
+/// The names of the item separated by commas destructure it as a tuple, `as |a, b|`,
+/// and the name after them separated by whitespace is the index, `as |a, b i|`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BlockParams {
+    pub item: Vec<Ident>,
+    pub index: Option<Ident>,
+}
+
+impl BlockParams {
+    pub fn is_empty(&self) -> bool {
+        self.item.is_empty()
+    }
+}
+
+pub type SArg = S<Arg>;
+
+/// Argument of a partial `expr` or `name = expr`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Arg {
+    Positional(Expr),
+    Named(Ident, Expr),
+}
+
+/// Constant of a template `{{! const NAME: Type = expr !}}`
+///
+/// The type can be left out when the value is a literal.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Const {
+    pub ident: Ident,
+    pub ty: Option<Type>,
+    pub expr: Expr,
+}
+
+/// Each helper modifier `name=expr` or `name`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Modifier(pub Ident, pub Option<Expr>);
@@ -0,0 +1,459 @@
+//! Adapted from [`proc-macro2`](https://github.com/alexcrichton/proc-macro2).
+// TODO: Remove
+#![allow(dead_code)]
+
+use std::{
+    cell::RefCell,
+    cmp,
+    convert::TryFrom,
+    fmt::{self, Debug},
+    path::{Path, PathBuf},
+};
+
+/// Offset in the source map of all templates of a thread
+///
+/// Offsets of a template are checked when it's added to the source map,
+/// so they can't wrap in the spans of its nodes.
+pub type BytePos = u32;
+
+thread_local! {
+    static SOURCE_MAP: RefCell<SourceMap> = RefCell::new(SourceMap {
+        files: vec![],
+    });
+}
+
+/// Add file to source map and return its span
+///
+/// Use in the same thread
+pub fn add_file(p: &Path, src: &str) -> Span {
+    SOURCE_MAP.with(|x| x.borrow_mut().add_file(p, src))
+}
+
+/// Reinitialize source map instance when run multiple times in the same thread
+///
+/// Use in the same thread
+pub fn clean() {
+    SOURCE_MAP.with(|x| *x.borrow_mut() = SourceMap { files: vec![] });
+}
+
+/// Line, starting at 1, and column, starting at 0, of a position in a source
+///
+/// The column is counted in chars
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Mapping of the byte offsets of a source to lines and columns
+///
+/// Lines end at `\n`, so the `\r` of a `\r\n` is the last column of its line, and a
+/// multi-byte char is a column. Shared by the error messages and the tools working on
+/// templates, independent of the source map of the derive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineIndex {
+    /// Offset of the start of each line
+    lines: Vec<usize>,
+    /// Offset and length of each multi-byte char
+    multibyte: Vec<(usize, usize)>,
+    /// Length of the source in bytes
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(src: &str) -> LineIndex {
+        let mut lines = vec![0];
+        let mut multibyte = vec![];
+        for (i, c) in src.char_indices() {
+            if c == '\n' {
+                lines.push(i + 1);
+            } else if c.len_utf8() > 1 {
+                multibyte.push((i, c.len_utf8()));
+            }
+        }
+
+        LineIndex {
+            lines,
+            multibyte,
+            len: src.len(),
+        }
+    }
+
+    /// Line and column of the byte `offset`
+    ///
+    /// An offset inside a multi-byte char is at the column of the char.
+    /// Panics when the offset is out of the source, its end included.
+    pub fn line_column(&self, offset: usize) -> LineColumn {
+        assert!(
+            offset <= self.len,
+            "offset {} out of a source of {} bytes",
+            offset,
+            self.len
+        );
+        let line = match self.lines.binary_search(&offset) {
+            Ok(found) => found,
+            Err(idx) => idx - 1,
+        };
+        let start = self.lines[line];
+        let wide: usize = self
+            .multibyte_in(start, offset)
+            .iter()
+            .map(|(i, len)| cmp::min(len - 1, offset - i))
+            .sum();
+
+        LineColumn {
+            line: line + 1,
+            column: offset - start - wide,
+        }
+    }
+
+    /// Byte offset of a line and column, `None` out of the source
+    ///
+    /// The column after the last char of a line is its end, before the `\n`.
+    pub fn offset(&self, lc: LineColumn) -> Option<usize> {
+        let start = self.line_start(lc.line)?;
+        let end = self.lines.get(lc.line).map_or(self.len, |next| next - 1);
+
+        let mut offset = start;
+        let mut column = 0;
+        for (i, len) in self.multibyte_in(start, end) {
+            if column + (i - offset) >= lc.column {
+                break;
+            }
+            column += i - offset + 1;
+            offset = i + len;
+        }
+        offset += lc.column - column;
+
+        if offset <= end {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
+    /// Byte offset of the start of `line`, starting at 1
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        line.checked_sub(1).and_then(|i| self.lines.get(i)).copied()
+    }
+
+    /// Number of lines, a source without `\n` has one
+    pub fn lines(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Multi-byte chars starting in `lo..hi`
+    fn multibyte_in(&self, lo: usize, hi: usize) -> &[(usize, usize)] {
+        let position = |offset| match self.multibyte.binary_search_by_key(&offset, |(i, _)| *i) {
+            Ok(i) | Err(i) => i,
+        };
+        &self.multibyte[position(lo)..position(hi)]
+    }
+}
+
+struct FileInfo {
+    name: PathBuf,
+    span: Span,
+    index: LineIndex,
+}
+
+impl FileInfo {
+    fn offset_line_column(&self, offset: usize) -> LineColumn {
+        assert!(self.span_within(Span {
+            lo: offset as BytePos,
+            hi: offset as BytePos,
+        }));
+        self.index.line_column(offset - self.span.lo as usize)
+    }
+
+    fn get_init_line(&self, lc: LineColumn) -> Option<usize> {
+        assert_ne!(lc.line, 0);
+        self.index.line_start(lc.line)
+    }
+
+    fn span_within(&self, span: Span) -> bool {
+        span.lo >= self.span.lo && span.hi <= self.span.hi
+    }
+}
+
+struct SourceMap {
+    files: Vec<FileInfo>,
+}
+
+impl SourceMap {
+    fn next_start_pos(&self) -> BytePos {
+        // Add 1 so there's always space between files.
+        //
+        self.files.last().map(|f| f.span.hi + 1).unwrap_or(0)
+    }
+
+    fn add_file(&mut self, name: &Path, src: &str) -> Span {
+        let index = LineIndex::new(src);
+        let lo = self.next_start_pos();
+        let hi = end_pos(lo, src.len()).unwrap_or_else(|| {
+            panic!(
+                "Template too large: `{}` with {} bytes exceeds the {} bytes of the source map \
+                 of all the templates in a derive",
+                name.display(),
+                src.len(),
+                BytePos::max_value()
+            )
+        });
+        let span = Span { lo, hi };
+
+        self.files.push(FileInfo {
+            name: name.to_owned(),
+            span,
+            index,
+        });
+
+        span
+    }
+
+    fn fileinfo(&self, span: Span) -> &FileInfo {
+        for file in &self.files {
+            if file.span_within(span) {
+                return file;
+            }
+        }
+        panic!("Invalid span with no related FileInfo!");
+    }
+}
+
+/// End of `len` bytes from `lo`, leaving space for the start of the next file
+fn end_pos(lo: BytePos, len: usize) -> Option<BytePos> {
+    BytePos::try_from(len)
+        .ok()
+        .and_then(|len| lo.checked_add(len))
+        .filter(|hi| hi.checked_add(1).is_some())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Span {
+    pub lo: BytePos,
+    pub hi: BytePos,
+}
+
+// Don't allow `Span` to transfer between thread
+//impl !Send for Span {}
+//impl !Sync for Span {}
+
+impl Span {
+    pub fn range_in_line(self) -> (usize, usize) {
+        SOURCE_MAP.with(|cm| {
+            let cm = cm.borrow();
+            let fi = cm.fileinfo(self);
+            let lc = fi.offset_line_column(self.lo as usize);
+            let init = fi.get_init_line(lc).expect("Spam in source map");
+            let lo = fi.span.lo;
+            (init, (self.hi - lo) as usize)
+        })
+    }
+
+    pub fn file_path(self) -> PathBuf {
+        SOURCE_MAP.with(|cm| {
+            let cm = cm.borrow();
+            let fi = cm.fileinfo(self);
+            fi.name.clone()
+        })
+    }
+
+    pub fn start(self) -> LineColumn {
+        SOURCE_MAP.with(|cm| {
+            let cm = cm.borrow();
+            let fi = cm.fileinfo(self);
+            fi.offset_line_column(self.lo as usize)
+        })
+    }
+
+    pub fn end(self) -> LineColumn {
+        SOURCE_MAP.with(|cm| {
+            let cm = cm.borrow();
+            let fi = cm.fileinfo(self);
+            fi.offset_line_column(self.hi as usize)
+        })
+    }
+
+    pub fn join(self, other: Span) -> Option<Span> {
+        SOURCE_MAP.with(|cm| {
+            let cm = cm.borrow();
+            // If `other` is not within the same FileInfo as us, return None.
+            if !cm.fileinfo(self).span_within(other) {
+                return None;
+            }
+            Some(Span {
+                lo: cmp::min(self.lo, other.lo),
+                hi: cmp::max(self.hi, other.hi),
+            })
+        })
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bytes({}..{})", self.lo, self.hi)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct S<T: Debug + PartialEq + Clone>(pub T, pub Span);
+
+impl<T: Debug + PartialEq + Clone> S<T> {
+    pub fn t(&self) -> &T {
+        &self.0
+    }
+    pub fn span(&self) -> &Span {
+        &self.1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_end_pos() {
+        assert_eq!(end_pos(0, 10), Some(10));
+        assert_eq!(
+            end_pos(BytePos::max_value() - 11, 10),
+            Some(BytePos::max_value() - 1)
+        );
+        assert_eq!(end_pos(BytePos::max_value() - 10, 10), None);
+        assert_eq!(end_pos(1, BytePos::max_value() as usize), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Template too large")]
+    fn test_too_large() {
+        let mut sm = SourceMap { files: vec![] };
+        let name = PathBuf::from("big.hbs");
+        sm.files.push(FileInfo {
+            name: name.clone(),
+            span: Span {
+                lo: 0,
+                hi: BytePos::max_value() - 4,
+            },
+            index: LineIndex::new(""),
+        });
+        sm.add_file(&name, "{{ a }}");
+    }
+
+    fn lc(line: usize, column: usize) -> LineColumn {
+        LineColumn { line, column }
+    }
+
+    /// Each char boundary maps to its line and column, and back
+    fn check(src: &str, expected: &[(usize, usize)]) {
+        let index = LineIndex::new(src);
+        let boundaries: Vec<_> = src
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(Some(src.len()))
+            .collect();
+        assert_eq!(boundaries.len(), expected.len(), "{:?}", src);
+        for (i, &(line, column)) in boundaries.iter().zip(expected) {
+            assert_eq!(
+                index.line_column(*i),
+                lc(line, column),
+                "{:?} at {}",
+                src,
+                i
+            );
+            assert_eq!(
+                index.offset(lc(line, column)),
+                Some(*i),
+                "{:?} at {}",
+                src,
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_index() {
+        check("", &[(1, 0)]);
+        check("ab", &[(1, 0), (1, 1), (1, 2)]);
+        check("a\nb\n", &[(1, 0), (1, 1), (2, 0), (2, 1), (3, 0)]);
+        check("\n\n", &[(1, 0), (2, 0), (3, 0)]);
+        check("a\r\nb", &[(1, 0), (1, 1), (1, 2), (2, 0), (2, 1)]);
+        check("a\rb", &[(1, 0), (1, 1), (1, 2), (1, 3)]);
+        check("\r\n\r\n", &[(1, 0), (1, 1), (2, 0), (2, 1), (3, 0)]);
+
+        let index = LineIndex::new("a\nbc\r\nd");
+        assert_eq!(index.lines(), 3);
+        assert_eq!(index.line_start(0), None);
+        assert_eq!(index.line_start(1), Some(0));
+        assert_eq!(index.line_start(3), Some(6));
+        assert_eq!(index.line_start(4), None);
+        assert_eq!(index.offset(lc(1, 2)), None);
+        assert_eq!(index.offset(lc(2, 3)), Some(5));
+        assert_eq!(index.offset(lc(2, 4)), None);
+        assert_eq!(index.offset(lc(3, 2)), None);
+        assert_eq!(index.offset(lc(4, 0)), None);
+    }
+
+    #[test]
+    fn test_line_index_multibyte() {
+        // 2, 3 and 4 bytes
+        check("é中😀", &[(1, 0), (1, 1), (1, 2), (1, 3)]);
+        check("aé\nb", &[(1, 0), (1, 1), (1, 2), (2, 0), (2, 1)]);
+        check(
+            "{{ a }}😀\r\n😀{{ b }}",
+            &[
+                (1, 0),
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (1, 4),
+                (1, 5),
+                (1, 6),
+                (1, 7),
+                (1, 8),
+                (1, 9),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (2, 3),
+                (2, 4),
+                (2, 5),
+                (2, 6),
+                (2, 7),
+                (2, 8),
+            ],
+        );
+        // Woman astronaut, three chars joined in a grapheme
+        check("👩‍🚀!", &[(1, 0), (1, 1), (1, 2), (1, 3), (1, 4)]);
+
+        // Inside a char, at the column of the char
+        let index = LineIndex::new("a😀b");
+        assert_eq!(index.line_column(2), lc(1, 1));
+        assert_eq!(index.line_column(4), lc(1, 1));
+        assert_eq!(index.line_column(5), lc(1, 2));
+        assert_eq!(index.offset(lc(1, 3)), Some(6));
+        assert_eq!(index.offset(lc(1, 4)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "offset 3 out of a source of 2 bytes")]
+    fn test_line_index_out() {
+        LineIndex::new("é").line_column(3);
+    }
+
+    #[test]
+    fn test_span_line_column() {
+        let path = PathBuf::from("emoji.hbs");
+        let src = "<p>😀</p>\r\n<b>中 {{ name }}</b>";
+        let file = add_file(&path, src);
+        let lo = src.find("{{").unwrap();
+        let span = Span {
+            lo: file.lo + lo as BytePos,
+            hi: file.lo + (lo + "{{ name }}".len()) as BytePos,
+        };
+
+        assert_eq!(span.start(), lc(2, 5));
+        assert_eq!(span.end(), lc(2, 15));
+        assert_eq!(span.range_in_line(), (src.find("<b>").unwrap(), lo + 10));
+        assert_eq!(span.file_path(), path);
+        clean();
+    }
+}
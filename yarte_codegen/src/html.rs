@@ -1,9 +1,12 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use yarte_dom::DOMFmt;
+use std::fs;
 
-use super::{CodeGen, EachCodeGen, IfElseCodeGen, HIR};
+use yarte_dom::{dom_fmt::to_emailfmt, text::to_textfmt, DOMFmt};
+use yarte_hir::Struct;
+
+use super::{CodeGen, EachCodeGen, IfElseCodeGen, TextCodeGen, HIR};
 
 pub struct HTMLCodeGen;
 
@@ -27,6 +30,114 @@ impl CodeGen for HTMLMinCodeGen {
     }
 }
 
+/// Html written in the buffer `__yarte_buf` without formatter, for method `call`
+///
+/// Faster than the writes through the formatter of `Display`, above all escaping.
+/// The nodes of `html-min` templates are minified by the caller
+#[derive(Default)]
+pub struct DirectCodeGen {
+    bytes: bool,
+}
+
+impl DirectCodeGen {
+    pub fn new() -> DirectCodeGen {
+        DirectCodeGen { bytes: false }
+    }
+
+    /// Html written in the bytes `__yarte_buf`, for method `write_bytes`
+    pub fn bytes() -> DirectCodeGen {
+        DirectCodeGen { bytes: true }
+    }
+}
+
+impl EachCodeGen for DirectCodeGen {}
+impl IfElseCodeGen for DirectCodeGen {}
+
+impl CodeGen for DirectCodeGen {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for i in v {
+            use HIR::*;
+            tokens.extend(match i {
+                Local(a) => quote!(#a),
+                Lit(a) if self.bytes => quote!(__yarte_buf.extend_from_slice(#a.as_bytes());),
+                Safe(a) if self.bytes => {
+                    safe(quote!((&::yarte::Safe(&(#a))).safe_bytes(__yarte_buf)?;))
+                }
+                Expr(a) if self.bytes => {
+                    quote!(::yarte::Render::render_bytes(&(#a), __yarte_buf)?;)
+                }
+                Lit(a) => quote!(::std::string::String::push_str(__yarte_buf, #a);),
+                Safe(a) => safe(quote!((&::yarte::Safe(&(#a))).safe_into(__yarte_buf)?;)),
+                Expr(a) => quote!(::yarte::Render::render_into(&(#a), __yarte_buf)?;),
+                Each(a) => self.gen_each(*a),
+                IfElse(a) => self.gen_if_else(*a),
+            })
+        }
+        tokens
+    }
+}
+
+/// Write of a safe expression, the strings are written without formatter by `SafeStr`
+pub(crate) fn safe(write: TokenStream) -> TokenStream {
+    quote!({
+        #[allow(unused_imports)]
+        use ::yarte::{SafeDisplay as _, SafeStr as _};
+        #write
+    })
+}
+
+/// Minified html with the css rules inlined in `style` attributes
+pub struct EmailCodeGen {
+    css: String,
+    inline: bool,
+}
+
+impl EmailCodeGen {
+    pub fn new(s: &Struct) -> EmailCodeGen {
+        let css = s.css.as_ref().map_or_else(String::new, |path| {
+            fs::read_to_string(path)
+                .unwrap_or_else(|_| panic!("css file not found: {}", path.display()))
+        });
+
+        EmailCodeGen { css, inline: true }
+    }
+
+    /// Method `render_text_alt` with the plain text alternative of the email
+    pub fn text_alt(v: Vec<HIR>, s: &Struct) -> TokenStream {
+        let body = TextCodeGen.gen(to_textfmt(v).expect("correct html"));
+
+        s.implement_inherent(&quote! {
+            /// Plain text alternative of the email, for multipart messages
+            pub fn render_text_alt(&self) -> ::std::string::String {
+                let text = ::std::string::ToString::to_string(&::yarte::lazy(
+                    |__yarte_fmt: &mut ::core::fmt::Formatter| -> ::core::fmt::Result {
+                        #body
+                        ::core::result::Result::Ok(())
+                    },
+                ));
+                // Blocks of the bodies of helpers start with new lines
+                text.trim().to_owned()
+            }
+        })
+    }
+}
+
+impl EachCodeGen for EmailCodeGen {}
+impl IfElseCodeGen for EmailCodeGen {}
+
+impl CodeGen for EmailCodeGen {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let v = if self.inline {
+            self.inline = false;
+            to_emailfmt(v, &self.css).expect("correct html")
+        } else {
+            v
+        };
+        gen(self, v)
+    }
+}
+
 fn gen<C>(codegen: &mut C, v: Vec<HIR>) -> TokenStream
 where
     C: CodeGen + EachCodeGen + IfElseCodeGen,
@@ -36,9 +147,9 @@ where
         use HIR::*;
         tokens.extend(match i {
             Local(a) => quote!(#a),
-            Lit(a) => quote!(_fmt.write_str(#a)?;),
-            Safe(a) => quote!(::std::fmt::Display::fmt(&(#a), _fmt)?;),
-            Expr(a) => quote!(::yarte::Render::render(&(#a), _fmt)?;),
+            Lit(a) => quote!(::core::fmt::Formatter::write_str(__yarte_fmt, #a)?;),
+            Safe(a) => quote!(::core::fmt::Display::fmt(&(#a), __yarte_fmt)?;),
+            Expr(a) => quote!(::yarte::Render::render(&(#a), __yarte_fmt)?;),
             Each(a) => codegen.gen_each(*a),
             IfElse(a) => codegen.gen_if_else(*a),
         })
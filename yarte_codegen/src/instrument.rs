@@ -0,0 +1,95 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use yarte_hir::{Mode, Struct};
+
+use super::{CodeGen, EachCodeGen, IfElseCodeGen, HIR};
+
+/// Instrumented code generator, tallies written bytes by node
+pub struct InstrumentCodeGen {
+    template: String,
+    text: bool,
+    region: usize,
+}
+
+impl InstrumentCodeGen {
+    pub fn new(s: &Struct) -> InstrumentCodeGen {
+        InstrumentCodeGen {
+            template: s.ident.to_string(),
+            text: s.mode == Mode::Text,
+            region: 0,
+        }
+    }
+
+    fn label(i: &HIR) -> String {
+        use HIR::*;
+        match i {
+            Lit(a) => {
+                let mut label: String = a.chars().take(16).collect();
+                if label.len() < a.len() {
+                    label.push_str("..");
+                }
+                format!("{:?}", label)
+            }
+            Expr(a) | Safe(a) => quote!(#a).to_string(),
+            Each(a) => {
+                let args = &a.args;
+                format!("each {}", quote!(#args))
+            }
+            IfElse(a) => {
+                let ifs = &a.ifs.0;
+                format!("if {}", quote!(#ifs))
+            }
+            Local(_) => unreachable!(),
+        }
+    }
+}
+
+impl EachCodeGen for InstrumentCodeGen {}
+impl IfElseCodeGen for InstrumentCodeGen {}
+
+impl CodeGen for InstrumentCodeGen {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for i in v {
+            use HIR::*;
+            if let Local(a) = i {
+                tokens.extend(quote!(#a));
+                continue;
+            }
+
+            let template = self.template.clone();
+            let region = self.region;
+            let label = Self::label(&i);
+            self.region += 1;
+
+            let body = match i {
+                Lit(a) => quote!(::core::fmt::Formatter::write_str(__yarte_fmt, #a)?;),
+                Safe(a) => quote!(::core::fmt::Display::fmt(&(#a), __yarte_fmt)?;),
+                Expr(a) if self.text => quote!(::core::fmt::Display::fmt(&(#a), __yarte_fmt)?;),
+                Expr(a) => quote!(::yarte::Render::render(&(#a), __yarte_fmt)?;),
+                Each(a) => self.gen_each(*a),
+                IfElse(a) => self.gen_if_else(*a),
+                Local(_) => unreachable!(),
+            };
+
+            tokens.extend(quote!({
+                let mut __yarte_counter = ::yarte::instrument::Counter::new(__yarte_fmt);
+                ::core::fmt::Write::write_fmt(
+                    &mut __yarte_counter,
+                    ::core::format_args!(
+                        "{}",
+                        ::yarte::instrument::Region::new(
+                            |__yarte_fmt: &mut ::core::fmt::Formatter| -> ::core::fmt::Result {
+                                #body
+                                ::core::result::Result::Ok(())
+                            }
+                        )
+                    ),
+                )?;
+                ::yarte::instrument::record(#template, #region, #label, __yarte_counter.bytes());
+            }));
+        }
+        tokens
+    }
+}
@@ -0,0 +1,53 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::{html::safe, CodeGen, EachCodeGen, IfElseCodeGen, HIR};
+
+/// Json written through the formatter, the expressions `{{ ... }}` escaped as
+/// the content of a json string
+pub struct JSONCodeGen;
+
+impl EachCodeGen for JSONCodeGen {}
+impl IfElseCodeGen for JSONCodeGen {}
+
+impl CodeGen for JSONCodeGen {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for i in v {
+            use HIR::*;
+            tokens.extend(match i {
+                Local(a) => quote!(#a),
+                Lit(a) => quote!(::core::fmt::Formatter::write_str(__yarte_fmt, #a)?;),
+                Safe(a) => quote!(::core::fmt::Display::fmt(&(#a), __yarte_fmt)?;),
+                Expr(a) => quote!(::yarte::RenderJson::render_json(&(#a), __yarte_fmt)?;),
+                Each(a) => self.gen_each(*a),
+                IfElse(a) => self.gen_if_else(*a),
+            });
+        }
+        tokens
+    }
+}
+
+/// Json written in the bytes `__yarte_buf` without formatter, for method `write_bytes`
+pub struct BytesCodeGen;
+
+impl EachCodeGen for BytesCodeGen {}
+impl IfElseCodeGen for BytesCodeGen {}
+
+impl CodeGen for BytesCodeGen {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for i in v {
+            use HIR::*;
+            tokens.extend(match i {
+                Local(a) => quote!(#a),
+                Lit(a) => quote!(__yarte_buf.extend_from_slice(#a.as_bytes());),
+                Safe(a) => safe(quote!((&::yarte::Safe(&(#a))).safe_bytes(__yarte_buf)?;)),
+                Expr(a) => quote!(::yarte::RenderJson::render_json_into(&(#a), __yarte_buf)?;),
+                Each(a) => self.gen_each(*a),
+                IfElse(a) => self.gen_if_else(*a),
+            });
+        }
+        tokens
+    }
+}
@@ -1,15 +1,21 @@
+use std::mem;
+
 use mime_guess::from_ext;
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use yarte_hir::{Each, IfElse, Mode, Struct, HIR};
+use yarte_hir::{is_builtin, is_region, Each, IfElse, Mode, Struct, HIR};
 
 mod html;
+mod instrument;
+mod json;
 mod text;
 pub mod wasm;
 
 pub use self::{
-    html::{HTMLCodeGen, HTMLMinCodeGen},
+    html::{DirectCodeGen, EmailCodeGen, HTMLCodeGen, HTMLMinCodeGen},
+    instrument::InstrumentCodeGen,
+    json::{BytesCodeGen, JSONCodeGen},
     text::TextCodeGen,
 };
 
@@ -20,57 +26,256 @@ pub trait CodeGen {
 pub struct FmtCodeGen<'a, T: CodeGen> {
     codegen: T,
     s: &'a Struct<'a>,
+    direct: Option<DirectCodeGen>,
+    bytes: Option<Box<dyn CodeGen>>,
 }
 
 impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
     pub fn new<'n>(codegen: T, s: &'n Struct) -> FmtCodeGen<'n, T> {
-        FmtCodeGen { codegen, s }
+        FmtCodeGen {
+            codegen,
+            s,
+            direct: None,
+            bytes: None,
+        }
+    }
+
+    /// Method `call` writing in the `String` without formatter
+    pub fn with_direct(mut self, direct: DirectCodeGen) -> Self {
+        self.direct = Some(direct);
+        self
+    }
+
+    /// Method `write_bytes` writing in the `Buffer` without formatter
+    pub fn with_bytes<B: CodeGen + 'static>(mut self, bytes: B) -> Self {
+        self.bytes = Some(Box::new(bytes));
+        self
     }
 
     fn get_mime(&self) -> String {
+        if let Some(r) = self.s.representation {
+            return r.mime().to_owned();
+        }
         let ext = match self.s.mode {
             Mode::Text => match self.s.path.extension() {
                 Some(s) => s.to_str().unwrap(),
                 None => "txt",
             },
+            Mode::JSON => "json",
             _ => "html",
         };
 
         from_ext(ext).first_or_text_plain().to_string()
     }
 
-    fn template(&self, size_hint: usize, tokens: &mut TokenStream) {
+    fn template(
+        &self,
+        size_hint: usize,
+        constant: Option<String>,
+        direct: Option<TokenStream>,
+        bytes: Option<TokenStream>,
+        skeleton: Option<TokenStream>,
+        tokens: &mut TokenStream,
+    ) {
         let mut body = quote!(
             fn size_hint() -> usize {
                 #size_hint
             }
         );
+        if direct.is_some() || skeleton.is_some() {
+            let render = match direct {
+                Some(nodes) => {
+                    let profile = self.profile();
+                    self.hook(quote!({
+                        let __yarte_buf = &mut __yarte_out;
+                        #profile
+                        #nodes
+                    }))
+                }
+                // The hook is called by `Display`
+                None => quote!(
+                    ::core::fmt::Write::write_fmt(&mut __yarte_out, ::core::format_args!("{}", self))?;
+                ),
+            };
+            let integrity = skeleton.map(|nodes| {
+                let template = self.s.ident.to_string();
+                quote!(
+                    #[cfg(debug_assertions)]
+                    ::yarte::integrity::assert(#template, &__yarte_out, &::yarte::lazy(
+                        |__yarte_fmt: &mut ::core::fmt::Formatter| -> ::core::fmt::Result {
+                            #nodes
+                            ::core::result::Result::Ok(())
+                        },
+                    ));
+                )
+            });
+            body.extend(quote!(
+                fn call(&self) -> ::yarte::Result<::std::string::String> {
+                    let mut __yarte_out = ::std::string::String::with_capacity(#size_hint);
+                    #render
+                    #integrity
+                    ::core::result::Result::Ok(__yarte_out)
+                }
+            ));
+        }
+        if let Some(nodes) = bytes {
+            let profile = self.profile();
+            let render = self.hook(quote!({
+                let __yarte_buf = &mut *__yarte_out;
+                #profile
+                #nodes
+            }));
+            body.extend(quote!(
+                fn write_bytes<__B: ::yarte::Buffer>(&self, __yarte_out: &mut __B) -> ::yarte::Result<()> {
+                    #render
+                    ::core::result::Result::Ok(())
+                }
+            ));
+        }
+        if let Some(lit) = constant {
+            body.extend(quote!(
+                fn constant() -> ::core::option::Option<&'static str> {
+                    ::core::option::Option::Some(#lit)
+                }
+            ));
+        }
         if cfg!(feature = "actix-web") {
             let mime = self.get_mime() + "; charset=utf-8";
             body.extend(quote!(fn mime() -> &'static str { #mime }))
         }
 
-        tokens.extend(self.s.implement_head(quote!(Template), &body));
+        // Text templates can be rendered without `std`, depending on `yarte_template`
+        let template = if self.s.mode == Mode::Text {
+            quote!(Template)
+        } else {
+            quote!(::yarte::Template)
+        };
+        tokens.extend(self.s.implement_head(template, &body));
+    }
+
+    /// Integrity assertion of the html output in `call`, with the feature `integrity`
+    fn integrity(&self) -> bool {
+        cfg!(feature = "integrity") && (self.s.mode == Mode::HTML || self.s.mode == Mode::HTMLMin)
+    }
+
+    /// Reset of the profile of the template at the start of a render
+    fn profile(&self) -> TokenStream {
+        if cfg!(feature = "profile") {
+            let template = self.s.ident.to_string();
+            quote!(#[cfg(debug_assertions)] ::yarte::profile::start(#template);)
+        } else {
+            quote!()
+        }
+    }
+
+    /// Calls of the render hook around the render in `__yarte_out`, with the feature `hook`
+    fn hook(&self, render: TokenStream) -> TokenStream {
+        if cfg!(feature = "hook") {
+            let template = self.s.ident.to_string();
+            quote!(
+                ::yarte::hook::start(#template);
+                let __yarte_start = __yarte_out.len();
+                #render
+                ::yarte::hook::end(#template, __yarte_out.len() - __yarte_start);
+            )
+        } else {
+            render
+        }
+    }
+
+    /// Body of a formatter method, rendered by the render hook with the feature `hook`
+    fn hook_fmt(&self, body: TokenStream) -> TokenStream {
+        if cfg!(feature = "hook") {
+            let template = self.s.ident.to_string();
+            quote!(
+                ::yarte::hook::fmt(
+                    __yarte_fmt,
+                    #template,
+                    |__yarte_fmt: &mut ::core::fmt::Formatter| -> ::core::fmt::Result {
+                        #body
+                        ::core::result::Result::Ok(())
+                    },
+                )
+            )
+        } else {
+            quote!(
+                #body
+                ::core::result::Result::Ok(())
+            )
+        }
+    }
+
+    /// `OnceRender` of a `once` template, instead of `Display` and `Template`
+    fn once_render(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) {
+        let nodes = self.codegen.gen(nodes);
+        let size_hint = nodes.to_string().len();
+        let profile = self.profile();
+        let render = self.hook_fmt(quote!(
+            #profile
+            #nodes
+        ));
+        let body = quote!(
+            fn fmt_once(self, __yarte_fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                #render
+            }
+
+            fn size_hint() -> usize {
+                #size_hint
+            }
+        );
+
+        tokens.extend(self.s.implement_head(quote!(::yarte::OnceRender), &body));
     }
 
     fn display(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) -> usize {
         let nodes = self.codegen.gen(nodes);
         // heuristic based on https://github.com/lfairy/maud
         let size_hint = nodes.to_string().len();
+        let profile = self.profile();
+        let render = self.hook_fmt(quote!(
+            #profile
+            #nodes
+        ));
         let func = quote!(
-            fn fmt(&self, _fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                #nodes
-                Ok(())
+            fn fmt(&self, __yarte_fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                #render
             }
         );
 
-        tokens.extend(self.s.implement_head(quote!(::std::fmt::Display), &func));
+        tokens.extend(self.s.implement_head(quote!(::core::fmt::Display), &func));
 
         size_hint
     }
 
     fn responder(&self, tokens: &mut TokenStream) {
         let err_msg = &self.s.err_msg;
+        let (e, on_error) = match &self.s.error_template {
+            // The error template is rendered with the message of the error as `error`
+            Some(path) => (
+                quote!(e),
+                quote!(
+                    #[derive(::yarte::Template)]
+                    #[template(path = #path)]
+                    struct __YarteErrorTemplate {
+                        error: ::std::string::String,
+                    }
+
+                    let error = ::std::string::ToString::to_string(&e);
+                    match ::yarte::Template::call(&__YarteErrorTemplate { error }) {
+                        ::std::result::Result::Ok(body) => ::yarte::aw::ok(
+                            ::yarte::aw::HttpResponse::InternalServerError()
+                                .content_type(<__YarteErrorTemplate as ::yarte::Template>::mime())
+                                .body(body)
+                        ),
+                        ::std::result::Result::Err(_) => ::yarte::aw::err(::yarte::aw::ErrorInternalServerError(#err_msg)),
+                    }
+                ),
+            ),
+            None => (
+                quote!(_),
+                quote!(::yarte::aw::err(::yarte::aw::ErrorInternalServerError(#err_msg))),
+            ),
+        };
 
         let body = quote!(
             type Error = ::yarte::aw::Error;
@@ -78,12 +283,16 @@ impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
 
             #[inline]
             fn respond_to(self, _req: &::yarte::aw::HttpRequest) -> Self::Future {
-                match self.call() {
-                    Ok(body) => {
-                        ::yarte::aw::ok(::yarte::aw::HttpResponse::Ok().content_type(Self::mime()).body(body))
+                match ::yarte::Template::try_render(&self) {
+                    ::std::result::Result::Ok(body) => {
+                        ::yarte::aw::ok(
+                            ::yarte::aw::HttpResponse::Ok()
+                                .content_type(<Self as ::yarte::Template>::mime())
+                                .body(body)
+                        )
                     }
-                    Err(_) => {
-                        ::yarte::aw::err(::yarte::aw::ErrorInternalServerError(#err_msg))
+                    ::std::result::Result::Err(#e) => {
+                        #on_error
                     }
                 }
             }
@@ -96,9 +305,30 @@ impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
 impl<'a, T: CodeGen> CodeGen for FmtCodeGen<'a, T> {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
         let mut tokens = TokenStream::new();
+        if self.s.once {
+            self.once_render(v, &mut tokens);
+            return tokens;
+        }
 
+        // Written as is in text and html, a constant template is borrowed
+        let constant = match (v.as_slice(), &self.s.mode) {
+            ([HIR::Lit(lit)], Mode::Text) | ([HIR::Lit(lit)], Mode::HTML) => Some(lit.clone()),
+            _ => None,
+        };
+        let direct = match self.direct.as_mut() {
+            Some(direct) if constant.is_none() => Some(direct.gen(v.clone())),
+            _ => None,
+        };
+        let bytes = self.bytes.as_mut().map(|bytes| bytes.gen(v.clone()));
+        // Without the regions of `instrument`, the skeleton isn't part of the render.
+        // The nodes of `html-min` templates are minified by the caller
+        let skeleton = if self.integrity() && constant.is_none() {
+            Some(HTMLCodeGen.gen(skeleton(v.clone())))
+        } else {
+            None
+        };
         let size_hint = self.display(v, &mut tokens);
-        self.template(size_hint, &mut tokens);
+        self.template(size_hint, constant, direct, bytes, skeleton, &mut tokens);
 
         if cfg!(feature = "actix-web") {
             self.responder(&mut tokens);
@@ -108,6 +338,42 @@ impl<'a, T: CodeGen> CodeGen for FmtCodeGen<'a, T> {
     }
 }
 
+/// Nodes of the template without dynamic text, the escaped expressions are in closures
+/// never called, so the types of the template are still inferred without side effects
+///
+/// Built-in helpers are kept, they render trusted markup escaping their arguments
+fn skeleton(v: Vec<HIR>) -> Vec<HIR> {
+    v.into_iter()
+        .filter_map(|i| match i {
+            HIR::Expr(a) if is_builtin(&a) => Some(HIR::Expr(a)),
+            HIR::Expr(a) => match syn::parse2(quote!(
+                let _ = || -> ::core::fmt::Result {
+                    let _ = &(#a);
+                    ::core::result::Result::Ok(())
+                };
+            )) {
+                Ok(syn::Stmt::Local(local)) => Some(HIR::Local(Box::new(local))),
+                _ => None,
+            },
+            // The regions of `coverage`, `profile` and `context` are recorded by the render only
+            HIR::Local(a) if is_region(&a) => None,
+            HIR::Each(mut a) => {
+                a.body = skeleton(a.body);
+                Some(HIR::Each(a))
+            }
+            HIR::IfElse(mut a) => {
+                a.ifs.1 = skeleton(mem::take(&mut a.ifs.1));
+                for (_, body) in &mut a.if_else {
+                    *body = skeleton(mem::take(body));
+                }
+                a.els = a.els.map(skeleton);
+                Some(HIR::IfElse(a))
+            }
+            i => Some(i),
+        })
+        .collect()
+}
+
 pub trait EachCodeGen: CodeGen {
     fn gen_each(&mut self, Each { args, body, expr }: Each) -> TokenStream {
         let body = self.gen(body);
@@ -136,3 +402,240 @@ pub trait IfElseCodeGen: CodeGen {
         tokens
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use syn::parse_str;
+    use yarte_config::Config;
+    use yarte_hir::{generate, visit_derive};
+    use yarte_parser::{parse, source_map, Arena};
+
+    use super::*;
+
+    fn gen(mode: &str, src: &str) -> String {
+        // The wasm applications with their messages and their black box
+        let (attrs, item) = match mode {
+            "wasm" | "iso" => (
+                ", script = \"./pkg/app.js\"",
+                "#[msg(pub enum Msg { Clear, Select })] struct Test { items: Vec<Item>, \
+                 name: String, title: String, black_box: <Self as Template>::BlackBox }",
+            ),
+            _ => ("", "struct Test;"),
+        };
+        let i = parse_str::<syn::DeriveInput>(&format!(
+            "#[template(src = {:?}, mode = {:?}{})] {}",
+            src, mode, attrs, item
+        ))
+        .unwrap();
+        let config = Config::new("");
+        let s = visit_derive(&i, &config);
+        let arena = Arena::new();
+        let mut ctx = BTreeMap::new();
+        ctx.insert(
+            &s.path,
+            parse(source_map::get_cursor(&s.path, &s.src), &arena),
+        );
+        let hir = generate(&config, &s, &ctx).unwrap();
+        source_map::clean();
+
+        // As the derive, with the methods `call` and `write_bytes` without formatter
+        match s.mode {
+            Mode::Text => FmtCodeGen::new(TextCodeGen, &s).gen(hir),
+            Mode::JSON => FmtCodeGen::new(JSONCodeGen, &s)
+                .with_bytes(BytesCodeGen)
+                .gen(hir),
+            Mode::WASM => wasm::client::WASMCodeGen::new(&s).gen(hir),
+            Mode::WASMServer => wasm::server::WASMCodeGen::new(&s).gen(hir),
+            mode => {
+                let hir = if mode == Mode::HTMLMin {
+                    yarte_dom::DOMFmt::from(hir).0
+                } else {
+                    hir
+                };
+                FmtCodeGen::new(HTMLCodeGen, &s)
+                    .with_direct(DirectCodeGen::new())
+                    .with_bytes(DirectCodeGen::bytes())
+                    .gen(hir)
+            }
+        }
+        .to_string()
+    }
+
+    // Instrumented code counts the output of each region with `write_fmt`, so it isn't checked
+    #[test]
+    fn test_no_format_args() {
+        let corpus = [
+            "Hello, {{ name }}! {{{ raw }}} {{ a + 1 }}",
+            "<ul>{{#each items }}<li class=\"{{ @cycle \"a\" \"b\" }}\">{{ index }} {{ this }}</li>\
+             {{/each }}</ul>",
+            "{{#if a }}<p>{{ b }}</p>{{else if c }}{{ d }}{{else }}e{{/if }}\
+             {{#unless f }}g{{/unless }}",
+            "{{#with user }}<a href=\"{{ url }}\">{{ name }}</a>{{/with }}{{ let x = 1 }}{{ x }}",
+            "<head>{{ @meta title = t description = d }}</head>\
+             <p>{{ @money price }} {{ @date day }}</p>{{ @paginate page total url }}",
+            "{{! comment }}{{R}}raw {{ a }}{{/R}}{{~ b ~}}",
+        ];
+        for src in corpus.iter() {
+            for mode in &["text", "html", "html-min", "json"] {
+                let code = gen(mode, src);
+                for forbidden in &["format_args", "format !", "write !", "write_fmt"] {
+                    assert!(
+                        !code.contains(forbidden),
+                        "`{}` in {} mode of {:?}: {}",
+                        forbidden,
+                        mode,
+                        src,
+                        code
+                    );
+                }
+            }
+        }
+    }
+
+    // Crates with `#![forbid(unsafe_code)]` can't check the code of derives
+    #[test]
+    fn test_no_unsafe() {
+        let src = "{{#each items reverse skip=1 limit=2 }}{{#if @last }}{{ this }}{{/if }}{{/each }}\
+                   {{ #once a.len() }}{{{ b }}}{{ @num c }}{{#each (zip d e) as |x, y| }}{{ x }}{{ y }}{{/each }}";
+        for mode in &["text", "html", "html-min"] {
+            let code = gen(mode, src);
+            assert!(
+                !code.contains("unsafe"),
+                "unsafe in {} mode: {}",
+                mode,
+                code
+            );
+        }
+    }
+
+    // The static skeleton of the items is parsed once as the html of a `<template>`
+    #[test]
+    fn test_wasm_component() {
+        let src = "<!DOCTYPE html><html><body><table><tbody>{{#each items }}<tr><td class=\"a&b\">{{ this.id }}</td>\
+                   <td><svg><circle r=\"1\"></circle></svg><br></td></tr>{{/each }}</tbody></table></body></html>";
+        let code = gen("wasm", src);
+        assert!(code.contains("set_inner_html"), "{}", code);
+        assert!(code.contains("HtmlTemplateElement"), "{}", code);
+        assert!(
+            code.contains(r#"<tr><td class=\"a&amp;b\"></td><td><svg><circle r=\"1\"></circle></svg><br></td></tr>"#),
+            "{}",
+            code
+        );
+        assert!(!code.contains("create_element_ns"), "{}", code);
+    }
+
+    // The static chunks of the texts are slices of the table of strings
+    #[test]
+    fn test_wasm_strings() {
+        let src = "<!DOCTYPE html><html><body><p>Hello {{ name }}!</p><p>Hello {{ title }}</p>\
+                   </body></html>";
+        let code = gen("wasm", src);
+        assert!(!code.contains("push_str (\"Hello \")"), "{}", code);
+        let table = code
+            .split("const __YARTE_STRINGS : & 'static str = ")
+            .nth(1)
+            .expect("table of strings");
+        assert!(table.starts_with("\"Hello !"), "{}", code);
+    }
+
+    // The content of the portals is built from their `<template>` and moved to the element
+    #[test]
+    fn test_wasm_portal() {
+        let src = "<!DOCTYPE html><html><body><p>{{ name }}</p>\
+                   {{#portal \"#modal\" }}<div><b>{{ title }}</b></div>{{/portal }}\
+                   <p>{{ name }}</p></body></html>";
+        let code = gen("wasm", src);
+        assert!(
+            code.contains(
+                "let __yportal__0 = doc . query_selector (\"template[data-yarte-portal]\") \
+                 . unwrap_throw () . unwrap_throw () ; \
+                 __yportal__0 . remove_attribute (\"data-yarte-portal\") . unwrap_throw () ; \
+                 let __yportal__0 = yarte :: JsCast :: unchecked_into :: < yarte :: web :: \
+                 HtmlTemplateElement > (__yportal__0) . content () ; \
+                 let __ynode__1 = __yportal__0 . first_element_child () . unwrap_throw () \
+                 . first_element_child () . unwrap_throw () ;"
+            ),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(
+                "doc . query_selector (\"#modal\") . unwrap_throw () . unwrap_throw () \
+                 . append_child (& __yportal__0) . unwrap_throw () ;"
+            ),
+            "{}",
+            code
+        );
+        // The `<template>` is a sibling of the nodes of the body
+        assert!(
+            code.contains(
+                "let __ynode__2 = __ynode__0 . next_element_sibling () . unwrap_throw () \
+                 . next_element_sibling () . unwrap_throw () ;"
+            ),
+            "{}",
+            code
+        );
+
+        let code = gen("iso", src);
+        assert!(
+            code.contains(r##"<template data-yarte-portal=\"#modal\"><div><b>"##),
+            "{}",
+            code
+        );
+    }
+
+    // Event attributes are marked with the index of their message, delegated at the body
+    #[test]
+    fn test_wasm_events() {
+        let src = "<!DOCTYPE html><html><body><button onclick=\"{{ Msg::Clear }}\">Clear</button>\
+                   <table><tbody>{{#each items }}<tr><td onclick=\"{{ Msg::Select }}\" \
+                   onmouseover=\"{{ Msg::Clear }}\">{{ this.id }}</td></tr>{{/each }}</tbody></table>\
+                   <input autofocus-on=\"{{ Msg::Select }}\"></body></html>";
+        let code = gen("wasm", src);
+        assert_eq!(code.matches("yarte :: delegate").count(), 2, "{}", code);
+        assert!(
+            code.contains(
+                "yarte :: delegate (& body , \"click\" , \"data-yarte-onclick\" , move | __index | \
+                 match __index { 0usize => __addr . send (Msg :: Clear) , \
+                 1usize => __addr . send (Msg :: Select) , _ => () , })"
+            ),
+            "{}",
+            code
+        );
+        // Focused after the render of the message
+        assert!(
+            code.contains(
+                r#"if let Msg :: Select = & __msg { yarte :: focus ("[data-yarte-autofocus-on=\"3\"]") ; }"#
+            ),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(
+                r#"<tr><td data-yarte-onclick=\"1\" data-yarte-onmouseover=\"2\"></td></tr>"#
+            ),
+            "{}",
+            code
+        );
+
+        let code = gen("iso", src);
+        assert!(!code.contains("Msg"), "{}", code);
+        assert!(
+            code.contains(r#"<button data-yarte-onclick=\"0\">Clear</button>"#),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(r#"<tr><td data-yarte-onclick=\"1\" data-yarte-onmouseover=\"2\">"#),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(r#"<input data-yarte-autofocus-on=\"3\">"#),
+            "{}",
+            code
+        );
+    }
+}
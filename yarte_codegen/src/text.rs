@@ -15,8 +15,8 @@ impl CodeGen for TextCodeGen {
             use HIR::*;
             tokens.extend(match i {
                 Local(a) => quote!(#a),
-                Lit(a) => quote!(_fmt.write_str(#a)?;),
-                Safe(a) | Expr(a) => quote!(::std::fmt::Display::fmt(&(#a), _fmt)?;),
+                Lit(a) => quote!(::core::fmt::Formatter::write_str(__yarte_fmt, #a)?;),
+                Safe(a) | Expr(a) => quote!(::core::fmt::Display::fmt(&(#a), __yarte_fmt)?;),
                 Each(a) => self.gen_each(*a),
                 IfElse(a) => self.gen_if_else(*a),
             });
@@ -1,10 +1,10 @@
 use std::{cell::RefCell, collections::HashMap};
 
-use proc_macro2::TokenStream;
+use markup5ever::LocalName;
 use quote::{format_ident, quote};
 use syn::Ident;
 
-use yarte_dom::dom::{Attribute, Document, Element, ExprId, ExprOrText, Node};
+use yarte_dom::dom::{Attribute, Document, Element, ExprId, ExprOrText, Node, Ns};
 
 use super::WASMCodeGen;
 
@@ -20,13 +20,13 @@ pub fn get_component(id: ExprId, doc: &Document, builder: &mut WASMCodeGen) -> I
     ComponentBuilder::new(id, builder).build(doc)
 }
 
-const HEAD: &str = "__n__";
+/// Template element of the component, its html is parsed once by the browser
+const TEMPLATE: &str = "template";
 
 struct ComponentBuilder<'a, 'b> {
     builder: &'a mut WASMCodeGen<'b>,
     id: ExprId,
-    count: usize,
-    tokens: TokenStream,
+    html: String,
 }
 
 impl<'a, 'b> ComponentBuilder<'a, 'b> {
@@ -34,51 +34,69 @@ impl<'a, 'b> ComponentBuilder<'a, 'b> {
         ComponentBuilder {
             builder,
             id,
-            count: 0,
-            tokens: TokenStream::new(),
+            html: String::new(),
         }
     }
 
+    /// Static skeleton of the component as the content of a `<template>`, created once and
+    /// cloned for each item, instead of creating its elements one by one
     fn build(mut self, doc: &Document) -> Ident {
         let ident = format_ident!("component_{}", self.id);
 
         let doc: Vec<&Node> = Self::filter(doc).collect();
 
-        if doc.len() == 1 {
+        // Foreign elements are parsed in their namespace inside its root element
+        let wrapper = if doc.len() == 1 {
             match &doc[0] {
                 Node::Elem(Element::Node {
                     name,
                     attrs,
                     children,
                 }) => {
-                    let id = self.get_ident();
-                    let tag = name.1.to_string();
-
-                    self.tokens.extend(quote! {
-                        let #id = doc.create_element(#tag).unwrap_throw();
-                    });
-                    self.step(children, &id);
-                    self.set_attrs(&id, attrs);
-
-                    self.tokens.extend(quote!(#id))
+                    let wrapper = match name.0 {
+                        Ns::Html => None,
+                        Ns::Svg => Some("svg"),
+                        Ns::MathMl => Some("math"),
+                    };
+                    if let Some(wrapper) = wrapper {
+                        self.open(wrapper);
+                        self.html.push('>');
+                    }
+                    self.element(name, attrs, children);
+                    if let Some(wrapper) = wrapper {
+                        self.close(wrapper);
+                    }
+                    wrapper
                 }
                 _ => todo!("no node element"),
             }
         } else {
             todo!("len +1")
-        }
+        };
 
-        let tokens = self.tokens.to_string();
         let cached = CACHE.with(|c| {
-            if !c.borrow().contains_key(&tokens) {
-                c.borrow_mut().insert(tokens, ident.clone());
+            if !c.borrow().contains_key(&self.html) {
+                c.borrow_mut().insert(self.html.clone(), ident.clone());
                 return None;
             }
-            c.borrow().get(&tokens).cloned()
+            c.borrow().get(&self.html).cloned()
         });
 
         cached.unwrap_or_else(|| {
-            self.builder.component.push((ident.clone(), self.tokens));
+            let template = self.builder.strings.intern(TEMPLATE);
+            let html = self.builder.strings.intern(&self.html);
+            let root = if wrapper.is_some() {
+                quote!(__content__.first_element_child().unwrap_throw().first_element_child().unwrap_throw())
+            } else {
+                quote!(__content__.first_element_child().unwrap_throw())
+            };
+            let tokens = quote! {
+                let __template__ = doc.create_element(#template).unwrap_throw();
+                __template__.set_inner_html(#html);
+                let __content__ = yarte::JsCast::unchecked_into::<yarte::web::HtmlTemplateElement>(__template__).content();
+                #root
+            };
+            self.builder.component.push((ident.clone(), tokens));
             ident
         })
     }
@@ -90,7 +108,29 @@ impl<'a, 'b> ComponentBuilder<'a, 'b> {
         })
     }
 
-    fn step(&mut self, doc: &Document, p_id: &Ident) {
+    fn element(&mut self, (ns, name): &(Ns, LocalName), attrs: &[Attribute], children: &Document) {
+        self.open(name);
+        self.set_attrs(attrs);
+        self.html.push('>');
+        self.step(children);
+        match ns {
+            Ns::Html if VOID_ELEMENTS.contains(&&**name) => (),
+            _ => self.close(name),
+        }
+    }
+
+    fn open(&mut self, name: &str) {
+        self.html.push('<');
+        self.html.push_str(name);
+    }
+
+    fn close(&mut self, name: &str) {
+        self.html.push_str("</");
+        self.html.push_str(name);
+        self.html.push('>');
+    }
+
+    fn step(&mut self, doc: &Document) {
         let doc: Vec<&Node> = Self::filter(doc).collect();
         for node in &doc {
             match node {
@@ -98,23 +138,10 @@ impl<'a, 'b> ComponentBuilder<'a, 'b> {
                     name,
                     attrs,
                     children,
-                }) => {
-                    let id = self.get_ident();
-                    let tag = name.1.to_string();
-
-                    self.tokens.extend(quote! {
-                        let #id = doc.create_element(#tag).unwrap_throw();
-                        #p_id.append_child(&#id).unwrap_throw();
-                    });
-                    self.set_attrs(&id, attrs);
-
-                    self.step(children, &id);
-                }
+                }) => self.element(name, attrs, children),
                 Node::Elem(Element::Text(s)) => {
                     if doc.len() == 1 {
-                        self.tokens.extend(quote! {
-                            #p_id.set_text_content(Some(#s));
-                        })
+                        escape(&mut self.html, s);
                     } else {
                         todo!("text +1")
                     }
@@ -124,7 +151,8 @@ impl<'a, 'b> ComponentBuilder<'a, 'b> {
         }
     }
 
-    fn set_attrs(&mut self, id: &Ident, attrs: &[Attribute]) {
+    /// Attributes without expressions, the others are set at the build of each item
+    fn set_attrs(&mut self, attrs: &[Attribute]) {
         for attr in attrs {
             if attr.value.iter().all(|x| {
                 if let ExprOrText::Text(_) = x {
@@ -133,23 +161,36 @@ impl<'a, 'b> ComponentBuilder<'a, 'b> {
                     false
                 }
             }) {
-                let value = attr.value.iter().fold(String::new(), |mut acc, x| {
+                // Prefixed names, as `xlink:href`, are parsed in their namespace
+                self.html.push(' ');
+                self.html.push_str(&attr.name);
+                self.html.push_str("=\"");
+                for x in &attr.value {
                     if let ExprOrText::Text(t) = x {
-                        acc.push_str(t)
+                        escape(&mut self.html, t);
                     }
-
-                    acc
-                });
-                let name = &attr.name;
-                self.tokens
-                    .extend(quote!(#id.set_attribute(#name, #value).unwrap_throw();));
+                }
+                self.html.push('"');
             }
         }
     }
+}
 
-    fn get_ident(&mut self) -> Ident {
-        let id = format_ident!("{}{}", HEAD, self.count);
-        self.count += 1;
-        id
+/// Elements without end tag
+static VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Escape text and attribute values of the html of a component
+fn escape(buf: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            c => buf.push(c),
+        }
     }
 }
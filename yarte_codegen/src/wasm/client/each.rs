@@ -85,7 +85,7 @@ impl<'a> WASMCodeGen<'a> {
                 let ident = Self::get_vdom_ident(&id);
                 quote!(#ident)
             }
-            Parent::Body | Parent::Head => quote!(#current_bb.#table_dom),
+            Parent::Body | Parent::Head | Parent::Portal => quote!(#current_bb.#table_dom),
         };
         let (new, cached) = self.new_each(
             &component,
@@ -295,6 +295,22 @@ impl<'a> WASMCodeGen<'a> {
                 }
             }
         };
+        // Without the bounds check of `drain` in the no panic profile
+        let remove = if cfg!(feature = "no-panic") {
+            quote! {
+                while #table.len() > __data_len__ {
+                    if let Some(__d__) = #table.pop() {
+                        __d__.#froot.remove()
+                    }
+                }
+            }
+        } else {
+            quote! {
+                for __d__ in #table.drain(__data_len__..) {
+                    __d__.#froot.remove()
+                }
+            }
+        };
         let body = quote! {
             for (#vdom, #expr) in #table
                 .iter_mut()
@@ -305,11 +321,7 @@ impl<'a> WASMCodeGen<'a> {
                     #vdom.t_root = 0;
                 }
 
-            if __dom_len__ < __data_len__ { #new_block } else {
-                for __d__ in #table.drain(__data_len__..) {
-                    __d__.#froot.remove()
-                }
-            }
+            if __dom_len__ < __data_len__ { #new_block } else { #remove }
         };
 
         // TODO: #[filter] or child is `if`
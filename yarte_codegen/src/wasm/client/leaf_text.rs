@@ -2,65 +2,106 @@ use std::collections::HashSet;
 
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{punctuated::Punctuated, Expr, Token};
 
-use yarte_dom::dom::{Document, Element, Expression, Node, TreeMap, VarId, VarMap};
+use yarte_dom::dom::{Document, Element, ExprOrText, Expression, Node, TreeMap, VarId, VarMap};
+
+use super::strings::Strings;
 
 pub fn get_leaf_text(
     children: &Document,
     tree_map: &TreeMap,
     var_map: &VarMap,
+    strings: &mut Strings,
+) -> (HashSet<VarId>, TokenStream) {
+    LeafTextBuilder::new(tree_map, var_map, strings).build(children)
+}
+
+pub fn get_attr_text(
+    value: &[ExprOrText],
+    tree_map: &TreeMap,
+    var_map: &VarMap,
+    strings: &mut Strings,
 ) -> (HashSet<VarId>, TokenStream) {
-    LeafTextBuilder::new(tree_map, var_map).build(children)
+    let mut builder = LeafTextBuilder::new(tree_map, var_map, strings);
+    for chunk in value {
+        match chunk {
+            ExprOrText::Text(t) => builder.text(t),
+            ExprOrText::Expr(e) => builder.expr(e),
+        }
+    }
+
+    builder.finish()
 }
 
 struct LeafTextBuilder<'a> {
     tree_map: &'a TreeMap,
     var_map: &'a VarMap,
+    strings: &'a mut Strings,
     buff: HashSet<VarId>,
-    buff_expr: String,
-    buff_args: Punctuated<Expr, Token![,]>,
+    buff_push: TokenStream,
 }
 
 // TODO: #[str] alone expression for no reallocate string
 impl<'a> LeafTextBuilder<'a> {
-    fn new<'n>(tree_map: &'n TreeMap, var_map: &'n VarMap) -> LeafTextBuilder<'n> {
+    fn new<'n>(
+        tree_map: &'n TreeMap,
+        var_map: &'n VarMap,
+        strings: &'n mut Strings,
+    ) -> LeafTextBuilder<'n> {
         LeafTextBuilder {
             tree_map,
             var_map,
+            strings,
             buff: Default::default(),
-            buff_expr: "".into(),
-            buff_args: Default::default(),
+            buff_push: TokenStream::new(),
         }
     }
 
     fn build(mut self, children: &Document) -> (HashSet<VarId>, TokenStream) {
         self.init(children);
+        self.finish()
+    }
 
-        let args = self.buff_args;
-        let expr = self.buff_expr;
-        (self.buff, quote!(format!(#expr, #args)))
+    fn finish(self) -> (HashSet<VarId>, TokenStream) {
+        let push = self.buff_push;
+        (
+            self.buff,
+            quote!({
+                let mut __text__ = String::new();
+                #push
+                __text__
+            }),
+        )
     }
 
     fn init(&mut self, children: &Document) {
         for child in children {
             match child {
-                Node::Elem(Element::Text(t)) => self
-                    .buff_expr
-                    .push_str(&t.replace("{", "{{").replace("}", "}}")),
-                Node::Expr(e) => match e {
-                    Expression::Safe(id, e) | Expression::Unsafe(id, e) => {
-                        let vars = self.tree_map.get(id).expect("Expression to be defined");
-                        self.buff.extend(vars);
-                        self.buff_expr.push_str("{}");
-                        self.buff_args.push(*e.clone());
-                    }
-                    Expression::Each(_id, _e) => todo!(),
-                    Expression::IfElse(_id, _e) => todo!(),
-                    Expression::Local(..) => todo!(),
-                },
+                Node::Elem(Element::Text(t)) => self.text(t),
+                Node::Expr(e) => self.expr(e),
                 _ => unreachable!(),
             }
         }
     }
+
+    /// Static chunk of the text, a slice of the table of strings
+    fn text(&mut self, t: &str) {
+        let t = self.strings.intern(t);
+        self.buff_push.extend(quote!(__text__.push_str(#t);))
+    }
+
+    fn expr(&mut self, e: &Expression) {
+        match e {
+            Expression::Safe(id, e) | Expression::Unsafe(id, e) => {
+                let vars = self.tree_map.get(id).expect("Expression to be defined");
+                self.buff.extend(vars);
+                self.buff_push.extend(quote!(
+                    __text__.push_str(&::std::string::ToString::to_string(&(#e)));
+                ));
+            }
+            Expression::Each(_id, _e) => todo!(),
+            Expression::IfElse(_id, _e) => todo!(),
+            Expression::Local(..) => todo!(),
+        }
+    }
 }
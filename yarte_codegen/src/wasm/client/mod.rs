@@ -1,7 +1,7 @@
 #![allow(warnings)]
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     mem,
 };
 
@@ -12,13 +12,13 @@ use syn::{
     parse::{Parse, ParseBuffer},
     parse2, parse_str,
     punctuated::Punctuated,
-    Field, FieldValue, Ident, Member, Meta, MetaList, NestedMeta, Path, Token, Type, VisPublic,
-    Visibility,
+    Field, FieldValue, Ident, Member, Meta, MetaList, NestedMeta, Pat, Path, Token, Type,
+    VisPublic, Visibility,
 };
 
 use yarte_dom::dom::{
-    Attribute, Document, Each, Element, ExprId, ExprOrText, Expression, IfBlock, IfElse, Node,
-    TreeMap, VarId, VarMap, DOM,
+    event_attr, Attribute, Document, Each, Element, Event, ExprId, ExprOrText, Expression, IfBlock,
+    IfElse, Node, TreeMap, VarId, VarMap, AUTOFOCUS, DOM, PORTAL,
 };
 use yarte_hir::{Struct, HIR};
 
@@ -29,8 +29,12 @@ mod each;
 mod if_else;
 mod leaf_text;
 mod messages;
+mod strings;
 
-use self::leaf_text::get_leaf_text;
+use self::{
+    leaf_text::{get_attr_text, get_leaf_text},
+    strings::Strings,
+};
 use crate::wasm::client::component::clean;
 
 // TODO:
@@ -56,6 +60,8 @@ impl ToTokens for Step {
 pub enum Parent {
     Head,
     Body,
+    /// Content of `{{#portal "#modal-root" }}`, moved to the element of the selector
+    Portal,
     Expr(ExprId),
 }
 
@@ -99,6 +105,7 @@ pub struct WASMCodeGen<'a> {
     build: TokenStream,
     render: TokenStream,
     hydrate: TokenStream,
+    focus: TokenStream,
     helpers: TokenStream,
     buff_build: Vec<TokenStream>,
     buff_new: Vec<TokenStream>,
@@ -108,8 +115,10 @@ pub struct WASMCodeGen<'a> {
     bit_array: Vec<VarId>,
     steps: Vec<Step>,
     path_nodes: Vec<(Ident, Vec<Step>)>,
+    strings: Strings,
     on: Option<Parent>,
     count: usize,
+    portals: usize,
     tree_map: TreeMap,
     var_map: VarMap,
 }
@@ -167,8 +176,10 @@ impl<'a> WASMCodeGen<'a> {
             buff_render: vec![],
             build: TokenStream::new(),
             count: 0,
+            portals: 0,
             helpers: TokenStream::new(),
             hydrate: TokenStream::new(),
+            focus: TokenStream::new(),
             on: None,
             render: TokenStream::new(),
             s,
@@ -176,6 +187,7 @@ impl<'a> WASMCodeGen<'a> {
             tree_map: HashMap::new(),
             var_map: HashMap::new(),
             path_nodes: vec![],
+            strings: Strings::default(),
             buff_build: vec![],
             buff_new: vec![],
         }
@@ -503,12 +515,51 @@ impl<'a> WASMCodeGen<'a> {
         });
     }
 
-    fn init_hydrate(&mut self) {
+    // One listener by event type at the body, matching the closest element with the index of
+    // its message instead of a listener by element of each lists
+    fn init_hydrate(&mut self, events: &[Event]) {
         self.hydrate.extend(quote! {
             let body = yarte::web::window().unwrap_throw()
                 .document().unwrap_throw()
                 .body().unwrap_throw();
         });
+        let mut types: BTreeMap<&str, Vec<(usize, &syn::Expr)>> = BTreeMap::new();
+        for (i, e) in events.iter().enumerate() {
+            if let Some(listen) = e.listen() {
+                types.entry(listen).or_default().push((i, &e.msg));
+            }
+        }
+        for (name, msgs) in types {
+            let attr = event_attr(&format!("on{}", name));
+            let (i, msg): (Vec<_>, Vec<_>) = msgs.into_iter().unzip();
+            self.hydrate.extend(quote! {
+                {
+                    let __addr = __addr.clone();
+                    yarte::delegate(&body, #name, #attr, move |__index| match __index {
+                        #(#i => __addr.send(#msg),)*
+                        _ => (),
+                    });
+                }
+            });
+        }
+    }
+
+    // The elements of `autofocus-on` are focused after the render of their message
+    fn init_focus(&mut self, events: &[Event]) {
+        for (i, e) in events.iter().enumerate() {
+            if e.listen().is_some() {
+                continue;
+            }
+            let msg = &e.msg;
+            let pat: Pat = parse2(quote!(#msg))
+                .unwrap_or_else(|_| panic!("message of `{}` is a pattern", AUTOFOCUS));
+            let selector = format!("[{}=\"{}\"]", event_attr(AUTOFOCUS), i);
+            self.focus.extend(quote! {
+                if let #pat = &__msg {
+                    yarte::focus(#selector);
+                }
+            });
+        }
     }
 
     fn init_render(&mut self) {
@@ -524,7 +575,8 @@ impl<'a> WASMCodeGen<'a> {
     fn init(&mut self, dom: DOM) {
         self.resolve_tree_var(dom.tree_map, dom.var_map);
         self.init_build();
-        self.init_hydrate();
+        self.init_hydrate(&dom.events);
+        self.init_focus(&dom.events);
         self.init_render();
 
         assert_eq!(dom.doc.len(), 1);
@@ -591,12 +643,30 @@ impl<'a> WASMCodeGen<'a> {
     // Main recursive loop
     fn step(&mut self, doc: &Document) {
         let last_node = self.steps.len();
+        // Declarative shadow roots aren't in the light DOM of the browser
+        let doc: Vec<_> = doc
+            .iter()
+            .filter(|x| match x {
+                Node::Elem(e @ Element::Node { .. }) if e.is_shadow_root() => {
+                    if let Element::Node {
+                        attrs, children, ..
+                    } = e
+                    {
+                        if !attrs.iter().all(check_attr_is_text) || !all_children_static(children) {
+                            panic!("declarative shadow roots are static, use custom elements");
+                        }
+                    }
+                    false
+                }
+                _ => true,
+            })
+            .collect();
         let len = doc.iter().fold(0, |acc, x| match x {
             Node::Elem(Element::Text(_)) => acc,
             _ => acc + 1,
         });
         let mut last = 0usize;
-        let nodes = doc.iter().map(|x| match x {
+        let nodes = doc.iter().cloned().map(|x| match x {
             Node::Elem(Element::Text(_)) => (last, x),
             _ => {
                 let l = last;
@@ -604,7 +674,7 @@ impl<'a> WASMCodeGen<'a> {
                 (l, x)
             }
         });
-        let children = doc.iter().filter(|x| match x {
+        let children = doc.iter().cloned().filter(|x| match x {
             Node::Elem(Element::Text(_)) => false,
             _ => true,
         });
@@ -638,6 +708,32 @@ impl<'a> WASMCodeGen<'a> {
         o: F,
     ) {
         match node {
+            Node::Elem(e @ Element::Node { .. }) if e.is_opaque() => {
+                if let Element::Node {
+                    name,
+                    attrs,
+                    children,
+                } = e
+                {
+                    if !all_children_static(children) {
+                        panic!(
+                            "children of custom element <{}> are opaque, use attributes",
+                            name.1
+                        );
+                    }
+                    self.write_attrs(attrs, step.expect("Some step"));
+                }
+            }
+            Node::Elem(e @ Element::Node { .. }) if e.portal().is_some() => {
+                if let (Some(selector), Element::Node { children, .. }) = (e.portal(), e) {
+                    if self.on != Some(Parent::Body) {
+                        panic!("portals are in the body, out of the blocks");
+                    }
+                    self.write_portal(selector, children);
+                }
+                // The `<template>` is kept, without content
+                self.steps.push(step.expect("Some step"));
+            }
             Node::Elem(Element::Node {
                 children, attrs, ..
             }) => {
@@ -714,14 +810,106 @@ impl<'a> WASMCodeGen<'a> {
             .into_token_stream()
     }
 
+    // Builds the nodes of a portal from the content of its `<template>` and moves it to the
+    // element of the selector, the nodes are rendered as the nodes of the body
+    fn write_portal(&mut self, selector: &str, children: &Document) {
+        let ident = format_ident!("__yportal__{}", self.portals);
+        self.portals += 1;
+        // The next portal in document order, the built ones are without attribute
+        let template = format!("template[{}]", PORTAL);
+        self.build.extend(quote! {
+            let #ident = doc.query_selector(#template).unwrap_throw().unwrap_throw();
+            #ident.remove_attribute(#PORTAL).unwrap_throw();
+            let #ident = yarte::JsCast::unchecked_into::<yarte::web::HtmlTemplateElement>(#ident).content();
+        });
+
+        let steps = mem::take(&mut self.steps);
+        let path_nodes = mem::take(&mut self.path_nodes);
+        let buff_build = mem::take(&mut self.buff_build);
+        let on = self.on.replace(Parent::Portal);
+        self.step(children);
+        if !self.path_nodes.is_empty() {
+            let tokens = self.get_steps(quote!(#ident));
+            self.build.extend(tokens);
+            self.build
+                .extend(mem::take(&mut self.buff_build).into_iter().flatten());
+        }
+        self.build.extend(quote! {
+            doc.query_selector(#selector).unwrap_throw().unwrap_throw()
+                .append_child(&#ident).unwrap_throw();
+        });
+        self.steps = steps;
+        self.path_nodes = path_nodes;
+        self.buff_build = buff_build;
+        self.on = on;
+    }
+
+    // Writes current attributes of an opaque element
+    fn write_attrs(&mut self, attrs: &[Attribute], step: Step) {
+        let attrs: Vec<_> = attrs.iter().filter(|x| !check_attr_is_text(x)).collect();
+        if attrs.is_empty() {
+            self.steps.push(step);
+            return;
+        }
+
+        let name = format_ident!("__ynode__{}", self.count);
+        self.count += 1;
+
+        let dom = match self.on.as_ref().expect("Some parent") {
+            Parent::Body | Parent::Portal => {
+                let ident = self.get_global_bbox_ident();
+                quote!(self.#ident)
+            }
+            Parent::Expr(i) => {
+                let ident = Self::get_vdom_ident(i);
+                quote!(#ident)
+            }
+            Parent::Head => todo!(),
+        };
+        for attr in attrs {
+            let (t, e) = get_attr_text(
+                &attr.value,
+                &self.tree_map,
+                &self.var_map,
+                &mut self.strings,
+            );
+            let set = match &attr.ns {
+                Some(ns) => {
+                    let ns = self.strings.intern(ns);
+                    let attr = self.strings.intern(&attr.name);
+                    quote!(set_attribute_ns(Some(#ns), #attr, &#e))
+                }
+                None => {
+                    let attr = self.strings.intern(&attr.name);
+                    quote!(set_attribute(#attr, &#e))
+                }
+            };
+            self.buff_render
+                .push((t, quote! { #dom.#name.#set.unwrap_throw(); }));
+            self.buff_new.push(quote! { #name.#set.unwrap_throw(); });
+            // TODO: remove self
+            let set: TokenStream = set.to_string().replace("self .", "").parse().unwrap();
+            self.buff_build.push(quote! { #name.#set.unwrap_throw(); });
+        }
+
+        self.steps.push(step);
+        self.path_nodes.push((name.clone(), self.steps.clone()));
+
+        self.black_box.push(BlackBox {
+            doc: "Yarte custom element".into(),
+            name,
+            ty: parse2(quote!(yarte::web::Element)).unwrap(),
+        });
+    }
+
     // Writes current state
     fn write_leaf_text(&mut self, children: &Document, step: Step) {
-        let (t, e) = get_leaf_text(children, &self.tree_map, &self.var_map);
+        let (t, e) = get_leaf_text(children, &self.tree_map, &self.var_map, &mut self.strings);
         let name = format_ident!("__ynode__{}", self.count);
         self.count += 1;
 
         let dom = match self.on.as_ref().expect("Some parent") {
-            Parent::Body => {
+            Parent::Body | Parent::Portal => {
                 let ident = self.get_global_bbox_ident();
                 quote!(self.#ident)
             }
@@ -757,7 +945,11 @@ impl<'a> WASMCodeGen<'a> {
 
 impl<'a> CodeGen for WASMCodeGen<'a> {
     fn gen(&mut self, ir: Vec<HIR>) -> TokenStream {
-        self.init(ir.into());
+        let dom = DOM::try_new(ir).unwrap_or_else(|errors| {
+            let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+            panic!("{}", errors.join(", "))
+        });
+        self.init(dom);
 
         let initial_state = self.get_initial_state();
         let black_box_name = format_ident!("{}BlackBox", self.s.ident);
@@ -814,6 +1006,7 @@ impl<'a> CodeGen for WASMCodeGen<'a> {
             .as_ref()
             .expect("Need define messages for application");
         let (dispatch, enu) = messages::gen_messages(msgs);
+        let focus = &self.focus;
         let type_msgs = &msgs.ident;
         let app = quote! {
             type BlackBox = #black_box_name;
@@ -828,10 +1021,14 @@ impl<'a> CodeGen for WASMCodeGen<'a> {
             fn __hydrate(&mut self, __addr: &yarte::Addr<Self>) { # hydrate }
 
             #[doc(hidden)]
-            fn __dispatch(&mut self, __msg: Self::Message, __addr: &yarte::Addr<Self>) { #dispatch }
+            fn __dispatch(&mut self, __msg: Self::Message, __addr: &yarte::Addr<Self>) {
+                #focus
+                #dispatch
+            }
         };
         let app = self.s.implement_head(quote!(yarte::Template), &app);
         let helpers = &self.helpers;
+        let strings = self.s.implement_inherent(&self.strings.table());
 
         // Multi app compilation
         clean();
@@ -847,6 +1044,7 @@ impl<'a> CodeGen for WASMCodeGen<'a> {
             #initial_state
             #black_box
             #helpers
+            #strings
             #default
         }
     }
@@ -884,10 +1082,19 @@ fn all_if_block_text(IfBlock { block, .. }: &IfBlock) -> bool {
     all_children_text(block)
 }
 
-fn check_attr_is_text(attr: Attribute) -> bool {
-    attr.value.len() == 1
-        && match attr.value[0] {
-            ExprOrText::Text(..) => true,
-            ExprOrText::Expr(..) => false,
-        }
+fn all_children_static(doc: &Document) -> bool {
+    doc.iter().all(|x| match x {
+        Node::Elem(Element::Text(_)) => true,
+        Node::Elem(Element::Node {
+            attrs, children, ..
+        }) => attrs.iter().all(check_attr_is_text) && all_children_static(children),
+        Node::Expr(_) => false,
+    })
+}
+
+fn check_attr_is_text(attr: &Attribute) -> bool {
+    attr.value.iter().all(|x| match x {
+        ExprOrText::Text(..) => true,
+        ExprOrText::Expr(..) => false,
+    })
 }
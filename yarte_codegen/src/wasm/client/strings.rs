@@ -0,0 +1,61 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Static strings of the application in one table, each string is a slice of it
+///
+/// The html of the components, the names of the attributes and the static chunks of
+/// the texts
+///
+/// Repeated strings, and strings contained in others, share their bytes
+#[derive(Default)]
+pub struct Strings(String);
+
+impl Strings {
+    /// Slice of the table with `s`
+    pub fn intern(&mut self, s: &str) -> TokenStream {
+        let start = match self.0.find(s) {
+            Some(start) => start,
+            None => {
+                let start = self.0.len();
+                self.0.push_str(s);
+                start
+            }
+        };
+        let end = start + s.len();
+
+        if cfg!(feature = "forbid-unsafe") {
+            quote!(&Self::__YARTE_STRINGS[#start..#end])
+        } else {
+            // Offsets are char boundaries of the table
+            quote!(unsafe { Self::__YARTE_STRINGS.get_unchecked(#start..#end) })
+        }
+    }
+
+    /// Declaration of the table
+    pub fn table(&self) -> TokenStream {
+        let table = &self.0;
+        quote!(const __YARTE_STRINGS: &'static str = #table;)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut strings = Strings::default();
+        let a = strings.intern("button").to_string();
+        strings.intern("div");
+        assert_eq!(strings.intern("button").to_string(), a);
+        assert!(strings
+            .intern("but")
+            .to_string()
+            .contains("0usize .. 3usize"));
+        assert!(strings
+            .intern("ton")
+            .to_string()
+            .contains("3usize .. 6usize"));
+        assert_eq!(strings.0, "buttondiv");
+    }
+}
@@ -23,15 +23,18 @@ pub mod server {
 
     impl<'a> CodeGen for WASMCodeGen<'a> {
         fn gen(&mut self, ir: Vec<HIR>) -> TokenStream {
-            let ir = to_wasmfmt(ir, self.s).expect("html");
+            let ir = to_wasmfmt(ir, self.s).unwrap_or_else(|errors| {
+                let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+                panic!("{}", errors.join(", "))
+            });
             let mut tokens = TokenStream::new();
             for i in ir {
                 use HIR::*;
                 tokens.extend(match i {
                     Local(a) => quote!(#a),
-                    Lit(a) => quote!(_fmt.write_str(#a)?;),
-                    Safe(a) => quote!(::std::fmt::Display::fmt(&(#a), _fmt)?;),
-                    Expr(a) => quote!(::yarte::Render::render(&(#a), _fmt)?;),
+                    Lit(a) => quote!(::core::fmt::Formatter::write_str(__yarte_fmt, #a)?;),
+                    Safe(a) => quote!(::core::fmt::Display::fmt(&(#a), __yarte_fmt)?;),
+                    Expr(a) => quote!(::yarte::Render::render(&(#a), __yarte_fmt)?;),
                     Each(a) => self.gen_each(*a),
                     IfElse(a) => self.gen_if_else(*a),
                 })
@@ -10,12 +10,48 @@
 //!   - **`debug`**: type of output of debug mode. The code and/or  ast generated by  Yarte
 //! can be visualize, to do so, at most one of three possible values has to be given:
 //! `code`, `ast`, or `all`.
+//!   - **`routes`**: path of the route manifest, relative to the crate root. Static `href`
+//! and `action` attributes with internal paths must match one of its routes.
+//!   - **`assets`**: path of the asset manifest, relative to the crate root. The hashed urls
+//! of the widths of each image of `{{ @img }}`, one by line as `source width url`.
+//!   - **`newlines`** (default: `keep`): line endings of the template sources, normalized
+//! before parsing to `lf` or `crlf`, also mixed ones. Lines of the error positions don't change.
+//!   - **`encoding`** (default: `utf-8`): encoding of the template files without a byte order
+//! mark, `utf-8` or `latin1` for legacy template sets. Files with a BOM are decoded as UTF-8 or UTF-16.
+//!   - **`whitespace`** (default: `ascii`): whitespace of the literals, trimmed by `~` and
+//! collapsed by the html passes, the ASCII whitespace of html or every `unicode` whitespace.
+//!   - **`fragment_tag`** (default: `marquee`): tag wrapping the templates in the html passes,
+//! the templates can't use it.
+//!   - **`expression_mark`** (default: `yarteHashHTMLExpressionsATTT`): mark of the expressions
+//! in the html passes, letters and digits, the templates can't contain it.
+//!   - **`archive`**: path of an uncompressed tar archive of templates, relative to the crate root.
+//! The templates and partials not found in `dir` are read from the archive, with the paths
+//! of its entries relative to `dir`.
+//!   - **`graph`**: path of the usage graph, relative to the crate root. Each derived template
+//! rewrites the graph of structs, templates and partials of the crate, as `json` when the path
+//! has this extension and as `dot` otherwise.
 //!
 //! - **`partials`** (partials aliasing - optional): each entry must be of the type
 //! `name_alias = "./alias/path/"`, where `./` makes reference to `dir` value. Path
 //! must exist, or error will be prompt. If the tag `partials` doesn't exist no aliasing
 //! will be possible.
 //!
+//! - **`aliases`** (directory aliases - optional): each entry must be of the type
+//! `"@name" = "path/of/dir"`, relative to the crate root. Template paths and partials
+//! starting with `@name/` are resolved in the directory.
+//!
+//! - **`builtins`** (built-in helper overrides - optional): each entry must be of the type
+//! `helper_name = "path/of/partial"`, relative to `dir`. The built-in helper is rendered
+//! with the partial, receiving its named arguments.
+//!
+//! - **`helpers`** (custom block helpers - optional): each entry must be of the type
+//! `helper_name = "path::of::Helper"`. `{{#helper_name args}}..{{/helper_name}}` is rendered
+//! for each item of the blocks of the `CustomBlockHelper` implementation of the type.
+//!
+//! - **`conditions`** (custom condition helpers - optional): each entry must be of the type
+//! `helper_name = "path::of::Helper"`. `{{#helper_name args}}..{{/helper_name}}` is rendered
+//! once with the block of the `CustomConditionHelper` implementation of the type, if any.
+//!
 //! - **`debug`** (debugging configuration - optional): in order to visualize clearly generated code
 //! in a debugging environment Yarte gives it a tabulated format, and the possibility
 //! to see the number line use a color theme. Options are the following:
@@ -40,15 +76,38 @@
 //!   - **`paging`** (default:  `false`): Boolean
 //!   - **`short`** (default:  `true`): Boolean, if set to `false` to verbose
 //!
+//! - **`limits`** (compile-time guard rails - optional): lowering of a template fails with
+//! an error when it exceeds some of the following limits:
+//!   - **`depth`** (default: `128`): max nesting depth of helpers and partials.
+//!   - **`nodes`** (default: `65536`): max number of nodes after partial inlining.
+//!   - **`expressions`** (default: `16384`): max number of expressions after partial inlining.
+//!   - **`recursion`** (default: `0`): max number of calls of a partial in itself, the next
+//! call renders nothing. Recursive partials fail with `0`.
+//!
 //! ### Example of a config file
 //! ```toml
 //! [main]
 //! dir = "templates"
 //! debug = "all"
+//! routes = "routes.txt"
+//! assets = "assets.txt"
+//! graph = "target/yarte/graph.dot"
 //!
 //! [partials]
 //! alias = "./deep/more/deep"
 //!
+//! [aliases]
+//! "@components" = "src/components/templates"
+//!
+//! [builtins]
+//! meta = "partials/meta"
+//!
+//! [helpers]
+//! repeat = "crate::helpers::Repeat"
+//!
+//! [conditions]
+//! present = "crate::helpers::Present"
+//!
 //! [debug]
 //! theme = "zenburn"
 //! number_line = true
@@ -56,17 +115,47 @@
 //! header = true
 //! paging = false
 //! short = false
+//!
+//! [limits]
+//! depth = 64
 //! ```
 //!
 //! With this configuration, the user can call `alias` in a partial instance with
 //! `{{> alias context}}` or `{{> alias}}` if the current context is well defined.
 //!
+//! ### Without a config file
+//! `Config::from_str` reads a config from a string and `Config::builder` builds it field
+//! by field, as tools and tests do:
+//!
+//! ```
+//! use yarte_config::{Config, Newlines};
+//!
+//! let config = Config::builder()
+//!     .dir("templates")
+//!     .alias("alias", "./deep/more/deep")
+//!     .newlines(Newlines::Lf)
+//!     .build();
+//! ```
+//!
+//! ### Workspaces
+//! The `yarte.toml` of the workspace root holds the defaults of its members. The config
+//! of a member is merged over it, its tables by key, so a member only writes the values
+//! it overrides and its `partials`, `builtins`, `helpers` and `conditions` are added to the
+//! ones of the workspace.
+//! Paths are relative to the member crate, as in its own config.
+//!
+//! ### Environment variables
+//! `${VAR}` in a string value is replaced with the environment variable `VAR` at
+//! expansion time, an undefined variable is an error. `$${` is a literal `${`.
+//!
 use std::{
     collections::BTreeMap,
     env, fs,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
 };
 
+use serde::{de::Error, Deserialize, Deserializer};
 use serde_derive::Deserialize;
 
 #[derive(Debug)]
@@ -74,20 +163,21 @@ pub struct Dir(PathBuf);
 
 impl Dir {
     pub fn get_template(&self, path: &str) -> PathBuf {
-        let template = self.0.join(path);
+        exists(self.0.join(path))
+    }
+}
 
-        if template.exists() {
-            template
-        } else {
-            panic!("template not found in directory {:?}", template)
-        }
+fn exists(template: PathBuf) -> PathBuf {
+    if template.exists() {
+        template
+    } else {
+        panic!("template not found in directory {:?}", template)
     }
 }
 
 impl From<Option<&str>> for Dir {
     fn from(p: Option<&str>) -> Self {
-        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
-        Dir(p.map_or_else(|| root.join(DEFAULT_DIR), |v| root.join(v)))
+        Dir(manifest_dir().join(p.unwrap_or(DEFAULT_DIR)))
     }
 }
 
@@ -110,26 +200,166 @@ impl From<Option<&str>> for PrintConfig {
     }
 }
 
+/// Handling of the html parse errors, `on_html_error` of `[main]`
+#[derive(Debug, PartialEq)]
+pub enum OnHtmlError {
+    /// Fail the build
+    Error,
+    /// Compile the template without the html passes, as `html` mode, with a warning
+    FallbackText,
+}
+
+impl FromStr for OnHtmlError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(OnHtmlError::Error),
+            "fallback-text" => Ok(OnHtmlError::FallbackText),
+            s => Err(format!(
+                "invalid `on_html_error` \"{}\" in {}, expected \"error\" or \"fallback-text\"",
+                s, CONFIG_FILE_NAME
+            )),
+        }
+    }
+}
+
+/// Line endings of the template sources, `newlines` of `[main]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Newlines {
+    Keep,
+    Lf,
+    Crlf,
+}
+
+impl FromStr for Newlines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Newlines::Keep),
+            "lf" => Ok(Newlines::Lf),
+            "crlf" => Ok(Newlines::Crlf),
+            s => Err(format!(
+                "invalid `newlines` \"{}\" in {}, expected \"keep\", \"lf\" or \"crlf\"",
+                s, CONFIG_FILE_NAME
+            )),
+        }
+    }
+}
+
+impl Newlines {
+    /// Normalize the line endings of a template source before parsing
+    ///
+    /// Only `\r\n` and `\n` are line endings, so the line of each position is kept.
+    pub fn normalize(self, src: String) -> String {
+        match self {
+            Newlines::Keep => src,
+            Newlines::Lf if src.contains("\r\n") => src.replace("\r\n", "\n"),
+            Newlines::Crlf if src.contains('\n') => {
+                let mut out = String::with_capacity(src.len() + src.len() / 32);
+                let mut last = 0;
+                for (i, _) in src.match_indices('\n') {
+                    out.push_str(&src[last..i]);
+                    if !out.ends_with('\r') {
+                        out.push('\r');
+                    }
+                    out.push('\n');
+                    last = i + 1;
+                }
+                out.push_str(&src[last..]);
+                out
+            }
+            _ => src,
+        }
+    }
+}
+
+/// Encoding of the template files without a byte order mark, `encoding` of `[main]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    /// ISO-8859-1, each byte is a char
+    Latin1,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "latin1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            s => Err(format!(
+                "invalid `encoding` \"{}\" in {}, expected \"utf-8\" or \"latin1\"",
+                s, CONFIG_FILE_NAME
+            )),
+        }
+    }
+}
+
+/// Whitespace of the literals, `whitespace` of `[main]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Whitespace {
+    Ascii,
+    Unicode,
+}
+
+impl FromStr for Whitespace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Whitespace::Ascii),
+            "unicode" => Ok(Whitespace::Unicode),
+            s => Err(format!(
+                "invalid `whitespace` \"{}\" in {}, expected \"ascii\" or \"unicode\"",
+                s, CONFIG_FILE_NAME
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Config<'a> {
     dir: Dir,
     alias: BTreeMap<&'a str, &'a str>,
+    aliases: BTreeMap<&'a str, &'a str>,
+    builtins: BTreeMap<&'a str, &'a str>,
+    helpers: BTreeMap<&'a str, &'a str>,
+    conditions: BTreeMap<&'a str, &'a str>,
     pub print_override: PrintConfig,
     pub debug: PrintOption<'a>,
+    pub limits: Limits,
+    pub routes: Option<Routes>,
+    pub assets: Option<Assets>,
+    pub archive: Option<Archive>,
+    pub graph: Option<PathBuf>,
+    pub on_html_error: OnHtmlError,
+    pub newlines: Newlines,
+    pub whitespace: Whitespace,
+    pub encoding: Encoding,
+    pub fragment_tag: &'a str,
+    pub expression_mark: &'a str,
 }
 
 impl<'a> Config<'a> {
+    /// Config of a `yarte.toml` source, panics on an invalid config
     pub fn new(s: &str) -> Config {
-        let raw: RawConfig =
-            toml::from_str(&s).unwrap_or_else(|_| panic!("invalid TOML in {}", CONFIG_FILE_NAME));
-        let (dir, print) = raw.main.map(|x| (x.dir, x.debug)).unwrap_or((None, None));
-
-        Config {
-            dir: Dir::from(dir),
-            print_override: PrintConfig::from(print),
-            debug: raw.debug.unwrap_or_default(),
-            alias: raw.partials.unwrap_or_default(),
-        }
+        Config::from_str(s).unwrap_or_else(|e| panic!("invalid {}: {}", CONFIG_FILE_NAME, e))
+    }
+
+    /// Config of a `yarte.toml` source
+    ///
+    /// Not `FromStr`, the config borrows the source.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> Result<Config<'a>, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    /// Builder of a config with the default values, without a config file
+    pub fn builder() -> ConfigBuilder<'a> {
+        ConfigBuilder::default()
     }
 
     pub fn get_dir(&self) -> &PathBuf {
@@ -137,41 +367,479 @@ impl<'a> Config<'a> {
     }
 
     pub fn get_template(&self, ident: &str) -> (PathBuf, String) {
-        let path = self.dir.get_template(ident);
-        let src = get_source(path.as_path());
+        let path = match self.resolve_alias(ident) {
+            Some(path) => self.exists(path),
+            None => self.exists(self.dir.0.join(ident)),
+        };
+        let src = self.get_source(path.as_path());
         (path, src)
     }
 
+    /// Source of a template file in the configured encoding
+    pub fn get_source(&self, path: &Path) -> String {
+        match self.archived(path) {
+            Some(bytes) => source(decode(path, bytes.to_vec(), self.encoding)),
+            None => read_source(path, self.encoding),
+        }
+    }
+
+    /// Path of a partial, a directory resolves to its `index`
     pub fn resolve_partial(&self, parent: &Path, ident: &str) -> PathBuf {
-        let (mut buf, is_alias) = self
-            .alias
-            .iter()
-            .find_map(|(k, v)| {
-                if ident.starts_with(k) {
-                    let mut path = (*v).to_string();
-                    path.push_str(&ident[k.len()..]);
-                    Some(PathBuf::from(path))
+        self.normalize(self.exists(self.partial_path(parent, ident)))
+    }
+
+    /// Path of the partial when it exists, as the partial of a partial block
+    pub fn find_partial(&self, parent: &Path, ident: &str) -> Option<PathBuf> {
+        let path = self.partial_path(parent, ident);
+        if self.is_file(&path) {
+            Some(self.normalize(path))
+        } else {
+            None
+        }
+    }
+
+    /// Bytes of a template of the archive, the files of the templates directory go first
+    fn archived(&self, path: &Path) -> Option<&[u8]> {
+        if path.exists() {
+            return None;
+        }
+        let archive = self.archive.as_ref()?;
+        archive.get(lexical(path).strip_prefix(&self.dir.0).ok()?)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.exists() || self.archived(path).is_some()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+            || self.archive.as_ref().map_or(false, |archive| {
+                lexical(path)
+                    .strip_prefix(&self.dir.0)
+                    .map_or(false, |dir| archive.is_dir(dir))
+            })
+    }
+
+    fn exists(&self, template: PathBuf) -> PathBuf {
+        if self.is_file(&template) {
+            template
+        } else {
+            panic!("template not found in directory {:?}", template)
+        }
+    }
+
+    /// Canonical path of a file, the templates of the archive aren't in the file system
+    fn normalize(&self, path: PathBuf) -> PathBuf {
+        if path.exists() {
+            normalize(path)
+        } else {
+            lexical(&path)
+        }
+    }
+
+    fn partial_path(&self, parent: &Path, ident: &str) -> PathBuf {
+        if let Some(path) = self.resolve_alias(ident) {
+            return self.partial_file(path, ident, parent);
+        }
+
+        let alias = self.alias.iter().find_map(|(k, v)| {
+            if ident.starts_with(k) {
+                let mut path = (*v).to_string();
+                path.push_str(&ident[k.len()..]);
+                Some(path)
+            } else {
+                None
+            }
+        });
+
+        match alias {
+            Some(path) => self.partial_file(self.dir.0.join(path), ident, parent),
+            None => {
+                let mut path = parent.to_owned();
+                path.pop();
+                path.push(ident);
+                self.partial_file(path, ident, parent)
+            }
+        }
+    }
+
+    /// File of a partial, with the extension of the including template when it has none,
+    /// or the `index` of a directory, with a trailing `/` or without a file of its name
+    fn partial_file(&self, path: PathBuf, ident: &str, parent: &Path) -> PathBuf {
+        let with_extension = |path: PathBuf| match parent.extension() {
+            Some(ext) if path.extension().is_none() => path.with_extension(ext),
+            _ => path,
+        };
+
+        if ident.ends_with('/') {
+            return with_extension(path.join(INDEX));
+        }
+
+        let file = with_extension(path.clone());
+        if !self.is_file(&file) && self.is_dir(&path) {
+            with_extension(path.join(INDEX))
+        } else {
+            file
+        }
+    }
+
+    /// Path of a template starting with an alias of `[aliases]`, `@alias/path`
+    fn resolve_alias(&self, ident: &str) -> Option<PathBuf> {
+        self.aliases.iter().find_map(|(k, v)| {
+            if ident.starts_with(k) {
+                let rest = &ident[k.len()..];
+                if rest.is_empty() {
+                    Some(manifest_dir().join(v))
+                } else if rest.starts_with('/') {
+                    Some(manifest_dir().join(v).join(&rest[1..]))
                 } else {
                     None
                 }
-            })
-            .map_or((PathBuf::from(ident), false), |s| (s, true));
+            } else {
+                None
+            }
+        })
+    }
 
-        if buf.extension().is_none() {
-            if let Some(ext) = parent.extension() {
-                buf = buf.with_extension(ext);
+    /// Directories of the aliases of `[aliases]`
+    pub fn alias_dirs<'b>(&'b self) -> impl Iterator<Item = PathBuf> + 'b {
+        self.aliases.values().map(|v| manifest_dir().join(v))
+    }
+
+    /// Partial overriding the built-in helper `name`, with the extension of `parent`
+    /// when it has none
+    pub fn resolve_builtin(&self, parent: &Path, name: &str) -> Option<PathBuf> {
+        self.builtins.get(name).map(|path| {
+            let mut buf = PathBuf::from(path);
+            if buf.extension().is_none() {
+                if let Some(ext) = parent.extension() {
+                    buf = buf.with_extension(ext);
+                }
             }
-        };
 
-        if is_alias {
             normalize(self.dir.get_template(buf.to_str().unwrap()))
-        } else {
-            let mut parent = parent.to_owned();
-            parent.pop();
-            parent.push(buf);
-            normalize(parent)
+        })
+    }
+
+    /// Path of the type of the custom block helper `name`
+    pub fn helper(&self, name: &str) -> Option<&'a str> {
+        self.helpers.get(name).copied()
+    }
+
+    /// Path of the type of the custom condition helper `name`
+    pub fn condition(&self, name: &str) -> Option<&'a str> {
+        self.conditions.get(name).copied()
+    }
+
+    /// Partials overriding built-in helpers
+    pub fn builtin_partials<'b>(&'b self, parent: &'b Path) -> impl Iterator<Item = PathBuf> + 'b {
+        self.builtins
+            .keys()
+            .filter_map(move |name| self.resolve_builtin(parent, name))
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Config<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawConfig::deserialize(deserializer)?;
+        let mut builder = Config::builder()
+            .debug(raw.debug.unwrap_or_default())
+            .limits(raw.limits.unwrap_or_default());
+        for (name, path) in raw.partials.unwrap_or_default() {
+            builder = builder.alias(name, path);
+        }
+        for (name, path) in raw.aliases.unwrap_or_default() {
+            path_alias(name).map_err(D::Error::custom)?;
+            builder = builder.path_alias(name, path);
+        }
+        for (name, path) in raw.builtins.unwrap_or_default() {
+            builder = builder.builtin(name, path);
+        }
+        for (name, path) in raw.helpers.unwrap_or_default() {
+            helper("helpers", name).map_err(D::Error::custom)?;
+            builder = builder.helper(name, path);
         }
+        for (name, path) in raw.conditions.unwrap_or_default() {
+            helper("conditions", name).map_err(D::Error::custom)?;
+            builder = builder.condition(name, path);
+        }
+
+        if let Some(main) = raw.main {
+            if let Some(dir) = main.dir {
+                builder = builder.dir(dir);
+            }
+            if let Some(path) = main.routes {
+                let path = manifest_dir().join(path);
+                let src = fs::read_to_string(&path).map_err(|e| {
+                    D::Error::custom(format!("unable to read route manifest {:?}: {}", path, e))
+                })?;
+                builder = builder.routes(Routes::new(&src));
+            }
+            if let Some(path) = main.assets {
+                let path = manifest_dir().join(path);
+                let src = fs::read_to_string(&path).map_err(|e| {
+                    D::Error::custom(format!("unable to read asset manifest {:?}: {}", path, e))
+                })?;
+                builder = builder.assets(src.parse().map_err(D::Error::custom)?);
+            }
+            if let Some(path) = main.archive {
+                let path = manifest_dir().join(path);
+                let bytes = fs::read(&path).map_err(|e| {
+                    D::Error::custom(format!("unable to read template archive {:?}: {}", path, e))
+                })?;
+                let archive = Archive::from_bytes(path, &bytes).map_err(D::Error::custom)?;
+                builder = builder.archive(archive);
+            }
+            if let Some(path) = main.graph {
+                builder = builder.graph(path);
+            }
+            if let Some(tag) = main.fragment_tag {
+                fragment_tag(tag).map_err(D::Error::custom)?;
+                builder = builder.fragment_tag(tag);
+            }
+            if let Some(mark) = main.expression_mark {
+                expression_mark(mark).map_err(D::Error::custom)?;
+                builder = builder.expression_mark(mark);
+            }
+            fn parse<T: FromStr<Err = String>, E: Error>(s: Option<&str>) -> Result<Option<T>, E> {
+                s.map(str::parse).transpose().map_err(E::custom)
+            }
+
+            builder = builder.print(PrintConfig::from(main.debug));
+            if let Some(on_html_error) = parse::<_, D::Error>(main.on_html_error)? {
+                builder = builder.on_html_error(on_html_error);
+            }
+            if let Some(newlines) = parse::<_, D::Error>(main.newlines)? {
+                builder = builder.newlines(newlines);
+            }
+            if let Some(whitespace) = parse::<_, D::Error>(main.whitespace)? {
+                builder = builder.whitespace(whitespace);
+            }
+            if let Some(encoding) = parse::<_, D::Error>(main.encoding)? {
+                builder = builder.encoding(encoding);
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// Builder of a `Config`, as the config file without reading it
+///
+/// Relative paths are relative to the crate root, `CARGO_MANIFEST_DIR`, or the current
+/// directory out of cargo.
+#[derive(Debug)]
+pub struct ConfigBuilder<'a>(Config<'a>);
+
+impl<'a> Default for ConfigBuilder<'a> {
+    fn default() -> Self {
+        ConfigBuilder(Config {
+            dir: Dir::from(None),
+            alias: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            builtins: BTreeMap::new(),
+            helpers: BTreeMap::new(),
+            conditions: BTreeMap::new(),
+            print_override: PrintConfig::None,
+            debug: PrintOption::default(),
+            limits: Limits::default(),
+            routes: None,
+            assets: None,
+            archive: None,
+            graph: None,
+            on_html_error: OnHtmlError::Error,
+            newlines: Newlines::Keep,
+            whitespace: Whitespace::Ascii,
+            encoding: Encoding::Utf8,
+            fragment_tag: "marquee",
+            expression_mark: "yarteHashHTMLExpressionsATTT",
+        })
+    }
+}
+
+impl<'a> ConfigBuilder<'a> {
+    /// Templates directory, `dir` of `[main]`
+    pub fn dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.0.dir = Dir(manifest_dir().join(dir));
+        self
+    }
+
+    /// Partial alias, an entry of `[partials]`
+    pub fn alias(mut self, name: &'a str, path: &'a str) -> Self {
+        self.0.alias.insert(name, path);
+        self
+    }
+
+    /// Alias of a directory, relative to the crate root, for template paths and
+    /// partials, an entry of `[aliases]`
+    ///
+    /// # Panics
+    /// When the name doesn't start with `@`
+    pub fn path_alias(mut self, name: &'a str, path: &'a str) -> Self {
+        path_alias(name).unwrap_or_else(|e| panic!("{}", e));
+        self.0.aliases.insert(name, path);
+        self
+    }
+
+    /// Built-in helper override, an entry of `[builtins]`
+    pub fn builtin(mut self, name: &'a str, path: &'a str) -> Self {
+        self.0.builtins.insert(name, path);
+        self
+    }
+
+    /// Custom block helper, the path of its type, an entry of `[helpers]`
+    ///
+    /// # Panics
+    /// When the name is the one of a helper of yarte
+    pub fn helper(mut self, name: &'a str, path: &'a str) -> Self {
+        helper("helpers", name).unwrap_or_else(|e| panic!("{}", e));
+        self.0.helpers.insert(name, path);
+        self
+    }
+
+    /// Custom condition helper, the path of its type, an entry of `[conditions]`
+    ///
+    /// # Panics
+    /// When the name is the one of a helper of yarte
+    pub fn condition(mut self, name: &'a str, path: &'a str) -> Self {
+        helper("conditions", name).unwrap_or_else(|e| panic!("{}", e));
+        self.0.conditions.insert(name, path);
+        self
+    }
+
+    /// Output of debug mode, `debug` of `[main]`
+    pub fn print(mut self, print: PrintConfig) -> Self {
+        self.0.print_override = print;
+        self
+    }
+
+    /// Format of debug mode, `[debug]`
+    pub fn debug(mut self, debug: PrintOption<'a>) -> Self {
+        self.0.debug = debug;
+        self
+    }
+
+    /// Compile-time limits, `[limits]`
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.0.limits = limits;
+        self
+    }
+
+    /// Internal routes, the manifest of `routes` of `[main]`
+    pub fn routes(mut self, routes: Routes) -> Self {
+        self.0.routes = Some(routes);
+        self
+    }
+
+    /// Asset manifest, the manifest of `assets` of `[main]`
+    pub fn assets(mut self, assets: Assets) -> Self {
+        self.0.assets = Some(assets);
+        self
+    }
+
+    /// Templates out of the templates directory, the archive of `archive` of `[main]`
+    pub fn archive(mut self, archive: Archive) -> Self {
+        self.0.archive = Some(archive);
+        self
+    }
+
+    /// Path of the usage graph, `graph` of `[main]`
+    pub fn graph<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.0.graph = Some(manifest_dir().join(path));
+        self
+    }
+
+    /// Handling of the html parse errors, `on_html_error` of `[main]`
+    pub fn on_html_error(mut self, on_html_error: OnHtmlError) -> Self {
+        self.0.on_html_error = on_html_error;
+        self
+    }
+
+    /// Line endings of the template sources, `newlines` of `[main]`
+    pub fn newlines(mut self, newlines: Newlines) -> Self {
+        self.0.newlines = newlines;
+        self
+    }
+
+    /// Whitespace of the literals, `whitespace` of `[main]`
+    pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.0.whitespace = whitespace;
+        self
+    }
+
+    /// Encoding of the template files, `encoding` of `[main]`
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.0.encoding = encoding;
+        self
+    }
+
+    /// Tag wrapping the templates in the html passes, `fragment_tag` of `[main]`
+    ///
+    /// # Panics
+    /// When the tag isn't lowercase letters, digits and `-`, starting with a letter
+    pub fn fragment_tag(mut self, tag: &'a str) -> Self {
+        fragment_tag(tag).unwrap_or_else(|e| panic!("{}", e));
+        self.0.fragment_tag = tag;
+        self
+    }
+
+    /// Mark of the expressions in the html passes, `expression_mark` of `[main]`
+    ///
+    /// # Panics
+    /// When the mark isn't letters and digits
+    pub fn expression_mark(mut self, mark: &'a str) -> Self {
+        expression_mark(mark).unwrap_or_else(|e| panic!("{}", e));
+        self.0.expression_mark = mark;
+        self
+    }
+
+    pub fn build(self) -> Config<'a> {
+        self.0
+    }
+}
+
+fn path_alias(name: &str) -> Result<(), String> {
+    if !name.starts_with('@') || name.len() == 1 || name.contains('/') {
+        return Err(format!(
+            "invalid alias \"{}\" of [aliases] in {}, aliases are `@name`",
+            name, CONFIG_FILE_NAME
+        ));
+    }
+    Ok(())
+}
+
+fn helper(table: &str, name: &str) -> Result<(), String> {
+    if ["each", "if", "unless", "with", "portal"].contains(&name) {
+        return Err(format!(
+            "invalid helper \"{}\" of [{}] in {}, it's a helper of yarte",
+            name, table, CONFIG_FILE_NAME
+        ));
+    }
+    Ok(())
+}
+
+fn fragment_tag(tag: &str) -> Result<(), String> {
+    let valid = tag.starts_with(|c: char| c.is_ascii_lowercase())
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(format!(
+            "invalid `fragment_tag` \"{}\" in {}, expected a lowercase tag name",
+            tag, CONFIG_FILE_NAME
+        ));
+    }
+    Ok(())
+}
+
+fn expression_mark(mark: &str) -> Result<(), String> {
+    if mark.is_empty() || !mark.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "invalid `expression_mark` \"{}\" in {}, expected letters and digits",
+            mark, CONFIG_FILE_NAME
+        ));
     }
+    Ok(())
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -184,6 +852,21 @@ fn normalize(p: PathBuf) -> PathBuf {
     p
 }
 
+/// Path without `.` and `..` components, without reading the file system
+fn lexical(p: &Path) -> PathBuf {
+    let mut path = PathBuf::new();
+    for component in p.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                path.pop();
+            }
+            c => path.push(c),
+        }
+    }
+    path
+}
+
 #[derive(Deserialize)]
 struct RawConfig<'a> {
     #[serde(borrow)]
@@ -192,6 +875,15 @@ struct RawConfig<'a> {
     debug: Option<PrintOption<'a>>,
     #[serde(borrow)]
     partials: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    aliases: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    builtins: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    helpers: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    conditions: Option<BTreeMap<&'a str, &'a str>>,
+    limits: Option<Limits>,
 }
 
 #[derive(Deserialize)]
@@ -200,6 +892,26 @@ struct Main<'a> {
     dir: Option<&'a str>,
     #[serde(borrow)]
     debug: Option<&'a str>,
+    #[serde(borrow)]
+    routes: Option<&'a str>,
+    #[serde(borrow)]
+    assets: Option<&'a str>,
+    #[serde(borrow)]
+    archive: Option<&'a str>,
+    #[serde(borrow)]
+    graph: Option<&'a str>,
+    #[serde(borrow)]
+    on_html_error: Option<&'a str>,
+    #[serde(borrow)]
+    newlines: Option<&'a str>,
+    #[serde(borrow)]
+    whitespace: Option<&'a str>,
+    #[serde(borrow)]
+    encoding: Option<&'a str>,
+    #[serde(borrow)]
+    fragment_tag: Option<&'a str>,
+    #[serde(borrow)]
+    expression_mark: Option<&'a str>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -226,10 +938,230 @@ impl<'a> Default for PrintOption<'a> {
     }
 }
 
+/// Compile-time limits of a template
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Max nesting depth of helpers and partials
+    pub depth: usize,
+    /// Max number of nodes after partial inlining
+    pub nodes: usize,
+    /// Max number of expressions after partial inlining
+    pub expressions: usize,
+    /// Max number of calls of a partial in itself, the next call renders nothing
+    pub recursion: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            depth: 128,
+            nodes: 65536,
+            expressions: 16384,
+            recursion: 0,
+        }
+    }
+}
+
+/// Internal routes of the application
+///
+/// One route by line of the manifest, empty lines and lines starting with `#` are ignored.
+/// A segment `{name}` or `:name` matches any segment and a last segment `*` matches the rest
+/// of the path.
+#[derive(Debug)]
+pub struct Routes(Vec<Vec<String>>);
+
+impl Routes {
+    pub fn new(src: &str) -> Routes {
+        Routes(
+            src.lines()
+                .map(str::trim)
+                .filter(|x| !x.is_empty() && !x.starts_with('#'))
+                .map(|x| segments(x).map(ToOwned::to_owned).collect())
+                .collect(),
+        )
+    }
+
+    /// Path matches some route, query and fragment are ignored
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path
+            .split(|c| c == '?' || c == '#')
+            .next()
+            .unwrap_or_default();
+        let path: Vec<&str> = segments(path).collect();
+
+        self.0.iter().any(|route| {
+            for (i, segment) in route.iter().enumerate() {
+                if segment == "*" && i + 1 == route.len() {
+                    return true;
+                }
+                match path.get(i) {
+                    Some(x) if segment == x => (),
+                    Some(_) if segment.starts_with('{') || segment.starts_with(':') => (),
+                    _ => return false,
+                }
+            }
+
+            route.len() == path.len()
+        })
+    }
+}
+
+/// Hashed urls of the images of the application, for `{{ @img }}`
+///
+/// One url by line of the manifest, `source width url`, as `hero.jpg 480 /assets/hero.3f2a.jpg`.
+/// Empty lines and lines starting with `#` are ignored.
+#[derive(Debug)]
+pub struct Assets(BTreeMap<(String, u32), String>);
+
+impl Assets {
+    /// # Panics
+    /// When a line hasn't a source, an integer width and an url
+    pub fn new(src: &str) -> Assets {
+        src.parse().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Url of a width of an image
+    pub fn get(&self, source: &str, width: u32) -> Option<&str> {
+        self.0.get(&(source.to_owned(), width)).map(String::as_str)
+    }
+}
+
+impl FromStr for Assets {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        src.lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty() && !x.starts_with('#'))
+            .map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                match fields.as_slice() {
+                    [source, width, url] => match width.parse() {
+                        Ok(width) => Ok(((source.to_string(), width), url.to_string())),
+                        Err(_) => Err(line),
+                    },
+                    _ => Err(line),
+                }
+                .map_err(|line| {
+                    format!(
+                        "invalid line `{}` of asset manifest, expected `source width url`",
+                        line
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Assets)
+    }
+}
+
+/// Templates of an uncompressed tar archive, for the builds without the templates directory
+///
+/// The paths of the entries are relative to the templates directory, as `partials/head.hbs`.
+/// The files of the templates directory go before the entries of the archive.
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl Archive {
+    const BLOCK: usize = 512;
+
+    /// # Panics
+    /// When the archive isn't a valid tar archive
+    pub fn new(path: PathBuf, bytes: &[u8]) -> Archive {
+        Archive::from_bytes(path, bytes).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Archive of the bytes of a tar file, an error when it isn't a valid tar archive
+    pub fn from_bytes(path: PathBuf, bytes: &[u8]) -> Result<Archive, String> {
+        let invalid = || format!("invalid tar archive {:?}", path);
+        let field = |header: &[u8]| -> Vec<u8> {
+            header.iter().take_while(|x| **x != 0).cloned().collect()
+        };
+
+        let mut files = BTreeMap::new();
+        let mut long_name = None;
+        let mut blocks = bytes.chunks(Self::BLOCK);
+        while let Some(header) = blocks.next() {
+            if header.len() != Self::BLOCK {
+                return Err(invalid());
+            }
+            if header.iter().all(|x| *x == 0) {
+                break;
+            }
+
+            let size = std::str::from_utf8(&field(&header[124..136]))
+                .ok()
+                .and_then(|x| usize::from_str_radix(x.trim(), 8).ok())
+                .ok_or_else(invalid)?;
+            let mut data = Vec::with_capacity(size);
+            for _ in 0..(size + Self::BLOCK - 1) / Self::BLOCK {
+                data.extend_from_slice(blocks.next().ok_or_else(invalid)?);
+            }
+            if data.len() < size {
+                return Err(invalid());
+            }
+            data.truncate(size);
+
+            let mut name = field(&header[..100]);
+            if &header[257..262] == b"ustar" && header[345] != 0 {
+                let mut prefix = field(&header[345..500]);
+                prefix.push(b'/');
+                prefix.extend(name);
+                name = prefix;
+            }
+
+            match header[156] {
+                b'L' => long_name = Some(field(&data)),
+                b'0' | 0 => {
+                    let name = long_name.take().unwrap_or(name);
+                    let name = String::from_utf8(name).map_err(|_| invalid())?;
+                    files.insert(lexical(Path::new(&name)), data);
+                }
+                _ => long_name = None,
+            }
+        }
+
+        Ok(Archive { path, files })
+    }
+
+    /// Path of the archive
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn get(&self, path: &Path) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .any(|file| file != path && file.starts_with(path))
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|x| !x.is_empty())
+}
+
+/// Config of the crate, the config of its workspace root overridden by its own config,
+/// with the environment variables of its values
 pub fn read_config_file() -> String {
-    let filename = config_file_path();
+    let member = read_file(&config_file_path());
+    let config = match workspace_config_file_path() {
+        Some(path) => merge(&read_file(&path), &member),
+        None => member,
+    };
+
+    interpolate(&config, |name| env::var(name).ok())
+}
+
+fn read_file(filename: &Path) -> String {
     if filename.exists() {
-        fs::read_to_string(&filename)
+        fs::read_to_string(filename)
             .unwrap_or_else(|_| panic!("unable to read {}", filename.to_str().unwrap()))
     } else {
         String::new()
@@ -238,25 +1170,522 @@ pub fn read_config_file() -> String {
 
 #[inline]
 pub fn config_file_path() -> PathBuf {
-    PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join(CONFIG_FILE_NAME)
+    manifest_dir().join(CONFIG_FILE_NAME)
 }
 
-pub fn get_source(path: &Path) -> String {
-    match fs::read_to_string(path) {
-        Ok(mut source) => match source
-            .as_bytes()
-            .iter()
-            .rposition(|x| !x.is_ascii_whitespace())
-        {
-            Some(j) => {
-                source.drain(j + 1..);
-                source
+/// Root of the crate, the current directory out of cargo
+fn manifest_dir() -> PathBuf {
+    env::var_os("CARGO_MANIFEST_DIR").map_or_else(
+        || env::current_dir().expect("current directory"),
+        PathBuf::from,
+    )
+}
+
+/// Config file of the workspace root of the crate, when the crate is a member of a
+/// workspace in a parent directory
+pub fn workspace_config_file_path() -> Option<PathBuf> {
+    workspace_root(&manifest_dir()).map(|root| root.join(CONFIG_FILE_NAME))
+}
+
+/// Nearest parent directory with a `Cargo.toml` with a `[workspace]` table
+fn workspace_root(manifest_dir: &Path) -> Option<PathBuf> {
+    manifest_dir.ancestors().skip(1).find_map(|dir| {
+        let manifest = dir.join("Cargo.toml");
+        fs::read_to_string(&manifest)
+            .ok()?
+            .parse::<toml::Value>()
+            .ok()?
+            .get("workspace")
+            .map(|_| dir.to_owned())
+    })
+}
+
+/// Merge the config of a workspace member over the config of the workspace,
+/// the tables are merged by key and the values of the member are kept
+pub fn merge(workspace: &str, member: &str) -> String {
+    fn parse(s: &str, name: &str) -> toml::Value {
+        toml::from_str(s)
+            .unwrap_or_else(|_| panic!("invalid TOML in {} {}", name, CONFIG_FILE_NAME))
+    }
+
+    fn merge_value(base: &mut toml::Value, over: toml::Value) {
+        match (base, over) {
+            (toml::Value::Table(base), toml::Value::Table(over)) => {
+                for (k, v) in over {
+                    match base.get_mut(&k) {
+                        Some(b) => merge_value(b, v),
+                        None => {
+                            base.insert(k, v);
+                        }
+                    }
+                }
             }
-            None => source,
-        },
+            (base, over) => *base = over,
+        }
+    }
+
+    if workspace.trim().is_empty() {
+        return member.to_owned();
+    }
+
+    let mut base = parse(workspace, "workspace");
+    merge_value(&mut base, parse(member, "member"));
+    base.to_string()
+}
+
+/// Source of a template file in UTF-8
+pub fn get_source(path: &Path) -> String {
+    read_source(path, Encoding::Utf8)
+}
+
+fn read_source(path: &Path, encoding: Encoding) -> String {
+    match fs::read(path) {
+        Ok(bytes) => source(decode(path, bytes, encoding)),
         _ => panic!("unable to open template file '{:?}'", path),
     }
 }
 
+/// Source without trailing whitespace
+fn source(mut source: String) -> String {
+    match source
+        .as_bytes()
+        .iter()
+        .rposition(|x| !x.is_ascii_whitespace())
+    {
+        Some(j) => {
+            source.drain(j + 1..);
+            source
+        }
+        None => source,
+    }
+}
+
+/// Decode a template file, a byte order mark of UTF-8 or UTF-16 is stripped and
+/// chooses the encoding
+fn decode(path: &Path, mut bytes: Vec<u8>, encoding: Encoding) -> String {
+    let utf16 = |bytes: &[u8], le: bool| {
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|x| match *x {
+                [a, b] if le => u16::from_le_bytes([a, b]),
+                [a, b] => u16::from_be_bytes([a, b]),
+                _ => panic!("invalid UTF-16 in template file {:?}, odd length", path),
+            })
+            .collect();
+        String::from_utf16(&units)
+            .unwrap_or_else(|_| panic!("invalid UTF-16 in template file {:?}", path))
+    };
+
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        bytes.drain(..3);
+    } else if bytes.starts_with(&[0xFF, 0xFE]) {
+        return utf16(&bytes[2..], true);
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        return utf16(&bytes[2..], false);
+    } else if encoding == Encoding::Latin1 {
+        return bytes.into_iter().map(char::from).collect();
+    }
+
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        panic!(
+            "invalid UTF-8 in template file {:?} at byte {}, set `encoding` in {} \
+             for templates in other encodings",
+            path,
+            e.utf8_error().valid_up_to(),
+            CONFIG_FILE_NAME
+        )
+    })
+}
+
+/// Replace each `${VAR}` of the string values of a config with the value of `var`,
+/// `$${` is a literal `${`
+pub fn interpolate<F: Fn(&str) -> Option<String>>(src: &str, var: F) -> String {
+    fn visit<F: Fn(&str) -> Option<String>>(value: &mut toml::Value, var: &F) {
+        match value {
+            toml::Value::String(s) => *s = interpolate_str(s, var),
+            toml::Value::Array(a) => a.iter_mut().for_each(|x| visit(x, var)),
+            toml::Value::Table(t) => t.iter_mut().for_each(|(_, x)| visit(x, var)),
+            _ => (),
+        }
+    }
+
+    if !src.contains("${") {
+        return src.to_owned();
+    }
+
+    let mut value =
+        toml::from_str(src).unwrap_or_else(|_| panic!("invalid TOML in {}", CONFIG_FILE_NAME));
+    visit(&mut value, &var);
+    value.to_string()
+}
+
+/// Names of the environment variables of a config, for `cargo:rerun-if-env-changed`
+pub fn env_vars(src: &str) -> Vec<String> {
+    let mut vars = vec![];
+    interpolate_str(src, |name: &str| {
+        vars.push(name.to_owned());
+        Some(String::new())
+    });
+    vars
+}
+
+fn interpolate_str<F: FnMut(&str) -> Option<String>>(s: &str, mut var: F) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find("${") {
+        if rest[..i].ends_with('$') {
+            out.push_str(&rest[..i - 1]);
+            out.push_str("${");
+            rest = &rest[i + 2..];
+            continue;
+        }
+        out.push_str(&rest[..i]);
+        let len = rest[i + 2..]
+            .find('}')
+            .unwrap_or_else(|| panic!("unclosed `${{` in {}: {:?}", CONFIG_FILE_NAME, s));
+        let name = &rest[i + 2..i + 2 + len];
+        let value = var(name).unwrap_or_else(|| {
+            panic!(
+                "environment variable `{}` of {} is not defined",
+                name, CONFIG_FILE_NAME
+            )
+        });
+        out.push_str(&value);
+        rest = &rest[i + 3 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
 static CONFIG_FILE_NAME: &str = "yarte.toml";
 static DEFAULT_DIR: &str = "templates";
+static INDEX: &str = "index";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_routes() {
+        let routes =
+            Routes::new("# comment\n/\n/posts/\n\n/posts/{id}/edit\n/users/:name\n/static/*");
+
+        assert!(routes.matches("/"));
+        assert!(routes.matches("/posts"));
+        assert!(routes.matches("/posts/?page=2#top"));
+        assert!(routes.matches("/posts/1/edit"));
+        assert!(routes.matches("/users/yarte"));
+        assert!(routes.matches("/static/css/main.css"));
+        assert!(routes.matches("/static"));
+
+        assert!(!routes.matches("/post"));
+        assert!(!routes.matches("/posts/1"));
+        assert!(!routes.matches("/users/yarte/posts"));
+        assert!(!routes.matches("/comment"));
+    }
+
+    #[test]
+    fn test_marks() {
+        let config = Config::new("");
+        assert_eq!(config.fragment_tag, "marquee");
+        assert_eq!(config.expression_mark, "yarteHashHTMLExpressionsATTT");
+
+        let config =
+            Config::new("[main]\nfragment_tag = \"yarte-fragment\"\nexpression_mark = \"yMark0\"");
+        assert_eq!(config.fragment_tag, "yarte-fragment");
+        assert_eq!(config.expression_mark, "yMark0");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid `expression_mark` \"y-mark\" in yarte.toml")]
+    fn test_marks_invalid() {
+        Config::new("[main]\nexpression_mark = \"y-mark\"");
+    }
+
+    #[test]
+    fn test_assets() {
+        let assets =
+            Assets::new("# images\nhero.jpg 480 /a/hero.1f.jpg\n\n  hero.jpg  960\t/a/hero.2e.jpg");
+
+        assert_eq!(assets.get("hero.jpg", 480), Some("/a/hero.1f.jpg"));
+        assert_eq!(assets.get("hero.jpg", 960), Some("/a/hero.2e.jpg"));
+        assert_eq!(assets.get("hero.jpg", 1920), None);
+        assert_eq!(assets.get("logo.png", 480), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid line `hero.jpg big /a/hero.jpg` of asset manifest")]
+    fn test_assets_invalid() {
+        Assets::new("hero.jpg big /a/hero.jpg");
+    }
+
+    #[test]
+    fn test_on_html_error() {
+        let config = |toml| Config::new(toml).on_html_error;
+
+        assert_eq!(config(""), OnHtmlError::Error);
+        assert_eq!(
+            config("[main]\non_html_error = \"error\""),
+            OnHtmlError::Error
+        );
+        assert_eq!(
+            config("[main]\non_html_error = \"fallback-text\""),
+            OnHtmlError::FallbackText
+        );
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let config = |toml| Config::new(toml).whitespace;
+        assert_eq!(config(""), Whitespace::Ascii);
+        assert_eq!(
+            config("[main]\nwhitespace = \"unicode\""),
+            Whitespace::Unicode
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let path = Path::new("a.hbs");
+        let decode = |bytes: &[u8], encoding| decode(path, bytes.to_vec(), encoding);
+        assert_eq!(decode(b"\xEF\xBB\xBFa\xC3\xB1", Encoding::Utf8), "a\u{f1}");
+        assert_eq!(decode(b"a\xF1", Encoding::Latin1), "a\u{f1}");
+        assert_eq!(decode(b"\xEF\xBB\xBFa", Encoding::Latin1), "a");
+        assert_eq!(decode(b"\xFF\xFEa\x00\xF1\x00", Encoding::Utf8), "a\u{f1}");
+        assert_eq!(decode(b"\xFE\xFF\x00a\x00\xF1", Encoding::Utf8), "a\u{f1}");
+        assert_eq!(decode(b"", Encoding::Utf8), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid UTF-8 in template file \"a.hbs\" at byte 2")]
+    fn test_decode_invalid() {
+        decode(Path::new("a.hbs"), b"ab\xF1".to_vec(), Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_builder() {
+        let config = Config::builder()
+            .dir("views")
+            .alias("layout", "./layouts")
+            .builtin("meta", "partials/meta")
+            .newlines(Newlines::Lf)
+            .limits(Limits {
+                depth: 8,
+                ..Limits::default()
+            })
+            .build();
+
+        assert_eq!(config.get_dir(), &manifest_dir().join("views"));
+        assert_eq!(config.alias.get("layout"), Some(&"./layouts"));
+        assert_eq!(config.builtins.get("meta"), Some(&"partials/meta"));
+        assert_eq!(config.newlines, Newlines::Lf);
+        assert_eq!(config.limits.depth, 8);
+        assert_eq!(config.limits.nodes, 65536);
+
+        let parsed = Config::from_str("[main]\ndir = \"views\"\nnewlines = \"lf\"").unwrap();
+        assert_eq!(parsed.get_dir(), config.get_dir());
+        assert_eq!(parsed.newlines, config.newlines);
+        assert!(Config::from_str("[main").is_err());
+        for toml in &[
+            "[main]\nnewlines = \"cr\"",
+            "[main]\nexpression_mark = \"y-mark\"",
+            "[main]\nfragment_tag = \"Div\"",
+            "[main]\nroutes = \"missing.routes\"",
+            "[aliases]\ncomponents = \"templates/components\"",
+            "[helpers]\neach = \"crate::Each\"",
+            "[conditions]\nif = \"crate::If\"",
+        ] {
+            assert!(Config::from_str(toml).is_err());
+        }
+        assert_eq!(
+            Config::builder().build().get_dir(),
+            Config::new("").get_dir()
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        let config = Config::new("[aliases]\n\"@src\" = \"src\"\n\"@s\" = \"src\"");
+        let parent = manifest_dir().join("templates/index.hbs");
+        let lib = normalize(manifest_dir().join("src/lib.rs"));
+
+        assert_eq!(
+            config.get_template("@src/lib.rs").0,
+            manifest_dir().join("src/lib.rs")
+        );
+        assert_eq!(config.resolve_partial(&parent, "@src/lib.rs"), lib);
+        assert_eq!(config.resolve_partial(&parent, "@s/lib.rs"), lib);
+        assert_eq!(config.resolve_alias("@sr/lib.rs"), None);
+        assert_eq!(config.alias_dirs().count(), 2);
+    }
+
+    #[test]
+    fn test_partial_index() {
+        let config = Config::new("");
+        let parent = manifest_dir().join("index.rs");
+        let file = |ident| config.partial_file(manifest_dir().join(ident), ident, &parent);
+
+        assert_eq!(file("src/"), manifest_dir().join("src/index.rs"));
+        assert_eq!(file("src"), manifest_dir().join("src/index.rs"));
+        assert_eq!(file("src/lib"), manifest_dir().join("src/lib.rs"));
+        assert_eq!(file("src/lib.rs"), manifest_dir().join("src/lib.rs"));
+    }
+
+    fn tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut tar = vec![];
+        for (name, src) in files {
+            let mut header = [0; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", src.len()).as_bytes());
+            header[156] = b'0';
+            header[257..262].copy_from_slice(b"ustar");
+            tar.extend_from_slice(&header);
+            tar.extend_from_slice(src.as_bytes());
+            tar.resize((tar.len() + 511) / 512 * 512, 0);
+        }
+        tar.resize(tar.len() + 1024, 0);
+        tar
+    }
+
+    #[test]
+    fn test_archive() {
+        let archive = Archive::new(
+            PathBuf::from("templates.tar"),
+            &tar(&[
+                ("./index.hbs", "{{> partials/head }}\n"),
+                ("partials/head.hbs", "<head>"),
+                ("list/index.hbs", "<ul>"),
+            ]),
+        );
+        let config = Config::builder().dir("archived").archive(archive).build();
+        let dir = manifest_dir().join("archived");
+
+        let (path, src) = config.get_template("index.hbs");
+        assert_eq!(path, dir.join("index.hbs"));
+        assert_eq!(src, "{{> partials/head }}");
+
+        let head = config.resolve_partial(&path, "partials/head");
+        assert_eq!(head, dir.join("partials/head.hbs"));
+        assert_eq!(config.get_source(&head), "<head>");
+        assert_eq!(
+            config.resolve_partial(&head, "../list"),
+            dir.join("list/index.hbs")
+        );
+        assert_eq!(config.find_partial(&path, "partials/foot"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "template not found in directory")]
+    fn test_archive_not_found() {
+        let archive = Archive::new(PathBuf::from("templates.tar"), &tar(&[]));
+        Config::builder()
+            .dir("archived")
+            .archive(archive)
+            .build()
+            .get_template("index.hbs");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid alias \"components\" of [aliases]")]
+    fn test_aliases_invalid() {
+        Config::new("[aliases]\ncomponents = \"src\"");
+    }
+
+    #[test]
+    fn test_helpers() {
+        let config = Config::new("[helpers]\nrepeat = \"crate::Repeat\"");
+
+        assert_eq!(config.helper("repeat"), Some("crate::Repeat"));
+        assert_eq!(config.helper("times"), None);
+
+        let config = Config::new("[conditions]\npresent = \"crate::Present\"");
+
+        assert_eq!(config.condition("present"), Some("crate::Present"));
+        assert_eq!(config.helper("present"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid helper \"each\" of [helpers]")]
+    fn test_helpers_invalid() {
+        Config::new("[helpers]\neach = \"crate::Each\"");
+    }
+
+    #[test]
+    fn test_merge() {
+        let merged = merge(
+            "[main]\ndir = \"views\"\nnewlines = \"lf\"\n\
+             [partials]\nlayout = \"./layouts\"\nshared = \"./shared\"\n\
+             [limits]\ndepth = 32",
+            "[main]\nnewlines = \"crlf\"\n[partials]\nshared = \"./common\"\n",
+        );
+        let config = Config::new(&merged);
+
+        assert!(config.get_dir().ends_with("views"));
+        assert_eq!(config.newlines, Newlines::Crlf);
+        assert_eq!(config.alias.get("layout"), Some(&"./layouts"));
+        assert_eq!(config.alias.get("shared"), Some(&"./common"));
+        assert_eq!(config.limits.depth, 32);
+
+        assert_eq!(merge("", "[main]\ndir = \"a\""), "[main]\ndir = \"a\"");
+        assert_eq!(
+            Config::new(&merge("[main]\nwhitespace = \"unicode\"", "")).whitespace,
+            Whitespace::Unicode
+        );
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let var = |name: &str| match name {
+            "OUT" => Some("target/gen".to_owned()),
+            "ROOT" => Some("/ci".to_owned()),
+            _ => None,
+        };
+        let config = interpolate(
+            "[main]\ndir = \"${OUT}/templates\"\ngraph = \"${ROOT}/$${OUT}/${OUT}\"\n\
+             [partials]\nshared = \"${ROOT}\"",
+            var,
+        );
+        let config = Config::new(&config);
+
+        assert!(config.get_dir().ends_with("target/gen/templates"));
+        assert_eq!(config.alias.get("shared"), Some(&"/ci"));
+        assert!(config.graph.unwrap().ends_with("/ci/${OUT}/target/gen"));
+        assert_eq!(
+            interpolate_str("${ROOT}/$${OUT}/${OUT}", var),
+            "/ci/${OUT}/target/gen"
+        );
+        assert_eq!(
+            interpolate("[main]\ndir = \"a\"", var),
+            "[main]\ndir = \"a\""
+        );
+        assert_eq!(env_vars("dir = \"${A}/${B}/$${C}\""), vec!["A", "B"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "environment variable `NOPE` of yarte.toml is not defined")]
+    fn test_interpolate_undefined() {
+        interpolate("[main]\ndir = \"${NOPE}\"", |_| None);
+    }
+
+    #[test]
+    fn test_workspace_root() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            workspace_root(manifest_dir).as_deref(),
+            manifest_dir.parent()
+        );
+    }
+
+    #[test]
+    fn test_newlines() {
+        let config = |toml| Config::new(toml).newlines;
+        assert_eq!(config(""), Newlines::Keep);
+        assert_eq!(config("[main]\nnewlines = \"lf\""), Newlines::Lf);
+        assert_eq!(config("[main]\nnewlines = \"crlf\""), Newlines::Crlf);
+
+        let src = "a\r\nb\nc\r\n\r\nd\re".to_string();
+        assert_eq!(Newlines::Keep.normalize(src.clone()), src);
+        assert_eq!(Newlines::Lf.normalize(src.clone()), "a\nb\nc\n\nd\re");
+        assert_eq!(Newlines::Crlf.normalize(src), "a\r\nb\r\nc\r\n\r\nd\re");
+    }
+}
@@ -0,0 +1,46 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use yarte_hir::Struct;
+
+/// Method `with_{fields}` binding the fields of the attribute `bind`, it returns a closure
+/// receiving the rest of the fields, in the order of the struct, that builds the template
+///
+/// The bound values are cloned for each template, so a heavy part of the context is
+/// computed once and referenced, or shared with `Rc`, by many renders.
+pub(super) fn bind(s: &Struct) -> TokenStream {
+    if s.bind.is_empty() {
+        return TokenStream::new();
+    }
+
+    let bound = &s.bind;
+    let (bound_ty, rest): (Vec<_>, Vec<_>) = s
+        .fields
+        .iter()
+        .partition(|f| f.ident.iter().any(|x| bound.contains(x)));
+    let bound_ty = bound.iter().map(|x| {
+        &bound_ty
+            .iter()
+            .find(|f| f.ident.as_ref() == Some(x))
+            .unwrap()
+            .ty
+    });
+    let rest_ident: Vec<_> = rest.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let rest_ty = rest.iter().map(|f| &f.ty);
+
+    let names: Vec<_> = bound.iter().map(ToString::to_string).collect();
+    let method = format_ident!("with_{}", names.join("_"));
+    let doc = format!(
+        "Template with `{}` bound, the closure receives the rest of the fields",
+        names.join("`, `")
+    );
+    s.implement_inherent(&quote! {
+        #[doc = #doc]
+        pub fn #method(#(#bound: #bound_ty),*) -> impl Fn(#(#rest_ty),*) -> Self {
+            move |#(#rest_ident),*| Self {
+                #(#bound: ::core::clone::Clone::clone(&#bound),)*
+                #(#rest_ident,)*
+            }
+        }
+    })
+}
@@ -0,0 +1,115 @@
+use std::{env, fs, path::PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::visit::Visit;
+
+use yarte_config::{read_config_file, Config, Whitespace};
+use yarte_dom::set_marks;
+use yarte_hir::visit_derive;
+use yarte_parser::set_unicode_whitespace;
+
+use super::{lower, Lowered};
+
+/// Find the scoped templates of the rust files at the path, by default `src`, and join their
+/// deduplicated styles and scripts
+pub(super) fn collected(input: TokenStream) -> TokenStream {
+    let root = if input.is_empty() {
+        "src".to_owned()
+    } else {
+        syn::parse2::<syn::LitStr>(input)
+            .expect("path string literal")
+            .value()
+    };
+    let root =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("cargo manifest directory")).join(root);
+
+    let config_toml: &str = &read_config_file();
+    let config = &Config::new(config_toml);
+    set_unicode_whitespace(config.whitespace == Whitespace::Unicode);
+    set_marks(config.fragment_tag, config.expression_mark);
+
+    let mut templates = FindTemplates::default();
+    for path in rust_files(root) {
+        let src = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("rust file not found: {}", path.display()));
+        let file = syn::parse_file(&src).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        templates.visit_file(&file);
+    }
+
+    let (mut styles, mut scripts) = (vec![], vec![]);
+    for i in &templates.0 {
+        let s = &visit_derive(i, config);
+        if !s.scoped {
+            continue;
+        }
+
+        let Lowered { style, script, .. } = lower(config, s, None);
+        for (asset, all) in vec![(style, &mut styles), (script, &mut scripts)] {
+            if !asset.is_empty() && !all.contains(&asset) {
+                all.push(asset);
+            }
+        }
+    }
+
+    let styles = styles.join("\n");
+    let scripts = scripts.join("\n");
+    quote! {
+        pub mod collected {
+            pub const STYLES: &str = #styles;
+            pub const SCRIPTS: &str = #scripts;
+        }
+    }
+}
+
+/// Rust files at `root`, in path order
+fn rust_files(root: PathBuf) -> Vec<PathBuf> {
+    if root.is_file() {
+        return vec![root];
+    }
+
+    let mut files = vec![];
+    let mut stack = vec![root];
+    while let Some(dir) = stack.pop() {
+        for entry in
+            fs::read_dir(&dir).unwrap_or_else(|_| panic!("directory not found: {}", dir.display()))
+        {
+            let path = entry.expect("valid directory entry").path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if path.extension().map_or(false, |x| x == "rs") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    files
+}
+
+/// Structs deriving `Template`
+#[derive(Default)]
+struct FindTemplates(Vec<syn::DeriveInput>);
+
+impl<'a> Visit<'a> for FindTemplates {
+    fn visit_item_struct(&mut self, i: &'a syn::ItemStruct) {
+        if i.attrs.iter().any(is_template_derive) {
+            self.0.push(i.clone().into());
+        }
+    }
+}
+
+fn is_template_derive(attr: &syn::Attribute) -> bool {
+    attr.path.is_ident("derive")
+        && match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list.nested.iter().any(|x| match x {
+                syn::NestedMeta::Meta(meta) => meta
+                    .path()
+                    .segments
+                    .last()
+                    .map_or(false, |x| x.ident == "Template"),
+                _ => false,
+            }),
+            _ => false,
+        }
+}
@@ -0,0 +1,83 @@
+use std::{
+    env,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use yarte_config::Config;
+use yarte_dom::timing::take_elapsed;
+use yarte_hir::Struct;
+
+/// Expansion log of the templates, a JSON line by template appended to the file of
+/// `YARTE_EXPANSION_LOG`
+pub(super) struct Expansion {
+    path: PathBuf,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+    dom: Duration,
+}
+
+impl Expansion {
+    /// Start the timer, `None` without `YARTE_EXPANSION_LOG`
+    pub(super) fn new() -> Option<Expansion> {
+        let path = env::var_os("YARTE_EXPANSION_LOG").filter(|x| !x.is_empty())?;
+        // Time of previous expansions in the thread
+        take_elapsed();
+
+        Some(Expansion {
+            path: path.into(),
+            last: Instant::now(),
+            phases: vec![],
+            dom: Duration::default(),
+        })
+    }
+
+    /// End of the phase `name`, the dom rewrites of the phase are counted apart
+    pub(super) fn phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        let dom = take_elapsed();
+        let elapsed = now.duration_since(self.last);
+        self.phases
+            .push((name, elapsed.checked_sub(dom).unwrap_or_default()));
+        self.dom += dom;
+        self.last = now;
+    }
+
+    pub(super) fn write(
+        mut self,
+        config: &Config,
+        s: &Struct,
+        sizes: (usize, usize),
+        output: usize,
+    ) {
+        self.phases.push(("dom", self.dom));
+        let template = s.path.strip_prefix(config.get_dir()).unwrap_or(&s.path);
+        let mut entry = json!({
+            "struct": s.ident.to_string(),
+            "template": template.display().to_string(),
+            "mode": format!("{:?}", s.mode),
+            "sources": sizes.0,
+            "size": sizes.1,
+            "output": output,
+        });
+        if let Value::Object(map) = &mut entry {
+            for (name, time) in &self.phases {
+                map.insert(format!("{}_us", name), json!(time.as_micros() as u64));
+            }
+        }
+        let mut line = entry.to_string();
+        line.push('\n');
+
+        // A line by write, appends of parallel builds aren't mixed
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| f.write_all(line.as_bytes()))
+            .expect("write expansion log");
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::json;
+
+use yarte_config::Config;
+use yarte_helpers::helpers::{calculate_hash, Sources};
+use yarte_hir::Struct;
+use yarte_parser::{parse_partials, Partial};
+
+/// Struct to template and template to partial edges
+#[derive(Default)]
+struct Graph {
+    structs: BTreeSet<(String, String)>,
+    partials: BTreeMap<String, BTreeSet<String>>,
+    templates: BTreeSet<String>,
+}
+
+/// Save the edges of the struct and rewrite the usage graph of the crate at `path`
+///
+/// Edges of each struct are kept in the directory of the graph, in `graph.d`
+pub(super) fn graph(path: &Path, config: &Config, s: &Struct, sources: Sources) {
+    let dir = config.get_dir();
+    let name = |p: &Path| p.strip_prefix(dir).unwrap_or(p).display().to_string();
+
+    let template = name(&s.path);
+    let mut edges = format!("struct\t{}\t{}\n", s.ident, template);
+    for (parent, src) in sources {
+        let partials: BTreeSet<_> = parse_partials(src)
+            .iter()
+            .filter_map(|Partial(_, partial, _)| config.find_partial(parent, partial.t()))
+            .chain(if parent == &s.path {
+                config.builtin_partials(parent).collect()
+            } else {
+                vec![]
+            })
+            .collect();
+        for partial in partials {
+            edges.push_str(&format!("partial\t{}\t{}\n", name(parent), name(&partial)));
+        }
+    }
+
+    let parts = path.with_extension("d");
+    fs::create_dir_all(&parts).expect("create graph directory");
+    fs::write(
+        parts.join(format!("{}-{:x}", s.ident, calculate_hash(&s.path))),
+        edges,
+    )
+    .expect("write template edges");
+
+    let mut graph = Graph::default();
+    for entry in fs::read_dir(&parts).expect("read graph directory") {
+        let src = fs::read_to_string(entry.expect("graph directory entry").path())
+            .expect("read template edges");
+        for line in src.lines() {
+            let mut split = line.split('\t');
+            match (split.next(), split.next(), split.next()) {
+                (Some("struct"), Some(a), Some(b)) => {
+                    graph.structs.insert((a.to_owned(), b.to_owned()));
+                }
+                (Some("partial"), Some(a), Some(b)) => {
+                    graph
+                        .partials
+                        .entry(a.to_owned())
+                        .or_default()
+                        .insert(b.to_owned());
+                }
+                _ => (),
+            }
+        }
+    }
+    graph.templates = templates(dir).iter().map(|x| name(x)).collect();
+
+    let out = if path.extension().map_or(false, |x| x == "json") {
+        graph.json()
+    } else {
+        graph.dot()
+    };
+    fs::write(path, out).expect("write usage graph");
+}
+
+impl Graph {
+    /// Templates without struct nor parent template
+    fn unused(&self) -> BTreeSet<&String> {
+        self.templates
+            .iter()
+            .filter(|x| {
+                !self.structs.iter().any(|(_, t)| t == *x)
+                    && !self.partials.values().any(|p| p.contains(*x))
+            })
+            .collect()
+    }
+
+    fn dot(&self) -> String {
+        let mut out = String::from("digraph yarte {\n");
+        for (ident, template) in &self.structs {
+            out.push_str(&format!(
+                "    {:?} [shape=box];\n    {:?} -> {:?};\n",
+                ident, ident, template
+            ));
+        }
+        for (parent, partials) in &self.partials {
+            for partial in partials {
+                out.push_str(&format!("    {:?} -> {:?};\n", parent, partial));
+            }
+        }
+        for template in self.unused() {
+            out.push_str(&format!("    {:?} [style=dashed];\n", template));
+        }
+        out.push_str("}\n");
+
+        out
+    }
+
+    fn json(&self) -> String {
+        let structs: Vec<_> = self
+            .structs
+            .iter()
+            .map(|(ident, template)| json!({ "name": ident, "template": template }))
+            .collect();
+        let partials: Vec<_> = self
+            .partials
+            .iter()
+            .map(|(parent, partials)| json!({ "template": parent, "partials": partials }))
+            .collect();
+
+        let mut out = json!({
+            "structs": structs,
+            "partials": partials,
+            "unused": self.unused(),
+        })
+        .to_string();
+        out.push('\n');
+
+        out
+    }
+}
+
+/// Files of the templates directory
+fn templates(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    files
+}
@@ -1,42 +1,99 @@
 extern crate proc_macro;
 
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use proc_macro::TokenStream;
+use quote::quote;
 
 use yarte_codegen::{
     wasm::{client, server},
-    CodeGen, FmtCodeGen, HTMLCodeGen, HTMLMinCodeGen, TextCodeGen,
+    BytesCodeGen, CodeGen, DirectCodeGen, EmailCodeGen, FmtCodeGen, HTMLCodeGen, InstrumentCodeGen,
+    JSONCodeGen, TextCodeGen,
+};
+use yarte_config::{read_config_file, Config, OnHtmlError, PrintConfig, Whitespace};
+use yarte_dom::{
+    dom_fmt::{to_cspfmt, to_scopedfmt},
+    lint, set_marks, svg, DOMFmt,
 };
-use yarte_config::{read_config_file, Config, PrintConfig};
 use yarte_helpers::helpers;
-use yarte_hir::{generate, visit_derive, Mode, Print, Struct, HIR};
-use yarte_parser::{parse, source_map};
+use yarte_hir::{
+    constants, duplicate_branches, generate, map_hir, visit_derive, Escape, Mode, Print, Struct,
+    Warnings, HIR,
+};
+use yarte_parser::{parse, set_unicode_whitespace, source_map, Arena};
 
+mod bind;
+mod collected;
+mod expansion;
+mod graph;
 mod logger;
+mod representations;
 
-use self::logger::log;
-use yarte_helpers::helpers::Sources;
+use self::{expansion::Expansion, logger::log};
 
 #[proc_macro_derive(Template, attributes(template, msg, inner))]
 pub fn derive(input: TokenStream) -> TokenStream {
     build(&syn::parse(input).unwrap())
 }
 
+/// Styles and scripts of the scoped templates of the crate
+///
+/// Expands to the module `collected` with the constants `STYLES` and `SCRIPTS`
+#[proc_macro]
+pub fn collected(input: TokenStream) -> TokenStream {
+    collected::collected(input.into()).into()
+}
+
 #[inline]
 fn build(i: &syn::DeriveInput) -> TokenStream {
+    let mut expansion = Expansion::new();
     let config_toml: &str = &read_config_file();
     let config = &Config::new(config_toml);
+    set_unicode_whitespace(config.whitespace == Whitespace::Unicode);
+    set_marks(config.fragment_tag, config.expression_mark);
     let s = &visit_derive(i, config);
-    let sources = &helpers::read(s.path.clone(), s.src.clone(), config);
+    let lowered = lower(config, s, expansion.as_mut());
+    if let Some(path) = &config.graph {
+        graph::graph(path, config, s, &lowered.sources);
+    }
+    let sources = lowered.sources.len();
+    let size = lowered.sources.values().map(String::len).sum();
+
+    let tokens = lowered_to_tokens(lowered, config, s);
+    if let Some(mut expansion) = expansion {
+        expansion.phase("codegen");
+        expansion.write(config, s, (sources, size), tokens.to_string().len());
+    }
 
-    sources_to_tokens(sources, config, s).into()
+    tokens.into()
 }
 
-fn sources_to_tokens(sources: Sources, config: &Config, s: &Struct) -> proc_macro2::TokenStream {
+/// Template lowered for its codegen
+struct Lowered {
+    sources: BTreeMap<PathBuf, String>,
+    hir: Vec<HIR>,
+    consts: Vec<syn::ItemConst>,
+    /// Scoped styles and scripts
+    style: String,
+    script: String,
+    /// Invalid html compiled as text, see `html_fallback`
+    text: bool,
+    warnings: Warnings,
+}
+
+/// Read, parse and lower the sources of the template, with the passes of its attributes
+fn lower(config: &Config, s: &Struct, mut expansion: Option<&mut Expansion>) -> Lowered {
+    let mut phase = |name| {
+        if let Some(expansion) = expansion.as_mut() {
+            expansion.phase(name);
+        }
+    };
+
+    let sources = helpers::read(s.path.clone(), s.src.clone(), config);
+    let arena = Arena::new();
     let mut parsed = BTreeMap::new();
-    for (p, src) in sources {
-        parsed.insert(p, parse(source_map::get_cursor(p, src)));
+    for (p, src) in &sources {
+        parsed.insert(p, parse(source_map::get_cursor(p, src), &arena));
     }
 
     if cfg!(debug_assertions) && config.print_override == PrintConfig::Ast
@@ -47,11 +104,102 @@ fn sources_to_tokens(sources: Sources, config: &Config, s: &Struct) -> proc_macr
         eprintln!("{:?}\n", parsed);
     }
 
-    let hir = generate(config, s, &parsed).unwrap_or_else(|e| helpers::emitter(sources, config, e));
+    phase("parse");
+
+    let hir =
+        generate(config, s, &parsed).unwrap_or_else(|e| helpers::emitter(&sources, config, e));
+    let hir = svg::inline_svg(hir);
+    let consts = constants(s, &parsed);
+    if !consts.is_empty() && s.mode == Mode::WASM {
+        panic!("constants of templates can't be used in wasm templates");
+    }
+    let mut warnings = Warnings::new();
+    duplicate_branches(config, s, &parsed, &mut warnings);
     // when multiple templates
     source_map::clean();
 
-    let tokens = hir_to_tokens(hir, s);
+    if s.strict_html && !s.mode.is_text() {
+        let path = s.path.strip_prefix(config.get_dir()).unwrap_or(&s.path);
+        warnings.extend(
+            lint::strict_html(&hir)
+                .into_iter()
+                .map(|m| format!("strict_html in `{}`: {}", path.display(), m)),
+        );
+    }
+    if s.mode == Mode::HTML || s.mode == Mode::HTMLMin {
+        let path = s.path.strip_prefix(config.get_dir()).unwrap_or(&s.path);
+        warnings.extend(
+            lint::duplicate_ids(&hir)
+                .into_iter()
+                .map(|m| format!("duplicate id in `{}`: {}", path.display(), m)),
+        );
+        warnings.extend(
+            lint::misplaced(&hir)
+                .into_iter()
+                .map(|m| format!("misplaced in `{}`: {}", path.display(), m)),
+        );
+    }
+    let hir = escape(hir, s);
+    let fallback = html_fallback(&hir, config, s);
+    let text = fallback.is_some();
+    warnings.extend(fallback);
+    let (hir, style, script) = if text {
+        (hir, String::new(), String::new())
+    } else {
+        let (hir, style, script) = scoped(hir, config, s);
+        (csp(hir, s), style, script)
+    };
+    phase("lower");
+
+    Lowered {
+        sources,
+        hir,
+        consts,
+        style,
+        script,
+        text,
+        warnings,
+    }
+}
+
+fn lowered_to_tokens(lowered: Lowered, config: &Config, s: &Struct) -> proc_macro2::TokenStream {
+    let Lowered {
+        hir,
+        consts,
+        style,
+        script,
+        text,
+        warnings,
+        ..
+    } = lowered;
+    let mut tokens = if text {
+        FmtCodeGen::new(HTMLCodeGen, s).gen(hir)
+    } else {
+        hir_to_tokens(hir, s)
+    };
+    if s.scoped {
+        tokens.extend(s.implement_inherent(&quote! {
+            pub const STYLE: &'static str = #style;
+            pub const SCRIPT: &'static str = #script;
+        }));
+    }
+    if cfg!(feature = "profile") && s.mode != Mode::WASM {
+        let template = s.ident.to_string();
+        tokens.extend(s.implement_inherent(&quote! {
+            /// Region timings of the last render on the current thread, empty in release builds
+            pub fn last_render_profile() -> Vec<::yarte::profile::Entry> {
+                ::yarte::profile::last(#template)
+            }
+        }));
+    }
+    tokens.extend(representations::representations(config, s));
+    tokens.extend(bind::bind(s));
+    tokens.extend(warnings.emit(s));
+    // Isolated from the scope of the struct, the client of wasm defines public items
+    // The constants of the template are items of the scope of all the generated functions
+    if s.mode != Mode::WASM {
+        tokens = quote!(const _: () = { #(#consts)* #tokens };);
+    }
 
     if cfg!(debug_assertions) && config.print_override == PrintConfig::Code
         || config.print_override == PrintConfig::All
@@ -69,11 +217,110 @@ fn sources_to_tokens(sources: Sources, config: &Config, s: &Struct) -> proc_macr
 }
 
 fn hir_to_tokens(hir: Vec<HIR>, s: &Struct) -> proc_macro2::TokenStream {
+    if s.once {
+        match s.mode {
+            Mode::Text | Mode::HTML | Mode::HTMLMin => (),
+            _ => panic!("'once' attribute can only be used in text and html templates"),
+        }
+    }
+
+    // Minified once for every render method
+    let hir = if s.mode == Mode::HTMLMin {
+        DOMFmt::from(hir).0
+    } else {
+        hir
+    };
     match s.mode {
+        // Regions of `once` templates would move the consumed fields into the closures
+        Mode::Text | Mode::HTML | Mode::HTMLMin if cfg!(feature = "instrument") && !s.once => {
+            FmtCodeGen::new(InstrumentCodeGen::new(s), s).gen(hir)
+        }
         Mode::Text => FmtCodeGen::new(TextCodeGen, s).gen(hir),
-        Mode::HTML => FmtCodeGen::new(HTMLCodeGen, s).gen(hir),
-        Mode::HTMLMin => FmtCodeGen::new(HTMLMinCodeGen, s).gen(hir),
+        // Every render through the formatter with the simd escape
+        Mode::HTML | Mode::HTMLMin if s.escape == Escape::Simd => {
+            FmtCodeGen::new(HTMLCodeGen, s).gen(hir)
+        }
+        Mode::HTML | Mode::HTMLMin => FmtCodeGen::new(HTMLCodeGen, s)
+            .with_direct(DirectCodeGen::new())
+            .with_bytes(DirectCodeGen::bytes())
+            .gen(hir),
+        Mode::Email => {
+            let text_alt = EmailCodeGen::text_alt(hir.clone(), s);
+            let mut tokens = FmtCodeGen::new(EmailCodeGen::new(s), s).gen(hir);
+            tokens.extend(text_alt);
+            tokens
+        }
         Mode::WASM => client::WASMCodeGen::new(s).gen(hir),
         Mode::WASMServer => FmtCodeGen::new(server::WASMCodeGen::new(s), s).gen(hir),
+        Mode::JSON => FmtCodeGen::new(JSONCodeGen, s)
+            .with_bytes(BytesCodeGen)
+            .gen(hir),
+    }
+}
+
+/// Warning of a template with invalid html compiled as `html` mode, without the html passes,
+/// when `on_html_error` is `fallback-text`
+fn html_fallback(hir: &[HIR], config: &Config, s: &Struct) -> Option<String> {
+    let parsed = s.mode == Mode::HTMLMin || s.mode == Mode::HTML && (s.scoped || s.csp);
+    if config.on_html_error != OnHtmlError::FallbackText || !parsed {
+        return None;
+    }
+
+    DOMFmt::try_new(hir.to_vec()).err().map(|errors| {
+        let path = s.path.strip_prefix(config.get_dir()).unwrap_or(&s.path);
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        format!(
+            "invalid html in `{}`, compiled without html processing: {}",
+            path.display(),
+            errors.join(", ")
+        )
+    })
+}
+
+/// Scope the styles of a `scoped` template, returns its css and javascript
+fn scoped(hir: Vec<HIR>, config: &Config, s: &Struct) -> (Vec<HIR>, String, String) {
+    if !s.scoped {
+        return (hir, String::new(), String::new());
+    }
+    match s.mode {
+        Mode::HTML | Mode::HTMLMin | Mode::Email => (),
+        _ => panic!("'scoped' attribute can only be used in html templates"),
+    }
+
+    let key = s.path.strip_prefix(config.get_dir()).unwrap_or(&s.path);
+    to_scopedfmt(hir, &key.to_string_lossy()).expect("correct html")
+}
+
+/// Expressions of the templates with `escape = "scalar"` rendered by `yarte::Scalar`
+fn escape(hir: Vec<HIR>, s: &Struct) -> Vec<HIR> {
+    if s.escape == Escape::Auto {
+        return hir;
+    }
+    match s.mode {
+        Mode::HTML | Mode::HTMLMin => (),
+        _ => panic!("'escape' attribute can only be used in html templates"),
+    }
+    if s.escape == Escape::Simd {
+        return hir;
+    }
+
+    map_hir(hir, &mut |x| match x {
+        HIR::Expr(e) => HIR::Expr(Box::new(
+            syn::parse2(quote!(::yarte::Scalar(&(#e)))).unwrap(),
+        )),
+        x => x,
+    })
+}
+
+/// Nonce the inline scripts and styles of a `csp` template
+fn csp(hir: Vec<HIR>, s: &Struct) -> Vec<HIR> {
+    if !s.csp {
+        return hir;
     }
+    match s.mode {
+        Mode::HTML | Mode::HTMLMin => (),
+        _ => panic!("'csp' attribute can only be used in html templates"),
+    }
+
+    to_cspfmt(hir).expect("correct html")
 }
@@ -0,0 +1,104 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use yarte_codegen::{CodeGen, HTMLCodeGen, HTMLMinCodeGen, JSONCodeGen, TextCodeGen};
+use yarte_config::Config;
+use yarte_hir::{Mode, Struct, Warnings};
+
+use super::{lower, Lowered};
+
+/// Methods `render_{representation}` of each representation of the struct, and the
+/// dispatcher `render_for` of the content negotiation
+pub(super) fn representations(config: &Config, s: &Struct) -> TokenStream {
+    let r = match s.representation {
+        Some(r) => r,
+        None => return TokenStream::new(),
+    };
+    if s.once {
+        panic!("'once' attribute can't be used with representations");
+    }
+
+    let mut representations = vec![r];
+    let method = format_ident!("render_{}", r.name());
+    let doc = format!("`{}` representation, the output of the template", r.name());
+    let mut methods = quote! {
+        #[doc = #doc]
+        pub fn #method(&self) -> ::yarte::Result<::std::string::String> {
+            ::yarte::Template::call(self)
+        }
+    };
+    let mut warnings = Warnings::new();
+    for a in &s.alternates {
+        let s = &s.alternate(a);
+        methods.extend(alternate(config, s, &mut warnings));
+        representations.push(a.representation);
+    }
+
+    let mimes = representations.iter().map(|r| r.mime());
+    let content_types = representations
+        .iter()
+        .map(|r| format!("{}; charset=utf-8", r.mime()));
+    let methods_call = representations
+        .iter()
+        .map(|r| format_ident!("render_{}", r.name()));
+    let index = 0..representations.len();
+    let mut tokens = s.implement_inherent(&quote! {
+        #methods
+
+        /// Representation preferred by the `Accept` header, with its content type,
+        /// `None` when no one is acceptable
+        pub fn render_for(
+            &self,
+            accept: &str,
+        ) -> ::core::option::Option<(&'static str, ::yarte::Result<::std::string::String>)> {
+            match ::yarte::negotiate(accept, &[#(#mimes),*]) {
+                #(::core::option::Option::Some(#index) => {
+                    ::core::option::Option::Some((#content_types, self.#methods_call()))
+                })*
+                _ => ::core::option::Option::None,
+            }
+        }
+    });
+    tokens.extend(warnings.emit(s));
+
+    tokens
+}
+
+/// Method rendering the template of an alternate representation
+fn alternate(config: &Config, s: &Struct, warnings: &mut Warnings) -> TokenStream {
+    let r = s.representation.expect("representation");
+    let Lowered {
+        hir,
+        consts,
+        warnings: lints,
+        ..
+    } = lower(config, s, None);
+    warnings.extend(lints.messages().iter().cloned());
+
+    let body = match s.mode {
+        Mode::HTML => HTMLCodeGen.gen(hir),
+        Mode::HTMLMin => HTMLMinCodeGen.gen(hir),
+        Mode::JSON => JSONCodeGen.gen(hir),
+        _ => TextCodeGen.gen(hir),
+    };
+    let method = format_ident!("render_{}", r.name());
+    let path = s.path.strip_prefix(config.get_dir()).unwrap_or(&s.path);
+    let doc = format!("`{}` representation, of `{}`", r.name(), path.display());
+    quote! {
+        #[doc = #doc]
+        pub fn #method(&self) -> ::yarte::Result<::std::string::String> {
+            #(#consts)*
+            let mut __yarte_out = ::std::string::String::new();
+            ::core::fmt::Write::write_fmt(
+                &mut __yarte_out,
+                ::core::format_args!("{}", ::yarte::lazy(
+                    |__yarte_fmt: &mut ::core::fmt::Formatter| -> ::core::fmt::Result {
+                        #body
+                        ::core::result::Result::Ok(())
+                    },
+                )),
+            )?;
+            ::core::result::Result::Ok(__yarte_out)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use markup5ever::{local_name, namespace_url, ns, QualName};
+use quote::quote;
+use syn::parse2;
+
+use yarte_hir::HIR;
+
+use crate::{
+    dom_fmt::Rewrite,
+    sink::{head, ParseAttribute, ParseElement, Sink, TAIL},
+};
+
+/// Hash of the nonce marks, the other expressions are marked with `0x00000000`
+pub(crate) const NONCE: &str = "0x00000001";
+
+/// Nonce attribute in the inline `<script>` and `<style>` elements of a `csp` template
+pub(crate) struct Nonce;
+
+impl Nonce {
+    /// Nonce of the `SecurityContext` of the template
+    pub(crate) fn expr() -> HIR {
+        HIR::Expr(Box::new(
+            parse2(quote!(::yarte::builtins::SecurityContext::nonce(self))).unwrap(),
+        ))
+    }
+}
+
+impl Rewrite for Nonce {
+    fn rewrite(&self, sink: &mut Sink) {
+        for node in sink.nodes.values_mut() {
+            if let ParseElement::Node { name, attrs, .. } = node {
+                let inline = match name.local {
+                    local_name!("style") => true,
+                    local_name!("script") => !has_attr(attrs, "src"),
+                    _ => false,
+                };
+                if inline && name.ns == ns!(html) && !has_attr(attrs, "nonce") {
+                    attrs.push(ParseAttribute {
+                        name: QualName::new(None, ns!(), local_name!("nonce")),
+                        value: format!("{}{}{}", head(), NONCE, TAIL),
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn has_attr(attrs: &[ParseAttribute], name: &str) -> bool {
+    attrs.iter().any(|a| &*a.name.local == name)
+}
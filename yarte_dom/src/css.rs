@@ -0,0 +1,442 @@
+use markup5ever::{local_name, namespace_url, ns, QualName};
+
+use crate::{
+    dom_fmt::Rewrite,
+    sink::{mark, yarte_tag, ParseAttribute, ParseElement, ParseNodeId, Sink},
+};
+
+/// Style rules inlined in email mode
+///
+/// Only type, class, id and universal selectors with descendant and child combinators are
+/// inlined, the rest of rules and at-rules aren't.
+#[derive(Debug, Default)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    selector: Vec<Part>,
+    specificity: (usize, usize, usize),
+    order: usize,
+    declarations: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Part {
+    combinator: Combinator,
+    compound: Compound,
+}
+
+#[derive(Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Default)]
+struct Compound {
+    tag: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+}
+
+impl Stylesheet {
+    /// Add the rules of `src` and return the css that can't be inlined
+    pub fn parse(&mut self, src: &str) -> String {
+        let src = strip_comments(src);
+        let mut rest = String::new();
+        let mut src = src.trim_start();
+        while !src.is_empty() {
+            if src.starts_with('@') {
+                let end = at_rule_end(src);
+                rest.push_str(src[..end].trim());
+                src = src[end..].trim_start();
+                continue;
+            }
+
+            let (open, close) = match src
+                .find('{')
+                .and_then(|o| src[o..].find('}').map(|c| (o, o + c)))
+            {
+                Some(x) => x,
+                None => break,
+            };
+            let declarations = declarations(&src[open + 1..close]);
+            if declarations.is_empty() {
+                src = src[close + 1..].trim_start();
+                continue;
+            }
+            for selector in src[..open].split(',').map(str::trim) {
+                match parse_selector(selector) {
+                    Some(parts) => {
+                        let order = self.rules.len();
+                        self.rules.push(Rule {
+                            specificity: specificity(&parts),
+                            selector: parts,
+                            order,
+                            declarations: declarations.clone(),
+                        })
+                    }
+                    None if !selector.is_empty() => {
+                        rest.push_str(selector);
+                        rest.push('{');
+                        rest.push_str(&declarations.join(";"));
+                        rest.push('}');
+                    }
+                    None => (),
+                }
+            }
+            src = src[close + 1..].trim_start();
+        }
+
+        rest
+    }
+
+    /// Move the rules of static `<style>` elements to the stylesheet
+    ///
+    /// Elements are removed when all their rules can be inlined
+    pub fn extract(&mut self, sink: &mut Sink) {
+        let styles: Vec<(ParseNodeId, Option<ParseNodeId>, Vec<ParseNodeId>)> = sink
+            .nodes
+            .iter()
+            .filter_map(|(id, node)| match node {
+                ParseElement::Node {
+                    name,
+                    children,
+                    parent,
+                    ..
+                } if name.local == local_name!("style") => Some((*id, *parent, children.clone())),
+                _ => None,
+            })
+            .collect();
+
+        for (id, parent, children) in styles {
+            let mut css = String::new();
+            for child in &children {
+                if let Some(ParseElement::Text(text)) = sink.nodes.get(child) {
+                    css.push_str(text);
+                }
+            }
+            if css.contains(mark()) {
+                continue;
+            }
+
+            let rest = self.parse(&css);
+            if rest.is_empty() {
+                remove_child(sink, parent, id);
+            } else {
+                if let Some(ParseElement::Text(text)) =
+                    children.first().and_then(|x| sink.nodes.get_mut(x))
+                {
+                    *text = rest;
+                }
+                if let Some(ParseElement::Node { children, .. }) = sink.nodes.get_mut(&id) {
+                    children.truncate(1);
+                }
+            }
+        }
+    }
+
+    /// Write the declarations of the matching rules in the `style` attribute of each element
+    ///
+    /// Declarations of the element's own `style` attribute take precedence
+    pub fn inline(&self, sink: &mut Sink) {
+        let mut styles = vec![];
+        for (id, node) in &sink.nodes {
+            if let ParseElement::Node { name, .. } = node {
+                if name == &yarte_tag() {
+                    continue;
+                }
+                let mut rules: Vec<&Rule> = self
+                    .rules
+                    .iter()
+                    .filter(|rule| matches(sink, &rule.selector, *id))
+                    .collect();
+                if !rules.is_empty() {
+                    rules.sort_by_key(|rule| (rule.specificity, rule.order));
+                    let declarations: Vec<&str> = rules
+                        .iter()
+                        .flat_map(|rule| rule.declarations.iter().map(String::as_str))
+                        .collect();
+                    styles.push((*id, declarations.join(";")));
+                }
+            }
+        }
+
+        for (id, style) in styles {
+            if let Some(ParseElement::Node { attrs, .. }) = sink.nodes.get_mut(&id) {
+                match attrs
+                    .iter_mut()
+                    .find(|a| a.name.local == local_name!("style"))
+                {
+                    Some(a) => {
+                        let own = a.value.trim();
+                        a.value = if own.is_empty() {
+                            style
+                        } else {
+                            format!("{};{}", style, own)
+                        };
+                    }
+                    None => attrs.push(ParseAttribute {
+                        name: QualName::new(None, ns!(), local_name!("style")),
+                        value: style,
+                    }),
+                }
+            }
+        }
+    }
+}
+
+impl Rewrite for Stylesheet {
+    fn rewrite(&self, sink: &mut Sink) {
+        self.inline(sink)
+    }
+}
+
+pub(crate) fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut src = src;
+    while let Some(start) = src.find("/*") {
+        out.push_str(&src[..start]);
+        src = match src[start + 2..].find("*/") {
+            Some(end) => &src[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(src);
+
+    out
+}
+
+/// Length of the at-rule at the start of `src`, block included
+fn at_rule_end(src: &str) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in src.char_indices() {
+        match c {
+            ';' if depth == 0 => return i + 1,
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    src.len()
+}
+
+fn declarations(src: &str) -> Vec<String> {
+    src.split(';')
+        .filter_map(|x| {
+            let mut split = x.splitn(2, ':');
+            let name = split.next()?.trim();
+            let value = split.next()?.trim();
+            if name.is_empty() || value.is_empty() {
+                None
+            } else {
+                Some(format!("{}:{}", name, value))
+            }
+        })
+        .collect()
+}
+
+fn parse_selector(src: &str) -> Option<Vec<Part>> {
+    let mut parts = vec![];
+    let mut combinator = Combinator::Descendant;
+    let mut chars = src.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '>' {
+            if combinator == Combinator::Child || parts.is_empty() {
+                return None;
+            }
+            combinator = Combinator::Child;
+            chars.next();
+            continue;
+        }
+
+        let mut compound = Compound::default();
+        let mut any = false;
+        while let Some(&c) = chars.peek() {
+            match c {
+                '*' => {
+                    chars.next();
+                }
+                '.' | '#' => {
+                    chars.next();
+                    let name = ident(&mut chars)?;
+                    if c == '.' {
+                        compound.classes.push(name);
+                    } else {
+                        compound.ids.push(name);
+                    }
+                }
+                c if is_ident(c) && !any => {
+                    compound.tag = Some(ident(&mut chars)?.to_ascii_lowercase());
+                }
+                c if c.is_whitespace() || c == '>' => break,
+                _ => return None,
+            }
+            any = true;
+        }
+
+        parts.push(Part {
+            combinator,
+            compound,
+        });
+        combinator = Combinator::Descendant;
+    }
+
+    if parts.is_empty() || combinator == Combinator::Child {
+        None
+    } else {
+        Some(parts)
+    }
+}
+
+pub(crate) fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+fn ident<I: Iterator<Item = char>>(chars: &mut std::iter::Peekable<I>) -> Option<String> {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if !is_ident(c) {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn specificity(parts: &[Part]) -> (usize, usize, usize) {
+    parts
+        .iter()
+        .fold((0, 0, 0), |(a, b, c), Part { compound, .. }| {
+            (
+                a + compound.ids.len(),
+                b + compound.classes.len(),
+                c + compound.tag.is_some() as usize,
+            )
+        })
+}
+
+fn matches(sink: &Sink, parts: &[Part], id: ParseNodeId) -> bool {
+    let (last, rest) = match parts.split_last() {
+        Some(x) => x,
+        None => return true,
+    };
+    if !compound_matches(sink, &last.compound, id) {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+
+    let mut parent = parent(sink, id);
+    match last.combinator {
+        Combinator::Child => parent.map_or(false, |p| matches(sink, rest, p)),
+        Combinator::Descendant => {
+            while let Some(p) = parent {
+                if matches(sink, rest, p) {
+                    return true;
+                }
+                parent = self::parent(sink, p);
+            }
+            false
+        }
+    }
+}
+
+fn parent(sink: &Sink, id: ParseNodeId) -> Option<ParseNodeId> {
+    match sink.nodes.get(&id) {
+        Some(ParseElement::Node { parent, .. }) => parent.filter(|p| match sink.nodes.get(p) {
+            Some(ParseElement::Node { name, .. }) => name != &yarte_tag(),
+            _ => false,
+        }),
+        _ => None,
+    }
+}
+
+fn compound_matches(sink: &Sink, compound: &Compound, id: ParseNodeId) -> bool {
+    let (name, attrs) = match sink.nodes.get(&id) {
+        Some(ParseElement::Node { name, attrs, .. }) if name != &yarte_tag() => (name, attrs),
+        _ => return false,
+    };
+    let attr = |x: &str| {
+        attrs
+            .iter()
+            .find(|a| &*a.name.local == x)
+            .map(|a| a.value.as_str())
+    };
+
+    compound
+        .tag
+        .as_ref()
+        .map_or(true, |tag| &*name.local == tag.as_str())
+        && compound
+            .ids
+            .iter()
+            .all(|id| attr("id") == Some(id.as_str()))
+        && compound
+            .classes
+            .iter()
+            .all(|class| attr("class").map_or(false, |x| x.split_whitespace().any(|x| x == class)))
+}
+
+pub(crate) fn remove_child(sink: &mut Sink, parent: Option<ParseNodeId>, id: ParseNodeId) {
+    let children = match parent {
+        Some(parent) => match sink.nodes.get_mut(&parent) {
+            Some(ParseElement::Node { children, .. }) => Some(children),
+            _ => None,
+        },
+        None => sink.nodes.values_mut().find_map(|node| match node {
+            ParseElement::Document(children) | ParseElement::Node { children, .. }
+                if children.contains(&id) =>
+            {
+                Some(children)
+            }
+            _ => None,
+        }),
+    };
+    if let Some(children) = children {
+        children.retain(|x| *x != id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut sheet = Stylesheet::default();
+        let rest = sheet.parse(
+            "/* base */ p, .a > #b { color: red; margin : 0 } a:hover { color: blue }\
+             @media (max-width: 600px) { p { color: green } } div.c span { padding: 0 } ul {}",
+        );
+        assert_eq!(
+            rest,
+            "a:hover{color:blue}@media (max-width: 600px) { p { color: green } }"
+        );
+        assert_eq!(sheet.rules.len(), 3);
+        assert_eq!(sheet.rules[0].declarations, vec!["color:red", "margin:0"]);
+        assert_eq!(sheet.rules[0].specificity, (0, 0, 1));
+        assert_eq!(sheet.rules[1].specificity, (1, 1, 0));
+        assert_eq!(sheet.rules[1].selector[1].combinator, Combinator::Child);
+        assert_eq!(sheet.rules[2].specificity, (0, 1, 2));
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::{collections::HashMap, vec::Drain};
 
-use markup5ever::{namespace_url, ns, LocalName, QualName};
+use markup5ever::{local_name, namespace_url, ns, LocalName, QualName};
 use yarte_hir::{Each as HEach, IfElse as HIfElse, HIR};
 
 mod visit_each;
@@ -12,10 +12,11 @@ mod visit_local;
 
 use crate::{
     sink::{
-        parse_document, parse_fragment, ParseAttribute, ParseElement, ParseNodeId, ParseResult,
-        Sink, HEAD, TAIL,
+        head, is_custom_name, parse_document, parse_fragment, yarte_tag, ParseAttribute,
+        ParseElement, ParseError, ParseNodeId, ParseResult, Sink, TAIL,
     },
-    tree_builder::YARTE_TAG,
+    table::Sections,
+    timing::measure,
 };
 
 use self::{
@@ -77,6 +78,18 @@ pub struct Each {
 pub enum Ns {
     Html,
     Svg,
+    MathMl,
+}
+
+impl Ns {
+    /// Namespace of foreign elements, created with `createElementNS`
+    pub fn url(&self) -> Option<&'static str> {
+        match self {
+            Ns::Html => None,
+            Ns::Svg => Some("http://www.w3.org/2000/svg"),
+            Ns::MathMl => Some("http://www.w3.org/1998/Math/MathML"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -89,9 +102,63 @@ pub enum Element {
     Text(String),
 }
 
+impl Element {
+    /// Custom elements own their children, only their attributes are diffed
+    pub fn is_opaque(&self) -> bool {
+        match self {
+            Element::Node {
+                name: (Ns::Html, name),
+                ..
+            } => is_custom_name(name),
+            _ => false,
+        }
+    }
+
+    /// Declarative shadow root `<template shadowrootmode>`, attached by the browser to its
+    /// parent out of the light DOM
+    pub fn is_shadow_root(&self) -> bool {
+        match self {
+            Element::Node {
+                name: (Ns::Html, name),
+                attrs,
+                ..
+            } => {
+                name == &local_name!("template") && attrs.iter().any(|a| a.name == "shadowrootmode")
+            }
+            _ => false,
+        }
+    }
+
+    /// Selector of the element of a portal `<template data-yarte-portal>`, where the wasm client
+    /// moves its content
+    pub fn portal(&self) -> Option<&str> {
+        match self {
+            Element::Node {
+                name: (Ns::Html, name),
+                attrs,
+                ..
+            } if name == &local_name!("template") => {
+                attrs
+                    .iter()
+                    .find_map(|a| match (a.name.as_str(), a.value.as_slice()) {
+                        (PORTAL, [ExprOrText::Text(selector)]) => Some(selector.as_str()),
+                        _ => None,
+                    })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Attribute of the `<template>` of the portals, with the selector of their element
+pub const PORTAL: &str = "data-yarte-portal";
+
 #[derive(Debug)]
 pub struct Attribute {
+    /// Name as written, with its prefix as `xlink:href`
     pub name: String,
+    /// Namespace of prefixed attributes, set with `setAttributeNS`
+    pub ns: Option<String>,
     pub value: Vec<ExprOrText>,
 }
 
@@ -104,16 +171,82 @@ pub enum ExprOrText {
 pub type TreeMap = HashMap<ExprId, HashSet<VarId>>;
 pub type VarMap = HashMap<VarId, Var>;
 
+/// Message of an event attribute `onclick={{ Msg::Clear }}`, or of the focus of the element
+/// after its render `autofocus-on={{ Msg::Opened }}`
+///
+/// The element is marked with the index of the message, see [`event_attr`]
+#[derive(Debug)]
+pub struct Event {
+    /// Name of the attribute, as `onclick`
+    pub name: String,
+    pub msg: syn::Expr,
+}
+
+impl Event {
+    /// Type of the listened event, as `click`, none for `autofocus-on`
+    pub fn listen(&self) -> Option<&str> {
+        if self.name == AUTOFOCUS {
+            None
+        } else {
+            Some(&self.name[2..])
+        }
+    }
+}
+
+/// Attribute with the message focusing its element after the render
+pub const AUTOFOCUS: &str = "autofocus-on";
+
+/// Attribute with the index of the message of the event attribute `name`
+///
+/// One listener by event type at the root matches the closest element with it
+pub fn event_attr(name: &str) -> String {
+    format!("data-yarte-{}", name)
+}
+
+/// Types of the events of the event attributes, sorted
+#[rustfmt::skip]
+const EVENTS: &[&str] = &[
+    "abort", "afterprint", "animationcancel", "animationend", "animationiteration",
+    "animationstart", "auxclick", "beforeinput", "beforeprint", "beforeunload", "blur", "cancel",
+    "canplay", "canplaythrough", "change", "click", "close", "contextmenu", "copy", "cuechange",
+    "cut", "dblclick", "drag", "dragend", "dragenter", "dragleave", "dragover", "dragstart", "drop",
+    "durationchange", "emptied", "ended", "error", "focus", "focusin", "focusout", "formdata",
+    "gotpointercapture", "hashchange", "input", "invalid", "keydown", "keypress", "keyup",
+    "languagechange", "load", "loadeddata", "loadedmetadata", "loadstart", "lostpointercapture",
+    "message", "messageerror", "mousedown", "mouseenter", "mouseleave", "mousemove", "mouseout",
+    "mouseover", "mouseup", "offline", "online", "pagehide", "pageshow", "paste", "pause", "play",
+    "playing", "pointercancel", "pointerdown", "pointerenter", "pointerleave", "pointermove",
+    "pointerout", "pointerover", "pointerup", "popstate", "progress", "ratechange",
+    "rejectionhandled", "reset", "resize", "scroll", "scrollend", "securitypolicyviolation",
+    "seeked", "seeking", "select", "selectionchange", "selectstart", "slotchange", "stalled",
+    "storage", "submit", "suspend", "timeupdate", "toggle", "touchcancel", "touchend", "touchmove",
+    "touchstart", "transitioncancel", "transitionend", "transitionrun", "transitionstart",
+    "unhandledrejection", "unload", "volumechange", "waiting", "wheel",
+];
+
+/// Event attributes are a message, as `onclick={{ Msg::Clear }}`
+pub(crate) fn is_event(name: &str) -> bool {
+    name == AUTOFOCUS || name.starts_with("on") && EVENTS.binary_search(&&name[2..]).is_ok()
+}
+
 #[derive(Debug)]
 pub struct DOM {
     pub doc: Document,
     pub tree_map: TreeMap,
     pub var_map: VarMap,
+    /// Messages of the event attributes, in document order
+    pub events: Vec<Event>,
+}
+
+impl DOM {
+    pub fn try_new(ir: Vec<HIR>) -> ParseResult<DOM> {
+        measure(|| DOMBuilder::default().build(ir))
+    }
 }
 
 impl From<Vec<HIR>> for DOM {
     fn from(ir: Vec<HIR>) -> Self {
-        DOMBuilder::default().build(ir)
+        DOM::try_new(ir).expect("Dom builder")
     }
 }
 
@@ -123,31 +256,35 @@ pub struct DOMBuilder {
     count: usize,
     tree_map: HashMap<ExprId, HashSet<VarId>>,
     var_map: HashMap<VarId, Var>,
+    events: Vec<Event>,
 }
 
 // 0x00_00_00_00
 const HASH_LEN: usize = 10;
 
 impl DOMBuilder {
-    fn build(mut self, ir: Vec<HIR>) -> DOM {
-        DOM {
-            doc: self.init(ir).expect("Dom builder"),
+    fn build(mut self, ir: Vec<HIR>) -> ParseResult<DOM> {
+        Ok(DOM {
+            doc: self.init(ir)?,
             tree_map: self.tree_map,
             var_map: self.var_map.into_iter().collect(),
-        }
+            events: self.events,
+        })
     }
 
     fn generate_html(&mut self, ir: Vec<HIR>) -> (Vec<HIR>, String) {
         let mut html = String::new();
+        let mut sections = Sections::default();
         let ir: Vec<HIR> = ir
             .into_iter()
             .filter(|x| match x {
                 HIR::Lit(x) => {
-                    html.push_str(x);
+                    sections.lit(&mut html, x);
                     false
                 }
                 _ => {
-                    html.push_str(HEAD);
+                    sections.block(&mut html, x);
+                    html.push_str(head());
                     let id = self.count;
                     self.count += 1;
                     html.push_str(&format!("{:#010x?}", id));
@@ -185,9 +322,9 @@ impl DOMBuilder {
                 children,
                 ..
             }) => {
-                if name == &*YARTE_TAG {
+                if name == &yarte_tag() {
                     if self.inner {
-                        panic!("not use <{}> tag", &*YARTE_TAG.local);
+                        panic!("not use <{}> tag", yarte_tag().local);
                     }
                     self.inner = true;
                     self.get_children(children, &sink, &mut ir)?
@@ -216,6 +353,7 @@ impl DOMBuilder {
         let ns = match name.ns {
             ns!(html) => Ns::Html,
             ns!(svg) => Ns::Svg,
+            ns!(mathml) => Ns::MathMl,
             _ => panic!("Name space"),
         };
 
@@ -244,8 +382,20 @@ impl DOMBuilder {
         attr: &ParseAttribute,
         ir: &mut Drain<HIR>,
     ) -> ParseResult<Attribute> {
-        let name = attr.name.local.to_string();
-        let mut chunks = attr.value.split(HEAD).peekable();
+        let (name, ns) = match attr.name.ns {
+            ns!() => (attr.name.local.to_string(), None),
+            ns!(xmlns) if attr.name.local == local_name!("xmlns") => {
+                (attr.name.local.to_string(), Some(attr.name.ns.to_string()))
+            }
+            ref ns => (
+                match &attr.name.prefix {
+                    Some(prefix) => format!("{}:{}", prefix, attr.name.local),
+                    None => attr.name.local.to_string(),
+                },
+                Some(ns.to_string()),
+            ),
+        };
+        let mut chunks = attr.value.split(head()).peekable();
         if let Some(first) = chunks.peek() {
             if first.is_empty() {
                 chunks.next();
@@ -255,7 +405,7 @@ impl DOMBuilder {
         for chunk in chunks {
             if HASH_LEN < chunk.len() && &chunk[..2] == "0x" {
                 if let Ok(id) = u32::from_str_radix(&chunk[2..HASH_LEN], 16).map(|x| x as usize) {
-                    if self.tree_map.contains_key(&id) && chunk[HASH_LEN..].starts_with(TAIL) {
+                    if id < self.count && chunk[HASH_LEN..].starts_with(TAIL) {
                         value.push(ExprOrText::Expr(self.resolve_expr(id, ir)?));
                         if !&chunk[HASH_LEN + TAIL.len()..].is_empty() {
                             value.push(ExprOrText::Text(chunk[HASH_LEN + TAIL.len()..].into()))
@@ -269,7 +419,49 @@ impl DOMBuilder {
             value.push(ExprOrText::Text(chunk.into()))
         }
 
-        Ok(Attribute { name, value })
+        let expr = value.iter().any(|x| match x {
+            ExprOrText::Expr(_) => true,
+            ExprOrText::Text(_) => false,
+        });
+        if ns.is_none() && expr && is_event(&name) {
+            let (id, msg) = match value.pop() {
+                Some(ExprOrText::Expr(Expression::Unsafe(id, e)))
+                | Some(ExprOrText::Expr(Expression::Safe(id, e)))
+                    if value.is_empty() =>
+                {
+                    (id, *e)
+                }
+                _ => {
+                    return Err(vec![ParseError::new(format!(
+                        "event attribute `{0}` is a message, as `{0}={{{{ Msg::Clear }}}}`",
+                        name
+                    ))])
+                }
+            };
+            if self
+                .tree_map
+                .get(&id)
+                .map_or(false, |vars| !vars.is_empty())
+            {
+                return Err(vec![ParseError::new(format!(
+                    "message of event attribute `{}` can't use the variables of the template",
+                    name
+                ))]);
+            }
+            let index = self.events.len().to_string();
+            self.events.push(Event {
+                name: name.clone(),
+                msg,
+            });
+
+            return Ok(Attribute {
+                name: event_attr(&name),
+                ns,
+                value: vec![ExprOrText::Text(index)],
+            });
+        }
+
+        Ok(Attribute { name, ns, value })
     }
 
     fn resolve_mark(&mut self, id: &str, ir: &mut Drain<HIR>) -> ParseResult<Node> {
@@ -1,35 +1,63 @@
+use std::cell::Cell;
+
 use quote::quote;
 
 use yarte_hir::{Each as HEach, IfElse as HIfElse, Struct, HIR};
 
 use crate::{
+    csp::{Nonce, NONCE},
+    css::Stylesheet,
+    scope::Scope,
     serialize::serialize,
     serializer::SerializerOpt,
     sink::{
-        parse_document, parse_fragment, ParseAttribute, ParseElement, ParseResult, Sink, HEAD, TAIL,
+        head, parse_document, parse_fragment, ParseAttribute, ParseElement, ParseError,
+        ParseResult, Sink, TAIL,
     },
+    table::Sections,
+    timing::measure,
 };
 use markup5ever::{local_name, namespace_url, ns, QualName};
 use syn::parse2;
 
 pub struct DOMFmt(pub Vec<HIR>);
 
-// TODO: to try from
+impl DOMFmt {
+    /// Minified template, or the html parse errors
+    pub fn try_new(ir: Vec<HIR>) -> ParseResult<DOMFmt> {
+        measure(|| to_domfmt_init(ir)).map(DOMFmt)
+    }
+}
+
 impl From<Vec<HIR>> for DOMFmt {
     fn from(ir: Vec<HIR>) -> Self {
-        DOMFmt(to_domfmt_init(ir).expect("correct html"))
+        DOMFmt::try_new(ir).expect("correct html")
     }
 }
 
-const HASH: &str = "0x00000000";
+/// Rewrite of the nodes of the document and of each block
+pub(crate) trait Rewrite {
+    fn rewrite(&self, sink: &mut Sink);
+}
+
+pub(crate) const HASH: &str = "0x00000000";
+/// Mark of the index of the message of an event attribute in wasm templates
+pub(crate) const EVENT: &str = "0x00000002";
 
-fn get_html(ir: &[HIR]) -> String {
+thread_local!(
+    // Messages of the event attributes written, in document order as the client
+    static EVENTS: Cell<usize> = Cell::new(0);
+);
+
+pub(crate) fn get_html(ir: &[HIR]) -> String {
     let mut html = String::new();
+    let mut sections = Sections::default();
     for x in ir {
         match x {
-            HIR::Lit(x) => html.push_str(x),
+            HIR::Lit(x) => sections.lit(&mut html, x),
             _ => {
-                html.push_str(HEAD);
+                sections.block(&mut html, x);
+                html.push_str(head());
                 html.push_str(HASH);
                 html.push_str(TAIL);
             }
@@ -39,7 +67,12 @@ fn get_html(ir: &[HIR]) -> String {
     html
 }
 
-pub fn to_wasmfmt(mut ir: Vec<HIR>, s: &Struct) -> ParseResult<Vec<HIR>> {
+pub fn to_wasmfmt(ir: Vec<HIR>, s: &Struct) -> ParseResult<Vec<HIR>> {
+    measure(|| wasmfmt(ir, s))
+}
+
+fn wasmfmt(mut ir: Vec<HIR>, s: &Struct) -> ParseResult<Vec<HIR>> {
+    EVENTS.with(|x| x.set(0));
     let html = get_html(&ir);
     let sink = match parse_document(&html) {
         Ok(mut sink) => {
@@ -49,7 +82,69 @@ pub fn to_wasmfmt(mut ir: Vec<HIR>, s: &Struct) -> ParseResult<Vec<HIR>> {
         Err(_) => parse_fragment(&html)?,
     };
 
-    serialize_domfmt(sink, ir, SerializerOpt { wasm: true })
+    serialize_domfmt(sink, ir, SerializerOpt { wasm: true }, None)
+}
+
+/// Inline the css rules of `css` and of the `<style>` elements in `style` attributes
+pub fn to_emailfmt(ir: Vec<HIR>, css: &str) -> ParseResult<Vec<HIR>> {
+    measure(|| emailfmt(ir, css))
+}
+
+fn emailfmt(ir: Vec<HIR>, css: &str) -> ParseResult<Vec<HIR>> {
+    let html = get_html(&ir);
+    let mut sink = match parse_document(&html) {
+        Ok(a) => a,
+        Err(_) => parse_fragment(&html)?,
+    };
+
+    let mut sheet = Stylesheet::default();
+    sheet.parse(css);
+    sheet.extract(&mut sink);
+    sheet.inline(&mut sink);
+
+    serialize_domfmt(sink, ir, Default::default(), Some(&sheet))
+}
+
+/// Suffix the class names defined in the `<style>` elements with the hash of `key`
+///
+/// Returns the template without its static `<style>` and inline `<script>` elements,
+/// their scoped css and their javascript
+pub fn to_scopedfmt(ir: Vec<HIR>, key: &str) -> ParseResult<(Vec<HIR>, String, String)> {
+    measure(|| scopedfmt(ir, key))
+}
+
+fn scopedfmt(ir: Vec<HIR>, key: &str) -> ParseResult<(Vec<HIR>, String, String)> {
+    let html = get_html(&ir);
+    let mut sink = match parse_document(&html) {
+        Ok(a) => a,
+        Err(_) => parse_fragment(&html)?,
+    };
+
+    let mut scope = Scope::new(key);
+    let (css, js) = scope.extract(&mut sink);
+    scope.rewrite(&mut sink);
+
+    Ok((
+        serialize_domfmt(sink, ir, Default::default(), Some(&scope))?,
+        css.trim().to_owned(),
+        js.trim().to_owned(),
+    ))
+}
+
+/// Add the nonce of the `SecurityContext` to the inline `<script>` and `<style>` elements
+pub fn to_cspfmt(ir: Vec<HIR>) -> ParseResult<Vec<HIR>> {
+    measure(|| cspfmt(ir))
+}
+
+fn cspfmt(ir: Vec<HIR>) -> ParseResult<Vec<HIR>> {
+    let html = get_html(&ir);
+    let mut sink = match parse_document(&html) {
+        Ok(a) => a,
+        Err(_) => parse_fragment(&html)?,
+    };
+
+    Nonce.rewrite(&mut sink);
+    serialize_domfmt(sink, ir, Default::default(), Some(&Nonce))
 }
 
 fn add_scripts(s: &Struct, sink: &mut Sink, ir: &mut Vec<HIR>) {
@@ -75,7 +170,9 @@ fn add_scripts(s: &Struct, sink: &mut Sink, ir: &mut Vec<HIR>) {
     let mut last = *sink.nodes.keys().last().unwrap() + 1;
     let get_state = format!(
         "function get_state(){{return JSON.stringify({}{}{});}}",
-        HEAD, HASH, TAIL
+        crate::sink::head(),
+        HASH,
+        TAIL
     );
 
     let mut buf = vec![HIR::Safe(Box::new(
@@ -170,20 +267,33 @@ fn to_domfmt_init(ir: Vec<HIR>) -> ParseResult<Vec<HIR>> {
         Err(_) => parse_fragment(&html)?,
     };
 
-    serialize_domfmt(sink, ir, Default::default())
+    serialize_domfmt(sink, ir, Default::default(), None)
 }
 
-fn to_domfmt(ir: Vec<HIR>, opts: SerializerOpt) -> ParseResult<Vec<HIR>> {
+fn to_domfmt(
+    ir: Vec<HIR>,
+    opts: SerializerOpt,
+    rewrite: Option<&dyn Rewrite>,
+) -> ParseResult<Vec<HIR>> {
     let html = get_html(&ir);
-    serialize_domfmt(parse_fragment(&html)?, ir, opts)
+    let mut sink = parse_fragment(&html)?;
+    if let Some(rewrite) = rewrite {
+        rewrite.rewrite(&mut sink);
+    }
+    serialize_domfmt(sink, ir, opts, rewrite)
 }
 
-fn serialize_domfmt(sink: Sink, mut ir: Vec<HIR>, opts: SerializerOpt) -> ParseResult<Vec<HIR>> {
+fn serialize_domfmt(
+    sink: Sink,
+    mut ir: Vec<HIR>,
+    opts: SerializerOpt,
+    rewrite: Option<&dyn Rewrite>,
+) -> ParseResult<Vec<HIR>> {
     let mut writer = Vec::new();
-    serialize(&mut writer, &sink.into(), opts).expect("some serialize node");
+    serialize(&mut writer, &sink.into(), opts).map_err(|e| vec![ParseError::new(e.to_string())])?;
 
     let html = String::from_utf8(writer).expect("");
-    let mut chunks = html.split(HEAD).peekable();
+    let mut chunks = html.split(head()).peekable();
 
     if let Some(first) = chunks.peek() {
         if first.is_empty() {
@@ -200,11 +310,31 @@ fn serialize_domfmt(sink: Sink, mut ir: Vec<HIR>, opts: SerializerOpt) -> ParseR
         if chunk.is_empty() {
             panic!("chunk empty")
         } else if chunk.starts_with(HASH) {
-            resolve_node(ir.next().expect("Some HIR expression"), &mut buff, opts)?;
+            resolve_node(
+                ir.next().expect("Some HIR expression"),
+                &mut buff,
+                opts,
+                rewrite,
+            )?;
             let cut = &chunk[HASH.len() + TAIL.len()..];
             if !cut.is_empty() {
                 buff.push(HIR::Lit(cut.into()));
             }
+        } else if chunk.starts_with(EVENT) {
+            // The message is sent by the client
+            ir.next().expect("Some HIR expression");
+            let index = EVENTS.with(|x| x.replace(x.get() + 1));
+            let lit = format!("{}{}", index, &chunk[EVENT.len() + TAIL.len()..]);
+            match buff.last_mut() {
+                Some(HIR::Lit(last)) => last.push_str(&lit),
+                _ => buff.push(HIR::Lit(lit)),
+            }
+        } else if chunk.starts_with(NONCE) {
+            buff.push(Nonce::expr());
+            let cut = &chunk[NONCE.len() + TAIL.len()..];
+            if !cut.is_empty() {
+                buff.push(HIR::Lit(cut.into()));
+            }
         } else {
             buff.push(HIR::Lit(chunk.into()));
         }
@@ -216,29 +346,36 @@ fn serialize_domfmt(sink: Sink, mut ir: Vec<HIR>, opts: SerializerOpt) -> ParseR
     Ok(buff)
 }
 
-fn resolve_node(ir: HIR, buff: &mut Vec<HIR>, opts: SerializerOpt) -> ParseResult<()> {
+fn resolve_node(
+    ir: HIR,
+    buff: &mut Vec<HIR>,
+    opts: SerializerOpt,
+    rewrite: Option<&dyn Rewrite>,
+) -> ParseResult<()> {
     match ir {
         HIR::Each(each) => {
             let HEach { args, body, expr } = *each;
             buff.push(HIR::Each(Box::new(HEach {
                 args,
                 expr,
-                body: to_domfmt(body, opts)?,
+                body: to_domfmt(body, opts, rewrite)?,
             })))
         }
         HIR::IfElse(if_else) => {
             let HIfElse { ifs, if_else, els } = *if_else;
+            // In document order
+            let ifs = (ifs.0, to_domfmt(ifs.1, opts, rewrite)?);
             let mut buf_if_else = vec![];
             for (expr, body) in if_else {
-                buf_if_else.push((expr, to_domfmt(body, opts)?));
+                buf_if_else.push((expr, to_domfmt(body, opts, rewrite)?));
             }
             let els = if let Some(els) = els {
-                Some(to_domfmt(els, opts)?)
+                Some(to_domfmt(els, opts, rewrite)?)
             } else {
                 None
             };
             buff.push(HIR::IfElse(Box::new(HIfElse {
-                ifs: (ifs.0, to_domfmt(ifs.1, opts)?),
+                ifs,
                 if_else: buf_if_else,
                 els,
             })));
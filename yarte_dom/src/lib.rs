@@ -2,19 +2,37 @@
 
 #[macro_use]
 mod macros;
+mod csp;
+mod css;
 pub mod dom;
 pub mod dom_fmt;
-mod driver;
+/// Html5 parser driver over any `TreeSink`, with the yarte tree builder
+pub mod driver;
+pub mod lint;
+mod scope;
 mod serialize;
 mod serializer;
 mod sink;
-mod tree_builder;
-
-pub use self::{dom_fmt::DOMFmt, serializer::ElemInfo};
+/// Whitelist sanitizer of the inlined svg files
+pub mod svg;
+mod table;
+pub mod text;
+pub mod timing;
+/// Html5 tree builder of html5ever, it keeps the yarte marks in place
+pub mod tree_builder;
+
+pub use self::{
+    dom_fmt::DOMFmt,
+    serializer::ElemInfo,
+    sink::{set_marks, ParseError},
+};
 
 #[cfg(test)]
 mod test {
+    use yarte_hir::HIR;
+
     use crate::{
+        dom::{Element, Node, Ns, DOM},
         serialize::serialize,
         sink::{parse_document, parse_fragment},
     };
@@ -137,4 +155,191 @@ mod test {
 
         assert_eq!(expected, html);
     }
+
+    #[test]
+    fn test_custom_element() {
+        let src = "<my-widget submitLabel=\"<!--yarteHashHTMLExpressionsATTT0x00000000-->\" \
+                   data-Id=\"a\"><b someAttr=\"b\">hi</b></my-widget>";
+        let expected = "<my-widget submitLabel=\"<!--yarteHashHTMLExpressionsATTT0x00000000-->\" \
+                        data-Id=\"a\"><b someattr=\"b\">hi</b></my-widget>";
+
+        let a = parse_fragment(src).unwrap();
+        let mut writer = Vec::new();
+        serialize(&mut writer, &a.into(), Default::default()).expect("some serialize node");
+
+        let html = String::from_utf8(writer).expect("");
+
+        assert_eq!(expected, html);
+    }
+
+    #[test]
+    fn test_custom_element_opaque() {
+        let src = "<html><body><my-widget maxItems=\"3\"></my-widget><div></div>\
+                   <font-face></font-face></body></html>";
+
+        let dom = DOM::from(vec![HIR::Lit(src.into())]);
+        let body = match &dom.doc[0] {
+            Node::Elem(Element::Node { children, .. }) => match children.last() {
+                Some(Node::Elem(Element::Node { children, .. })) => children,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let opaque: Vec<_> = body
+            .iter()
+            .map(|x| match x {
+                Node::Elem(e) => e.is_opaque(),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(opaque, vec![true, false, false]);
+        match &body[0] {
+            Node::Elem(Element::Node { attrs, .. }) => assert_eq!(attrs[0].name, "maxItems"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_foreign_attributes() {
+        let src = "<html><body><svg viewBox=\"0 0 1 1\"><use xlink:href=\"#a\"></use></svg>\
+                   <math definitionURL=\"b\"></math></body></html>";
+
+        let dom = DOM::from(vec![HIR::Lit(src.into())]);
+        let body = match &dom.doc[0] {
+            Node::Elem(Element::Node { children, .. }) => match children.last() {
+                Some(Node::Elem(Element::Node { children, .. })) => children,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        match &body[0] {
+            Node::Elem(Element::Node {
+                name: (Ns::Svg, name),
+                attrs,
+                children,
+            }) => {
+                assert_eq!(&**name, "svg");
+                assert_eq!(attrs[0].name, "viewBox");
+                assert_eq!(attrs[0].ns, None);
+                match &children[0] {
+                    Node::Elem(Element::Node { attrs, .. }) => {
+                        assert_eq!(attrs[0].name, "xlink:href");
+                        assert_eq!(attrs[0].ns.as_deref(), Some("http://www.w3.org/1999/xlink"));
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+        match &body[1] {
+            Node::Elem(Element::Node {
+                name: (Ns::MathMl, _),
+                attrs,
+                ..
+            }) => assert_eq!(attrs[0].name, "definitionURL"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let ir = vec![HIR::Lit("<div></span></div>".into())];
+
+        let errors = crate::DOMFmt::try_new(ir).err().expect("some parse error");
+        assert!(!errors.is_empty());
+        assert!(!errors[0].to_string().is_empty());
+    }
+
+    #[test]
+    fn test_event_attributes() {
+        let expr = |src| HIR::Expr(Box::new(syn::parse_str(src).unwrap()));
+        let ir = vec![
+            HIR::Lit("<html><body><div online-status=\"".into()),
+            expr("self.status"),
+            HIR::Lit("\" onclick=\"".into()),
+            expr("Msg::Clear"),
+            HIR::Lit("\"></div></body></html>".into()),
+        ];
+
+        let dom = DOM::try_new(ir).expect("some dom");
+        assert_eq!(dom.events.len(), 1);
+        assert_eq!(dom.events[0].listen(), Some("click"));
+        let attrs = match &dom.doc[0] {
+            Node::Elem(Element::Node { children, .. }) => match children.last() {
+                Some(Node::Elem(Element::Node { children, .. })) => match &children[0] {
+                    Node::Elem(Element::Node { attrs, .. }) => attrs,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(attrs[0].name, "online-status");
+        assert_eq!(attrs[1].name, "data-yarte-onclick");
+
+        let ir = vec![
+            HIR::Lit("<html><body><div onclick=\"clear ".into()),
+            expr("Msg::Clear"),
+            HIR::Lit("\"></div></body></html>".into()),
+        ];
+        let errors = DOM::try_new(ir).expect_err("some error");
+        assert_eq!(
+            errors[0].to_string(),
+            "event attribute `onclick` is a message, as `onclick={{ Msg::Clear }}`"
+        );
+    }
+
+    #[test]
+    fn test_marks() {
+        crate::set_marks("yarte-fragment", "yMark0");
+        let expr = HIR::Expr(Box::new(syn::parse_str("a").unwrap()));
+        let ir = vec![
+            HIR::Lit("<marquee><p>yarteHashHTMLExpressionsATTT ".into()),
+            expr.clone(),
+            HIR::Lit("</p></marquee>".into()),
+        ];
+        let fmt = crate::DOMFmt::try_new(ir.clone());
+        crate::set_marks("marquee", "yarteHashHTMLExpressionsATTT");
+
+        assert_eq!(fmt.expect("parsed html").0, ir);
+    }
+
+    #[test]
+    fn test_sanitize_svg() {
+        use crate::svg::sanitize_svg;
+
+        let src = "<?xml version=\"1.0\"?><!-- icon --><svg onload=\"alert(1)\">\
+                   <script>alert(\"<svg>\")</script><foreignObject><div>a</div></foreignObject>\
+                   <a href=\" JavaScript:alert(1)\"><path id=\"p\" fill=\"url(#g)\"/></a>\
+                   <use xlink:href=\"#p\"/><text>a &amp; b</text></svg>";
+
+        assert_eq!(
+            sanitize_svg(src, None),
+            "<svg><a><path id=\"p\" fill=\"url(#g)\"/></a><use xlink:href=\"#p\"/>\
+             <text>a &amp; b</text></svg>"
+        );
+        assert_eq!(
+            sanitize_svg(src, Some("i")),
+            "<svg><a><path id=\"i-p\" fill=\"url(#i-g)\"/></a><use xlink:href=\"#i-p\"/>\
+             <text>a &amp; b</text></svg>"
+        );
+
+        let src = "<svg><a href=\"java\tscript:alert(1)\"></a><a href=\"https://a.b/c\"></a>\
+                   <image href=\" data:image/png;base64,AA\"/><image href=\"data:image/svg+xml,a\"/>\
+                   <a href=\"data:image/png;base64,AA\"></a><iframe srcdoc=\"<svg>\"><g></g></iframe>\
+                   <embed src=\"a.swf\"/><animate attributeName=\"href\" to=\"javascript:a\"/>\
+                   <rect fill=\"url(http://a.b/c)\" stroke=\"url('#a')\" style=\"a:\\75rl(b)\"/>\
+                   <style><![CDATA[rect > g { fill: url(#a) }]]></style>\
+                   <style>@import 'a.css';</style><style><img src=x onerror=alert(1)></style>\
+                   <g>";
+
+        assert_eq!(
+            sanitize_svg(src, None),
+            "<svg><a></a><a href=\"https://a.b/c\"></a>\
+             <image href=\"data:image/png;base64,AA\"/><image/><a></a>\
+             <rect stroke=\"url('#a')\"/><style>rect &gt; g { fill: url(#a) }</style>\
+             <style></style><style></style><g></g></svg>"
+        );
+    }
 }
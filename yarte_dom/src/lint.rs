@@ -0,0 +1,434 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+};
+
+use markup5ever::{local_name, LocalName};
+use quote::quote;
+use yarte_hir::{is_builtin, Each, IfElse, HIR};
+
+use crate::{
+    dom_fmt::get_html,
+    sink::{head, parse_document, parse_fragment, ParseAttribute, ParseElement, Sink, TAIL},
+    timing::measure,
+};
+
+/// Accessibility checks of a `strict_html` template
+///
+/// Images without `alt` and form controls without label.
+/// Attributes with expressions are assumed correct.
+pub fn strict_html(ir: &[HIR]) -> Vec<String> {
+    measure(|| {
+        let mut lint = Lint {
+            strict: true,
+            ..Lint::default()
+        };
+        lint.document(ir);
+        lint.finish()
+    })
+}
+
+/// Static `id` values used more than once in the document, partials included
+///
+/// The ids are the values of the parsed `id` attributes, with expressions they are dynamic
+/// and skipped. Ids in different branches of the same conditional aren't repeated.
+pub fn duplicate_ids(ir: &[HIR]) -> Vec<String> {
+    measure(|| {
+        let mut lint = Lint::default();
+        lint.document(ir);
+        lint.ids
+            .into_iter()
+            .filter(|(_, count)| 1 < *count)
+            .map(|(id, count)| format!("`{}` used {} times", id, count))
+            .collect()
+    })
+}
+
+#[derive(Default)]
+struct Lint {
+    /// Accessibility checks of `strict_html`
+    strict: bool,
+    warnings: Vec<String>,
+    labels: BTreeSet<String>,
+    dynamic_label: bool,
+    controls: Vec<(String, Option<String>)>,
+    /// Count of the static ids
+    ids: BTreeMap<String, usize>,
+}
+
+impl Lint {
+    fn document(&mut self, ir: &[HIR]) {
+        let html = get_html(ir);
+        // Invalid html is reported by the code generator
+        if let Ok(sink) = parse_document(&html).or_else(|_| parse_fragment(&html)) {
+            self.sink(&sink);
+        }
+        self.blocks(ir);
+    }
+
+    fn fragment(&mut self, ir: &[HIR]) {
+        if let Ok(sink) = parse_fragment(&get_html(ir)) {
+            self.sink(&sink);
+        }
+        self.blocks(ir);
+    }
+
+    fn blocks(&mut self, ir: &[HIR]) {
+        for i in ir {
+            match i {
+                HIR::Each(each) => {
+                    let Each { body, .. } = &**each;
+                    self.fragment(body);
+                }
+                HIR::IfElse(if_else) => {
+                    let IfElse { ifs, if_else, els } = &**if_else;
+                    // Only one branch is rendered, ids are repeated between them
+                    let mut ids = self.branch(&ifs.1);
+                    let blocks = if_else.iter().map(|(_, b)| b).chain(els);
+                    for block in blocks {
+                        for (id, count) in self.branch(block) {
+                            let max = ids.entry(id).or_insert(0);
+                            *max = (*max).max(count);
+                        }
+                    }
+                    for (id, count) in ids {
+                        *self.ids.entry(id).or_insert(0) += count;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Ids of a branch of a conditional
+    fn branch(&mut self, ir: &[HIR]) -> BTreeMap<String, usize> {
+        let parent = mem::take(&mut self.ids);
+        self.fragment(ir);
+        mem::replace(&mut self.ids, parent)
+    }
+
+    fn sink(&mut self, sink: &Sink) {
+        for node in sink.nodes.values() {
+            if let ParseElement::Node {
+                name,
+                attrs,
+                parent,
+                ..
+            } = node
+            {
+                let attr = |x: &str| attrs.iter().find(|a| &*a.name.local == x);
+                if let Some(id) = attr("id").and_then(static_value) {
+                    *self.ids.entry(id.to_owned()).or_insert(0) += 1;
+                }
+                if !self.strict {
+                    continue;
+                }
+
+                match name.local {
+                    local_name!("img") => {
+                        if attr("alt").is_none() {
+                            self.warn("image without `alt` attribute", &name.local, attrs);
+                        }
+                    }
+                    local_name!("label") => {
+                        if let Some(a) = attr("for") {
+                            match static_value(a) {
+                                Some(id) => {
+                                    self.labels.insert(id.to_owned());
+                                }
+                                None => self.dynamic_label = true,
+                            }
+                        }
+                    }
+                    local_name!("input") | local_name!("select") | local_name!("textarea") => {
+                        let kind = attr("type").map(|a| a.value.to_ascii_lowercase());
+                        match kind.as_deref() {
+                            Some("hidden") | Some("submit") | Some("reset") | Some("button") => {
+                                continue;
+                            }
+                            Some("image") => {
+                                if attr("alt").is_none() {
+                                    self.warn(
+                                        "image input without `alt` attribute",
+                                        &name.local,
+                                        attrs,
+                                    );
+                                }
+                                continue;
+                            }
+                            _ => (),
+                        }
+
+                        if attr("aria-label").is_some()
+                            || attr("aria-labelledby").is_some()
+                            || attr("title").is_some()
+                            || within(sink, *parent, local_name!("label"))
+                        {
+                            continue;
+                        }
+
+                        match attr("id") {
+                            Some(a) => {
+                                if let Some(id) = static_value(a) {
+                                    self.controls
+                                        .push((describe(&name.local, attrs), Some(id.to_owned())));
+                                }
+                            }
+                            None => self.controls.push((describe(&name.local, attrs), None)),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn warn(&mut self, message: &str, name: &str, attrs: &[ParseAttribute]) {
+        self.warnings
+            .push(format!("{}: {}", message, describe(name, attrs)));
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        for (control, id) in mem::take(&mut self.controls) {
+            let labeled = self.dynamic_label || id.map_or(false, |id| self.labels.contains(&id));
+            if !labeled {
+                self.warnings
+                    .push(format!("form control without label: {}", control));
+            }
+        }
+
+        self.warnings
+    }
+}
+
+/// Expressions `{{ .. }}` whose text the html parser of the browser moves out of a table
+///
+/// Text in a table out of its cells is foster parented, moved before the table. The blocks
+/// are inlined, all their branches in order. Templates with invalid html aren't checked.
+pub fn misplaced(ir: &[HIR]) -> Vec<String> {
+    measure(|| {
+        let mut html = String::new();
+        let mut exprs = vec![];
+        inline(ir, &mut html, &mut exprs);
+        let sink = match parse_document(&html).or_else(|_| parse_fragment(&html)) {
+            Ok(sink) => sink,
+            Err(_) => return vec![],
+        };
+
+        let mut warnings = vec![];
+        for node in sink.nodes.values() {
+            if let ParseElement::Node {
+                name,
+                children,
+                parent,
+                ..
+            } = node
+            {
+                let hint = match name.local {
+                    local_name!("table") => "a `<td>` of a `<tr>`",
+                    // Only in a table, the rows of a partial are checked where it's included
+                    local_name!("thead") | local_name!("tbody") | local_name!("tfoot")
+                        if within(&sink, *parent, local_name!("table")) =>
+                    {
+                        "a `<td>` of a `<tr>`"
+                    }
+                    local_name!("tr") if within(&sink, *parent, local_name!("table")) => "a `<td>`",
+                    _ => continue,
+                };
+                for child in children {
+                    let expr = match sink.nodes.get(child) {
+                        Some(ParseElement::Mark(hash)) => mark_index(hash)
+                            .and_then(|i| exprs.get(i))
+                            .and_then(Option::as_ref),
+                        _ => None,
+                    };
+                    if let Some(expr) = expr {
+                        warnings.push(format!(
+                            "expression `{{{{ {} }}}}` in `<{}>` is moved before the `<table>` \
+                             by the html parser, write it in {}",
+                            quote!(#expr),
+                            name.local,
+                            hint
+                        ));
+                    }
+                }
+            }
+        }
+
+        warnings
+    })
+}
+
+/// Html of the template with the blocks inlined, one indexed mark by expression
+///
+/// The escaped expressions are kept by index, safe expressions and built-in helpers
+/// write html.
+fn inline<'a>(ir: &'a [HIR], html: &mut String, exprs: &mut Vec<Option<&'a syn::Expr>>) {
+    for i in ir {
+        match i {
+            HIR::Lit(lit) => html.push_str(lit),
+            HIR::Expr(_) | HIR::Safe(_) => {
+                html.push_str(&format!("{}0x{:08x}{}", head(), exprs.len(), TAIL));
+                exprs.push(match i {
+                    HIR::Expr(e) if !is_builtin(e) => Some(&**e),
+                    _ => None,
+                });
+            }
+            HIR::Local(_) => (),
+            HIR::Each(each) => inline(&each.body, html, exprs),
+            HIR::IfElse(if_else) => {
+                let IfElse { ifs, if_else, els } = &**if_else;
+                inline(&ifs.1, html, exprs);
+                for (_, block) in if_else {
+                    inline(block, html, exprs);
+                }
+                if let Some(els) = els {
+                    inline(els, html, exprs);
+                }
+            }
+        }
+    }
+}
+
+fn mark_index(hash: &str) -> Option<usize> {
+    usize::from_str_radix(hash.get(2..)?, 16).ok()
+}
+
+fn static_value(a: &ParseAttribute) -> Option<&str> {
+    if a.value.contains(head()) {
+        None
+    } else {
+        Some(&a.value)
+    }
+}
+
+fn within(sink: &Sink, mut parent: Option<usize>, local: LocalName) -> bool {
+    while let Some(ParseElement::Node {
+        name, parent: next, ..
+    }) = parent.and_then(|x| sink.nodes.get(&x))
+    {
+        if name.local == local {
+            return true;
+        }
+        parent = *next;
+    }
+
+    false
+}
+
+fn describe(name: &str, attrs: &[ParseAttribute]) -> String {
+    let mut out = format!("<{}", name);
+    for a in attrs {
+        match static_value(a) {
+            Some(value) => out.push_str(&format!(" {}={:?}", &*a.name.local, value)),
+            None => out.push_str(&format!(" {}=\"{{{{ .. }}}}\"", &*a.name.local)),
+        }
+    }
+    out.push('>');
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+
+    fn expr(src: &str) -> HIR {
+        HIR::Expr(Box::new(parse_str(src).unwrap()))
+    }
+
+    #[test]
+    fn test_alt() {
+        let ir = vec![
+            HIR::Lit("<img src=\"a.png\"><img src=\"b.png\" alt=\"\"><img alt=\"".into()),
+            expr("alt"),
+            HIR::Lit("\">".into()),
+        ];
+        assert_eq!(
+            strict_html(&ir),
+            vec!["image without `alt` attribute: <img src=\"a.png\">"]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let ir = vec![
+            HIR::Lit("<div id=\"a\"></div><div id=\"".into()),
+            expr("id"),
+            HIR::Lit("\"></div><p>id=a</p><div data-id=\"a\"></div>".into()),
+            HIR::IfElse(Box::new(IfElse {
+                ifs: (
+                    parse_str("cond").unwrap(),
+                    vec![HIR::Lit("<p id=\"a\"></p>".into())],
+                ),
+                if_else: vec![],
+                els: Some(vec![HIR::Lit("<p id=\"b\"></p>".into())]),
+            })),
+            HIR::IfElse(Box::new(IfElse {
+                ifs: (
+                    parse_str("cond").unwrap(),
+                    vec![HIR::Lit("<p id=\"b\"></p>".into())],
+                ),
+                if_else: vec![],
+                els: Some(vec![HIR::Lit("<p id=\"b\"></p>".into())]),
+            })),
+        ];
+        assert_eq!(
+            duplicate_ids(&ir),
+            vec!["`a` used 2 times", "`b` used 2 times"]
+        );
+    }
+
+    #[test]
+    fn test_misplaced() {
+        let ir = vec![
+            HIR::Lit("<table><tr><td>".into()),
+            expr("a"),
+            HIR::Lit("</td>".into()),
+            expr("b"),
+            HIR::Lit("</tr></table><ul>".into()),
+            HIR::Safe(Box::new(parse_str("items").unwrap())),
+            HIR::IfElse(Box::new(IfElse {
+                ifs: (parse_str("c").unwrap(), vec![expr("c")]),
+                if_else: vec![],
+                els: Some(vec![
+                    HIR::Lit("<li>".into()),
+                    expr("d"),
+                    HIR::Lit("</li>".into()),
+                ]),
+            })),
+            HIR::Lit("</ul><p>".into()),
+            expr("e"),
+            HIR::Lit("</p>".into()),
+        ];
+        assert_eq!(
+            misplaced(&ir),
+            vec![
+                "expression `{{ b }}` in `<tr>` is moved before the `<table>` by the html parser, \
+                 write it in a `<td>`",
+            ]
+        );
+        // Rows of a partial, out of a table
+        let ir = vec![HIR::Lit("<tr>".into()), expr("a"), HIR::Lit("</tr>".into())];
+        assert!(misplaced(&ir).is_empty());
+    }
+
+    #[test]
+    fn test_labels() {
+        let ir = vec![HIR::Lit(
+            "<form><label for=\"name\">Name</label><input id=\"name\">\
+             <label>Age <input name=\"age\"></label><input name=\"q\">\
+             <input type=\"submit\"><textarea aria-label=\"Text\"></textarea>\
+             <select id=\"lang\"></select></form>"
+                .into(),
+        )];
+        assert_eq!(
+            strict_html(&ir),
+            vec![
+                "form control without label: <input name=\"q\">",
+                "form control without label: <select id=\"lang\">",
+            ]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeSet;
+
+use markup5ever::local_name;
+
+use crate::{
+    css::{is_ident, remove_child, strip_comments},
+    dom_fmt::Rewrite,
+    sink::{mark, ParseAttribute, ParseElement, ParseNodeId, Sink},
+};
+
+/// Class names of the static `<style>` elements of a scoped template, suffixed with
+/// the template hash
+pub(crate) struct Scope {
+    suffix: String,
+    classes: BTreeSet<String>,
+}
+
+impl Scope {
+    pub(crate) fn new(key: &str) -> Scope {
+        Scope {
+            suffix: format!("{:06x}", fnv(key) & 0x00ff_ffff),
+            classes: BTreeSet::new(),
+        }
+    }
+
+    /// Remove the static `<style>` and inline `<script>` elements and return their css,
+    /// with the class selectors scoped, and their javascript
+    ///
+    /// The elements of the declarative shadow roots are scoped by the browser, they are kept
+    pub(crate) fn extract(&mut self, sink: &mut Sink) -> (String, String) {
+        let elements: Vec<(ParseNodeId, Option<ParseNodeId>, bool)> = sink
+            .nodes
+            .iter()
+            .filter(|(id, _)| !in_shadow_root(sink, **id))
+            .filter_map(|(id, node)| match node {
+                ParseElement::Node {
+                    name,
+                    attrs,
+                    parent,
+                    ..
+                } => match name.local {
+                    local_name!("style") => Some((*id, *parent, true)),
+                    local_name!("script") if is_inline_script(attrs) => Some((*id, *parent, false)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        let (mut css, mut js) = (String::new(), String::new());
+        for (id, parent, style) in elements {
+            let mut text = String::new();
+            if let Some(ParseElement::Node { children, .. }) = sink.nodes.get(&id) {
+                for child in children {
+                    if let Some(ParseElement::Text(x)) = sink.nodes.get(child) {
+                        text.push_str(x);
+                    }
+                }
+            }
+            if text.contains(mark()) {
+                continue;
+            }
+
+            if style {
+                css.push_str(&self.css(&text));
+            } else {
+                js.push_str(text.trim());
+                js.push('\n');
+            }
+            remove_child(sink, parent, id);
+        }
+
+        (css, js)
+    }
+
+    fn css(&mut self, src: &str) -> String {
+        let src = strip_comments(src);
+        let mut out = String::with_capacity(src.len());
+        // Blocks of declarations
+        let mut stack: Vec<bool> = vec![];
+        let mut prelude = String::new();
+        let mut chars = src.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' | '\'' => {
+                    out.push(c);
+                    while let Some(x) = chars.next() {
+                        out.push(x);
+                        if x == '\\' {
+                            out.extend(chars.next());
+                        } else if x == c {
+                            break;
+                        }
+                    }
+                }
+                '{' => {
+                    let p = prelude.trim();
+                    let group = GROUP_RULES.iter().any(|x| p.starts_with(x));
+                    stack.push(stack.last().cloned().unwrap_or(false) || !group);
+                    prelude.clear();
+                    out.push(c);
+                }
+                '}' => {
+                    stack.pop();
+                    prelude.clear();
+                    out.push(c);
+                }
+                ';' => {
+                    prelude.clear();
+                    out.push(c);
+                }
+                '.' if !stack.last().cloned().unwrap_or(false)
+                    && chars
+                        .peek()
+                        .map_or(false, |x| x.is_alphabetic() || *x == '_' || *x == '-') =>
+                {
+                    let mut class = String::new();
+                    while let Some(&x) = chars.peek() {
+                        if !is_ident(x) {
+                            break;
+                        }
+                        class.push(x);
+                        chars.next();
+                    }
+                    out.push('.');
+                    out.push_str(&class);
+                    out.push('-');
+                    out.push_str(&self.suffix);
+                    prelude.push_str(&class);
+                    self.classes.insert(class);
+                    continue;
+                }
+                _ => {
+                    prelude.push(c);
+                    out.push(c);
+                }
+            }
+        }
+
+        out
+    }
+}
+
+impl Rewrite for Scope {
+    /// Suffix the static class names of the `class` attributes defined in the scoped css,
+    /// out of the declarative shadow roots
+    fn rewrite(&self, sink: &mut Sink) {
+        let shadow: BTreeSet<ParseNodeId> = sink
+            .nodes
+            .keys()
+            .filter(|id| in_shadow_root(sink, **id))
+            .cloned()
+            .collect();
+        for (id, node) in sink.nodes.iter_mut() {
+            if shadow.contains(id) {
+                continue;
+            }
+            if let ParseElement::Node { attrs, .. } = node {
+                for attr in attrs
+                    .iter_mut()
+                    .filter(|a| a.name.local == local_name!("class"))
+                {
+                    let mut value = String::with_capacity(attr.value.len());
+                    let mut token = String::new();
+                    for c in attr.value.chars().chain(Some(' ')) {
+                        if c.is_whitespace() {
+                            if !token.is_empty() {
+                                value.push_str(&token);
+                                if !token.contains(mark()) && self.classes.contains(&token) {
+                                    value.push('-');
+                                    value.push_str(&self.suffix);
+                                }
+                                token.clear();
+                            }
+                            value.push(c);
+                        } else {
+                            token.push(c);
+                        }
+                    }
+                    value.pop();
+                    attr.value = value;
+                }
+            }
+        }
+    }
+}
+
+/// Node in a declarative shadow root `<template shadowrootmode>`, or the root itself
+fn in_shadow_root(sink: &Sink, mut id: ParseNodeId) -> bool {
+    while let Some(ParseElement::Node {
+        name,
+        attrs,
+        parent,
+        ..
+    }) = sink.nodes.get(&id)
+    {
+        if name.local == local_name!("template")
+            && attrs.iter().any(|a| &*a.name.local == "shadowrootmode")
+        {
+            return true;
+        }
+        match parent {
+            Some(parent) => id = *parent,
+            None => break,
+        }
+    }
+    false
+}
+
+/// Script without `src` attribute and of javascript type
+fn is_inline_script(attrs: &[ParseAttribute]) -> bool {
+    attrs.iter().all(|a| match &*a.name.local {
+        "src" => false,
+        "type" => {
+            let kind = a.value.trim().to_ascii_lowercase();
+            kind.is_empty() || kind == "text/javascript"
+        }
+        _ => true,
+    })
+}
+
+/// At-rules with nested rules
+static GROUP_RULES: [&str; 4] = ["@media", "@supports", "@document", "@layer"];
+
+/// Stable hash of the template
+fn fnv(key: &str) -> u32 {
+    key.bytes().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_css() {
+        let mut scope = Scope::new("card.hbs");
+        let suffix = scope.suffix.clone();
+        let css = scope.css(
+            "/* card */ .card > p.title, div .a-b { margin: 0.5em; background: url(\"a.png\") }\
+             @media (min-width: 1.5em) { .card { padding: 0 } }\
+             @font-face { src: url(f.woff) }",
+        );
+        assert_eq!(
+            css,
+            format!(
+                " .card-{0} > p.title-{0}, div .a-b-{0} {{ margin: 0.5em; background: url(\"a.png\") }}\
+                 @media (min-width: 1.5em) {{ .card-{0} {{ padding: 0 }} }}\
+                 @font-face {{ src: url(f.woff) }}",
+                suffix
+            )
+        );
+        assert_eq!(
+            scope.classes.iter().collect::<Vec<_>>(),
+            vec!["a-b", "card", "title"]
+        );
+    }
+}
@@ -4,8 +4,7 @@ use markup5ever::QualName;
 
 use crate::{
     serializer::{HtmlSerializer, SerializerOpt},
-    sink::{ParseAttribute, ParseElement, ParseNodeId, Sink, MARK},
-    tree_builder::YARTE_TAG,
+    sink::{mark, yarte_tag, ParseAttribute, ParseElement, ParseNodeId, Sink},
 };
 
 pub fn serialize<Wr>(writer: Wr, node: &Tree, opts: SerializerOpt) -> io::Result<()>
@@ -48,7 +47,7 @@ impl From<Sink> for Tree {
                 children,
                 ..
             }) => {
-                if name == &*YARTE_TAG {
+                if name == &yarte_tag() {
                     get_children(children, &sink)
                 } else {
                     vec![TreeElement::Node {
@@ -125,7 +124,7 @@ fn _serialize<W: Write>(
             }
             Text(s) => serializer.write_text(s)?,
             DocType => serializer.write_doctype("html")?,
-            Mark(s) => serializer.write_comment(&format!("{}{}", MARK, s))?,
+            Mark(s) => serializer.write_comment(&format!("{}{}", mark(), s))?,
         }
     }
     serializer.end(parent)
@@ -4,7 +4,13 @@ use log::warn;
 pub use markup5ever::serialize::AttrRef;
 use markup5ever::{local_name, namespace_url, ns, LocalName, QualName};
 
-use yarte_parser::trim;
+use yarte_parser::trim_lit;
+
+use crate::{
+    dom::{event_attr, is_event},
+    dom_fmt::{EVENT, HASH},
+    sink::{head, TAIL},
+};
 
 #[derive(Default)]
 pub struct ElemInfo {
@@ -99,7 +105,27 @@ impl<Wr: Write> HtmlSerializer<Wr> {
         self.writer.write_all(b"<")?;
         self.writer.write_all(tagname(&name).as_bytes())?;
         for (name, value) in attrs {
-            if self.opts.wasm && name.local.to_string().starts_with("on") {
+            if self.opts.wasm && is_event(&name.local) {
+                // Delegated at the root by the index of its message
+                if value.contains(head()) {
+                    if value != format!("{}{}{}", head(), HASH, TAIL) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "event attribute `{0}` is a message, as `{0}={{{{ Msg::Clear }}}}`",
+                                name.local
+                            ),
+                        ));
+                    }
+                    write!(
+                        self.writer,
+                        " {}=\"{}{}{}\"",
+                        event_attr(&name.local),
+                        head(),
+                        EVENT,
+                        TAIL
+                    )?;
+                }
                 continue;
             }
             self.writer.write_all(b" ")?;
@@ -197,7 +223,7 @@ impl<Wr: Write> HtmlSerializer<Wr> {
                 text
             }
             _ => {
-                let (l, v, r) = trim(text);
+                let (l, v, r) = trim_lit(text);
 
                 if !l.is_empty() && v.is_empty() && r.is_empty() {
                     self.next_ws = Some(l.into());
@@ -1,6 +1,7 @@
 use std::{
     borrow::{Cow, Cow::Borrowed},
-    collections::BTreeMap,
+    cell::Cell,
+    collections::{BTreeMap, VecDeque},
     fmt::{self, Debug, Formatter},
 };
 
@@ -10,10 +11,11 @@ use html5ever::{
         Attribute as HtmlAttribute, ElementFlags, NodeOrText as HtmlNodeOrText, QuirksMode,
         TreeBuilderOpts, TreeSink,
     },
-    ExpandedName, ParseOpts, QualName,
+    ExpandedName, LocalName, ParseOpts, QualName,
 };
+use markup5ever::{namespace_url, ns};
 
-use crate::{driver, tree_builder::YARTE_TAG};
+use crate::driver;
 
 pub type ParseNodeId = usize;
 
@@ -90,9 +92,34 @@ pub struct Sink {
     pub nodes: BTreeMap<ParseNodeId, ParseElement>,
     fragment: bool,
     err: Vec<ParseError>,
+    custom: VecDeque<(String, Vec<String>)>,
 }
 
 impl Sink {
+    fn new(doc: &str) -> Sink {
+        Sink {
+            custom: custom_attr_names(doc),
+            ..Default::default()
+        }
+    }
+
+    /// Restore the case of the attribute names of a custom element, lowercased by the tokenizer
+    fn custom_attrs(&mut self, name: &QualName, attrs: &mut [HtmlAttribute]) {
+        while let Some((tag, names)) = self.custom.pop_front() {
+            if tag == *name.local {
+                for attr in attrs.iter_mut() {
+                    if let Some(x) = names
+                        .iter()
+                        .find(|x| x.eq_ignore_ascii_case(&attr.name.local))
+                    {
+                        attr.name.local = LocalName::from(x.as_str());
+                    }
+                }
+                return;
+            }
+        }
+    }
+
     fn new_parse_node(&mut self) -> ParseNode {
         let id = self.count;
         self.count += 1;
@@ -113,7 +140,7 @@ impl Sink {
                     .get_mut(&node.id)
                     .and_then(|x| match x {
                         ParseElement::Node { parent, name, .. } => {
-                            if name != &*YARTE_TAG {
+                            if name != &yarte_tag() {
                                 *parent = Some(p);
                             }
                             Some(())
@@ -137,12 +164,78 @@ impl Sink {
 #[derive(Debug)]
 pub struct ParseError(Cow<'static, str>);
 
+impl ParseError {
+    pub(crate) fn new(msg: impl Into<Cow<'static, str>>) -> ParseError {
+        ParseError(msg.into())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 pub type ParseResult<T> = Result<T, Vec<ParseError>>;
 
-pub const MARK: &str = "yarteHashHTMLExpressionsATTT";
-pub const HEAD: &str = "<!--yarteHashHTMLExpressionsATTT";
 pub const TAIL: &str = "-->";
 
+/// Wrapper tag of the fragments and mark of the expressions in the parsed html
+#[derive(Clone, Copy)]
+struct Marks {
+    tag: &'static str,
+    mark: &'static str,
+    head: &'static str,
+}
+
+thread_local! {
+    static MARKS: Cell<Marks> = Cell::new(Marks {
+        tag: "marquee",
+        mark: "yarteHashHTMLExpressionsATTT",
+        head: "<!--yarteHashHTMLExpressionsATTT",
+    });
+}
+
+/// Set the wrapper tag of the fragments and the mark of the expressions, `fragment_tag`
+/// and `expression_mark` of the config
+///
+/// Use in the same thread, before the html passes. The template can't contain the tag
+/// nor the mark.
+pub fn set_marks(tag: &str, mark: &str) {
+    MARKS.with(|x| {
+        let marks = x.get();
+        if marks.tag == tag && marks.mark == mark {
+            return;
+        }
+        // Once by config, the same in every template of a crate
+        let leak = |s: String| &*Box::leak(s.into_boxed_str());
+        x.set(Marks {
+            tag: leak(tag.to_owned()),
+            mark: leak(mark.to_owned()),
+            head: leak(format!("<!--{}", mark)),
+        });
+    })
+}
+
+/// Mark of the expressions, the start of its comments
+pub fn mark() -> &'static str {
+    MARKS.with(|x| x.get().mark)
+}
+
+/// Start of the comments of the expressions
+pub fn head() -> &'static str {
+    MARKS.with(|x| x.get().head)
+}
+
+/// Wrapper tag of the fragments
+pub fn yarte_tag() -> QualName {
+    QualName::new(
+        None,
+        ns!(html),
+        LocalName::from(MARKS.with(|x| x.get().tag)),
+    )
+}
+
 impl TreeSink for Sink {
     type Handle = ParseNode;
     type Output = ParseResult<Self>;
@@ -176,9 +269,12 @@ impl TreeSink for Sink {
     fn create_element(
         &mut self,
         name: QualName,
-        html_attrs: Vec<HtmlAttribute>,
+        mut html_attrs: Vec<HtmlAttribute>,
         _flags: ElementFlags,
     ) -> Self::Handle {
+        if is_custom_element(&name) {
+            self.custom_attrs(&name, &mut html_attrs);
+        }
         let mut new_node = self.new_parse_node();
         new_node.qual_name = Some(name.clone());
         let attrs = html_attrs
@@ -204,12 +300,12 @@ impl TreeSink for Sink {
 
     fn create_comment(&mut self, text: StrTendril) -> Self::Handle {
         let node = self.new_parse_node();
-        if text.as_bytes().starts_with(MARK.as_bytes()) {
+        if text.as_bytes().starts_with(mark().as_bytes()) {
             self.nodes.insert(
                 node.id,
                 ParseElement::Mark(
                     text.to_string()
-                        .get(MARK.len()..)
+                        .get(mark().len()..)
                         .expect("SOME")
                         .to_string(),
                 ),
@@ -288,7 +384,7 @@ impl TreeSink for Sink {
 
 pub fn parse_document(doc: &str) -> ParseResult<Sink> {
     let parser = driver::parse_document(
-        Sink::default(),
+        Sink::new(doc),
         ParseOpts {
             tree_builder: TreeBuilderOpts {
                 exact_errors: cfg!(debug_assertions),
@@ -304,7 +400,7 @@ pub fn parse_document(doc: &str) -> ParseResult<Sink> {
 
 pub fn parse_fragment(doc: &str) -> ParseResult<Sink> {
     let parser = driver::parse_fragment(
-        Sink::default(),
+        Sink::new(doc),
         ParseOpts {
             tree_builder: TreeBuilderOpts {
                 exact_errors: cfg!(debug_assertions),
@@ -312,7 +408,7 @@ pub fn parse_fragment(doc: &str) -> ParseResult<Sink> {
             },
             ..Default::default()
         },
-        YARTE_TAG.clone(),
+        yarte_tag(),
         vec![],
     )
     .from_utf8();
@@ -321,7 +417,7 @@ pub fn parse_fragment(doc: &str) -> ParseResult<Sink> {
             .remove(&0)
             .and_then(|_| {
                 if let Some(ParseElement::Node { name, .. }) = a.nodes.get_mut(&2) {
-                    *name = YARTE_TAG.clone();
+                    *name = yarte_tag();
                     Some(a)
                 } else {
                     None
@@ -330,3 +426,77 @@ pub fn parse_fragment(doc: &str) -> ParseResult<Sink> {
             .ok_or_else(|| vec![])
     })
 }
+
+/// Autonomous custom elements, as `<my-widget>`, are passed through untouched
+pub(crate) fn is_custom_element(name: &QualName) -> bool {
+    name.ns == ns!(html) && is_custom_name(&name.local)
+}
+
+pub(crate) fn is_custom_name(local: &str) -> bool {
+    local.starts_with(|x: char| x.is_ascii_lowercase())
+        && local.contains('-')
+        && !RESERVED_CUSTOM.contains(&local)
+}
+
+/// Hyphenated names of svg and mathml elements
+static RESERVED_CUSTOM: &[&str] = &[
+    "annotation-xml",
+    "color-profile",
+    "font-face",
+    "font-face-src",
+    "font-face-uri",
+    "font-face-format",
+    "font-face-name",
+    "missing-glyph",
+];
+
+/// Tag and attribute names, as written, of the custom elements start tags of `doc`
+fn custom_attr_names(doc: &str) -> VecDeque<(String, Vec<String>)> {
+    let mut custom = VecDeque::new();
+    let mut rest = doc;
+    while let Some(i) = rest.find('<') {
+        rest = &rest[i + 1..];
+        if rest.starts_with("!--") {
+            rest = rest.find("-->").map_or("", |i| &rest[i + 3..]);
+            continue;
+        }
+
+        let end = rest
+            .find(|x: char| x.is_whitespace() || x == '/' || x == '>')
+            .unwrap_or_else(|| rest.len());
+        let tag = rest[..end].to_ascii_lowercase();
+        rest = &rest[end..];
+        if !tag.contains('-') {
+            continue;
+        }
+
+        let mut names = vec![];
+        loop {
+            rest = rest.trim_start_matches(|x: char| x.is_whitespace() || x == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+            let end = rest
+                .find(|x: char| x.is_whitespace() || x == '/' || x == '>' || x == '=')
+                .unwrap_or_else(|| rest.len())
+                .max(1);
+            names.push(rest[..end].to_owned());
+            rest = rest[end..].trim_start();
+            if rest.starts_with('=') {
+                rest = rest[1..].trim_start();
+                let end = match rest.chars().next() {
+                    Some(q) if q == '"' || q == '\'' => {
+                        rest[1..].find(q).map_or(rest.len(), |i| i + 2)
+                    }
+                    _ => rest
+                        .find(|x: char| x.is_whitespace() || x == '>')
+                        .unwrap_or_else(|| rest.len()),
+                };
+                rest = &rest[end..];
+            }
+        }
+        custom.push_back((tag, names));
+    }
+
+    custom
+}
@@ -0,0 +1,496 @@
+use html5ever::{
+    tendril::StrTendril,
+    tokenizer::{
+        BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+    },
+};
+
+use yarte_helpers::helpers::url_scheme;
+use yarte_hir::{map_hir, HIR};
+
+/// Allowed elements, the others are dropped with their content
+static TAGS: [&str; 55] = [
+    "a",
+    "circle",
+    "clippath",
+    "defs",
+    "desc",
+    "ellipse",
+    "feblend",
+    "fecolormatrix",
+    "fecomponenttransfer",
+    "fecomposite",
+    "feconvolvematrix",
+    "fediffuselighting",
+    "fedisplacementmap",
+    "fedistantlight",
+    "fedropshadow",
+    "feflood",
+    "fefunca",
+    "fefuncb",
+    "fefuncg",
+    "fefuncr",
+    "fegaussianblur",
+    "feimage",
+    "femerge",
+    "femergenode",
+    "femorphology",
+    "feoffset",
+    "fepointlight",
+    "fespecularlighting",
+    "fespotlight",
+    "fetile",
+    "feturbulence",
+    "filter",
+    "g",
+    "image",
+    "line",
+    "lineargradient",
+    "marker",
+    "mask",
+    "path",
+    "pattern",
+    "polygon",
+    "polyline",
+    "radialgradient",
+    "rect",
+    "stop",
+    "style",
+    "svg",
+    "switch",
+    "symbol",
+    "text",
+    "textpath",
+    "title",
+    "tspan",
+    "use",
+    "view",
+];
+
+/// Allowed attributes, the `aria-*` attributes are allowed too
+static ATTRS: [&str; 158] = [
+    "alignment-baseline",
+    "amplitude",
+    "azimuth",
+    "basefrequency",
+    "baseline-shift",
+    "bias",
+    "class",
+    "clip-path",
+    "clip-rule",
+    "clippathunits",
+    "color",
+    "color-interpolation",
+    "color-interpolation-filters",
+    "cx",
+    "cy",
+    "d",
+    "diffuseconstant",
+    "direction",
+    "display",
+    "divisor",
+    "dominant-baseline",
+    "dx",
+    "dy",
+    "edgemode",
+    "elevation",
+    "exponent",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "filter",
+    "filterunits",
+    "flood-color",
+    "flood-opacity",
+    "font-family",
+    "font-size",
+    "font-size-adjust",
+    "font-stretch",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "fr",
+    "fx",
+    "fy",
+    "gradienttransform",
+    "gradientunits",
+    "height",
+    "href",
+    "id",
+    "image-rendering",
+    "in",
+    "in2",
+    "intercept",
+    "k1",
+    "k2",
+    "k3",
+    "k4",
+    "kernelmatrix",
+    "kernelunitlength",
+    "lang",
+    "lengthadjust",
+    "letter-spacing",
+    "lighting-color",
+    "limitingconeangle",
+    "marker-end",
+    "marker-mid",
+    "marker-start",
+    "markerheight",
+    "markerunits",
+    "markerwidth",
+    "mask",
+    "maskcontentunits",
+    "maskunits",
+    "media",
+    "method",
+    "mix-blend-mode",
+    "mode",
+    "numoctaves",
+    "offset",
+    "opacity",
+    "operator",
+    "order",
+    "orient",
+    "overflow",
+    "paint-order",
+    "pathlength",
+    "patterncontentunits",
+    "patterntransform",
+    "patternunits",
+    "points",
+    "pointsatx",
+    "pointsaty",
+    "pointsatz",
+    "preservealpha",
+    "preserveaspectratio",
+    "primitiveunits",
+    "r",
+    "radius",
+    "refx",
+    "refy",
+    "result",
+    "role",
+    "rotate",
+    "rx",
+    "ry",
+    "scale",
+    "seed",
+    "shape-rendering",
+    "side",
+    "slope",
+    "spacing",
+    "specularconstant",
+    "specularexponent",
+    "spreadmethod",
+    "startoffset",
+    "stddeviation",
+    "stitchtiles",
+    "stop-color",
+    "stop-opacity",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "style",
+    "surfacescale",
+    "systemlanguage",
+    "tablevalues",
+    "targetx",
+    "targety",
+    "text-anchor",
+    "text-decoration",
+    "text-rendering",
+    "textlength",
+    "transform",
+    "transform-origin",
+    "type",
+    "values",
+    "vector-effect",
+    "version",
+    "viewbox",
+    "visibility",
+    "width",
+    "word-spacing",
+    "writing-mode",
+    "x",
+    "x1",
+    "x2",
+    "xchannelselector",
+    "xlink:href",
+    "xlink:title",
+    "xml:lang",
+    "xml:space",
+    "xmlns",
+    "xmlns:xlink",
+    "y",
+];
+
+/// Url schemes allowed in the `href`s, the urls without scheme are allowed
+static SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Media types of the `data:` urls allowed in the `href` of `<image>` and `<feImage>`
+static IMAGES: [&str; 4] = ["image/gif", "image/jpeg", "image/png", "image/webp"];
+
+/// Replace the `{{ @inline_svg "path" }}` expressions with the sanitized file
+pub fn inline_svg(hir: Vec<HIR>) -> Vec<HIR> {
+    map_hir(hir, &mut |x| match x {
+        HIR::Expr(e) | HIR::Safe(e) if inlined(&e).is_some() => {
+            let (src, ids) = inlined(&e).expect("inline_svg");
+            HIR::Lit(sanitize_svg(&src, ids.as_deref()))
+        }
+        x => x,
+    })
+}
+
+/// Source and ids of the lowered `::yarte::builtins::InlineSvg(src, ids)`
+fn inlined(e: &syn::Expr) -> Option<(String, Option<String>)> {
+    let (func, args) = match e {
+        syn::Expr::Call(syn::ExprCall { func, args, .. }) => (func, args),
+        _ => return None,
+    };
+    match &**func {
+        syn::Expr::Path(p) if p.path.segments.last()?.ident == "InlineSvg" => (),
+        _ => return None,
+    }
+    let str_lit = |e: &syn::Expr| match e {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => Some(s.value()),
+        _ => None,
+    };
+    let src = str_lit(args.first()?)?;
+    let ids = match args.iter().nth(1)? {
+        syn::Expr::Call(syn::ExprCall { args, .. }) => Some(str_lit(args.first()?)?),
+        _ => None,
+    };
+
+    Some((src, ids))
+}
+
+/// Sanitized svg of a file inlined by `{{ @inline_svg "path" }}`
+///
+/// Only the svg elements and attributes of a whitelist are kept, inside the `<svg>` root. The
+/// other elements are dropped with their content, as the scripts, the foreign objects or the
+/// animations, and the event attributes `on*` too. The `href`s must be references `#id`,
+/// relative urls or `http`, `https` and `mailto` urls, or `data:` urls of raster images in
+/// `<image>`. The styles and attributes with css escapes, `@import`, `expression(` or urls
+/// other than `url(#id)` are dropped. With `ids` the ids are prefixed with it and its
+/// references `#id` and `url(#id)` too, so the same icon can be inlined twice.
+pub fn sanitize_svg(src: &str, ids: Option<&str>) -> String {
+    let mut tokenizer = Tokenizer::new(
+        Sanitizer {
+            out: String::with_capacity(src.len()),
+            ids,
+            open: vec![],
+            skip: None,
+            css: None,
+        },
+        TokenizerOpts::default(),
+    );
+    let mut queue = BufferQueue::new();
+    queue.push_back(StrTendril::from_slice(src));
+    let _ = tokenizer.feed(&mut queue);
+    tokenizer.end();
+
+    let mut sink = tokenizer.sink;
+    sink.close(0);
+    sink.out
+}
+
+struct Sanitizer<'a> {
+    out: String,
+    ids: Option<&'a str>,
+    /// Open allowed elements
+    open: Vec<String>,
+    /// Dropped element and its nested elements of the same name
+    skip: Option<(String, usize)>,
+    /// Text of the open `<style>`, written at its end if it's safe
+    css: Option<String>,
+}
+
+impl<'a> Sanitizer<'a> {
+    fn attr(&mut self, tag: &str, name: &str, value: &str) {
+        if !(ATTRS.contains(&name) || name.starts_with("aria-")) {
+            return;
+        }
+        let value = if name == "href" || name == "xlink:href" {
+            let value = value.trim();
+            if value.starts_with('#') {
+                match self.ids {
+                    Some(prefix) => format!("#{}-{}", prefix, &value[1..]),
+                    None => value.to_owned(),
+                }
+            } else if url(tag, value) {
+                value.to_owned()
+            } else {
+                return;
+            }
+        } else if name == "id" {
+            match self.ids {
+                Some(prefix) => format!("{}-{}", prefix, value),
+                None => value.to_owned(),
+            }
+        } else if css(value) {
+            self.prefix(value)
+        } else {
+            return;
+        };
+
+        self.out.push(' ');
+        self.out.push_str(name);
+        self.out.push_str("=\"");
+        for c in value.chars() {
+            match c {
+                '&' => self.out.push_str("&amp;"),
+                '"' => self.out.push_str("&quot;"),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    /// Prefix the ids and their references `url(#id)`
+    fn prefix(&self, value: &str) -> String {
+        match self.ids {
+            Some(prefix) => value.replace("url(#", &format!("url(#{}-", prefix)),
+            None => value.to_owned(),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '&' => self.out.push_str("&amp;"),
+                '<' => self.out.push_str("&lt;"),
+                '>' => self.out.push_str("&gt;"),
+                c => self.out.push(c),
+            }
+        }
+    }
+
+    /// Close the open elements from `i`
+    fn close(&mut self, i: usize) {
+        for tag in self.open.split_off(i).iter().rev() {
+            if tag == "style" {
+                if let Some(text) = self.css.take() {
+                    if css(&text) {
+                        let text = self.prefix(&text);
+                        self.text(&text);
+                    }
+                }
+            }
+            self.out.push_str("</");
+            self.out.push_str(tag);
+            self.out.push('>');
+        }
+    }
+}
+
+/// The `href` is a relative url, an url of an allowed scheme or an image of the `<image>`s
+fn url(tag: &str, value: &str) -> bool {
+    match url_scheme(value) {
+        None => true,
+        Some((scheme, rest)) if scheme == "data" => {
+            let rest = rest.to_ascii_lowercase();
+            (tag == "image" || tag == "feimage")
+                && IMAGES.iter().any(|mime| {
+                    rest.starts_with(mime)
+                        && (rest[mime.len()..].starts_with(';')
+                            || rest[mime.len()..].starts_with(','))
+                })
+        }
+        Some((scheme, _)) => SCHEMES.contains(&scheme.as_str()),
+    }
+}
+
+/// The css doesn't load resources or run scripts, its urls are references `url(#id)`
+fn css(value: &str) -> bool {
+    let value: String = value
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if value.contains('\\')
+        || value.contains("@import")
+        || value.contains("expression(")
+        || value.contains("javascript:")
+        || value.contains("-moz-binding")
+        || value.contains("behavior:")
+    {
+        return false;
+    }
+
+    value.match_indices("url(").all(|(i, _)| {
+        value[i + 4..]
+            .trim_start_matches(&['"', '\''][..])
+            .starts_with('#')
+    })
+}
+
+impl<'a> TokenSink for Sanitizer<'a> {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) => {
+                let name = tag.name.to_string();
+                if let Some((skip, depth)) = &mut self.skip {
+                    if *skip == name {
+                        match tag.kind {
+                            TagKind::StartTag if !tag.self_closing => *depth += 1,
+                            TagKind::EndTag if *depth == 0 => self.skip = None,
+                            TagKind::EndTag => *depth -= 1,
+                            _ => (),
+                        }
+                    }
+                    return TokenSinkResult::Continue;
+                }
+
+                if tag.kind == TagKind::EndTag {
+                    if let Some(i) = self.open.iter().rposition(|x| *x == name) {
+                        self.close(i);
+                    }
+                } else if self.css.is_none()
+                    && TAGS.contains(&name.as_str())
+                    && (name == "svg" || !self.open.is_empty())
+                {
+                    self.out.push('<');
+                    self.out.push_str(&name);
+                    for a in &tag.attrs {
+                        self.attr(&name, &a.name.local, &a.value);
+                    }
+                    if tag.self_closing {
+                        self.out.push_str("/>");
+                    } else {
+                        self.out.push('>');
+                        if name == "style" {
+                            self.css = Some(String::new());
+                        }
+                        self.open.push(name);
+                    }
+                } else if !tag.self_closing && self.css.is_none() {
+                    // The tags in a style are dropped, its text is kept
+                    self.skip = Some((name, 0));
+                }
+            }
+            Token::CharacterTokens(text) if self.skip.is_none() => match &mut self.css {
+                Some(css) => css.push_str(&text),
+                None if !self.open.is_empty() => self.text(&text),
+                None => (),
+            },
+            _ => (),
+        }
+
+        TokenSinkResult::Continue
+    }
+
+    // The `<![CDATA[..]]>` sections of the styles are text in svg
+    fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+        !self.open.is_empty()
+    }
+}
@@ -0,0 +1,253 @@
+//! Implied `<tbody>` of the rows of a `<table>`
+//!
+//! The browser wraps the rows written as children of a `<table>` in a `<tbody>`, so the
+//! html of the templates is written with it and the elements built by the client and the
+//! ones parsed by the browser are the same. A block whose body starts with a row, as
+//! `{{#each rows}}<tr>..</tr>{{/each}}`, is a row of its parent.
+use yarte_hir::{Each, IfElse, HIR};
+
+/// Elements of the structure of the tables, `true` the `<tbody>` written by `Sections`
+#[derive(Clone, Copy, PartialEq)]
+enum Open {
+    Table,
+    Section(bool),
+    Row,
+    Cell,
+    Other,
+}
+
+#[derive(Default)]
+pub(crate) struct Sections {
+    open: Vec<Open>,
+}
+
+impl Sections {
+    /// Push `lit` to `html` with the `<tbody>` tags of its rows
+    pub(crate) fn lit(&mut self, html: &mut String, lit: &str) {
+        let mut rest = lit;
+        while let Some(i) = rest.find('<') {
+            html.push_str(&rest[..i]);
+            rest = &rest[i..];
+            if rest.starts_with("<!--") {
+                let end = rest.find("-->").map_or(rest.len(), |i| i + 3);
+                html.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+
+            let (end, start) = if rest.starts_with("</") {
+                (true, 2)
+            } else {
+                (false, 1)
+            };
+            let len = rest[start..]
+                .find(|x: char| !x.is_ascii_alphanumeric())
+                .unwrap_or_else(|| rest.len() - start);
+            if len == 0 {
+                html.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+            let name = rest[start..start + len].to_ascii_lowercase();
+            if end {
+                self.end(html, &name);
+            } else {
+                self.start(html, &name);
+            }
+
+            let close = rest.find('>').map_or(rest.len(), |i| i + 1);
+            html.push_str(&rest[..close]);
+            rest = &rest[close..];
+            if !end && (name == "script" || name == "style") {
+                let raw = rest
+                    .to_ascii_lowercase()
+                    .find(&format!("</{}", name))
+                    .unwrap_or_else(|| rest.len());
+                html.push_str(&rest[..raw]);
+                rest = &rest[raw..];
+            }
+        }
+        html.push_str(rest);
+    }
+
+    /// Opens the `<tbody>` of a block of rows
+    pub(crate) fn block(&mut self, html: &mut String, ir: &HIR) {
+        if self.open.last() == Some(&Open::Table) && starts_row(ir) {
+            html.push_str("<tbody>");
+            self.open.push(Open::Section(true));
+        }
+    }
+
+    fn start(&mut self, html: &mut String, name: &str) {
+        match name {
+            "table" => self.open.push(Open::Table),
+            "caption" | "colgroup" => self.open.push(Open::Other),
+            "thead" | "tbody" | "tfoot" => {
+                self.close(html, |x| x != Open::Table);
+                self.open.push(Open::Section(false));
+            }
+            "tr" => {
+                self.close(html, |x| x == Open::Row || x == Open::Cell);
+                if self.open.last() == Some(&Open::Table) {
+                    html.push_str("<tbody>");
+                    self.open.push(Open::Section(true));
+                }
+                self.open.push(Open::Row);
+            }
+            "td" | "th" => {
+                self.close(html, |x| x == Open::Cell);
+                self.open.push(Open::Cell);
+            }
+            _ => (),
+        }
+    }
+
+    fn end(&mut self, html: &mut String, name: &str) {
+        let kind = match name {
+            "table" => Open::Table,
+            "caption" | "colgroup" => Open::Other,
+            "thead" | "tbody" | "tfoot" => Open::Section(false),
+            "tr" => Open::Row,
+            "td" | "th" => Open::Cell,
+            _ => return,
+        };
+        let same = |x: Open| match (x, kind) {
+            (Open::Section(_), Open::Section(_)) => true,
+            (x, kind) => x == kind,
+        };
+
+        if self.open.iter().any(|x| same(*x)) {
+            self.close(html, |x| !same(x));
+            self.open.pop();
+        }
+    }
+
+    /// Pop the open elements while `pred`, closing the written `<tbody>`
+    fn close<F: Fn(Open) -> bool>(&mut self, html: &mut String, pred: F) {
+        while let Some(last) = self.open.last() {
+            if !pred(*last) {
+                break;
+            }
+            if *last == Open::Section(true) {
+                html.push_str("</tbody>");
+            }
+            self.open.pop();
+        }
+    }
+}
+
+/// The body of the block starts with a `<tr>`
+fn starts_row(ir: &HIR) -> bool {
+    let body = match ir {
+        HIR::Each(each) => {
+            let Each { body, .. } = &**each;
+            body
+        }
+        HIR::IfElse(if_else) => {
+            let IfElse { ifs, .. } = &**if_else;
+            &ifs.1
+        }
+        _ => return false,
+    };
+
+    for ir in body {
+        match ir {
+            HIR::Lit(lit) => {
+                let lit = lit.trim_start();
+                if lit.is_empty() {
+                    continue;
+                }
+                let lit = lit.to_ascii_lowercase();
+                return lit.starts_with("<tr")
+                    && !lit[3..].starts_with(|x: char| x.is_ascii_alphanumeric());
+            }
+            // Region markers, `#once` and `let` don't write
+            HIR::Local(_) => continue,
+            ir => return starts_row(ir),
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use syn::parse_str;
+    use yarte_hir::{Each, HIR};
+
+    use super::Sections;
+
+    fn html(ir: &[HIR]) -> String {
+        let mut html = String::new();
+        let mut sections = Sections::default();
+        for x in ir {
+            match x {
+                HIR::Lit(x) => sections.lit(&mut html, x),
+                x => {
+                    sections.block(&mut html, x);
+                    html.push_str("{}");
+                }
+            }
+        }
+        html
+    }
+
+    #[test]
+    fn test_rows() {
+        let ir = vec![HIR::Lit(
+            "<table><caption>a</caption><tr><td>b<tr><td>c</tr></table>\
+             <table><tbody><tr><td>d</td></tr></tbody><tr></tr></table>"
+                .into(),
+        )];
+        assert_eq!(
+            html(&ir),
+            "<table><caption>a</caption><tbody><tr><td>b<tr><td>c</tr></tbody></table>\
+             <table><tbody><tr><td>d</td></tr></tbody><tbody><tr></tr></tbody></table>"
+        );
+    }
+
+    #[test]
+    fn test_block_rows() {
+        let each = |body: &str| {
+            HIR::Each(Box::new(Each {
+                args: parse_str("rows").unwrap(),
+                body: vec![HIR::Lit(body.into())],
+                expr: parse_str("_row").unwrap(),
+            }))
+        };
+        let ir = vec![
+            HIR::Lit("<table><thead><tr><th>a</th></tr></thead>\n".into()),
+            each("\n<tr><td>b</td></tr>"),
+            each("<tr><td>c</td></tr>"),
+            HIR::Lit("<tfoot></tfoot></table><table>".into()),
+            each("<td>d</td>"),
+            HIR::Lit("</table>".into()),
+        ];
+        assert_eq!(
+            html(&ir),
+            "<table><thead><tr><th>a</th></tr></thead>\n<tbody>{}{}</tbody><tfoot></tfoot></table>\
+             <table>{}</table>"
+        );
+    }
+
+    #[test]
+    fn test_local_before_row() {
+        let local = match parse_str("let _hit = 1;").unwrap() {
+            syn::Stmt::Local(local) => local,
+            _ => unreachable!(),
+        };
+        let ir = vec![
+            HIR::Lit("<table>".into()),
+            HIR::Each(Box::new(Each {
+                args: parse_str("rows").unwrap(),
+                body: vec![
+                    HIR::Local(Box::new(local)),
+                    HIR::Lit("<tr><td>a</td></tr>".into()),
+                ],
+                expr: parse_str("_row").unwrap(),
+            })),
+            HIR::Lit("</table>".into()),
+        ];
+        assert_eq!(html(&ir), "<table><tbody>{}</tbody></table>");
+    }
+}
@@ -0,0 +1,347 @@
+use markup5ever::local_name;
+
+use yarte_hir::{Each as HEach, IfElse as HIfElse, HIR};
+use yarte_parser::is_lit_ws;
+
+use crate::{
+    sink::{
+        head, mark, parse_document, parse_fragment, ParseAttribute, ParseElement, ParseNodeId,
+        ParseResult, Sink, TAIL,
+    },
+    timing::measure,
+};
+
+/// Plain text rendering of a html template, for the text alternative of emails
+///
+/// Tags are stripped, the entities decoded, the links written as `text (url)` and
+/// the blocks separated by new lines. Expressions aren't escaped.
+pub fn to_textfmt(ir: Vec<HIR>) -> ParseResult<Vec<HIR>> {
+    measure(|| {
+        let html = get_indexed_html(&ir);
+        let sink = match parse_document(&html) {
+            Ok(a) => a,
+            Err(_) => parse_fragment(&html)?,
+        };
+
+        TextAlt::new(ir, false).build(&sink)
+    })
+}
+
+fn to_textfmt_fragment(ir: Vec<HIR>) -> ParseResult<Vec<HIR>> {
+    let sink = parse_fragment(&get_indexed_html(&ir))?;
+
+    TextAlt::new(ir, true).build(&sink)
+}
+
+/// Html with a mark by expression, the hash of each mark is the index of its expression
+fn get_indexed_html(ir: &[HIR]) -> String {
+    let mut html = String::new();
+    let mut index = 0;
+    for x in ir {
+        match x {
+            HIR::Lit(x) => html.push_str(x),
+            _ => {
+                html.push_str(head());
+                html.push_str(&format!("{:#010x}", index));
+                html.push_str(TAIL);
+                index += 1;
+            }
+        }
+    }
+
+    html
+}
+
+/// Whitespace between two written chars
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Pending {
+    None,
+    Space,
+    Line,
+    Blank,
+}
+
+struct TextAlt {
+    ir: Vec<Option<HIR>>,
+    buf: Vec<HIR>,
+    text: String,
+    pending: Pending,
+    started: bool,
+    fragment: bool,
+}
+
+impl TextAlt {
+    fn new(ir: Vec<HIR>, fragment: bool) -> TextAlt {
+        TextAlt {
+            ir: ir
+                .into_iter()
+                .filter(|x| match x {
+                    HIR::Lit(_) => false,
+                    _ => true,
+                })
+                .map(Some)
+                .collect(),
+            buf: vec![],
+            text: String::new(),
+            pending: Pending::None,
+            // Whitespace at the start of a block body separates it from the previous content
+            started: fragment,
+            fragment,
+        }
+    }
+
+    fn build(mut self, sink: &Sink) -> ParseResult<Vec<HIR>> {
+        if let Some(root) = sink.nodes.keys().next() {
+            self.node(sink, *root)?;
+        }
+        if self.fragment && self.pending == Pending::Space {
+            self.text.push(' ');
+        }
+        self.flush();
+
+        Ok(self.buf)
+    }
+
+    fn node(&mut self, sink: &Sink, id: ParseNodeId) -> ParseResult<()> {
+        match sink.nodes.get(&id) {
+            Some(ParseElement::Document(children)) => self.children(sink, children)?,
+            Some(ParseElement::Node {
+                name,
+                attrs,
+                children,
+                ..
+            }) => match name.local {
+                local_name!("head")
+                | local_name!("title")
+                | local_name!("style")
+                | local_name!("script")
+                | local_name!("template") => (),
+                local_name!("br") => self.block(Pending::Line),
+                local_name!("hr") => {
+                    self.block(Pending::Blank);
+                    self.write("---")?;
+                    self.block(Pending::Blank);
+                }
+                local_name!("img") => {
+                    if let Some(alt) = attr(attrs, "alt") {
+                        self.write(alt)?;
+                    }
+                }
+                local_name!("a") => {
+                    let start = self.text.len();
+                    self.children(sink, children)?;
+                    if let Some(href) = attr(attrs, "href").map(str::trim) {
+                        let same = self.text.get(start..).map_or(false, |x| x.trim() == href);
+                        if !(href.is_empty() || href.starts_with('#') || same) {
+                            self.write(" (")?;
+                            self.write(href)?;
+                            self.write(")")?;
+                        }
+                    }
+                }
+                local_name!("li") => {
+                    self.block(Pending::Line);
+                    self.write("- ")?;
+                    self.children(sink, children)?;
+                    self.block(Pending::Line);
+                }
+                local_name!("td") | local_name!("th") => {
+                    self.children(sink, children)?;
+                    self.block(Pending::Space);
+                }
+                local_name!("p")
+                | local_name!("h1")
+                | local_name!("h2")
+                | local_name!("h3")
+                | local_name!("h4")
+                | local_name!("h5")
+                | local_name!("h6")
+                | local_name!("ul")
+                | local_name!("ol")
+                | local_name!("table")
+                | local_name!("blockquote")
+                | local_name!("pre") => {
+                    self.block(Pending::Blank);
+                    self.children(sink, children)?;
+                    self.block(Pending::Blank);
+                }
+                local_name!("div")
+                | local_name!("tr")
+                | local_name!("section")
+                | local_name!("article")
+                | local_name!("header")
+                | local_name!("footer")
+                | local_name!("nav")
+                | local_name!("main")
+                | local_name!("aside") => {
+                    self.block(Pending::Line);
+                    self.children(sink, children)?;
+                    self.block(Pending::Line);
+                }
+                _ => self.children(sink, children)?,
+            },
+            Some(ParseElement::Text(text)) => self.write(text)?,
+            Some(ParseElement::Mark(hash)) => self.expr(hash)?,
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    fn children(&mut self, sink: &Sink, children: &[ParseNodeId]) -> ParseResult<()> {
+        for child in children {
+            self.node(sink, *child)?;
+        }
+
+        Ok(())
+    }
+
+    fn block(&mut self, pending: Pending) {
+        if pending > self.pending {
+            self.pending = pending;
+        }
+    }
+
+    /// Write text with the whitespace collapsed, the marks of attributes are expressions
+    fn write(&mut self, text: &str) -> ParseResult<()> {
+        let mut text = text;
+        while let Some(i) = text.find(head()) {
+            self.write_text(&text[..i]);
+            let rest = &text[i + head().len()..];
+            let end = rest.find(TAIL).unwrap_or_else(|| rest.len());
+            self.expr(&rest[..end])?;
+            text = rest.get(end + TAIL.len()..).unwrap_or("");
+        }
+        self.write_text(text);
+
+        Ok(())
+    }
+
+    fn write_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if is_lit_ws(c) {
+                self.block(Pending::Space);
+            } else {
+                self.whitespace();
+                self.text.push(c);
+            }
+        }
+    }
+
+    fn whitespace(&mut self) {
+        if self.started {
+            match self.pending {
+                Pending::None => (),
+                Pending::Space => self.text.push(' '),
+                Pending::Line => self.text.push('\n'),
+                Pending::Blank => self.text.push_str("\n\n"),
+            }
+        }
+        self.pending = Pending::None;
+        self.started = true;
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.buf.push(HIR::Lit(self.text.split_off(0)));
+        }
+    }
+
+    fn expr(&mut self, hash: &str) -> ParseResult<()> {
+        let hash = hash.trim_start_matches(mark());
+        let index = usize::from_str_radix(hash.trim_start_matches("0x"), 16).expect("mark index");
+        let ir = match self.ir.get_mut(index).and_then(Option::take) {
+            Some(ir) => ir,
+            None => return Ok(()),
+        };
+
+        if let HIR::Local(_) = ir {
+        } else {
+            self.whitespace();
+        }
+        self.flush();
+        self.buf.push(match ir {
+            HIR::Expr(e) => HIR::Safe(e),
+            HIR::Each(each) => {
+                let HEach { args, body, expr } = *each;
+                HIR::Each(Box::new(HEach {
+                    args,
+                    expr,
+                    body: to_textfmt_fragment(body)?,
+                }))
+            }
+            HIR::IfElse(if_else) => {
+                let HIfElse { ifs, if_else, els } = *if_else;
+                let mut buf_if_else = vec![];
+                for (expr, body) in if_else {
+                    buf_if_else.push((expr, to_textfmt_fragment(body)?));
+                }
+                let els = match els {
+                    Some(els) => Some(to_textfmt_fragment(els)?),
+                    None => None,
+                };
+                HIR::IfElse(Box::new(HIfElse {
+                    ifs: (ifs.0, to_textfmt_fragment(ifs.1)?),
+                    if_else: buf_if_else,
+                    els,
+                }))
+            }
+            ir => ir,
+        });
+
+        Ok(())
+    }
+}
+
+fn attr<'a>(attrs: &'a [ParseAttribute], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|a| &*a.name.local == name)
+        .map(|a| a.value.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(ir: Vec<HIR>) -> String {
+        to_textfmt(ir)
+            .unwrap()
+            .into_iter()
+            .map(|x| match x {
+                HIR::Lit(x) => x,
+                HIR::Safe(_) => "{}".to_owned(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text() {
+        let ir = vec![HIR::Lit(
+            "<!DOCTYPE html><html><head><title>A</title><style>p { color: red }</style></head>\
+             <body><h1>Hello &amp; welcome</h1><p>Read   the\n <a href=\"https://a.b/c\">docs</a>, \
+             <a href=\"https://a.b\">https://a.b</a></p><ul><li>one</li><li>two</li></ul>\
+             <img alt=\"logo\"><br>bye</body></html>"
+                .into(),
+        )];
+        assert_eq!(
+            text(ir),
+            "Hello & welcome\n\nRead the docs (https://a.b/c), https://a.b\n\n- one\n- two\n\n\
+             logo\nbye"
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        let expr = || Box::new(syn::parse_str("a").unwrap());
+        let ir = vec![
+            HIR::Lit("<p>Hi <b>".into()),
+            HIR::Expr(expr()),
+            HIR::Lit("</b>, <a href=\"".into()),
+            HIR::Safe(expr()),
+            HIR::Lit("\">link</a></p>".into()),
+        ];
+        assert_eq!(text(ir), "Hi {}, link ({})");
+    }
+}
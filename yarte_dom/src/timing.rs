@@ -0,0 +1,27 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static DEPTH: Cell<usize> = Cell::new(0);
+    static ELAPSED: Cell<Duration> = Cell::new(Duration::default());
+}
+
+/// Time spent in the dom rewrites of the thread since the last call
+pub fn take_elapsed() -> Duration {
+    ELAPSED.with(|x| x.replace(Duration::default()))
+}
+
+/// Add the time of `f` to the elapsed time, nested measures are counted once
+pub(crate) fn measure<T>(f: impl FnOnce() -> T) -> T {
+    let depth = DEPTH.with(|x| x.replace(x.get() + 1));
+    let start = Instant::now();
+    let out = f();
+    DEPTH.with(|x| x.set(depth));
+    if depth == 0 {
+        ELAPSED.with(|x| x.set(x.get() + start.elapsed()));
+    }
+
+    out
+}
@@ -39,8 +39,6 @@ use html5ever::tokenizer::states::RawKind;
 use log::{debug, log_enabled, Level};
 use mac::{_tt_as_expr_hack, format_if, matches};
 
-use lazy_static::lazy_static;
-
 /// ASCII whitespace characters, as defined by
 /// tree construction modes that treat them specially.
 pub fn is_ascii_whitespace(c: char) -> bool {
@@ -62,7 +60,12 @@ mod tag_sets;
 mod data;
 mod types;
 
-include!(concat!(env!("OUT_DIR"), "/rules.rs"));
+#[cfg(test)]
+#[path = "../../macros/match_token.rs"]
+mod match_token;
+
+// The tree builder rules, expanded from `macros/rules.rs`
+include!("rules.rs");
 
 /// The HTML tree builder.
 pub struct TreeBuilder<Handle, Sink> {
@@ -92,10 +95,6 @@ pub struct TreeBuilder<Handle, Sink> {
     context_elem: Option<Handle>,
 }
 
-lazy_static! {
-    pub static ref YARTE_TAG: QualName = QualName::new(None, ns!(html), LocalName::from("marquee"));
-}
-
 impl<Handle, Sink> TreeBuilder<Handle, Sink>
 where
     Handle: Clone,
@@ -1095,3 +1094,58 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, path::Path, process::Command};
+
+    use super::match_token;
+
+    /// The checked-in rules are the formatted expansion of `macros/rules.rs`, after a change
+    /// of the macro rules run this test with `YARTE_DOM_RULES=write`
+    #[test]
+    fn test_rules() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let expanded = env::temp_dir().join("yarte_dom_rules.rs");
+        match_token::expand(&dir.join("macros/rules.rs"), &expanded);
+        let code = fs::read_to_string(&expanded).unwrap();
+        fs::write(&expanded, format!("{}{}", HEADER, code)).unwrap();
+
+        let rustfmt = env::var("RUSTFMT").unwrap_or_else(|_| "rustfmt".into());
+        match Command::new(rustfmt)
+            .args(&["--edition", "2018"])
+            .arg(&expanded)
+            .status()
+        {
+            Ok(status) => assert!(status.success(), "rustfmt failed on the expanded rules"),
+            Err(_) => {
+                eprintln!("rustfmt not found, src/tree_builder/rules.rs isn't checked");
+                return;
+            }
+        }
+        let expanded = fs::read_to_string(&expanded).unwrap();
+
+        let rules = dir.join("src/tree_builder/rules.rs");
+        if env::var("YARTE_DOM_RULES").ok().as_deref() == Some("write") {
+            fs::write(&rules, &expanded).unwrap();
+        }
+        assert!(
+            expanded == fs::read_to_string(&rules).unwrap(),
+            "src/tree_builder/rules.rs is outdated, run this test with `YARTE_DOM_RULES=write`"
+        );
+    }
+
+    static HEADER: &str = "\
+// Copyright 2014-2017 The html5ever Project Developers. See the
+// COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Generated from `macros/rules.rs` by `tree_builder::test::test_rules`, don't edit
+
+";
+}
@@ -7,17 +7,14 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// The tree builder rules, as a single, enormous nested match expression.
+// Generated from `macros/rules.rs` by `tree_builder::test::test_rules`, don't edit
 
+use html5ever::tendril::SliceExt;
 use html5ever::tokenizer::states::{Rawtext, Rcdata};
 use markup5ever::{expanded_name, local_name, namespace_prefix, namespace_url, ns};
-
-use html5ever::tendril::SliceExt;
-
 fn current_node<Handle>(open_elems: &[Handle]) -> &Handle {
     open_elems.last().expect("no current element")
 }
-
 #[doc(hidden)]
 impl<Handle, Sink> TreeBuilder<Handle, Sink>
 where
@@ -26,79 +23,554 @@ where
 {
     fn step(&mut self, mode: InsertionMode, token: Token) -> ProcessResult<Handle> {
         self.debug_step(mode, &token);
-
         match mode {
-            Initial => match_token!(token {
+            Initial => match token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
                 tag @ CharacterTokens(NotWhitespace, _) => self.unexpected(&tag),
-                token => Reprocess(BeforeHtml, token),
-            }),
-            //§ the-before-html-insertion-mode
-            BeforeHtml => match_token!(token {
+                last_arm_token => {
+                    let enable_wildcards = match last_arm_token {
+                        _ => true,
+                    };
+                    match (enable_wildcards, last_arm_token) {
+                        (_, token) => Reprocess(BeforeHtml, token),
+                    }
+                }
+            },
+            BeforeHtml => match token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
-                tag @ <html> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("html"),
+                        ..
+                    },
+                ) => {
                     self.create_root(tag.attrs);
                     self.mode = InHtml;
                     Done
                 }
-
-                </head> </body> </html> </br> => else,
-
-                tag => self.unexpected(&tag),
-            }),
-            //§ parsing-main-inbody
-            InHtml => match_token!(token {
+                last_arm_token => {
+                    let enable_wildcards = match last_arm_token {
+                        crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                            kind: html5ever::tokenizer::EndTag,
+                            name: local_name!("head"),
+                            ..
+                        }) => false,
+                        crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                            kind: html5ever::tokenizer::EndTag,
+                            name: local_name!("body"),
+                            ..
+                        }) => false,
+                        crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                            kind: html5ever::tokenizer::EndTag,
+                            name: local_name!("html"),
+                            ..
+                        }) => false,
+                        crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                            kind: html5ever::tokenizer::EndTag,
+                            name: local_name!("br"),
+                            ..
+                        }) => false,
+                        _ => true,
+                    };
+                    match (enable_wildcards, last_arm_token) {
+                        (_, tag) => self.unexpected(&tag),
+                    }
+                }
+            },
+            InHtml => match token {
                 CharacterTokens(_, text) => self.append_text(text),
                 CommentToken(text) => self.append_comment(text),
                 NullCharacterToken => self.unexpected(&token),
-                EOFToken => {
-                    self.stop_parsing()
-                }
-                </body> => {
+                EOFToken => self.stop_parsing(),
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::EndTag,
+                    name: local_name!("body"),
+                    ..
+                }) => {
                     if self.in_scope_named(default_scope, local_name!("body")) {
                         self.check_body_end();
                         self.mode = AfterBody;
                     } else {
-                        self.sink.parse_error(Borrowed("</body> with no <body> in scope"));
+                        self.sink
+                            .parse_error(Borrowed("</body> with no <body> in scope"));
                     }
                     Done
                 }
-
-                </html> => {
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::EndTag,
+                    name: local_name!("html"),
+                    ..
+                }) => {
                     if self.in_scope_named(default_scope, local_name!("body")) {
                         self.check_body_end();
                         Reprocess(AfterBody, token)
                     } else {
-                        self.sink.parse_error(Borrowed("</html> with no <body> in scope"));
+                        self.sink
+                            .parse_error(Borrowed("</html> with no <body> in scope"));
                         Done
                     }
                 }
-
-                tag @ <base> <basefont> <bgsound> <link> <meta> => {
-                    // FIXME: handle <meta charset=...> and <meta http-equiv="Content-Type">
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("base"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("basefont"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("bgsound"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("link"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("meta"),
+                        ..
+                    },
+                ) => {
                     self.insert_and_pop_element_for(tag);
                     DoneAckSelfClosing
                 }
-
-                tag @ <area> <br> <embed> <img> <keygen> <wbr> <input> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("area"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("br"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("embed"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("img"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("keygen"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("wbr"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("input"),
+                        ..
+                    },
+                ) => {
                     self.reconstruct_formatting();
                     self.insert_and_pop_element_for(tag);
                     DoneAckSelfClosing
                 }
-
-                tag @ <address> <applet> <article> <aside> <blockquote> <body> <caption>
-                <center> <col> <colgroup> <dd> <details> <dialog> <dir> <div> <dl>
-                <dt> <fieldset> <figcaption> <figure> <footer> <form> <frame> <frameset>
-                <head> <header> <hgroup> <li> <main> <marquee> <menu> <nav>
-                <object> <ol> <p> <section> <select> <summary>
-                <table> <tbody> <td> <tfoot> <th> <thead> <tr> <ul> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("address"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("applet"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("article"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("aside"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("blockquote"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("body"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("caption"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("center"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("col"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("colgroup"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("dd"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("details"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("dialog"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("dir"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("div"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("dl"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("dt"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("fieldset"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("figcaption"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("figure"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("footer"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("form"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("frame"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("frameset"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("head"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("header"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("hgroup"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("li"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("main"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("marquee"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("menu"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("nav"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("object"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("ol"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("p"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("section"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("select"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("summary"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("table"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("tbody"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("td"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("tfoot"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("th"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("thead"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("tr"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("ul"),
+                        ..
+                    },
+                ) => {
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ <h1> <h2> <h3> <h4> <h5> <h6> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("h1"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("h2"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("h3"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("h4"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("h5"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("h6"),
+                        ..
+                    },
+                ) => {
                     if self.current_node_in(heading_tag) {
                         self.sink.parse_error(Borrowed("nested heading tags"));
                         self.pop();
@@ -106,18 +578,40 @@ where
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ <pre> <listing> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("pre"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("listing"),
+                        ..
+                    },
+                ) => {
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ <plaintext> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("plaintext"),
+                        ..
+                    },
+                ) => {
                     self.insert_element_for(tag);
                     ToPlaintext
                 }
-
-                tag @ <button> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("button"),
+                        ..
+                    },
+                ) => {
                     if self.in_scope_named(default_scope, local_name!("button")) {
                         self.sink.parse_error(Borrowed("nested buttons"));
                         self.generate_implied_end(cursory_implied_end);
@@ -127,13 +621,293 @@ where
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ </address> </applet> </article> </aside> </blockquote> </caption>
-                </center> </col> </colgroup> </details> </dialog> </dir> </div> </dl>
-                </fieldset> </figcaption> </figure> </footer> </form> </frame> </frameset>
-                </head> </header> </hgroup> </main> </marquee> </menu> </nav>
-                </object> </ol> </section> </select> </summary>
-                </table> </tbody> </td> </tfoot> </th> </thead> </tr> </ul> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("address"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("applet"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("article"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("aside"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("blockquote"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("caption"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("center"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("col"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("colgroup"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("details"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("dialog"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("dir"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("div"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("dl"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("fieldset"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("figcaption"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("figure"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("footer"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("form"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("frame"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("frameset"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("head"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("header"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("hgroup"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("main"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("marquee"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("menu"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("nav"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("object"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("ol"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("section"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("select"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("summary"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("table"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("tbody"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("td"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("tfoot"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("th"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("thead"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("tr"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("ul"),
+                        ..
+                    },
+                ) => {
                     if !self.in_scope_named(default_scope, tag.name.clone()) {
                         self.unexpected(&tag);
                     } else {
@@ -142,8 +916,11 @@ where
                     }
                     Done
                 }
-
-                </p> => {
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::EndTag,
+                    name: local_name!("p"),
+                    ..
+                }) => {
                     if !self.in_scope_named(button_scope, local_name!("p")) {
                         self.sink.parse_error(Borrowed("No <p> tag to close"));
                         self.insert_phantom(local_name!("p"));
@@ -151,8 +928,27 @@ where
                     self.close_p_element();
                     Done
                 }
-
-                tag @ </li> </dd> </dt> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("li"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("dd"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("dt"),
+                        ..
+                    },
+                ) => {
                     let in_scope = if tag.name == local_name!("li") {
                         self.in_scope_named(list_item_scope, tag.name.clone())
                     } else {
@@ -166,8 +962,48 @@ where
                     }
                     Done
                 }
-
-                tag @ </h1> </h2> </h3> </h4> </h5> </h6> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("h1"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("h2"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("h3"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("h4"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("h5"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("h6"),
+                        ..
+                    },
+                ) => {
                     if self.in_scope(default_scope, |n| self.elem_in(&n, heading_tag)) {
                         self.generate_implied_end(cursory_implied_end);
                         if !self.current_node_named(tag.name) {
@@ -179,21 +1015,113 @@ where
                     }
                     Done
                 }
-
-                tag @ <a> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("a"),
+                        ..
+                    },
+                ) => {
                     self.handle_misnested_a_tags(&tag);
                     self.reconstruct_formatting();
                     self.create_formatting_element_for(tag);
                     Done
                 }
-
-                tag @ <b> <big> <code> <em> <font> <i> <s> <small> <strike> <strong> <tt> <u> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("b"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("big"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("code"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("em"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("font"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("i"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("s"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("small"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("strike"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("strong"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("tt"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("u"),
+                        ..
+                    },
+                ) => {
                     self.reconstruct_formatting();
                     self.create_formatting_element_for(tag);
                     Done
                 }
-
-                tag @ <nobr> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("nobr"),
+                        ..
+                    },
+                ) => {
                     self.reconstruct_formatting();
                     if self.in_scope_named(default_scope, local_name!("nobr")) {
                         self.sink.parse_error(Borrowed("Nested <>obr>"));
@@ -203,58 +1131,219 @@ where
                     self.create_formatting_element_for(tag);
                     Done
                 }
-
-                tag @ </a> </b> </big> </code> </em> </font> </i> </nobr>
-                  </s> </small> </strike> </strong> </tt> </u> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("a"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("b"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("big"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("code"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("em"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("font"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("i"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("nobr"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("s"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("small"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("strike"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("strong"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("tt"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("u"),
+                        ..
+                    },
+                ) => {
                     self.adoption_agency(tag.name);
                     Done
                 }
-
-                tag @ </br> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::EndTag,
+                        name: local_name!("br"),
+                        ..
+                    },
+                ) => {
                     self.unexpected(&tag);
-                    self.step(InHtml, TagToken(Tag {
-                        kind: StartTag,
-                        attrs: vec!(),
-                        ..tag
-                    }))
+                    self.step(
+                        InHtml,
+                        TagToken(Tag {
+                            kind: StartTag,
+                            attrs: vec![],
+                            ..tag
+                        }),
+                    )
                 }
-
-                tag @ <param> <source> <track> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("param"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("source"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("track"),
+                        ..
+                    },
+                ) => {
                     self.insert_and_pop_element_for(tag);
                     DoneAckSelfClosing
                 }
-
-                tag @ <hr> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("hr"),
+                        ..
+                    },
+                ) => {
                     self.insert_and_pop_element_for(tag);
                     DoneAckSelfClosing
                 }
-
-                tag @ <image> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("image"),
+                        ..
+                    },
+                ) => {
                     self.unexpected(&tag);
-                    self.step(InHtml, TagToken(Tag {
-                        name: local_name!("img"),
-                        ..tag
-                    }))
+                    self.step(
+                        InHtml,
+                        TagToken(Tag {
+                            name: local_name!("img"),
+                            ..tag
+                        }),
+                    )
                 }
-
-                tag @ <textarea> => {
-                    self.parse_raw_data(tag, Rcdata)
-                }
-
-                tag @ <xmp> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("textarea"),
+                        ..
+                    },
+                ) => self.parse_raw_data(tag, Rcdata),
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("xmp"),
+                        ..
+                    },
+                ) => {
                     self.reconstruct_formatting();
                     self.parse_raw_data(tag, Rawtext)
                 }
-
-                tag @ <iframe> => {
-                    self.parse_raw_data(tag, Rawtext)
-                }
-
-                tag @ <noembed> => {
-                    self.parse_raw_data(tag, Rawtext)
-                }
-
-                tag @ <rb> <rtc> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("iframe"),
+                        ..
+                    },
+                ) => self.parse_raw_data(tag, Rawtext),
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("noembed"),
+                        ..
+                    },
+                ) => self.parse_raw_data(tag, Rawtext),
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("rb"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("rtc"),
+                        ..
+                    },
+                ) => {
                     if self.in_scope_named(default_scope, local_name!("ruby")) {
                         self.generate_implied_end(cursory_implied_end);
                     }
@@ -264,27 +1353,51 @@ where
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ <rp> <rt> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("rp"),
+                        ..
+                    },
+                )
+                | crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("rt"),
+                        ..
+                    },
+                ) => {
                     if self.in_scope_named(default_scope, local_name!("ruby")) {
                         self.generate_implied_end_except(local_name!("rtc"));
                     }
-                    if !self.current_node_named(local_name!("rtc")) && !self.current_node_named(local_name!("ruby")) {
+                    if !self.current_node_named(local_name!("rtc"))
+                        && !self.current_node_named(local_name!("ruby"))
+                    {
                         self.unexpected(&tag);
                     }
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ <option> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("option"),
+                        ..
+                    },
+                ) => {
                     if self.current_node_named(local_name!("option")) {
                         self.pop();
                     }
                     self.insert_element_for(tag);
                     Done
                 }
-
-                tag @ <optgroup> => {
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("optgroup"),
+                        ..
+                    },
+                ) => {
                     if self.current_node_named(local_name!("option")) {
                         self.pop();
                     }
@@ -294,12 +1407,18 @@ where
                     self.insert_element_for(tag);
                     Done
                 }
-
-                </optgroup> => {
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::EndTag,
+                    name: local_name!("optgroup"),
+                    ..
+                }) => {
                     if self.open_elems.len() >= 2
                         && self.current_node_named(local_name!("option"))
-                        && self.html_elem_named(&self.open_elems[self.open_elems.len() - 2],
-                            local_name!("optgroup")) {
+                        && self.html_elem_named(
+                            &self.open_elems[self.open_elems.len() - 2],
+                            local_name!("optgroup"),
+                        )
+                    {
                         self.pop();
                     }
                     if self.current_node_named(local_name!("optgroup")) {
@@ -309,8 +1428,11 @@ where
                     }
                     Done
                 }
-
-                </option> => {
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::EndTag,
+                    name: local_name!("option"),
+                    ..
+                }) => {
                     if self.current_node_named(local_name!("option")) {
                         self.pop();
                     } else {
@@ -318,34 +1440,67 @@ where
                     }
                     Done
                 }
-
-                tag @ <math> => self.enter_foreign(tag, ns!(mathml)),
-
-                tag @ <svg> => self.enter_foreign(tag, ns!(svg)),
-
-                tag @ <_> => {
-                    self.reconstruct_formatting();
-                    self.insert_element_for(tag);
-                    Done
-                }
-
-                tag @ </_> => {
-                    self.process_end_tag_in_body(tag);
-                    Done
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("math"),
+                        ..
+                    },
+                ) => self.enter_foreign(tag, ns!(mathml)),
+                crate::tree_builder::types::TagToken(
+                    tag @ html5ever::tokenizer::Tag {
+                        kind: html5ever::tokenizer::StartTag,
+                        name: local_name!("svg"),
+                        ..
+                    },
+                ) => self.enter_foreign(tag, ns!(svg)),
+                last_arm_token => {
+                    let enable_wildcards = match last_arm_token {
+                        _ => true,
+                    };
+                    match (enable_wildcards, last_arm_token) {
+                        (
+                            true,
+                            crate::tree_builder::types::TagToken(
+                                tag @ html5ever::tokenizer::Tag {
+                                    kind: html5ever::tokenizer::StartTag,
+                                    ..
+                                },
+                            ),
+                        ) => {
+                            self.reconstruct_formatting();
+                            self.insert_element_for(tag);
+                            Done
+                        }
+                        (
+                            true,
+                            crate::tree_builder::types::TagToken(
+                                tag @ html5ever::tokenizer::Tag {
+                                    kind: html5ever::tokenizer::EndTag,
+                                    ..
+                                },
+                            ),
+                        ) => {
+                            self.process_end_tag_in_body(tag);
+                            Done
+                        }
+                        (_, _) => panic!("impossible case in InHtml mode"),
+                    }
                 }
-
-                // FIXME: This should be unreachable, but match_token requires a
-                // catch-all case.
-                _ => panic!("impossible case in InHtml mode"),
-            }),
-            //§ parsing-main-afterbody
-            AfterBody => match_token!(token {
+            },
+            AfterBody => match token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
-
-                <html> => self.step(InHtml, token),
-
-                </html> => {
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("html"),
+                    ..
+                }) => self.step(InHtml, token),
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::EndTag,
+                    name: local_name!("html"),
+                    ..
+                }) => {
                     if self.is_fragment() {
                         self.unexpected(&token);
                     } else {
@@ -353,30 +1508,40 @@ where
                     }
                     Done
                 }
-
                 EOFToken => self.stop_parsing(),
-
-                token => {
-                    self.unexpected(&token);
-                    Reprocess(InHtml, token)
+                last_arm_token => {
+                    let enable_wildcards = match last_arm_token {
+                        _ => true,
+                    };
+                    match (enable_wildcards, last_arm_token) {
+                        (_, token) => {
+                            self.unexpected(&token);
+                            Reprocess(InHtml, token)
+                        }
+                    }
                 }
-            }),
-            //§ the-after-after-body-insertion-mode
-            AfterAfterBody => match_token!(token {
+            },
+            AfterAfterBody => match token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
-
-                <html> => self.step(InHtml, token),
-
+                crate::tree_builder::types::TagToken(html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("html"),
+                    ..
+                }) => self.step(InHtml, token),
                 EOFToken => self.stop_parsing(),
-
-                token => self.unexpected(&token),
-            }),
-            //§ parsing-main-incdata
-            RawText => match_token!(token {
+                last_arm_token => {
+                    let enable_wildcards = match last_arm_token {
+                        _ => true,
+                    };
+                    match (enable_wildcards, last_arm_token) {
+                        (_, token) => self.unexpected(&token),
+                    }
+                }
+            },
+            RawText => match token {
                 CommentToken(text) => self.append_comment(text),
                 CharacterTokens(_, text) => self.append_text(text),
-
                 EOFToken => {
                     self.unexpected(&token);
                     if self.current_node_named(local_name!("script")) {
@@ -386,50 +1551,363 @@ where
                     self.pop();
                     Reprocess(self.orig_mode.take().unwrap(), token)
                 }
-
-                tag @ </_> => {
-                    let node = self.pop();
-                    self.mode = self.orig_mode.take().unwrap();
-                    if tag.name == local_name!("script") {
-                        return Script(node);
+                last_arm_token => {
+                    let enable_wildcards = match last_arm_token {
+                        _ => true,
+                    };
+                    match (enable_wildcards, last_arm_token) {
+                        (
+                            true,
+                            crate::tree_builder::types::TagToken(
+                                tag @ html5ever::tokenizer::Tag {
+                                    kind: html5ever::tokenizer::EndTag,
+                                    ..
+                                },
+                            ),
+                        ) => {
+                            let node = self.pop();
+                            self.mode = self.orig_mode.take().unwrap();
+                            if tag.name == local_name!("script") {
+                                return Script(node);
+                            }
+                            Done
+                        }
+                        (_, _) => panic!("impossible case in Text mode"),
                     }
-                    Done
                 }
-
-                // The spec doesn't say what to do here.
-                // Other tokens are impossible?
-                _ => panic!("impossible case in Text mode"),
-            }),
-            //§ END
+            },
         }
     }
-
-    // TODO
     fn step_foreign(&mut self, token: Token) -> ProcessResult<Handle> {
-        match_token!(token {
+        match token {
             NullCharacterToken => {
                 self.unexpected(&token);
                 self.append_text("\u{fffd}".to_tendril())
             }
-
-            CharacterTokens(_, text) => {
-                self.append_text(text)
-            }
-
+            CharacterTokens(_, text) => self.append_text(text),
             CommentToken(text) => self.append_comment(text),
-
-            tag @ <b> <big> <blockquote> <body> <br> <center> <code> <dd> <div> <dl>
-                <dt> <em> <embed> <h1> <h2> <h3> <h4> <h5> <h6> <head> <hr> <i>
-                <img> <li> <listing> <menu> <meta> <nobr> <ol> <p> <pre> <ruby>
-                <s> <small> <span> <strong> <strike> <sub> <sup> <table> <tt>
-                <u> <ul> <var> => self.unexpected_start_tag_in_foreign_content(tag),
-
-            tag @ <font> => {
+            crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("b"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("big"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("blockquote"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("body"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("br"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("center"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("code"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("dd"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("div"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("dl"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("dt"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("em"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("embed"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("h1"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("h2"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("h3"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("h4"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("h5"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("h6"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("head"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("hr"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("i"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("img"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("li"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("listing"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("menu"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("meta"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("nobr"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("ol"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("p"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("pre"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("ruby"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("s"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("small"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("span"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("strong"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("strike"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("sub"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("sup"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("table"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("tt"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("u"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("ul"),
+                    ..
+                },
+            )
+            | crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("var"),
+                    ..
+                },
+            ) => self.unexpected_start_tag_in_foreign_content(tag),
+            crate::tree_builder::types::TagToken(
+                tag @ html5ever::tokenizer::Tag {
+                    kind: html5ever::tokenizer::StartTag,
+                    name: local_name!("font"),
+                    ..
+                },
+            ) => {
                 let unexpected = tag.attrs.iter().any(|attr| {
-                    matches!(attr.name.expanded(),
-                             expanded_name!("", "color") |
-                             expanded_name!("", "face") |
-                             expanded_name!("", "size"))
+                    matches!(
+                        attr.name.expanded(),
+                        expanded_name!("", "color")
+                            | expanded_name!("", "face")
+                            | expanded_name!("", "size")
+                    )
                 });
                 if unexpected {
                     self.unexpected_start_tag_in_foreign_content(tag)
@@ -437,47 +1915,60 @@ where
                     self.foreign_start_tag(tag)
                 }
             }
-
-            tag @ <_> => self.foreign_start_tag(tag),
-
-            // FIXME(#118): </script> in SVG
-
-            tag @ </_> => {
-                let mut first = true;
-                let mut stack_idx = self.open_elems.len() - 1;
-                loop {
-                    if stack_idx == 0 {
-                        return Done;
-                    }
-
-                    let html;
-                    let eq;
-                    {
-                        let node_name = self.sink.elem_name(&self.open_elems[stack_idx]);
-                        html = *node_name.ns == ns!(html);
-                        eq = node_name.local.eq_ignore_ascii_case(&tag.name);
-                    }
-                    if !first && html {
-                        let mode = self.mode;
-                        return self.step(mode, TagToken(tag));
-                    }
-
-                    if eq {
-                        self.open_elems.truncate(stack_idx);
-                        return Done;
-                    }
-
-                    if first {
-                        self.unexpected(&tag);
-                        first = false;
+            last_arm_token => {
+                let enable_wildcards = match last_arm_token {
+                    _ => true,
+                };
+                match (enable_wildcards, last_arm_token) {
+                    (
+                        true,
+                        crate::tree_builder::types::TagToken(
+                            tag @ html5ever::tokenizer::Tag {
+                                kind: html5ever::tokenizer::StartTag,
+                                ..
+                            },
+                        ),
+                    ) => self.foreign_start_tag(tag),
+                    (
+                        true,
+                        crate::tree_builder::types::TagToken(
+                            tag @ html5ever::tokenizer::Tag {
+                                kind: html5ever::tokenizer::EndTag,
+                                ..
+                            },
+                        ),
+                    ) => {
+                        let mut first = true;
+                        let mut stack_idx = self.open_elems.len() - 1;
+                        loop {
+                            if stack_idx == 0 {
+                                return Done;
+                            }
+                            let html;
+                            let eq;
+                            {
+                                let node_name = self.sink.elem_name(&self.open_elems[stack_idx]);
+                                html = *node_name.ns == ns!(html);
+                                eq = node_name.local.eq_ignore_ascii_case(&tag.name);
+                            }
+                            if !first && html {
+                                let mode = self.mode;
+                                return self.step(mode, TagToken(tag));
+                            }
+                            if eq {
+                                self.open_elems.truncate(stack_idx);
+                                return Done;
+                            }
+                            if first {
+                                self.unexpected(&tag);
+                                first = false;
+                            }
+                            stack_idx -= 1;
+                        }
                     }
-                    stack_idx -= 1;
+                    (_, _) => panic!("impossible case in foreign content"),
                 }
             }
-
-            // FIXME: This should be unreachable, but match_token requires a
-            // catch-all case.
-            _ => panic!("impossible case in foreign content"),
-        })
+        }
     }
 }
@@ -0,0 +1,75 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+
+use crate::helpers::{escape, Render};
+
+/// First strong isolate, the direction of the isolated text is its first strong char
+const FSI: char = '\u{2068}';
+/// Pop directional isolate
+const PDI: char = '\u{2069}';
+
+/// Text isolated from the surrounding direction `{{ @bidi x }}`
+///
+/// Right-to-left user content can't reorder the text around it. Only the value is escaped,
+/// so nested helpers as `{{ @bidi (@num x) }}` are escaped once.
+pub struct Bidi<'a>(pub &'a dyn Display);
+
+impl<'a> Display for Bidi<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", FSI, self.0, PDI)
+    }
+}
+
+impl<'a> Render for Bidi<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", FSI, escape(&self.0.to_string()), PDI)
+    }
+}
+
+/// Value of the `dir` attribute for a text `<p dir="{{ @dir x }}">`
+///
+/// `rtl` or `ltr` by the first strong char, `auto` without strong chars
+pub struct Dir<'a>(pub &'a dyn Display);
+
+impl<'a> Display for Dir<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dir = self
+            .0
+            .to_string()
+            .chars()
+            .find_map(|c| {
+                if is_rtl(c) {
+                    Some("rtl")
+                } else if c.is_alphabetic() {
+                    Some("ltr")
+                } else {
+                    None
+                }
+            })
+            .unwrap_or("auto");
+
+        f.write_str(dir)
+    }
+}
+
+impl<'a> Render for Dir<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
+
+/// Strong right-to-left char, of the Hebrew, Arabic, Syriac, Thaana or N'Ko blocks
+/// and their presentation forms
+fn is_rtl(c: char) -> bool {
+    match c as u32 {
+        0x0590..=0x08FF
+        | 0xFB1D..=0xFDFF
+        | 0xFE70..=0xFEFF
+        | 0x10800..=0x10FFF
+        | 0x1E800..=0x1EFFF => c.is_alphabetic(),
+        _ => false,
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+
+use crate::helpers::{escape, Render};
+
+/// Content Security Policy of a request, implemented by the templates with `csp = true`
+///
+/// The nonce is added to the inline `<script>` and `<style>` elements of the template,
+/// it must be a new random value for each response.
+pub trait SecurityContext {
+    fn nonce(&self) -> &str;
+
+    /// Url of the violation reports of `{{ @csp }}`
+    fn report_uri(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Strict policy of the nonce `{{ @csp }}`, for the header or a `<meta>` element
+pub struct Csp<'a> {
+    pub nonce: &'a str,
+    pub report_uri: Option<&'a str>,
+}
+
+impl<'a> Csp<'a> {
+    pub fn new<T: SecurityContext + ?Sized>(context: &'a T) -> Self {
+        Csp {
+            nonce: context.nonce(),
+            report_uri: context.report_uri(),
+        }
+    }
+}
+
+impl<'a> Display for Csp<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "script-src 'nonce-{0}' 'strict-dynamic'; style-src 'nonce-{0}'; \
+             object-src 'none'; base-uri 'none'",
+            self.nonce
+        )?;
+        if let Some(uri) = self.report_uri {
+            write!(f, "; report-uri {}", uri)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Render for Csp<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        escape(&self.to_string()).fmt(f)
+    }
+}
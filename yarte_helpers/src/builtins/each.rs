@@ -0,0 +1,279 @@
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::{cell::Cell, cmp::Ordering, iter::Peekable};
+#[cfg(feature = "std")]
+use std::vec;
+
+/// Items in chunks of `size`, `{{#each items chunks=3 }}`
+///
+/// The last chunk has the remaining items. The size is an integer literal greater than zero,
+/// checked when the template is compiled
+pub fn chunks<I: IntoIterator>(iter: I, size: usize) -> vec::IntoIter<Vec<I::Item>> {
+    let mut chunks = vec![];
+    let mut chunk = Vec::with_capacity(size);
+    for item in iter {
+        chunk.push(item);
+        if chunk.len() == size {
+            chunks.push(chunk);
+            chunk = Vec::with_capacity(size);
+        }
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    chunks.into_iter()
+}
+
+/// Items with the same key, `{{#each items group_by=key }}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group<K, T> {
+    pub key: K,
+    pub items: Vec<T>,
+}
+
+/// Group items by key in order of first appearance
+pub fn group_by<I, K, F>(iter: I, key: F) -> vec::IntoIter<Group<K, I::Item>>
+where
+    I: IntoIterator,
+    I::Item: Copy,
+    K: PartialEq,
+    F: Fn(I::Item) -> K,
+{
+    let mut groups: Vec<Group<K, I::Item>> = vec![];
+    for item in iter {
+        let k = key(item);
+        match groups.iter_mut().find(|g| g.key == k) {
+            Some(group) => group.items.push(item),
+            None => groups.push(Group {
+                key: k,
+                items: vec![item],
+            }),
+        }
+    }
+
+    groups.into_iter()
+}
+
+/// Items sorted by key, `{{#each items sort_by=key }}`
+///
+/// The sort is stable, keys without order between them keep their position
+pub fn sort_by<I, K, F>(iter: I, key: F) -> vec::IntoIter<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Copy,
+    K: PartialOrd,
+    F: Fn(I::Item) -> K,
+{
+    let mut items: Vec<I::Item> = iter.into_iter().collect();
+    items.sort_by(|a, b| key(*a).partial_cmp(&key(*b)).unwrap_or(Ordering::Equal));
+
+    items.into_iter()
+}
+
+/// Items paired with whether they are the last one, for `{{ @last }}`
+pub fn with_last<I: IntoIterator>(iter: I) -> WithLast<I::IntoIter> {
+    WithLast(iter.into_iter().peekable())
+}
+
+/// Iterator of `with_last`
+pub struct WithLast<I: Iterator>(Peekable<I>);
+
+impl<I: Iterator> Iterator for WithLast<I> {
+    type Item = (bool, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.0.next()?;
+        Some((self.0.peek().is_none(), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Items of an each with error arm, `{{#each items }}..{{else err }}..{{/each }}`
+///
+/// Iterates the items until the first error, which is kept for the arm
+pub struct TryEach<E>(Cell<Option<E>>);
+
+impl<E> TryEach<E> {
+    pub fn new() -> Self {
+        TryEach(Cell::new(None))
+    }
+
+    /// Values of the items until the first error
+    pub fn iter<I>(&self, iter: I) -> UntilErr<'_, I::IntoIter, E>
+    where
+        I: IntoIterator,
+        I::Item: Fallible<Err = E>,
+    {
+        UntilErr {
+            iter: iter.into_iter(),
+            err: &self.0,
+        }
+    }
+
+    /// Error which stopped the iteration
+    pub fn take(&self) -> Option<E> {
+        self.0.take()
+    }
+}
+
+impl<E> Default for TryEach<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator of `TryEach`
+pub struct UntilErr<'a, I, E> {
+    iter: I,
+    err: &'a Cell<Option<E>>,
+}
+
+impl<'a, I, E> Iterator for UntilErr<'a, I, E>
+where
+    I: Iterator,
+    I::Item: Fallible<Err = E>,
+{
+    type Item = <I::Item as Fallible>::Ok;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.iter.next()?.into_result() {
+            Ok(item) => Some(item),
+            Err(err) => {
+                self.err.set(Some(err));
+                None
+            }
+        }
+    }
+}
+
+/// Items of an each with empty arm, `{{#each items }}..{{else }}..{{/each }}`
+pub struct EmptyEach(Cell<bool>);
+
+impl EmptyEach {
+    pub fn new() -> Self {
+        EmptyEach(Cell::new(true))
+    }
+
+    /// Items, the first one marks it as not empty
+    pub fn iter<I: IntoIterator>(&self, iter: I) -> NonEmpty<'_, I::IntoIter> {
+        NonEmpty {
+            iter: iter.into_iter(),
+            empty: &self.0,
+        }
+    }
+
+    /// No item was iterated
+    pub fn is_empty(&self) -> bool {
+        self.0.get()
+    }
+}
+
+impl Default for EmptyEach {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator of `EmptyEach`
+pub struct NonEmpty<'a, I> {
+    iter: I,
+    empty: &'a Cell<bool>,
+}
+
+impl<'a, I: Iterator> Iterator for NonEmpty<'a, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next();
+        if item.is_some() {
+            self.empty.set(false);
+        }
+        item
+    }
+}
+
+/// Item of an each with error arm, `Result` or `Option`, `None` is the error `()`
+pub trait Fallible {
+    type Ok;
+    type Err;
+
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+}
+
+impl<T, E> Fallible for Result<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+impl<'a, T, E> Fallible for &'a Result<T, E> {
+    type Ok = &'a T;
+    type Err = &'a E;
+
+    fn into_result(self) -> Result<&'a T, &'a E> {
+        self.as_ref()
+    }
+}
+
+impl<T> Fallible for Option<T> {
+    type Ok = T;
+    type Err = ();
+
+    fn into_result(self) -> Result<T, ()> {
+        self.ok_or(())
+    }
+}
+
+impl<'a, T> Fallible for &'a Option<T> {
+    type Ok = &'a T;
+    type Err = ();
+
+    fn into_result(self) -> Result<&'a T, ()> {
+        self.as_ref().ok_or(())
+    }
+}
+
+/// Collections iterated in lockstep, `{{#each (zip names ages) as |name, age| }}`
+///
+/// Items are tuples, the iteration stops at the end of the shortest collection
+pub struct Zip<T>(pub T);
+
+/// Iterator of `Zip`
+pub struct ZipIter<T>(T);
+
+macro_rules! zip {
+    ($($T:ident $i:tt),+) => {
+        impl<'a, 'b, $($T: ?Sized),+> IntoIterator for &'b Zip<($(&'a $T,)+)>
+        where
+            $(&'a $T: IntoIterator,)+
+        {
+            type Item = ($(<&'a $T as IntoIterator>::Item,)+);
+            type IntoIter = ZipIter<($(<&'a $T as IntoIterator>::IntoIter,)+)>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                ZipIter(($((self.0).$i.into_iter(),)+))
+            }
+        }
+
+        impl<$($T: Iterator),+> Iterator for ZipIter<($($T,)+)> {
+            type Item = ($($T::Item,)+);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                Some(($((self.0).$i.next()?,)+))
+            }
+        }
+    };
+}
+
+zip!(A 0, B 1);
+zip!(A 0, B 1, C 2);
+zip!(A 0, B 1, C 2, D 3);
+zip!(A 0, B 1, C 2, D 3, E 4);
+zip!(A 0, B 1, C 2, D 3, E 4, F 5);
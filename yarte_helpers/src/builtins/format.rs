@@ -0,0 +1,75 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+
+use crate::helpers::{escape, Render};
+
+/// Presentation policy of a template, set with the attribute `formatter = "Type"`
+///
+/// Hooks of the built-in helpers `{{ @money x }}` and `{{ @date x }}`, the defaults
+/// are overridden for locale or currency.
+pub trait Formatter {
+    /// Format `{{ @money x }}`, by default numbers with two decimals as `Money`
+    fn fmt_money(value: &dyn Display, f: &mut fmt::Formatter) -> fmt::Result {
+        Money(value).fmt(f)
+    }
+
+    /// Format `{{ @date x }}`, by default as displayed
+    fn fmt_date(value: &dyn Display, f: &mut fmt::Formatter) -> fmt::Result {
+        value.fmt(f)
+    }
+}
+
+/// Formatter of the templates without `formatter` attribute
+pub struct DefaultFormatter;
+
+impl Formatter for DefaultFormatter {}
+
+/// Number with two decimals, other values are written unchanged
+///
+/// The value is a number when it's displayed as one, so integers are written with `.00`
+/// and floats are rounded as `{:.2}`, but a formatted string isn't truncated.
+pub struct Money<'a>(pub &'a dyn Display);
+
+impl<'a> Display for Money<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.0.to_string();
+        let digits = if s.starts_with('-') { &s[1..] } else { &s[..] };
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            write!(f, "{}.00", s)
+        } else {
+            match s.parse::<f64>() {
+                Ok(x) if is_decimal(digits) => write!(f, "{:.2}", x),
+                _ => f.write_str(&s),
+            }
+        }
+    }
+}
+
+/// Digits with a point or an exponent, not `inf` or `NaN`
+fn is_decimal(s: &str) -> bool {
+    s.bytes()
+        .next()
+        .map_or(false, |b| b.is_ascii_digit() || b == b'.')
+        && s.bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+}
+
+/// Value formatted by a hook of a `Formatter`
+pub struct Formatted<'a>(
+    pub &'a dyn Display,
+    pub fn(&dyn Display, &mut fmt::Formatter) -> fmt::Result,
+);
+
+impl<'a> Display for Formatted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
+
+impl<'a> Render for Formatted<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        escape(&self.to_string()).fmt(f)
+    }
+}
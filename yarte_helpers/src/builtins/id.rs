@@ -0,0 +1,35 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+
+use crate::helpers::{escape, Render};
+
+/// Element id deterministic by the template position `{{ @id "prefix" }}`
+///
+/// The prefix, the key of the template file and the include sites of its partials,
+/// and the index of each enclosing loop, so the server render and the client render
+/// have the same ids. The same prefix in the same template and iteration is the same id,
+/// as `<label for>` and its `<input id>`.
+pub struct Id<'a>(pub &'a dyn Display, pub &'static str, pub &'a [usize]);
+
+impl<'a> Display for Id<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.0, self.1)?;
+        for i in self.2 {
+            write!(f, "-{}", i)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Render for Id<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        escape(&self.0.to_string()).fmt(f)?;
+        write!(f, "-{}", self.1)?;
+        for i in self.2 {
+            write!(f, "-{}", i)?;
+        }
+        Ok(())
+    }
+}
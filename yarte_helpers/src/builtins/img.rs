@@ -0,0 +1,38 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+
+use crate::helpers::{escape, Render};
+
+/// Responsive image `{{ @img "hero.jpg" widths=[480, 960] sizes="50vw" alt=title }}`
+///
+/// The urls of `srcset` are the hashed urls of the asset manifest, checked at compile time.
+/// `src` is the url of the largest width, `sizes` is `100vw` and `alt` empty by default.
+/// `src` and `srcset` are escaped at compile time.
+pub struct Img<'a> {
+    pub src: &'static str,
+    pub srcset: &'static str,
+    pub sizes: &'a dyn Display,
+    pub alt: &'a dyn Display,
+}
+
+impl<'a> Display for Img<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("<img src=\"")?;
+        f.write_str(self.src)?;
+        f.write_str("\" srcset=\"")?;
+        f.write_str(self.srcset)?;
+        f.write_str("\" sizes=\"")?;
+        escape(&self.sizes.to_string()).fmt(f)?;
+        f.write_str("\" alt=\"")?;
+        escape(&self.alt.to_string()).fmt(f)?;
+        f.write_str("\">")
+    }
+}
+
+impl<'a> Render for Img<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
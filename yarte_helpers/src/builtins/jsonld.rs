@@ -0,0 +1,92 @@
+use std::fmt::{self, Display};
+
+use serde::Serialize;
+
+use crate::helpers::Render;
+
+/// Structured data block `{{ @jsonld value }}`
+///
+/// Renders `value` serialized as JSON in a `<script type="application/ld+json">`,
+/// with `<`, `>` and `&` escaped so it can't close the script element.
+pub struct JsonLd<'a, T>(pub &'a T);
+
+impl<'a, T: Serialize> Display for JsonLd<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json = serde_json::to_string(self.0).map_err(|_| fmt::Error)?;
+
+        f.write_str("<script type=\"application/ld+json\">")?;
+        let mut last = 0;
+        for (i, b) in json.bytes().enumerate() {
+            let escaped = match b {
+                b'<' => "\\u003c",
+                b'>' => "\\u003e",
+                b'&' => "\\u0026",
+                _ => continue,
+            };
+            f.write_str(&json[last..i])?;
+            f.write_str(escaped)?;
+            last = i + 1;
+        }
+        f.write_str(&json[last..])?;
+        f.write_str("</script>")
+    }
+}
+
+impl<'a, T: Serialize> Render for JsonLd<'a, T> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
+
+/// Typed schema.org items, `{{ @jsonld breadcrumb_list(items) }}`
+///
+/// The required properties of each type are arguments of its constructor
+pub mod schema {
+    use std::fmt::Display;
+
+    use serde_json::{json, Value};
+
+    /// `BreadcrumbList` of `(name, url)` items, in order
+    pub fn breadcrumb_list<N: Display, U: Display>(items: &[(N, U)]) -> Value {
+        let items: Vec<Value> = items
+            .iter()
+            .enumerate()
+            .map(|(i, (name, url))| {
+                json!({
+                    "@type": "ListItem",
+                    "position": i + 1,
+                    "name": name.to_string(),
+                    "item": url.to_string(),
+                })
+            })
+            .collect();
+
+        json!({
+            "@context": "https://schema.org",
+            "@type": "BreadcrumbList",
+            "itemListElement": items,
+        })
+    }
+
+    /// `Organization` with its name and url
+    pub fn organization(name: impl Display, url: impl Display) -> Value {
+        json!({
+            "@context": "https://schema.org",
+            "@type": "Organization",
+            "name": name.to_string(),
+            "url": url.to_string(),
+        })
+    }
+
+    /// `Article` with its headline, author name and publication date in ISO 8601
+    pub fn article(headline: impl Display, author: impl Display, published: impl Display) -> Value {
+        json!({
+            "@context": "https://schema.org",
+            "@type": "Article",
+            "headline": headline.to_string(),
+            "author": { "@type": "Person", "name": author.to_string() },
+            "datePublished": published.to_string(),
+        })
+    }
+}
@@ -0,0 +1,324 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Write};
+
+use crate::helpers::{escape, Render};
+
+/// Locale of a template, read by the built-in helpers `{{ @num x }}` and `{{ @plural n .. }}`
+///
+/// Implemented by the template struct, the locale is a BCP 47 language tag as `en`, `pt-BR`,
+/// `de_CH` or `zh-Hant-TW`. Malformed tags and languages without data fail the render with
+/// `fmt::Error`, see `LanguageTag::is_supported`.
+pub trait Localized {
+    fn locale(&self) -> &str;
+}
+
+/// Language, script and region subtags of a BCP 47 language tag, separated by `-` or `_`
+#[derive(Debug, PartialEq)]
+pub struct LanguageTag<'a> {
+    /// Lowercase language subtag, as `en` or `fil`
+    pub language: String,
+    /// Script subtag, as `Hant`
+    pub script: Option<&'a str>,
+    /// Region subtag, as `BR` or `419`
+    pub region: Option<&'a str>,
+}
+
+impl<'a> LanguageTag<'a> {
+    /// Parse the tag, `None` when it's malformed. Extended language, variant, extension and
+    /// private use subtags are checked but not kept
+    pub fn parse(tag: &'a str) -> Option<LanguageTag<'a>> {
+        let alpha = |s: &str, n: &[usize]| {
+            n.contains(&s.len()) && s.bytes().all(|x| x.is_ascii_alphabetic())
+        };
+        let mut subtags = tag.split(&['-', '_'][..]).peekable();
+        let language = subtags.next()?;
+        if !alpha(language, &[2, 3, 5, 6, 7, 8]) {
+            return None;
+        }
+        // Extended language subtags, as `zh-yue`
+        let mut extlang = 0;
+        while language.len() <= 3 && extlang < 3 {
+            match subtags.peek() {
+                Some(s) if alpha(s, &[3]) => {
+                    subtags.next();
+                    extlang += 1;
+                }
+                _ => break,
+            }
+        }
+        let script = match subtags.peek() {
+            Some(s) if alpha(s, &[4]) => subtags.next(),
+            _ => None,
+        };
+        let region = match subtags.peek() {
+            Some(s)
+                if alpha(s, &[2]) || (s.len() == 3 && s.bytes().all(|x| x.is_ascii_digit())) =>
+            {
+                subtags.next()
+            }
+            _ => None,
+        };
+        // Variants, extensions and private use
+        if !subtags
+            .all(|s| (1..=8).contains(&s.len()) && s.bytes().all(|x| x.is_ascii_alphanumeric()))
+        {
+            return None;
+        }
+
+        Some(LanguageTag {
+            language: language.to_ascii_lowercase(),
+            script,
+            region,
+        })
+    }
+
+    /// Language with separators and plural rules in the tables of `@num` and `@plural`
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED.contains(&self.language.as_str())
+    }
+
+    fn region_is(&self, region: &str) -> bool {
+        self.region
+            .map_or(false, |x| x.eq_ignore_ascii_case(region))
+    }
+}
+
+/// Languages of the built-in tables
+///
+/// It's a stopgap until the data of ICU is used, with the separators of the CLDR and the
+/// cardinal plural rules of integers for the common languages. The tables don't have other
+/// numbering systems than the latin digits, nor the `many` of millions in `es`, `fr`, `it`
+/// and `pt`, and the fractions of `hr` and `da` are `other`.
+const SUPPORTED: &[&str] = &[
+    "ar", "bg", "cs", "da", "de", "el", "en", "es", "fi", "fr", "hr", "hu", "id", "it", "ja", "ko",
+    "ms", "nb", "nl", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sv", "th", "tr", "uk", "vi", "zh",
+];
+
+/// Supported language tag of the locale
+fn tag(locale: &str) -> Result<LanguageTag<'_>, fmt::Error> {
+    LanguageTag::parse(locale)
+        .filter(LanguageTag::is_supported)
+        .ok_or(fmt::Error)
+}
+
+/// Write the `Display` in a string, keeping its error
+fn format(x: &dyn Display) -> Result<String, fmt::Error> {
+    let mut s = String::new();
+    write!(s, "{}", x).map(|_| s)
+}
+
+/// Number formatted with the separators of a locale
+pub struct Num<'a>(pub &'a dyn Display, pub &'a str);
+
+impl<'a> Display for Num<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tag = tag(self.1)?;
+        let value = self.0.to_string();
+        let (sign, int, frac) = match split(&value) {
+            Some(x) => x,
+            // Not a plain decimal number, as `NaN` or `1e-7`
+            None => return f.write_str(&value),
+        };
+        let Symbols {
+            decimal,
+            group,
+            min_grouping,
+        } = symbols(&tag);
+
+        f.write_str(sign)?;
+        if int.len() < 4 + min_grouping - 1 {
+            f.write_str(int)?;
+        } else {
+            let first = match int.len() % 3 {
+                0 => 3,
+                x => x,
+            };
+            f.write_str(&int[..first])?;
+            for i in (first..int.len()).step_by(3) {
+                f.write_str(group)?;
+                f.write_str(&int[i..i + 3])?;
+            }
+        }
+        if !frac.is_empty() {
+            f.write_str(decimal)?;
+            f.write_str(frac)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Render for Num<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        escape(&format(self)?).fmt(f)
+    }
+}
+
+/// Plural category of the CLDR rules
+#[derive(Clone, Copy)]
+enum Category {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Form of a plural category, by the number of `{{ @plural n one = "item" other = "items" }}`
+///
+/// Categories without form are written with the `other` form
+pub struct Plural<'a> {
+    n: &'a dyn Display,
+    locale: &'a str,
+    forms: [Option<&'a dyn Display>; 6],
+}
+
+impl<'a> Plural<'a> {
+    pub fn new(n: &'a dyn Display, locale: &'a str) -> Plural<'a> {
+        Plural {
+            n,
+            locale,
+            forms: [None; 6],
+        }
+    }
+
+    pub fn zero(self, form: &'a dyn Display) -> Self {
+        self.form(Category::Zero, form)
+    }
+
+    pub fn one(self, form: &'a dyn Display) -> Self {
+        self.form(Category::One, form)
+    }
+
+    pub fn two(self, form: &'a dyn Display) -> Self {
+        self.form(Category::Two, form)
+    }
+
+    pub fn few(self, form: &'a dyn Display) -> Self {
+        self.form(Category::Few, form)
+    }
+
+    pub fn many(self, form: &'a dyn Display) -> Self {
+        self.form(Category::Many, form)
+    }
+
+    pub fn other(self, form: &'a dyn Display) -> Self {
+        self.form(Category::Other, form)
+    }
+
+    fn form(mut self, category: Category, form: &'a dyn Display) -> Self {
+        self.forms[category as usize] = Some(form);
+        self
+    }
+}
+
+impl<'a> Display for Plural<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let category = category(&tag(self.locale)?, &self.n.to_string());
+        match self.forms[category as usize].or(self.forms[Category::Other as usize]) {
+            Some(form) => form.fmt(f),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Render for Plural<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        escape(&format(self)?).fmt(f)
+    }
+}
+
+/// Sign, integer and fraction digits of a plain decimal number
+fn split(value: &str) -> Option<(&str, &str, &str)> {
+    let (sign, digits) = if value.starts_with('-') {
+        value.split_at(1)
+    } else {
+        ("", value)
+    };
+    let (int, frac) = match digits.find('.') {
+        Some(i) => (&digits[..i], &digits[i + 1..]),
+        None => (digits, ""),
+    };
+    if int.is_empty() || !(int.bytes().chain(frac.bytes())).all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((sign, int, frac))
+}
+
+struct Symbols {
+    decimal: &'static str,
+    group: &'static str,
+    /// Digits of the first group for grouping, 2 writes `1234` and `12.345`
+    min_grouping: usize,
+}
+
+fn symbols(tag: &LanguageTag) -> Symbols {
+    let symbols = |decimal, group, min_grouping| Symbols {
+        decimal,
+        group,
+        min_grouping,
+    };
+    match tag.language.as_str() {
+        "de" | "it" if tag.region_is("ch") => symbols(".", "\u{2019}", 1),
+        "es" => symbols(",", ".", 2),
+        "pl" => symbols(",", "\u{a0}", 2),
+        "de" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl" | "vi" => {
+            symbols(",", ".", 1)
+        }
+        "fr" => symbols(",", "\u{202f}", 1),
+        "ru" | "uk" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "hu" | "bg" => {
+            symbols(",", "\u{a0}", 1)
+        }
+        _ => symbols(".", ",", 1),
+    }
+}
+
+/// Plural category of the number `n` with the cardinal rules of the language of the tag
+fn category(tag: &LanguageTag, n: &str) -> Category {
+    use Category::*;
+
+    let (int, v) = match split(n.trim_start_matches('-')) {
+        Some((_, int, frac)) => (int, frac.len()),
+        None => return Other,
+    };
+    // The integers out of `u64` are only compared by their last digits
+    let i = int.parse::<u64>().unwrap_or(u64::max_value());
+    let i100 = int[int.len().saturating_sub(2)..]
+        .parse::<u64>()
+        .unwrap_or(0);
+    let i10 = i100 % 10;
+    let few = v == 0 && (2..=4).contains(&i10) && !(12..=14).contains(&i100);
+
+    match tag.language.as_str() {
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" => Other,
+        "fr" | "pt" if i <= 1 => One,
+        "fr" | "pt" => Other,
+        "ru" | "uk" | "hr" if v == 0 && i10 == 1 && i100 != 11 => One,
+        "ru" | "uk" | "hr" if few => Few,
+        "ru" | "uk" if v == 0 => Many,
+        "pl" if v == 0 && i == 1 => One,
+        "pl" if few => Few,
+        "pl" if v == 0 => Many,
+        "cs" | "sk" if v == 0 && i == 1 => One,
+        "cs" | "sk" if v == 0 && (2..=4).contains(&i) => Few,
+        "cs" | "sk" if v != 0 => Many,
+        "ar" if v == 0 && i == 0 => Zero,
+        "ar" if v == 0 && i == 1 => One,
+        "ar" if v == 0 && i == 2 => Two,
+        "ar" if v == 0 && (3..=10).contains(&i100) => Few,
+        "ar" if v == 0 && (11..=99).contains(&i100) => Many,
+        "ro" if v == 0 && i == 1 => One,
+        "ro" if v != 0 || i == 0 || (2..=19).contains(&i100) => Few,
+        "sl" if v == 0 && i100 == 1 => One,
+        "sl" if v == 0 && i100 == 2 => Two,
+        "sl" if v != 0 || (3..=4).contains(&i100) => Few,
+        "ru" | "uk" | "hr" | "pl" | "cs" | "sk" | "ar" | "ro" | "sl" => Other,
+        _ if v == 0 && i == 1 => One,
+        _ => Other,
+    }
+}
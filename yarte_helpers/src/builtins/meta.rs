@@ -0,0 +1,117 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+
+use crate::helpers::{escape, Render};
+
+/// Head meta tags `{{ @meta title=.. description=.. image=.. }}`
+///
+/// Renders the title, the description and their Open Graph and Twitter card tags,
+/// only the tags of the given arguments are rendered.
+#[derive(Default)]
+pub struct Meta<'a> {
+    title: Option<&'a dyn Display>,
+    description: Option<&'a dyn Display>,
+    image: Option<&'a dyn Display>,
+    url: Option<&'a dyn Display>,
+    site_name: Option<&'a dyn Display>,
+    kind: Option<&'a dyn Display>,
+}
+
+impl<'a> Meta<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: &'a dyn Display) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    pub fn description(mut self, description: &'a dyn Display) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Absolute url of the preview image
+    pub fn image(mut self, image: &'a dyn Display) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Canonical url of the page
+    pub fn url(mut self, url: &'a dyn Display) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    pub fn site_name(mut self, site_name: &'a dyn Display) -> Self {
+        self.site_name = Some(site_name);
+        self
+    }
+
+    /// Open Graph type, by default `website`
+    pub fn kind(mut self, kind: &'a dyn Display) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+}
+
+fn tag(f: &mut fmt::Formatter, attr: &str, name: &str, value: Option<&dyn Display>) -> fmt::Result {
+    if let Some(value) = value {
+        f.write_str("<meta ")?;
+        f.write_str(attr)?;
+        f.write_str("=\"")?;
+        f.write_str(name)?;
+        f.write_str("\" content=\"")?;
+        escape(&value.to_string()).fmt(f)?;
+        f.write_str("\">")?;
+    }
+
+    Ok(())
+}
+
+impl<'a> Display for Meta<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(title) = self.title {
+            f.write_str("<title>")?;
+            escape(&title.to_string()).fmt(f)?;
+            f.write_str("</title>")?;
+        }
+        tag(f, "name", "description", self.description)?;
+        if let Some(url) = self.url {
+            f.write_str("<link rel=\"canonical\" href=\"")?;
+            escape(&url.to_string()).fmt(f)?;
+            f.write_str("\">")?;
+        }
+
+        tag(
+            f,
+            "property",
+            "og:type",
+            self.kind.or(Some(&"website" as &dyn Display)),
+        )?;
+        tag(f, "property", "og:title", self.title)?;
+        tag(f, "property", "og:description", self.description)?;
+        tag(f, "property", "og:image", self.image)?;
+        tag(f, "property", "og:url", self.url)?;
+        tag(f, "property", "og:site_name", self.site_name)?;
+
+        let card = if self.image.is_some() {
+            "summary_large_image"
+        } else {
+            "summary"
+        };
+        tag(f, "name", "twitter:card", Some(&card as &dyn Display))?;
+        tag(f, "name", "twitter:title", self.title)?;
+        tag(f, "name", "twitter:description", self.description)?;
+        tag(f, "name", "twitter:image", self.image)
+    }
+}
+
+impl<'a> Render for Meta<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
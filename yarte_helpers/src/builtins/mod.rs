@@ -0,0 +1,35 @@
+//! Runtime side of the built-in helpers `{{ @name .. }}` and each helper modifiers
+mod bidi;
+mod csp;
+mod each;
+mod format;
+mod id;
+mod img;
+#[cfg(feature = "json")]
+mod jsonld;
+#[cfg(feature = "locale")]
+mod locale;
+mod meta;
+mod once;
+mod paginate;
+mod url;
+
+#[cfg(feature = "json")]
+pub use self::jsonld::{schema, JsonLd};
+#[cfg(feature = "locale")]
+pub use self::locale::{LanguageTag, Localized, Num, Plural};
+pub use self::{
+    bidi::{Bidi, Dir},
+    csp::{Csp, SecurityContext},
+    each::{
+        chunks, group_by, sort_by, with_last, EmptyEach, Fallible, Group, NonEmpty, TryEach,
+        UntilErr, WithLast, Zip, ZipIter,
+    },
+    format::{DefaultFormatter, Formatted, Formatter, Money},
+    id::Id,
+    img::Img,
+    meta::Meta,
+    once::Once,
+    paginate::{pages, Link, Page, Paginate},
+    url::{PathSeg, Query, QueryArg, UrlEncode},
+};
@@ -0,0 +1,26 @@
+use core::cell::{Ref, RefCell};
+
+/// Value of `{{ #once expr }}`, computed at its first use and reused after
+///
+/// Declared once per render, or per iteration of the innermost loop
+pub struct Once<T>(RefCell<Option<T>>);
+
+impl<T> Once<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Once(RefCell::new(None))
+    }
+
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> Ref<T> {
+        // The value is set only while empty, so it isn't borrowed yet
+        if self.0.borrow().is_none() {
+            let value = f();
+            let mut cell = self.0.borrow_mut();
+            if cell.is_none() {
+                *cell = Some(value);
+            }
+        }
+
+        Ref::map(self.0.borrow(), |value| value.as_ref().unwrap())
+    }
+}
@@ -0,0 +1,194 @@
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cmp::{max, min},
+    fmt::{self, Display},
+};
+
+use crate::helpers::{escape, Render};
+
+/// Pagination control `{{ @paginate current total url_fn window = 2 }}`
+///
+/// Pages are one-based. Renders first, previous, the window of pages around the current one,
+/// next and last links as a list, the url of each page is built with `url_fn`.
+/// With `partial = "name"` each item of the list is rendered by the inline partial `name`
+/// with its `Link` as scope.
+pub struct Paginate<'a, F> {
+    current: usize,
+    total: usize,
+    window: usize,
+    url: &'a F,
+}
+
+impl<'a, F, U> Paginate<'a, F>
+where
+    F: Fn(usize) -> U,
+    U: Display,
+{
+    pub fn new(current: usize, total: usize, url: &'a F) -> Self {
+        Paginate {
+            current,
+            total,
+            window: 2,
+            url,
+        }
+    }
+
+    /// Number of pages at each side of the current page
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn pages(&self) -> Vec<Page> {
+        pages(self.current, self.total, self.window)
+    }
+
+    /// Without pages the control isn't rendered
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Items of the control with their url and text, the scopes of the partial of the links
+    pub fn links(&self) -> Vec<Link<U>> {
+        self.pages()
+            .into_iter()
+            .map(|page| {
+                let (url, text) = match page {
+                    Page::First(url) => (url, "\u{ab}".into()),
+                    Page::Prev(url) => (url, "\u{2039}".into()),
+                    Page::Number(page) => (Some(page), page.to_string()),
+                    Page::Current(page) => (None, page.to_string()),
+                    Page::Next(url) => (url, "\u{203a}".into()),
+                    Page::Last(url) => (url, "\u{bb}".into()),
+                };
+                Link {
+                    page,
+                    url: url.map(self.url),
+                    text,
+                }
+            })
+            .collect()
+    }
+
+    fn item(&self, f: &mut fmt::Formatter, page: Option<usize>, text: &str) -> fmt::Result {
+        if let Some(page) = page {
+            f.write_str("<li><a href=\"")?;
+            escape(&(self.url)(page).to_string()).fmt(f)?;
+            f.write_str("\">")?;
+            f.write_str(text)?;
+            f.write_str("</a></li>")
+        } else {
+            f.write_str("<li class=\"disabled\"><span>")?;
+            f.write_str(text)?;
+            f.write_str("</span></li>")
+        }
+    }
+}
+
+impl<'a, F, U> Display for Paginate<'a, F>
+where
+    F: Fn(usize) -> U,
+    U: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.total == 0 {
+            return Ok(());
+        }
+
+        f.write_str("<nav><ul class=\"pagination\">")?;
+        for page in self.pages() {
+            match page {
+                Page::First(page) => self.item(f, page, "&laquo;")?,
+                Page::Prev(page) => self.item(f, page, "&lsaquo;")?,
+                Page::Number(page) => self.item(f, Some(page), &page.to_string())?,
+                Page::Current(page) => {
+                    f.write_str("<li class=\"active\"><span>")?;
+                    page.fmt(f)?;
+                    f.write_str("</span></li>")?
+                }
+                Page::Next(page) => self.item(f, page, "&rsaquo;")?,
+                Page::Last(page) => self.item(f, page, "&raquo;")?,
+            }
+        }
+        f.write_str("</ul></nav>")
+    }
+}
+
+impl<'a, F, U> Render for Paginate<'a, F>
+where
+    F: Fn(usize) -> U,
+    U: Display,
+{
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
+
+/// Item of a pagination control
+///
+/// Links to the first, previous, next and last pages are `None` when they point to the current page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+    First(Option<usize>),
+    Prev(Option<usize>),
+    Number(usize),
+    Current(usize),
+    Next(Option<usize>),
+    Last(Option<usize>),
+}
+
+/// Item of a pagination control rendered by the partial of `{{ @paginate }}`
+///
+/// ```handlebars
+/// {{#*inline "link"}}
+///   {{#if let Some(url) = &url }}<li><a href="{{ url }}">{{ text }}</a></li>
+///   {{~ else }}<li class="disabled"><span>{{ text }}</span></li>{{/if }}
+/// {{/inline}}
+/// {{ @paginate current total url_fn partial = "link" }}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link<U> {
+    pub page: Page,
+    /// Url of the page, `None` for the current page and the links to it
+    pub url: Option<U>,
+    /// Page number or arrow, `«`, `‹`, `›` and `»`, as text
+    pub text: String,
+}
+
+/// Items of a pagination control for build custom markup with `{{#each}}`
+///
+/// ```handlebars
+/// {{#each yarte::builtins::pages(current, total, 2) }}
+///   {{#if let yarte::builtins::Page::Number(n) = this }}<a href="?page={{ n }}">{{ n }}</a>{{/if }}
+/// {{/each}}
+/// ```
+pub fn pages(current: usize, total: usize, window: usize) -> Vec<Page> {
+    if total == 0 {
+        return vec![];
+    }
+
+    let current = min(max(current, 1), total);
+    let link = |cond: bool, page| if cond { Some(page) } else { None };
+
+    let mut pages = vec![
+        Page::First(link(1 < current, 1)),
+        Page::Prev(link(1 < current, current - 1)),
+    ];
+    for page in max(current.saturating_sub(window), 1)..=min(current + window, total) {
+        pages.push(if page == current {
+            Page::Current(page)
+        } else {
+            Page::Number(page)
+        });
+    }
+    pages.push(Page::Next(link(current < total, current + 1)));
+    pages.push(Page::Last(link(current < total, total)));
+
+    pages
+}
@@ -0,0 +1,204 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Write};
+
+use crate::helpers::{escape, Render};
+
+/// Query string of the named arguments `{{ @query page=p order=o }}`, `page=2&order=asc`
+///
+/// Names and values are percent-encoded and the `None` values are skipped. In html templates
+/// the separator is written `&amp;`, as an attribute value.
+pub struct Query<'a>(pub &'a [(&'static str, Option<&'a dyn Display>)]);
+
+impl<'a> Query<'a> {
+    fn write(&self, f: &mut fmt::Formatter, sep: &str) -> fmt::Result {
+        let mut first = true;
+        for (name, value) in self.0 {
+            if let Some(value) = value {
+                if !first {
+                    f.write_str(sep)?;
+                }
+                first = false;
+                let mut f = PercentWriter(f, is_unreserved);
+                f.write_str(name)?;
+                f.0.write_str("=")?;
+                write!(f, "{}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for Query<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, "&")
+    }
+}
+
+impl<'a> Render for Query<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, "&amp;")
+    }
+}
+
+/// Value percent-encoded as a query component `{{ @urlencode x }}`
+///
+/// All but the unreserved chars are encoded, `/`, `?`, `&` and `=` too.
+pub struct UrlEncode<'a>(pub &'a dyn Display);
+
+impl<'a> Display for UrlEncode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(PercentWriter(f, is_unreserved), "{}", self.0)
+    }
+}
+
+impl<'a> Render for UrlEncode<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
+
+/// Value percent-encoded as a path segment `{{ @pathseg x }}`
+///
+/// The sub-delimiters, `:` and `@` are kept, as allowed in a segment by RFC 3986, and `/`,
+/// `?`, `#` and `%` are encoded, so the value can't change the path. The dots of a `.` or
+/// `..` value are encoded too, they aren't dot-segments by RFC 3986, but the URL standard of
+/// browsers still resolves them. Html templates escape the kept `&` and `'`.
+pub struct PathSeg<'a>(pub &'a dyn Display);
+
+impl<'a> Display for PathSeg<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = SegWriter {
+            f: PercentWriter(f, is_pchar),
+            dots: 0,
+            dot_segment: true,
+        };
+        write!(f, "{}", self.0)?;
+        if f.dot_segment {
+            for _ in 0..f.dots {
+                f.f.0.write_str("%2E")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Render for PathSeg<'a> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        escape(&self.to_string()).fmt(f)
+    }
+}
+
+/// Value of an argument of `@query`, `None` values are skipped
+pub trait QueryArg {
+    fn query_arg(&self) -> Option<&dyn Display>;
+}
+
+macro_rules! option_arg {
+    ($($ty:ty)*) => {
+        $(
+            impl<T: Display> QueryArg for $ty {
+                #[inline]
+                fn query_arg(&self) -> Option<&dyn Display> {
+                    self.as_ref().map(|x| x as &dyn Display)
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+option_arg!(Option<T> &Option<T> &&Option<T> &&&Option<T>);
+
+macro_rules! display_arg {
+    ($($ty:ty)*) => {
+        $(
+            impl QueryArg for $ty {
+                #[inline]
+                fn query_arg(&self) -> Option<&dyn Display> {
+                    Some(self)
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+display_arg! {
+    &str &&str &&&str &&&&str
+    String &String &&String &&&String
+
+    bool char
+    u8 u16 u32 u64 u128 usize
+    i8 i16 i32 i64 i128 isize
+    f32 f64
+
+    &bool &char
+    &u8 &u16 &u32 &u64 &u128 &usize
+    &i8 &i16 &i32 &i64 &i128 &isize
+    &f32 &f64
+
+    &&bool &&char
+    &&u8 &&u16 &&u32 &&u64 &&u128 &&usize
+    &&i8 &&i16 &&i32 &&i64 &&i128 &&isize
+    &&f32 &&f64
+}
+
+/// Writer percent-encoding in the formatter all but the bytes of the set
+struct PercentWriter<'a, 'b>(&'a mut fmt::Formatter<'b>, fn(u8) -> bool);
+
+impl<'a, 'b> Write for PercentWriter<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+        let mut last = 0;
+        for (i, b) in s.bytes().enumerate() {
+            if !(self.1)(b) {
+                // Bytes of multibyte chars are encoded one by one
+                if last < i {
+                    self.0.write_str(&s[last..i])?;
+                }
+                let hex = [b'%', HEX[(b >> 4) as usize], HEX[(b & 0xF) as usize]];
+                self.0
+                    .write_str(core::str::from_utf8(&hex).expect("ascii"))?;
+                last = i + 1;
+            }
+        }
+        self.0.write_str(&s[last..])
+    }
+}
+
+/// Writer of a path segment, holds its leading dots while it can be a dot-segment
+struct SegWriter<'a, 'b> {
+    f: PercentWriter<'a, 'b>,
+    dots: usize,
+    dot_segment: bool,
+}
+
+impl<'a, 'b> Write for SegWriter<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.dot_segment {
+            if s.bytes().all(|b| b == b'.') && self.dots + s.len() <= 2 {
+                self.dots += s.len();
+                return Ok(());
+            }
+            self.dot_segment = false;
+            self.f.write_str(&".."[..self.dots])?;
+        }
+        self.f.write_str(s)
+    }
+}
+
+/// Letters, digits and `-._~`, never encoded
+#[inline]
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_' || b == b'~'
+}
+
+/// Unreserved chars, sub-delimiters `!$&'()*+,;=`, `:` and `@`, chars of a path segment
+#[inline]
+fn is_pchar(b: u8) -> bool {
+    is_unreserved(b) || b"!$&'()*+,;=:@".contains(&b)
+}
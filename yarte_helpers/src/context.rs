@@ -0,0 +1,35 @@
+//! Region being rendered, for the context of the errors of `try_render`, enabled with
+//! the `context` feature
+//!
+//! Debug builds of the templates enter each region, as the regions of `coverage`, when it
+//! starts and leave it at its end. A render that fails returns before leaving, so the last
+//! region entered is the one that failed.
+use std::cell::RefCell;
+
+thread_local! {
+    static REGIONS: RefCell<Vec<(usize, &'static str)>> = RefCell::new(Vec::new());
+}
+
+/// Start of a region
+#[doc(hidden)]
+pub fn enter(region: usize, label: &'static str) {
+    REGIONS.with(|r| r.borrow_mut().push((region, label)))
+}
+
+/// End of a region
+#[doc(hidden)]
+pub fn leave() {
+    REGIONS.with(|r| {
+        r.borrow_mut().pop();
+    })
+}
+
+/// Clear the regions of the current thread, before a render
+pub fn reset() {
+    REGIONS.with(|r| r.borrow_mut().clear())
+}
+
+/// Innermost region not left of the current thread
+pub fn current() -> Option<(usize, &'static str)> {
+    REGIONS.with(|r| r.borrow().last().cloned())
+}
@@ -0,0 +1,78 @@
+//! Template coverage, enabled with the `coverage` feature
+//!
+//! Every branch of a conditional, each body and partial of a template is a region,
+//! counted each time it's rendered. Regions of a template are known once one of them
+//! is rendered, templates without rendered regions aren't in the report.
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::{self, Display, Write},
+};
+
+thread_local! {
+    static REPORT: RefCell<BTreeMap<(&'static str, usize), Entry>> = RefCell::new(BTreeMap::new());
+}
+
+/// Renders of a template region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Template struct name
+    pub template: &'static str,
+    /// Region index in the template, in source order
+    pub region: usize,
+    /// Kind and location of the region
+    pub label: &'static str,
+    /// Number of renders
+    pub hits: usize,
+}
+
+/// Regions of the current thread, by template and region
+pub fn report() -> Vec<Entry> {
+    REPORT.with(|r| r.borrow().values().cloned().collect())
+}
+
+/// Regions of the current thread never rendered
+pub fn uncovered() -> Vec<Entry> {
+    report().into_iter().filter(|x| x.hits == 0).collect()
+}
+
+/// Clear the report of the current thread
+pub fn reset() {
+    REPORT.with(|r| r.borrow_mut().clear())
+}
+
+/// Render discarding the output and return the regions it touched and missed
+pub fn dry_run<T: Display + ?Sized>(t: &T) -> Vec<Entry> {
+    reset();
+    let _ = write!(Discard, "{}", t);
+    report()
+}
+
+struct Discard;
+
+impl Write for Discard {
+    #[inline]
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn hit(template: &'static str, region: usize, labels: &[&'static str]) {
+    REPORT.with(|r| {
+        let mut r = r.borrow_mut();
+        if !r.contains_key(&(template, region)) {
+            for (region, label) in labels.iter().enumerate() {
+                r.entry((template, region)).or_insert_with(|| Entry {
+                    template,
+                    region,
+                    label,
+                    hits: 0,
+                });
+            }
+        }
+        if let Some(entry) = r.get_mut(&(template, region)) {
+            entry.hits += 1;
+        }
+    })
+}
@@ -0,0 +1,115 @@
+//! Error of `try_render`, with the template context of debug builds
+//!
+//! The render methods return `fmt::Error`, it's the error of `Display` and of the `?` in the
+//! expressions. `try_render` converts it into `RenderError` with the type name of the template
+//! in debug builds and, with the `context` feature, the region being rendered when it failed.
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+use core::fmt::{self, Display};
+
+/// Template where the error occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    /// Type name of the template
+    pub template: &'static str,
+    /// Index of the region, as the regions of `coverage`, and its kind and location,
+    /// with the `context` feature
+    pub region: Option<(usize, &'static str)>,
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// Error of the formatter
+    Fmt,
+    /// Error of the writer
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Error of the application
+    #[cfg(feature = "std")]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+    /// Template in a state it can't be rendered
+    Unreachable,
+    /// Error in the render of a template, only in debug builds
+    Template(Context, Box<RenderError>),
+}
+
+impl RenderError {
+    /// Attach the context, keeps the innermost one
+    #[doc(hidden)]
+    pub fn within(self, context: Context) -> Self {
+        match self {
+            RenderError::Template(..) => self,
+            e => RenderError::Template(context, Box::new(e)),
+        }
+    }
+
+    /// Context of the error, when it's attached
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            RenderError::Template(context, _) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Error without context
+    pub fn kind(&self) -> &RenderError {
+        match self {
+            RenderError::Template(_, e) => e.kind(),
+            e => e,
+        }
+    }
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderError::Fmt => f.write_str("an error occurred when formatting an argument"),
+            #[cfg(feature = "std")]
+            RenderError::Io(e) => Display::fmt(e, f),
+            #[cfg(feature = "std")]
+            RenderError::Custom(e) => Display::fmt(e, f),
+            RenderError::Unreachable => f.write_str("the template can't be rendered in this state"),
+            RenderError::Template(context, e) => {
+                write!(f, "{} in template `{}`", e, context.template)?;
+                match context.region {
+                    Some((_, label)) => write!(f, ", {}", label),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Io(e) => Some(e),
+            RenderError::Custom(e) => Some(&**e),
+            RenderError::Template(_, e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+impl From<fmt::Error> for RenderError {
+    #[inline]
+    fn from(_: fmt::Error) -> Self {
+        RenderError::Fmt
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for RenderError {
+    #[inline]
+    fn from(e: std::io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+impl From<RenderError> for fmt::Error {
+    #[inline]
+    fn from(_: RenderError) -> Self {
+        fmt::Error
+    }
+}
@@ -0,0 +1,57 @@
+/// Custom block helper, `{{#name args}}..{{/name}}` with `name` registered to the
+/// implementor in the `[helpers]` of `yarte.toml`
+///
+/// The block is rendered once for each item of `blocks`, with the item as its context,
+/// as in an `{{#each}}`. A condition is a [`CustomConditionHelper`].
+///
+/// [`CustomConditionHelper`]: trait.CustomConditionHelper.html
+///
+/// ```
+/// # use yarte_helpers::helpers::CustomBlockHelper;
+/// /// `{{#repeat 3}}..{{/repeat}}`, the block with the number of the repetition
+/// pub struct Repeat;
+///
+/// impl<'a> CustomBlockHelper<'a, usize> for Repeat {
+///     type Blocks = core::ops::Range<usize>;
+///
+///     fn blocks(times: &'a usize) -> Self::Blocks {
+///         0..*times
+///     }
+/// }
+///
+/// assert_eq!(Repeat::blocks(&3).count(), 3);
+/// ```
+pub trait CustomBlockHelper<'a, A: ?Sized + 'a> {
+    type Blocks: IntoIterator;
+
+    /// Items of the blocks of the arguments `args`
+    fn blocks(args: &'a A) -> Self::Blocks;
+}
+
+/// Custom condition helper, `{{#name args}}..{{/name}}` with `name` registered to the
+/// implementor in the `[conditions]` of `yarte.toml`
+///
+/// The block is rendered when the arguments have a `block`, with it as its context,
+/// as in an `{{#with}}`.
+///
+/// ```
+/// # use yarte_helpers::helpers::CustomConditionHelper;
+/// /// `{{#present nick}}..{{/present}}`, the block with the value of the option
+/// pub struct Present;
+///
+/// impl<'a, T: 'a> CustomConditionHelper<'a, Option<T>> for Present {
+///     type Block = &'a T;
+///
+///     fn block(value: &'a Option<T>) -> Option<Self::Block> {
+///         value.as_ref()
+///     }
+/// }
+///
+/// assert_eq!(Present::block(&Some(1)), Some(&1));
+/// ```
+pub trait CustomConditionHelper<'a, A: ?Sized + 'a> {
+    type Block;
+
+    /// Context of the block of the arguments `args`, `None` when it isn't rendered
+    fn block(args: &'a A) -> Option<Self::Block>;
+}
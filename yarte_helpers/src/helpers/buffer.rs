@@ -0,0 +1,67 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// Buffer of the bytes written without formatter by `write_bytes`, a `Vec<u8>` or, with the
+/// feature `bytes-buf`, a `BytesMut`
+pub trait Buffer {
+    /// Append the bytes
+    fn extend_from_slice(&mut self, src: &[u8]);
+
+    /// Reserve capacity for at least `additional` more bytes
+    fn reserve(&mut self, additional: usize);
+
+    /// Number of bytes written
+    fn len(&self) -> usize;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Buffer for Vec<u8> {
+    #[inline]
+    fn extend_from_slice(&mut self, src: &[u8]) {
+        Vec::extend_from_slice(self, src)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+#[cfg(feature = "bytes-buf")]
+impl Buffer for bytes::BytesMut {
+    #[inline]
+    fn extend_from_slice(&mut self, src: &[u8]) {
+        bytes::BytesMut::extend_from_slice(self, src)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        bytes::BytesMut::reserve(self, additional)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        bytes::BytesMut::len(self)
+    }
+}
+
+/// Writer in the bytes, for the safe expressions `{{{ ... }}}` of `write_bytes`
+pub struct BytesWriter<'a>(pub &'a mut dyn Buffer);
+
+impl<'a> Write for BytesWriter<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
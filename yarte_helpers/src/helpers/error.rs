@@ -1,3 +1,5 @@
+use std::env;
+
 use annotate_snippets::{
     display_list::DisplayList,
     formatter::DisplayListFormatter,
@@ -41,12 +43,14 @@ pub fn emitter(sources: Sources, config: &Config, mut errors: Vec<ErrorMessage>)
                 .to_string_lossy()
                 .to_string();
 
+            // Ranges of annotations are in chars from the start of the line
+            let end = source.chars().count();
             Slice {
                 source,
                 line_start: start.line,
                 origin: Some(origin),
                 annotations: vec![SourceAnnotation {
-                    range: (start.column, hi),
+                    range: (start.column, end),
                     label: err.message,
                     annotation_type: AnnotationType::Error,
                 }],
@@ -66,7 +70,8 @@ pub fn emitter(sources: Sources, config: &Config, mut errors: Vec<ErrorMessage>)
     };
 
     let dl = DisplayList::from(s);
-    let dlf = DisplayListFormatter::new(true, false);
+    // Plain text with `NO_COLOR`, https://no-color.org
+    let dlf = DisplayListFormatter::new(env::var_os("NO_COLOR").is_none(), false);
 
     // TODO: decide when output is better
     panic!("{}", dlf.format(&dl))
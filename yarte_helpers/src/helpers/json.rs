@@ -0,0 +1,263 @@
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display, Write};
+
+use super::{Buffer, BytesWriter};
+
+/// Render trait of the expressions `{{ ... }}` of a json template, escaped as the content
+/// of a json string
+pub trait RenderJson {
+    fn render_json(&self, f: &mut fmt::Formatter) -> fmt::Result;
+
+    /// Write in the bytes without formatter, used in method `write_bytes` of json templates
+    #[inline]
+    fn render_json_into(&self, buf: &mut dyn Buffer) -> fmt::Result {
+        write!(JsonWriter(buf), "{}", RenderJsonFmt(self))
+    }
+}
+
+/// Display of a `RenderJson`
+struct RenderJsonFmt<'a, T: RenderJson + ?Sized>(&'a T);
+
+impl<'a, T: RenderJson + ?Sized> Display for RenderJsonFmt<'a, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.render_json(f)
+    }
+}
+
+macro_rules! str_json {
+    ($($ty:ty)*) => {
+        $(
+            impl RenderJson for &$ty {
+                #[inline(always)]
+                fn render_json(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    JsonEscape(self).fmt(f)
+                }
+
+                #[inline(always)]
+                fn render_json_into(&self, buf: &mut dyn Buffer) -> fmt::Result {
+                    json_escape_into(self, buf);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+str_json!(str &str &&str &&&str &&&&str);
+
+macro_rules! string_json {
+    ($($ty:ty)*) => {
+        $(
+            impl RenderJson for $ty {
+                #[inline(always)]
+                fn render_json(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    JsonEscape(self.as_str()).fmt(f)
+                }
+
+                #[inline(always)]
+                fn render_json_into(&self, buf: &mut dyn Buffer) -> fmt::Result {
+                    json_escape_into(self.as_str(), buf);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+string_json!(String &String &&String &&&String &&&&String);
+
+macro_rules! raw_json {
+    ($($ty:ty)*) => {
+        $(
+            impl RenderJson for $ty {
+                #[inline(always)]
+                fn render_json(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    self.fmt(f)
+                }
+
+                #[inline(always)]
+                fn render_json_into(&self, buf: &mut dyn Buffer) -> fmt::Result {
+                    write!(BytesWriter(buf), "{}", self)
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+raw_json! {
+    bool
+    u8 u16 u32 u64 u128 usize
+    i8 i16 i32 i64 i128 isize
+    f32 f64
+
+    &bool
+    &u8 &u16 &u32 &u64 &u128 &usize
+    &i8 &i16 &i32 &i64 &i128 &isize
+    &f32 &f64
+
+    &&bool
+    &&u8 &&u16 &&u32 &&u64 &&u128 &&usize
+    &&i8 &&i16 &&i32 &&i64 &&i128 &&isize
+    &&f32 &&f64
+}
+
+/// Json escape the formatted arguments as they are written, without intermediate `String`
+impl<'a> RenderJson for fmt::Arguments<'a> {
+    #[inline]
+    fn render_json(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::write(&mut JsonFmtWriter(f), *self)
+    }
+
+    #[inline]
+    fn render_json_into(&self, buf: &mut dyn Buffer) -> fmt::Result {
+        fmt::write(&mut JsonWriter(buf), *self)
+    }
+}
+
+/// Writer json escaping each chunk in the bytes
+struct JsonWriter<'a>(&'a mut dyn Buffer);
+
+impl<'a> Write for JsonWriter<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        json_escape_into(s, self.0);
+        Ok(())
+    }
+}
+
+/// Writer json escaping each chunk in the formatter
+struct JsonFmtWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl<'a, 'b> Write for JsonFmtWriter<'a, 'b> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        JsonEscape(s).fmt(self.0)
+    }
+}
+
+/// Json string escape in the formatter
+struct JsonEscape<'a>(&'a str);
+
+impl<'a> Display for JsonEscape<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = self.0.as_bytes();
+        let mut last = 0;
+        let mut i = 0;
+        while let Some(n) = next_escape(&bytes[i..]) {
+            i += n;
+            f.write_str(&self.0[last..i])?;
+            f.write_str(escaped(bytes[i]).as_str())?;
+            i += 1;
+            last = i;
+        }
+        f.write_str(&self.0[last..])
+    }
+}
+
+/// Json string escape in the bytes, same output as `serde_json`
+///
+/// Runs of bytes without escape are copied at once and only `"`, `\` and control chars
+/// are escaped, so the non ascii chars are copied as is.
+///
+/// ```
+/// # use yarte_helpers::helpers::json_escape_into;
+/// let mut buf = vec![];
+/// json_escape_into("a \"quote\"\n", &mut buf);
+/// assert_eq!(buf, br#"a \"quote\"\n"#);
+/// ```
+pub fn json_escape_into(s: &str, buf: &mut dyn Buffer) {
+    let bytes = s.as_bytes();
+    buf.reserve(bytes.len());
+    let mut i = 0;
+    while let Some(n) = next_escape(&bytes[i..]) {
+        buf.extend_from_slice(&bytes[i..i + n]);
+        i += n;
+        buf.extend_from_slice(escaped(bytes[i]).as_bytes());
+        i += 1;
+    }
+    buf.extend_from_slice(&bytes[i..]);
+}
+
+/// Json string escape in a `String`, for the literals escaped at compile time
+pub fn json_escape(s: &str) -> String {
+    let mut buf = Vec::with_capacity(s.len());
+    json_escape_into(s, &mut buf);
+    String::from_utf8(buf).expect("escape keeps utf-8")
+}
+
+/// Escape of each byte, `0` for the bytes without escape
+#[rustfmt::skip]
+static ESCAPE: [u8; 256] = {
+    const U: u8 = b'u';
+    const __: u8 = 0;
+    [
+        // one row per 16 bytes, from 0x00
+        U, U, U, U, U, U, U, U, b'b', b't', b'n', U, b'f', b'r', U, U, // 0
+        U, U, U, U, U, U, U, U, U, U, U, U, U, U, U, U, // 1
+        __, __, b'"', __, __, __, __, __, __, __, __, __, __, __, __, __, // 2
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 3
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 4
+        __, __, __, __, __, __, __, __, __, __, __, __, b'\\', __, __, __, // 5
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 6
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 7
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 8
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 9
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // A
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // B
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // C
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // D
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // E
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // F
+    ]
+};
+
+/// Offset of the first byte to escape
+#[inline]
+fn next_escape(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|b| ESCAPE[*b as usize] != 0)
+}
+
+/// Escape sequence of a byte, up to `\u001f`
+struct Escaped {
+    buf: [u8; 6],
+    len: usize,
+}
+
+impl Escaped {
+    #[inline]
+    fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    #[inline]
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(self.as_bytes()).expect("ascii escape")
+    }
+}
+
+#[inline]
+fn escaped(b: u8) -> Escaped {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    match ESCAPE[b as usize] {
+        b'u' => Escaped {
+            buf: [
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                HEX[(b >> 4) as usize],
+                HEX[(b & 0xF) as usize],
+            ],
+            len: 6,
+        },
+        e => Escaped {
+            buf: [b'\\', e, 0, 0, 0, 0],
+            len: 2,
+        },
+    }
+}
@@ -1,11 +1,115 @@
 // Based on https://github.com/utkarshkukreti/markup.rs/blob/master/markup/src/lib.rs
-use std::fmt::{self, Display};
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use core::fmt::{self, Display};
 
-use v_htmlescape::escape;
+use super::{Buffer, BytesWriter};
+
+#[cfg(not(feature = "std"))]
+use self::escape as escape_scalar;
+#[cfg(feature = "std")]
+pub(crate) use v_htmlescape::escape;
+#[cfg(feature = "std")]
+use v_htmlescape::fallback::escape as escape_scalar;
 
 /// Render trait, used for wrap unsafe expressions `{{ ... }}` when it's in a html template
 pub trait Render {
     fn render(&self, f: &mut fmt::Formatter) -> fmt::Result;
+
+    /// Write in the buffer without formatter, used in method `call` of html templates
+    #[inline]
+    fn render_into(&self, buf: &mut String) -> fmt::Result {
+        fmt::Write::write_fmt(buf, format_args!("{}", RenderFmt(self)))
+    }
+
+    /// Write in the bytes without formatter, used in method `write_bytes` of html templates
+    #[inline]
+    fn render_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+        fmt::Write::write_fmt(&mut BytesWriter(buf), format_args!("{}", RenderFmt(self)))
+    }
+
+    /// Render with the escape without simd, used in html templates with `escape = "scalar"`
+    #[inline]
+    fn render_scalar(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.render(f)
+    }
+}
+
+/// Render of the expressions `{{ ... }}` of the html templates with `escape = "scalar"`
+pub struct Scalar<'a, T: Render + ?Sized>(pub &'a T);
+
+impl<'a, T: Render + ?Sized> Render for Scalar<'a, T> {
+    #[inline]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.render_scalar(f)
+    }
+
+    #[inline]
+    fn render_into(&self, buf: &mut String) -> fmt::Result {
+        self.0.render_into(buf)
+    }
+
+    #[inline]
+    fn render_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+        self.0.render_bytes(buf)
+    }
+}
+
+/// Safe expression `{{{ ... }}}` of the methods `call` and `write_bytes` of html templates
+///
+/// Written with `(&Safe(&expr)).safe_into(buf)`, with `SafeStr` and `SafeDisplay` in scope.
+/// The method of `SafeStr` is found first, so the strings are written as is without
+/// formatter and the other values with their `Display`.
+pub struct Safe<'a, T: ?Sized>(pub &'a T);
+
+/// Write of the strings of a `Safe`
+pub trait SafeStr {
+    fn safe_into(&self, buf: &mut String) -> fmt::Result;
+
+    fn safe_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result;
+}
+
+impl<'a, T: AsRef<str> + ?Sized> SafeStr for Safe<'a, T> {
+    #[inline]
+    fn safe_into(&self, buf: &mut String) -> fmt::Result {
+        buf.push_str(self.0.as_ref());
+        Ok(())
+    }
+
+    #[inline]
+    fn safe_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+        buf.extend_from_slice(self.0.as_ref().as_bytes());
+        Ok(())
+    }
+}
+
+/// Write of the other values of a `Safe` with their `Display`
+pub trait SafeDisplay {
+    fn safe_into(&self, buf: &mut String) -> fmt::Result;
+
+    fn safe_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result;
+}
+
+impl<'a, 'b, T: Display + ?Sized> SafeDisplay for &'b Safe<'a, T> {
+    #[inline]
+    fn safe_into(&self, buf: &mut String) -> fmt::Result {
+        fmt::Write::write_fmt(buf, format_args!("{}", self.0))
+    }
+
+    #[inline]
+    fn safe_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+        fmt::Write::write_fmt(&mut BytesWriter(buf), format_args!("{}", self.0))
+    }
+}
+
+/// Display of a `Render`
+struct RenderFmt<'a, T: Render + ?Sized>(&'a T);
+
+impl<'a, T: Render + ?Sized> Display for RenderFmt<'a, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.render(f)
+    }
 }
 
 macro_rules! str_display {
@@ -16,6 +120,23 @@ macro_rules! str_display {
                 fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
                     escape(self).fmt(f)
                 }
+
+                #[inline(always)]
+                fn render_scalar(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    escape_scalar(self).fmt(f)
+                }
+
+                #[inline(always)]
+                fn render_into(&self, buf: &mut String) -> fmt::Result {
+                    escape_into(self, buf);
+                    Ok(())
+                }
+
+                #[inline(always)]
+                fn render_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+                    escape_bytes_into(self, buf);
+                    Ok(())
+                }
             }
         )*
     };
@@ -32,6 +153,23 @@ macro_rules! string_display {
                 fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
                     escape(self.as_str()).fmt(f)
                 }
+
+                #[inline(always)]
+                fn render_scalar(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    escape_scalar(self.as_str()).fmt(f)
+                }
+
+                #[inline(always)]
+                fn render_into(&self, buf: &mut String) -> fmt::Result {
+                    escape_into(self.as_str(), buf);
+                    Ok(())
+                }
+
+                #[inline(always)]
+                fn render_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+                    escape_bytes_into(self.as_str(), buf);
+                    Ok(())
+                }
             }
         )*
     };
@@ -48,6 +186,16 @@ macro_rules! raw_display {
                 fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
                     self.fmt(f)
                 }
+
+                #[inline(always)]
+                fn render_into(&self, buf: &mut String) -> fmt::Result {
+                    fmt::Write::write_fmt(buf, format_args!("{}", self))
+                }
+
+                #[inline(always)]
+                fn render_bytes(&self, buf: &mut dyn Buffer) -> fmt::Result {
+                    fmt::Write::write_fmt(&mut BytesWriter(buf), format_args!("{}", self))
+                }
             }
         )*
     };
@@ -85,3 +233,284 @@ raw_display! {
     &&&&i8 &&&&i16 &&&&i32 &&&&i64 &&&&i128 &&&&isize
     &&&&f32 &&&&f64
 }
+
+/// Html escape the formatted arguments as they are written, without intermediate `String`
+impl<'a> Render for fmt::Arguments<'a> {
+    #[inline]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::write(&mut EscapeWriter(f), *self)
+    }
+}
+
+macro_rules! option_render {
+    ($($ty:ty)*) => {
+        $(
+            /// Render of the value, nothing on `None`
+            impl<T: Render> Render for $ty {
+                #[inline]
+                fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    match self {
+                        Some(x) => x.render(f),
+                        None => Ok(()),
+                    }
+                }
+
+                #[inline]
+                fn render_into(&self, buf: &mut String) -> fmt::Result {
+                    match self {
+                        Some(x) => x.render_into(buf),
+                        None => Ok(()),
+                    }
+                }
+
+                #[inline]
+                fn render_scalar(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    match self {
+                        Some(x) => x.render_scalar(f),
+                        None => Ok(()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+option_render!(Option<T> &Option<T> &&Option<T>);
+
+macro_rules! result_render {
+    ($($ty:ty)*) => {
+        $(
+            /// Render of the value, an `Err` fails the render
+            impl<T: Render, E> Render for $ty {
+                #[inline]
+                fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    match self {
+                        Ok(x) => x.render(f),
+                        Err(_) => Err(fmt::Error),
+                    }
+                }
+
+                #[inline]
+                fn render_into(&self, buf: &mut String) -> fmt::Result {
+                    match self {
+                        Ok(x) => x.render_into(buf),
+                        Err(_) => Err(fmt::Error),
+                    }
+                }
+
+                #[inline]
+                fn render_scalar(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    match self {
+                        Ok(x) => x.render_scalar(f),
+                        Err(_) => Err(fmt::Error),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[rustfmt::skip]
+result_render!(Result<T, E> &Result<T, E> &&Result<T, E>);
+
+/// Items of an iterator rendered one after another
+///
+/// Unsafe expressions `{{ concat(iter) }}` html escape each item, safe expressions
+/// `{{{ concat(iter) }}}` don't. The iterator is cloned in each render.
+pub struct Concat<I>(pub I);
+
+/// Wrap the iterator in a `Concat`
+#[inline]
+pub fn concat<I: IntoIterator + Clone>(iter: I) -> Concat<I> {
+    Concat(iter)
+}
+
+impl<I> Display for Concat<I>
+where
+    I: IntoIterator + Clone,
+    I::Item: Display,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.clone().into_iter().try_for_each(|x| x.fmt(f))
+    }
+}
+
+impl<I> Render for Concat<I>
+where
+    I: IntoIterator + Clone,
+    I::Item: Render,
+{
+    #[inline]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.clone().into_iter().try_for_each(|x| x.render(f))
+    }
+
+    #[inline]
+    fn render_into(&self, buf: &mut String) -> fmt::Result {
+        self.0
+            .clone()
+            .into_iter()
+            .try_for_each(|x| x.render_into(buf))
+    }
+}
+
+/// Items of an iterator rendered with a separator between them
+///
+/// Unsafe expressions `{{ join(iter, ", ") }}` html escape each item and the separator,
+/// safe expressions `{{{ join(iter, ", ") }}}` don't. The iterator is cloned in each render.
+pub struct Join<I, S>(pub I, pub S);
+
+/// Wrap the iterator and the separator in a `Join`
+#[inline]
+pub fn join<I: IntoIterator + Clone, S>(iter: I, sep: S) -> Join<I, S> {
+    Join(iter, sep)
+}
+
+impl<I, S> Display for Join<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Display,
+    S: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut iter = self.0.clone().into_iter();
+        if let Some(x) = iter.next() {
+            x.fmt(f)?;
+        }
+        iter.try_for_each(|x| {
+            self.1.fmt(f)?;
+            x.fmt(f)
+        })
+    }
+}
+
+impl<I, S> Render for Join<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Render,
+    S: Render,
+{
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut iter = self.0.clone().into_iter();
+        if let Some(x) = iter.next() {
+            x.render(f)?;
+        }
+        iter.try_for_each(|x| {
+            self.1.render(f)?;
+            x.render(f)
+        })
+    }
+
+    fn render_into(&self, buf: &mut String) -> fmt::Result {
+        let mut iter = self.0.clone().into_iter();
+        if let Some(x) = iter.next() {
+            x.render_into(buf)?;
+        }
+        iter.try_for_each(|x| {
+            self.1.render_into(buf)?;
+            x.render_into(buf)
+        })
+    }
+}
+
+/// Lazily computed content, the closure writes in the template output when it's rendered
+///
+/// Unsafe expressions `{{ lazy(|f| ...) }}` html escape the output of the closure, safe
+/// expressions `{{{ lazy(|f| ...) }}}` don't.
+pub struct Lazy<F>(pub F);
+
+/// Wrap the closure in a `Lazy`
+#[inline]
+pub fn lazy<F: Fn(&mut fmt::Formatter) -> fmt::Result>(f: F) -> Lazy<F> {
+    Lazy(f)
+}
+
+impl<F: Fn(&mut fmt::Formatter) -> fmt::Result> Display for Lazy<F> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+impl<F: Fn(&mut fmt::Formatter) -> fmt::Result> Render for Lazy<F> {
+    #[inline]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_args!("{}", self).render(f)
+    }
+}
+
+/// Writer escaping each chunk in the formatter
+struct EscapeWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl<'a, 'b> fmt::Write for EscapeWriter<'a, 'b> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        escape(s).fmt(self.0)
+    }
+}
+
+/// Html escape in the buffer, same output as `v_htmlescape`
+pub fn escape_into(s: &str, buf: &mut String) {
+    let mut last = 0;
+    for (i, b) in s.bytes().enumerate() {
+        if let Some(quote) = quote(b) {
+            buf.push_str(&s[last..i]);
+            buf.push_str(quote);
+            last = i + 1;
+        }
+    }
+    buf.push_str(&s[last..]);
+}
+
+/// Html escape in the bytes, same output as `escape_into`
+pub fn escape_bytes_into(s: &str, buf: &mut dyn Buffer) {
+    let bytes = s.as_bytes();
+    let mut last = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        if let Some(quote) = quote(*b) {
+            buf.extend_from_slice(&bytes[last..i]);
+            buf.extend_from_slice(quote.as_bytes());
+            last = i + 1;
+        }
+    }
+    buf.extend_from_slice(&bytes[last..]);
+}
+
+#[inline]
+fn quote(b: u8) -> Option<&'static str> {
+    Some(match b {
+        b'<' => "&lt;",
+        b'>' => "&gt;",
+        b'&' => "&amp;",
+        b'"' => "&quot;",
+        b'\'' => "&#x27;",
+        b'/' => "&#x2f;",
+        _ => return None,
+    })
+}
+
+/// Html escape without `std`, same output as `v_htmlescape`
+#[cfg(not(feature = "std"))]
+pub(crate) fn escape(s: &str) -> Escape<'_> {
+    Escape(s)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) struct Escape<'a>(&'a str);
+
+#[cfg(not(feature = "std"))]
+impl<'a> Display for Escape<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut last = 0;
+        for (i, b) in self.0.bytes().enumerate() {
+            if let Some(quote) = quote(b) {
+                f.write_str(&self.0[last..i])?;
+                f.write_str(quote)?;
+                last = i + 1;
+            }
+        }
+        f.write_str(&self.0[last..])
+    }
+}
@@ -1,16 +1,38 @@
+#[cfg(feature = "std")]
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
 };
 
+mod block;
+mod buffer;
+#[cfg(feature = "std")]
 mod error;
+mod json;
 mod markup;
+mod negotiate;
+#[cfg(feature = "std")]
 mod read;
+#[cfg(feature = "std")]
+mod scheme;
 
+pub use block::{CustomBlockHelper, CustomConditionHelper};
+pub use buffer::{Buffer, BytesWriter};
+#[cfg(feature = "std")]
 pub use error::{emitter, ErrorMessage};
-pub use markup::Render;
+pub use json::{json_escape, json_escape_into, RenderJson};
+pub(crate) use markup::escape;
+pub use markup::{
+    concat, escape_bytes_into, escape_into, join, lazy, Concat, Join, Lazy, Render, Safe,
+    SafeDisplay, SafeStr, Scalar,
+};
+pub use negotiate::negotiate;
+#[cfg(feature = "std")]
 pub use read::{read, Sources};
+#[cfg(feature = "std")]
+pub use scheme::url_scheme;
 
+#[cfg(feature = "std")]
 pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
@@ -0,0 +1,74 @@
+/// Index of the media type of `available` preferred by the `Accept` header,
+/// `None` when no one is acceptable
+///
+/// The quality `q` of each range decides, with the most specific range matching each type,
+/// and ties are solved by the order of `available`. An empty header accepts anything.
+///
+/// ```
+/// # use yarte_helpers::helpers::negotiate;
+/// let accept = "text/html;q=0.8, application/json, */*;q=0.1";
+/// assert_eq!(negotiate(accept, &["text/html", "application/json"]), Some(1));
+/// assert_eq!(negotiate("image/png", &["text/html", "application/json"]), None);
+/// ```
+pub fn negotiate(accept: &str, available: &[&str]) -> Option<usize> {
+    if accept.trim().is_empty() {
+        return if available.is_empty() { None } else { Some(0) };
+    }
+
+    let mut best: Option<(usize, f32)> = None;
+    for (i, mime) in available.iter().enumerate() {
+        let q = match quality(accept, mime) {
+            Some(q) if q > 0.0 => q,
+            _ => continue,
+        };
+        match best {
+            Some((_, b)) if b >= q => (),
+            _ => best = Some((i, q)),
+        }
+    }
+
+    best.map(|(i, _)| i)
+}
+
+/// Quality of the most specific range of `accept` matching `mime`
+fn quality(accept: &str, mime: &str) -> Option<f32> {
+    let (ty, sub) = split(mime);
+    let mut best: Option<(u8, f32)> = None;
+    for range in accept.split(',') {
+        let mut params = range.split(';');
+        let (r_ty, r_sub) = split(params.next().unwrap_or(""));
+        let specificity = match (r_ty, r_sub) {
+            ("*", "*") => 0,
+            (t, "*") if t.eq_ignore_ascii_case(ty) => 1,
+            (t, s) if t.eq_ignore_ascii_case(ty) && s.eq_ignore_ascii_case(sub) => 2,
+            _ => continue,
+        };
+        let q = params
+            .filter_map(|p| {
+                let mut kv = p.splitn(2, '=');
+                match (kv.next(), kv.next()) {
+                    (Some(k), Some(v)) if k.trim().eq_ignore_ascii_case("q") => {
+                        v.trim().parse().ok()
+                    }
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(1.0);
+        match best {
+            Some((s, _)) if specificity <= s => (),
+            _ => best = Some((specificity, q)),
+        }
+    }
+
+    best.map(|(_, q)| q)
+}
+
+fn split(mime: &str) -> (&str, &str) {
+    let mime = mime.split(';').next().unwrap_or("").trim();
+    let mut parts = mime.splitn(2, '/');
+    (
+        parts.next().unwrap_or("").trim(),
+        parts.next().unwrap_or("").trim(),
+    )
+}
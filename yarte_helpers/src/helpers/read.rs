@@ -3,48 +3,41 @@ use std::{
     path::PathBuf,
 };
 
-use yarte_config::{get_source, Config};
+use yarte_config::Config;
 use yarte_parser::{parse_partials, Partial};
 
-use crate::helpers::calculate_hash;
-
 pub type Sources<'a> = &'a BTreeMap<PathBuf, String>;
 
 pub fn read(path: PathBuf, src: String, config: &Config) -> BTreeMap<PathBuf, String> {
-    fn _read(
-        path: PathBuf,
-        src: String,
-        config: &Config,
-        visited: &mut BTreeMap<PathBuf, String>,
-        stack: &mut Vec<u64>,
-    ) {
-        stack.push(calculate_hash(&path));
+    fn _read(path: PathBuf, src: String, config: &Config, visited: &mut BTreeMap<PathBuf, String>) {
+        let src = config.newlines.normalize(src);
 
         let partials = parse_partials(&src)
             .iter()
-            .map(|Partial(_, partial, _)| config.resolve_partial(&path, partial.t()))
+            .filter_map(|Partial(_, partial, _)| config.find_partial(&path, partial.t()))
             .collect::<BTreeSet<_>>();
 
         visited.insert(path.clone(), src);
 
         for partial in partials {
+            // Recursive partials are checked in the lowering, with `recursion` of `[limits]`
             if !visited.contains_key(&partial) {
-                let src = get_source(partial.as_path());
-                _read(partial, src, config, visited, stack);
-            } else if stack.contains(&calculate_hash(&partial)) {
-                panic!(
-                    "Partial cyclic dependency {:?} in template {:?}",
-                    partial, path
-                );
+                let src = config.get_source(partial.as_path());
+                _read(partial, src, config, visited);
             }
         }
-
-        stack.pop();
     }
 
     let mut visited = BTreeMap::new();
+    let builtins: Vec<_> = config.builtin_partials(&path).collect();
 
-    _read(path, src, config, &mut visited, &mut Vec::new());
+    _read(path, src, config, &mut visited);
+    for partial in builtins {
+        if !visited.contains_key(&partial) {
+            let src = config.get_source(partial.as_path());
+            _read(partial, src, config, &mut visited);
+        }
+    }
 
     visited
 }
@@ -0,0 +1,24 @@
+/// Scheme in lowercase and rest of `url`, `None` for the urls without scheme
+///
+/// The ascii whitespace and control chars are removed before, as the browsers ignore them.
+///
+/// ```
+/// # use yarte_helpers::helpers::url_scheme;
+/// assert_eq!(
+///     url_scheme(" Java\tScript:alert(1)"),
+///     Some(("javascript".to_string(), "alert(1)".to_string()))
+/// );
+/// assert_eq!(url_scheme("/path?a=b:c"), None);
+/// ```
+pub fn url_scheme(url: &str) -> Option<(String, String)> {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    match url.find(&[':', '/', '?', '#'][..]) {
+        Some(i) if url[i..].starts_with(':') => {
+            Some((url[..i].to_ascii_lowercase(), url[i + 1..].to_owned()))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,106 @@
+//! Render hook, enabled with the `hook` feature
+//!
+//! The templates call the `RenderHook` installed in the current thread at the start
+//! and at the end of each render, with the template name and the written bytes. Without
+//! the feature the templates don't call it.
+//!
+//! Install it in each thread rendering templates, as in the factory of the workers of a server:
+//!
+//! ```ignore
+//! HttpServer::new(|| {
+//!     yarte::hook::install(Metrics);
+//!     App::new().service(index)
+//! })
+//! ```
+use std::{
+    cell::{Cell, RefCell},
+    fmt::{self, Display, Write},
+    rc::Rc,
+};
+
+thread_local! {
+    static HOOK: RefCell<Option<Rc<dyn RenderHook>>> = RefCell::new(None);
+}
+
+/// Hook called around the renders of the templates
+pub trait RenderHook {
+    /// Start of the render of `template`
+    fn start(&self, template: &'static str);
+
+    /// End of the render of `template` without errors, with the written bytes
+    fn end(&self, template: &'static str, bytes: usize);
+}
+
+/// Install the hook of the current thread, replacing the previous one
+pub fn install<H: RenderHook + 'static>(hook: H) {
+    HOOK.with(|h| *h.borrow_mut() = Some(Rc::new(hook)))
+}
+
+/// Remove the hook of the current thread
+pub fn uninstall() {
+    HOOK.with(|h| *h.borrow_mut() = None)
+}
+
+fn current() -> Option<Rc<dyn RenderHook>> {
+    HOOK.with(|h| h.borrow().clone())
+}
+
+#[doc(hidden)]
+pub fn start(template: &'static str) {
+    if let Some(hook) = current() {
+        hook.start(template);
+    }
+}
+
+#[doc(hidden)]
+pub fn end(template: &'static str, bytes: usize) {
+    if let Some(hook) = current() {
+        hook.end(template, bytes);
+    }
+}
+
+/// Render of a template in the formatter, counting the written bytes when there is a hook
+#[doc(hidden)]
+pub fn fmt<F>(f: &mut fmt::Formatter, template: &'static str, render: F) -> fmt::Result
+where
+    F: FnOnce(&mut fmt::Formatter) -> fmt::Result,
+{
+    let hook = match current() {
+        Some(hook) => hook,
+        None => return render(f),
+    };
+
+    hook.start(template);
+    let mut counter = Counter { fmt: f, bytes: 0 };
+    write!(counter, "{}", Render(Cell::new(Some(render))))?;
+    hook.end(template, counter.bytes);
+    Ok(())
+}
+
+/// Writer counting the bytes written to the formatter
+struct Counter<'a, 'b> {
+    fmt: &'a mut fmt::Formatter<'b>,
+    bytes: usize,
+}
+
+impl<'a, 'b> Write for Counter<'a, 'b> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.bytes += s.len();
+        self.fmt.write_str(s)
+    }
+}
+
+struct Render<F>(Cell<Option<F>>);
+
+impl<F> Display for Render<F>
+where
+    F: FnOnce(&mut fmt::Formatter) -> fmt::Result,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.take() {
+            Some(render) => render(f),
+            None => Ok(()),
+        }
+    }
+}
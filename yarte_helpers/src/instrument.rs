@@ -0,0 +1,107 @@
+//! Output size instrumentation, enabled with the `instrument` feature
+//!
+//! Every region of an instrumented template, one per node in the template tree,
+//! tallies the bytes it writes. Regions nested in a loop or a conditional are
+//! included in the count of their parent.
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::{self, Display, Write},
+};
+
+thread_local! {
+    static REPORT: RefCell<BTreeMap<(&'static str, usize), Entry>> = RefCell::new(BTreeMap::new());
+}
+
+/// Written bytes by a template region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Template struct name
+    pub template: &'static str,
+    /// Region index in the template, in source order
+    pub region: usize,
+    /// Short description of the region
+    pub label: &'static str,
+    /// Total written bytes
+    pub bytes: usize,
+    /// Number of renders
+    pub hits: usize,
+}
+
+/// Regions of the current thread, heaviest first
+pub fn report() -> Vec<Entry> {
+    let mut entries: Vec<Entry> = REPORT.with(|r| r.borrow().values().cloned().collect());
+    entries.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    entries
+}
+
+/// Clear the report of the current thread
+pub fn reset() {
+    REPORT.with(|r| r.borrow_mut().clear())
+}
+
+#[doc(hidden)]
+pub fn record(template: &'static str, region: usize, label: &'static str, bytes: usize) {
+    REPORT.with(|r| {
+        let mut r = r.borrow_mut();
+        let entry = r.entry((template, region)).or_insert_with(|| Entry {
+            template,
+            region,
+            label,
+            bytes: 0,
+            hits: 0,
+        });
+        entry.bytes += bytes;
+        entry.hits += 1;
+    })
+}
+
+/// Writer counting the bytes written to the formatter
+#[doc(hidden)]
+pub struct Counter<'a, 'b> {
+    fmt: &'a mut fmt::Formatter<'b>,
+    bytes: usize,
+}
+
+impl<'a, 'b> Counter<'a, 'b> {
+    pub fn new(fmt: &'a mut fmt::Formatter<'b>) -> Self {
+        Counter { fmt, bytes: 0 }
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl<'a, 'b> Write for Counter<'a, 'b> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.bytes += s.len();
+        self.fmt.write_str(s)
+    }
+}
+
+/// Display a region of generated code
+#[doc(hidden)]
+pub struct Region<F>(Cell<Option<F>>);
+
+impl<F> Region<F>
+where
+    F: FnOnce(&mut fmt::Formatter) -> fmt::Result,
+{
+    pub fn new(f: F) -> Self {
+        Region(Cell::new(Some(f)))
+    }
+}
+
+impl<F> Display for Region<F>
+where
+    F: FnOnce(&mut fmt::Formatter) -> fmt::Result,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.take() {
+            Some(region) => region(f),
+            None => Ok(()),
+        }
+    }
+}
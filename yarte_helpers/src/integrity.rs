@@ -0,0 +1,125 @@
+//! Integrity of the html output, enabled with the `integrity` feature for tests
+//!
+//! In debug builds the method `call` of html templates tokenizes its output again with
+//! html5ever and compares its tags with the tags of the template without dynamic text,
+//! the same render with the escaped expressions `{{ ... }}` removed. Safe expressions and
+//! built-in helpers are written in both. A different structure is an escaping or
+//! serialization bug, the output isn't the tree that the template author wrote.
+use std::fmt::{Display, Write};
+
+use html5ever::{
+    tendril::StrTendril,
+    tokenizer::{
+        states::RawKind, BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer,
+        TokenizerOpts,
+    },
+};
+
+/// Tag of the structure of a document, without text nor attribute values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tag {
+    Doctype(Option<String>),
+    Start {
+        name: String,
+        attrs: Vec<String>,
+        self_closing: bool,
+    },
+    End(String),
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Tag::Doctype(name) => write!(f, "<!DOCTYPE {}>", name.as_deref().unwrap_or("")),
+            Tag::Start {
+                name,
+                attrs,
+                self_closing,
+            } => {
+                write!(f, "<{}", name)?;
+                for attr in attrs {
+                    write!(f, " {}", attr)?;
+                }
+                f.write_str(if *self_closing { "/>" } else { ">" })
+            }
+            Tag::End(name) => write!(f, "</{}>", name),
+        }
+    }
+}
+
+/// Panics when the tags of the `output` of `template` aren't the tags of its `skeleton`
+pub fn assert(template: &str, output: &str, skeleton: &dyn Display) {
+    let expected = tags(&skeleton.to_string());
+    let found = tags(output);
+    if expected == found {
+        return;
+    }
+
+    let i = expected
+        .iter()
+        .zip(&found)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let show = |tags: &[Tag]| tags.get(i).map_or("end".to_owned(), ToString::to_string);
+    let mut message = format!(
+        "integrity of `{}`: expected {} found {} at tag {} of the output",
+        template,
+        show(&expected),
+        show(&found),
+        i
+    );
+    write!(message, "\n{}", output).unwrap();
+    panic!("{}", message);
+}
+
+/// Tags of an html document, sorted attribute names
+pub fn tags(html: &str) -> Vec<Tag> {
+    let mut tokenizer = Tokenizer::new(Sink(vec![]), TokenizerOpts::default());
+    let mut queue = BufferQueue::new();
+    queue.push_back(StrTendril::from_slice(html));
+    let _ = tokenizer.feed(&mut queue);
+    tokenizer.end();
+
+    tokenizer.sink.0
+}
+
+struct Sink(Vec<Tag>);
+
+impl TokenSink for Sink {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::DoctypeToken(d) => self.0.push(Tag::Doctype(d.name.map(|x| x.to_string()))),
+            Token::TagToken(tag) => {
+                let name = tag.name.to_string();
+                if tag.kind == TagKind::EndTag {
+                    self.0.push(Tag::End(name));
+                    return TokenSinkResult::Continue;
+                }
+
+                let mut attrs: Vec<_> =
+                    tag.attrs.iter().map(|a| a.name.local.to_string()).collect();
+                attrs.sort();
+                self.0.push(Tag::Start {
+                    name: name.clone(),
+                    attrs,
+                    self_closing: tag.self_closing,
+                });
+                // Switch of the tokenizer state as the tree builder
+                match name.as_str() {
+                    "script" => return TokenSinkResult::RawData(RawKind::ScriptData),
+                    "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+                        return TokenSinkResult::RawData(RawKind::Rawtext)
+                    }
+                    "title" | "textarea" => return TokenSinkResult::RawData(RawKind::Rcdata),
+                    "plaintext" => return TokenSinkResult::Plaintext,
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+
+        TokenSinkResult::Continue
+    }
+}
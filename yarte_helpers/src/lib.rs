@@ -1,4 +1,28 @@
-pub use std::fmt::Error;
-pub type Result<I> = ::std::result::Result<I, Error>;
+#![cfg_attr(not(feature = "std"), no_std)]
+#![forbid(unsafe_code)]
 
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+pub use core::fmt::Error;
+pub type Result<I> = ::core::result::Result<I, Error>;
+
+pub mod builtins;
+#[cfg(feature = "context")]
+pub mod context;
+#[cfg(feature = "coverage")]
+pub mod coverage;
+mod error;
 pub mod helpers;
+#[cfg(feature = "hook")]
+pub mod hook;
+#[cfg(feature = "instrument")]
+pub mod instrument;
+#[cfg(feature = "integrity")]
+pub mod integrity;
+#[cfg(feature = "profile")]
+pub mod profile;
+#[cfg(feature = "sanitize")]
+pub mod sanitize;
+
+pub use error::{Context, RenderError};
@@ -0,0 +1,99 @@
+//! Template profiling, enabled with the `profile` feature
+//!
+//! In debug builds every branch of a conditional, each body and partial of a template
+//! is a region timed with `std::time::Instant` while it's rendered. Regions nested in
+//! a loop or a conditional are included in the time of their parent. Each render of
+//! a template resets its report, read it with the generated `last_render_profile`,
+//! regions not rendered aren't in the report.
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static REPORT: RefCell<BTreeMap<&'static str, BTreeMap<usize, Entry>>> =
+        RefCell::new(BTreeMap::new());
+}
+
+/// Render time of a template region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Template struct name
+    pub template: &'static str,
+    /// Region index in the template, in source order
+    pub region: usize,
+    /// Kind and location of the region
+    pub label: &'static str,
+    /// Number of renders
+    pub calls: usize,
+    /// Total render time
+    pub elapsed: Duration,
+}
+
+/// Regions in the last render of a template on the current thread
+pub fn last(template: &str) -> Vec<Entry> {
+    REPORT.with(|r| {
+        r.borrow()
+            .get(template)
+            .map(|entries| entries.values().cloned().collect())
+            .unwrap_or_default()
+    })
+}
+
+/// Clear the report of the current thread
+pub fn reset() {
+    REPORT.with(|r| r.borrow_mut().clear())
+}
+
+#[doc(hidden)]
+pub fn start(template: &'static str) {
+    REPORT.with(|r| r.borrow_mut().insert(template, BTreeMap::new()));
+}
+
+#[doc(hidden)]
+pub fn enter(template: &'static str, region: usize, label: &'static str) -> Timer {
+    Timer {
+        template,
+        region,
+        label,
+        start: Instant::now(),
+    }
+}
+
+/// End of a region not ending with its block
+#[doc(hidden)]
+pub fn leave(timer: Timer) {
+    drop(timer)
+}
+
+/// Time of a region, recorded when it's dropped
+#[doc(hidden)]
+pub struct Timer {
+    template: &'static str,
+    region: usize,
+    label: &'static str,
+    start: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        REPORT.with(|r| {
+            let mut r = r.borrow_mut();
+            let entry = r
+                .entry(self.template)
+                .or_insert_with(BTreeMap::new)
+                .entry(self.region)
+                .or_insert_with(|| Entry {
+                    template: self.template,
+                    region: self.region,
+                    label: self.label,
+                    calls: 0,
+                    elapsed: Duration::default(),
+                });
+            entry.calls += 1;
+            entry.elapsed += elapsed;
+        })
+    }
+}
@@ -0,0 +1,208 @@
+//! Sanitizer of user html, enabled with the `sanitize` feature
+//!
+//! The html is tokenized with html5ever and written again with the tags and attributes
+//! of a whitelist `Policy`, its text escaped. The other tags are removed with their
+//! attributes and their text is kept, except for the elements without user visible text,
+//! as `<script>` or `<style>`, removed with their content. The output is always balanced,
+//! the open tags are closed at the end.
+//!
+//! ```ignore
+//! #[derive(Template)]
+//! #[template(src = "<div class=\"comment\">{{{ sanitize(body, &Policy::basic()) }}}</div>")]
+//! struct CommentTemplate<'a> {
+//!     body: &'a str,
+//! }
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+
+use html5ever::{
+    tendril::StrTendril,
+    tokenizer::{
+        states::RawKind, BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer,
+        TokenizerOpts,
+    },
+};
+
+use crate::helpers::{escape_into, url_scheme};
+
+/// Whitelist of tags, attributes and url schemes
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    tags: BTreeMap<String, BTreeSet<String>>,
+    schemes: BTreeSet<String>,
+}
+
+impl Policy {
+    /// Policy without tags, only the text of the html is kept
+    pub fn text() -> Policy {
+        Policy::default()
+    }
+
+    /// Policy of rich text, formatting tags, lists and links and images of `http`, `https`
+    /// and `mailto` urls
+    pub fn basic() -> Policy {
+        let mut policy = [
+            "abbr", "b", "br", "code", "dd", "del", "dl", "dt", "em", "h1", "h2", "h3", "h4", "h5",
+            "h6", "hr", "i", "ins", "kbd", "li", "mark", "ol", "p", "pre", "s", "small", "strong",
+            "sub", "sup", "u", "ul",
+        ]
+        .iter()
+        .fold(Policy::text(), |policy, tag| policy.allow_tag(tag, &[]));
+        policy = policy
+            .allow_tag("a", &["href", "title"])
+            .allow_tag("blockquote", &["cite"])
+            .allow_tag("img", &["src", "alt", "title", "width", "height"])
+            .allow_tag("q", &["cite"]);
+        ["http", "https", "mailto"]
+            .iter()
+            .fold(policy, |policy, scheme| policy.allow_scheme(scheme))
+    }
+
+    /// Allow the tag with the attributes
+    pub fn allow_tag(mut self, tag: &str, attrs: &[&str]) -> Policy {
+        self.tags
+            .entry(tag.to_ascii_lowercase())
+            .or_default()
+            .extend(attrs.iter().map(|x| x.to_ascii_lowercase()));
+        self
+    }
+
+    /// Allow the scheme in the url attributes, the urls without scheme are allowed
+    pub fn allow_scheme(mut self, scheme: &str) -> Policy {
+        self.schemes.insert(scheme.to_ascii_lowercase());
+        self
+    }
+
+    fn attr(&self, tag: &str, name: &str, value: &str) -> bool {
+        match self.tags.get(tag) {
+            Some(attrs) if attrs.contains(name) => !URL_ATTRS.contains(&name) || self.url(value),
+            _ => false,
+        }
+    }
+
+    fn url(&self, value: &str) -> bool {
+        url_scheme(value).map_or(true, |(scheme, _)| self.schemes.contains(&scheme))
+    }
+}
+
+/// Html of `html` with the tags and attributes allowed by the `policy`
+pub fn sanitize(html: &str, policy: &Policy) -> String {
+    let sink = Sink {
+        policy,
+        out: String::with_capacity(html.len()),
+        open: vec![],
+        skip: None,
+    };
+    let mut tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
+    let mut queue = BufferQueue::new();
+    queue.push_back(StrTendril::from_slice(html));
+    let _ = tokenizer.feed(&mut queue);
+    tokenizer.end();
+
+    let Sink { mut out, open, .. } = tokenizer.sink;
+    for tag in open.iter().rev() {
+        end_tag(&mut out, tag);
+    }
+    out
+}
+
+/// Attributes with urls
+static URL_ATTRS: [&str; 4] = ["href", "src", "cite", "action"];
+
+/// Elements removed with their content
+static REMOVED: [&str; 14] = [
+    "script", "style", "template", "iframe", "object", "noscript", "textarea", "title", "xmp",
+    "noembed", "noframes", "select", "svg", "math",
+];
+
+/// Elements without end tag
+static VOID: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+struct Sink<'a> {
+    policy: &'a Policy,
+    out: String,
+    /// Open allowed tags
+    open: Vec<String>,
+    /// Removed element and its nested elements of the same name
+    skip: Option<(String, usize)>,
+}
+
+impl<'a> TokenSink for Sink<'a> {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) => {
+                let name = tag.name.to_string();
+                if let Some((skip, depth)) = &mut self.skip {
+                    if *skip == name {
+                        match tag.kind {
+                            TagKind::StartTag if !tag.self_closing => *depth += 1,
+                            TagKind::EndTag if *depth == 0 => self.skip = None,
+                            TagKind::EndTag => *depth -= 1,
+                            _ => (),
+                        }
+                    }
+                    return TokenSinkResult::Continue;
+                }
+
+                if tag.kind == TagKind::EndTag {
+                    if let Some(i) = self.open.iter().rposition(|x| *x == name) {
+                        for tag in self.open.drain(i..).rev() {
+                            end_tag(&mut self.out, &tag);
+                        }
+                    }
+                } else if REMOVED.contains(&name.as_str()) {
+                    // Self closing tags only in foreign content
+                    if !(tag.self_closing && (name == "svg" || name == "math")) {
+                        self.skip = Some((name.clone(), 0));
+                    }
+                    // Switch of the tokenizer state as the tree builder
+                    match name.as_str() {
+                        "script" => return TokenSinkResult::RawData(RawKind::ScriptData),
+                        "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+                            return TokenSinkResult::RawData(RawKind::Rawtext)
+                        }
+                        "title" | "textarea" => return TokenSinkResult::RawData(RawKind::Rcdata),
+                        _ => (),
+                    }
+                } else if name == "plaintext" {
+                    self.skip = Some((name, 0));
+                    return TokenSinkResult::Plaintext;
+                } else if self.policy.tags.contains_key(&name) {
+                    self.out.push('<');
+                    self.out.push_str(&name);
+                    for attr in &tag.attrs {
+                        let attr_name = &*attr.name.local;
+                        if self.policy.attr(&name, attr_name, &attr.value) {
+                            self.out.push(' ');
+                            self.out.push_str(attr_name);
+                            self.out.push_str("=\"");
+                            escape_into(&attr.value, &mut self.out);
+                            self.out.push('"');
+                        }
+                    }
+                    self.out.push('>');
+                    if !VOID.contains(&name.as_str()) {
+                        self.open.push(name);
+                    }
+                }
+            }
+            Token::CharacterTokens(text) if self.skip.is_none() => {
+                escape_into(&text, &mut self.out);
+            }
+            _ => (),
+        }
+
+        TokenSinkResult::Continue
+    }
+}
+
+fn end_tag(out: &mut String, tag: &str) {
+    out.push_str("</");
+    out.push_str(tag);
+    out.push('>');
+}
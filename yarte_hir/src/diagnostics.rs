@@ -0,0 +1,85 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use yarte_config::Config;
+use yarte_parser::source_map::Span;
+
+use super::Struct;
+
+/// Warnings of the expansion of a template, emitted as compiler warnings on stable
+///
+/// Proc macros can't emit warnings without nightly, so each message is the note of a
+/// deprecated constant used in the generated code.
+#[derive(Debug, Default)]
+pub struct Warnings(Vec<String>);
+
+impl Warnings {
+    pub fn new() -> Self {
+        Warnings(vec![])
+    }
+
+    /// Add a warning, a repeated message, like of partials included twice, is reported once
+    pub fn warn<M: Into<String>>(&mut self, message: M) {
+        let message = message.into();
+        if !self.0.contains(&message) {
+            self.0.push(message);
+        }
+    }
+
+    /// Add a warning at the location of `span` in its template
+    ///
+    /// The source map of the template must be alive
+    pub fn warn_at(&mut self, c: &Config, span: Span, message: &str) {
+        self.warn(format!("{} at {}", message, location(c, span)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn messages(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Tokens of the deprecated constants, in a scope without items
+    pub fn emit(&self, s: &Struct) -> TokenStream {
+        if self.0.is_empty() {
+            return TokenStream::new();
+        }
+
+        // Spanned at the struct, lints aren't reported in macro expansions
+        let ident = syn::Ident::new("WARNING", s.ident.span());
+        let messages = &self.0;
+        quote! {
+            const _: () = {
+                #({
+                    #[deprecated(note = #messages)]
+                    const #ident: () = ();
+                    #ident
+                };)*
+            };
+        }
+    }
+}
+
+impl Extend<String> for Warnings {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        for message in iter {
+            self.warn(message);
+        }
+    }
+}
+
+/// Path, relative to the crate, line and column of the start of `span`
+pub(super) fn location(c: &Config, span: Span) -> String {
+    let mut prefix = c.get_dir().clone();
+    prefix.pop();
+    let path = span.file_path();
+    let start = span.start();
+    format!(
+        "{}:{}:{}",
+        path.strip_prefix(&prefix).unwrap_or(&path).display(),
+        start.line,
+        start.column + 1
+    )
+}
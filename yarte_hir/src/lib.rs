@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, mem, path::PathBuf, str};
+use std::{collections::BTreeMap, mem, path::PathBuf, ptr, str};
 
 use quote::quote;
 use syn::{
@@ -11,17 +11,29 @@ use v_eval::{eval, Value};
 use v_htmlescape::escape;
 
 use yarte_config::Config;
-use yarte_helpers::helpers::ErrorMessage;
-use yarte_parser::{Helper, Node, Partial, SExpr, SNode, SVExpr, Ws};
+use yarte_helpers::helpers::{json_escape, ErrorMessage};
+use yarte_parser::{
+    find_inline, html_comment, source_map, Arg, BlockParams, Helper, Modifier, Node, Partial,
+    SArgs, SExpr, SNode, SStr, Ws, PARTIAL_BLOCK,
+};
 
 #[macro_use]
 mod macros;
+mod diagnostics;
 mod scope;
 mod validator;
+mod visit_branches;
+mod visit_builtin;
+mod visit_const;
+mod visit_consume;
+mod visit_coverage;
 mod visit_derive;
 mod visit_each;
+mod visit_modifier;
+mod visit_once;
 mod visit_partial;
 mod visits;
+mod whitespace;
 
 /// High level intermediate representation after lowering Ast
 #[derive(Debug, Clone, PartialEq)]
@@ -48,10 +60,80 @@ pub struct Each {
     pub expr: syn::Expr,
 }
 
+/// Module of yarte at the root of the expression, as `builtins` of
+/// `::yarte::builtins::Meta::new().title(..)`
+///
+/// The built-in helpers and the statements of the regions are lowered with their absolute
+/// paths, they are recognized by them
+pub fn yarte_module(e: &syn::Expr) -> Option<&syn::Ident> {
+    let path = match e {
+        syn::Expr::Call(e) => return yarte_module(&e.func),
+        syn::Expr::MethodCall(e) => return yarte_module(&e.receiver),
+        syn::Expr::Path(ExprPath {
+            path, qself: None, ..
+        }) => path,
+        syn::Expr::Struct(e) => &e.path,
+        _ => return None,
+    };
+    let mut segments = path.segments.iter();
+    match (path.leading_colon, segments.next(), segments.next()) {
+        (Some(_), Some(root), Some(module))
+            if root.ident == "yarte" && segments.next().is_some() =>
+        {
+            Some(&module.ident)
+        }
+        _ => None,
+    }
+}
+
+/// Expression of a built-in helper, `::yarte::builtins::..`
+pub fn is_builtin(e: &syn::Expr) -> bool {
+    yarte_module(e).map_or(false, |x| x == "builtins")
+}
+
+/// Statement of a coverage, profile or context region
+pub fn is_region(local: &syn::Local) -> bool {
+    local
+        .init
+        .as_ref()
+        .and_then(|(_, e)| yarte_module(e))
+        .map_or(false, |x| {
+            x == "coverage" || x == "profile" || x == "context"
+        })
+}
+
+/// Map the nodes of `hir` with `f`, the blocks are mapped by the nodes of their bodies
+pub fn map_hir<F: FnMut(HIR) -> HIR>(hir: Vec<HIR>, f: &mut F) -> Vec<HIR> {
+    hir.into_iter()
+        .map(|x| match x {
+            HIR::Each(mut each) => {
+                each.body = map_hir(mem::take(&mut each.body), f);
+                HIR::Each(each)
+            }
+            HIR::IfElse(mut if_else) => {
+                if_else.ifs.1 = map_hir(mem::take(&mut if_else.ifs.1), f);
+                for (_, body) in &mut if_else.if_else {
+                    *body = map_hir(mem::take(body), f);
+                }
+                if_else.els = if_else.els.map(|els| map_hir(els, f));
+                HIR::IfElse(if_else)
+            }
+            x => f(x),
+        })
+        .collect()
+}
+
 pub use self::visit_derive::Struct;
-use self::{scope::Scope, visit_each::find_loop_var, visit_partial::visit_partial};
+use self::{
+    scope::Scope, visit_each::find_loop_var, visit_partial::visit_partial, whitespace::Whitespace,
+};
 
-pub use self::visit_derive::{visit_derive, Mode, Print};
+pub use self::{
+    diagnostics::Warnings,
+    visit_branches::duplicate_branches,
+    visit_const::constants,
+    visit_derive::{visit_derive, Alternate, Escape, Mode, Print, Representation},
+};
 
 pub fn generate(c: &Config, s: &Struct, ctx: Context) -> Result<Vec<HIR>, Vec<ErrorMessage>> {
     Generator::new(c, s, ctx).build()
@@ -92,10 +174,28 @@ struct Generator<'a> {
     ctx: Context<'a>,
     /// current file path
     on_path: PathBuf,
-    /// whitespace buffer adapted from [`askama`](https://github.com/djc/askama)
-    next_ws: Option<&'a str>,
-    /// whitespace flag adapted from [`askama`](https://github.com/djc/askama)
-    skip_ws: bool,
+    /// pending whitespace
+    ws: Whitespace<'a>,
+    /// `#once` expressions by scope, with the ident of their value
+    once: Vec<Vec<(String, syn::Ident)>>,
+    /// current nesting depth
+    depth: usize,
+    /// visited nodes and expressions
+    count: (usize, usize),
+    /// some compile-time limit exceeded
+    exceeded: bool,
+    /// coverage and profile region labels
+    regions: Vec<String>,
+    /// fields consumed by an each of a `once` template
+    consumed: Vec<(String, source_map::Span)>,
+    /// blocks of the partial blocks being rendered, as partials of `@partial-block`
+    partial_blocks: Vec<(PathBuf, Ws, &'a [SNode<'a>])>,
+    /// nodes of the partials being inlined, for the recursive partials
+    partials: Vec<&'a [SNode<'a>]>,
+    /// span of the expression being lowered
+    span: source_map::Span,
+    /// include sites of the partials being inlined, `path:line:column`, keys of `@id`
+    sites: Vec<String>,
 }
 
 impl<'a> Generator<'a> {
@@ -105,13 +205,22 @@ impl<'a> Generator<'a> {
             s,
             ctx,
             buf_w: vec![],
-            next_ws: None,
             on: vec![],
             on_path: s.path.clone(),
             partial: None,
             scp: Scope::new(parse_str("self").unwrap(), 0),
-            skip_ws: false,
+            ws: Whitespace::default(),
+            once: vec![],
             errors: vec![],
+            depth: 0,
+            count: (0, 0),
+            exceeded: false,
+            regions: vec![],
+            consumed: vec![],
+            partial_blocks: vec![],
+            partials: vec![],
+            span: source_map::Span { lo: 0, hi: 0 },
+            sites: vec![],
         }
     }
 
@@ -119,15 +228,24 @@ impl<'a> Generator<'a> {
         let mut buf = vec![];
 
         let nodes: &[SNode] = self.ctx.get(&self.on_path).unwrap();
+        // The template is a call of itself as partial
+        self.partials.push(nodes);
 
+        self.once_scope();
         self.handle(nodes, &mut buf);
+        if self.exceeded {
+            return Err(self.errors);
+        }
         self.write_buf_writable(&mut buf);
+        self.once_locals(&mut buf);
+        self.regions(&mut buf);
+        self.check_consumed(&buf);
         debug_assert_eq!(self.scp.len(), 1);
         debug_assert_eq!(self.scp.root(), &parse_str::<syn::Expr>("self").unwrap());
         debug_assert!(self.on.is_empty());
         debug_assert!(self.buf_w.is_empty());
         debug_assert_eq!(self.on_path, self.s.path);
-        debug_assert_eq!(self.next_ws, None);
+        debug_assert!(self.ws.is_empty());
         // Extreme case
         if buf.is_empty() {
             buf.push(HIR::Lit("".into()));
@@ -145,20 +263,24 @@ impl<'a> Generator<'a> {
     }
 
     fn handle(&mut self, nodes: &'a [SNode], buf: &mut Vec<HIR>) {
+        self.depth += 1;
         for n in nodes {
+            if self.check_limits(n) {
+                break;
+            }
+
             match n.t() {
                 Node::Local(expr) => {
                     self.skip_ws();
                     self.write_buf_writable(buf);
-                    let mut expr = *expr.t().clone();
+                    self.span = *expr.span();
+                    let mut expr = (**expr.t()).clone();
                     self.visit_local_mut(&mut expr);
                     buf.push(HIR::Local(Box::new(expr)));
                 }
                 Node::Safe(ws, sexpr) => {
-                    let mut expr = *sexpr.t().clone();
-
                     self.handle_ws(*ws);
-                    self.visit_expr_mut(&mut expr);
+                    let mut expr = self.visit_sexpr(sexpr);
 
                     if self.read_attributes(&mut expr).is_none()
                         && self.const_eval(&expr, true).is_none()
@@ -168,119 +290,331 @@ impl<'a> Generator<'a> {
                     }
                 }
                 Node::Expr(ws, sexpr) => {
-                    let mut expr = *sexpr.t().clone();
-
+                    self.span = *sexpr.span();
+                    if let Some((path, args)) = self.builtin_partial(sexpr.t()) {
+                        let nodes = self.ctx.get(&path).unwrap();
+                        self.inline_partial(buf, *ws, (path, (false, false), nodes), &args, vec![]);
+                        continue;
+                    }
+                    if self.paginate_partial(buf, *ws, sexpr) {
+                        continue;
+                    }
                     self.handle_ws(*ws);
-                    self.visit_expr_mut(&mut expr);
+                    let mut expr = self.visit_sexpr(sexpr);
 
                     if self.const_eval(&expr, false).is_none() {
                         validator::expression(sexpr, &mut self.errors);
                         self.buf_w.push(Writable::Expr(Box::new(expr), false));
                     }
                 }
-                Node::Lit(l, lit, r) => self.visit_lit(l, lit.t(), r),
+                Node::Lit(l, lit, r) => {
+                    self.check_links(lit);
+                    self.visit_lit(l, lit.t(), r)
+                }
                 Node::Helper(h) => self.visit_helper(buf, &h),
-                Node::Partial(Partial(ws, path, expr)) => {
-                    self.visit_partial(buf, *ws, path.t(), expr)
+                Node::Partial(Partial(ws, path, expr)) => self.visit_partial(buf, *ws, path, expr),
+                Node::PartialBlock(ws, path, expr, block) => {
+                    self.visit_partial_block(buf, *ws, path, expr, block)
                 }
+                // Rendered by its calls
+                Node::Inline(ws, ..) => self.handle_ws(((ws.0).0, (ws.1).1)),
                 // TODO
                 Node::Comment(_) => self.skip_ws(),
+                Node::Const(c) => {
+                    self.skip_ws();
+                    self.visit_const(c, *n.span());
+                }
+                Node::Keep(text) => {
+                    if self.s.mode.is_text() && !self.s.keep_comments {
+                        self.errors.push(ErrorMessage {
+                            message: "Kept comments are html only, \
+                                      set `keep_comments = true` to write them in text mode"
+                                .into(),
+                            span: *n.span(),
+                        });
+                        continue;
+                    }
+
+                    // Expression, html minifier would reject it in a literal
+                    let comment = html_comment(text);
+                    self.handle_ws((false, false));
+                    self.buf_w.push(Writable::Expr(
+                        Box::new(syn::parse2(quote!(#comment)).unwrap()),
+                        true,
+                    ));
+                }
                 Node::Raw(ws, l, v, r) => {
+                    self.check_links(v);
                     self.handle_ws(ws.0);
                     self.visit_lit(l, v.t(), r);
                     self.handle_ws(ws.1);
                 }
             }
         }
+        self.depth -= 1;
     }
 
-    fn visit_lit(&mut self, lws: &'a str, lit: &'a str, rws: &'a str) {
-        debug_assert!(self.next_ws.is_none());
-        if !lws.is_empty() {
-            if self.skip_ws {
-                self.skip_ws = false;
-            } else if lit.is_empty() {
-                debug_assert!(rws.is_empty());
-                self.next_ws = Some(lws);
-            } else {
-                self.buf_w.push(Writable::Lit(lws));
+    /// Check compile-time limits, fails fast when some of them is exceeded
+    fn check_limits(&mut self, n: &SNode) -> bool {
+        if self.exceeded {
+            return true;
+        }
+
+        self.count.0 += 1;
+        if let Node::Expr(..) | Node::Safe(..) = n.t() {
+            self.count.1 += 1;
+        }
+
+        let limits = &self.c.limits;
+        let message = if limits.depth < self.depth {
+            format!(
+                "Exceeded max nesting depth of helpers and partials ({}), \
+                 set `depth` of `[limits]` in yarte.toml",
+                limits.depth
+            )
+        } else if limits.nodes < self.count.0 {
+            format!(
+                "Exceeded max number of nodes after partial inlining ({}), \
+                 set `nodes` of `[limits]` in yarte.toml",
+                limits.nodes
+            )
+        } else if limits.expressions < self.count.1 {
+            format!(
+                "Exceeded max number of expressions after partial inlining ({}), \
+                 set `expressions` of `[limits]` in yarte.toml",
+                limits.expressions
+            )
+        } else {
+            return false;
+        };
+
+        self.errors.push(ErrorMessage {
+            message,
+            span: *n.span(),
+        });
+        self.exceeded = true;
+
+        true
+    }
+
+    fn check_links(&mut self, lit: &SStr) {
+        if let Some(routes) = &self.c.routes {
+            if !self.s.mode.is_text() {
+                validator::links(lit, routes, &mut self.errors);
             }
         }
+    }
 
-        if !lit.is_empty() {
-            self.buf_w.push(Writable::Lit(lit));
+    fn visit_lit(&mut self, lws: &'a str, lit: &'a str, rws: &'a str) {
+        if let Some(lws) = self.ws.lit(lws, lit, rws) {
+            self.buf_w.push(Writable::Lit(lws));
         }
 
-        if !rws.is_empty() {
-            self.next_ws = Some(rws);
+        if !lit.is_empty() {
+            self.buf_w.push(Writable::Lit(lit));
         }
     }
 
     fn visit_helper(&mut self, buf: &mut Vec<HIR>, h: &'a Helper<'a>) {
         use yarte_parser::Helper::*;
         match h {
-            Each(ws, e, b) => self.visit_each(buf, *ws, e, b),
-            If(ifs, elsif, els) => self.visit_if(buf, ifs, elsif, els),
-            With(ws, e, b) => self.visit_with(buf, *ws, e, b),
-            Unless(ws, e, b) => self.visit_unless(buf, *ws, e, b),
-            Defined(..) => unimplemented!(),
+            Each(ws, e, m, p, b, els) => self.visit_each(buf, *ws, e, m, p, b, els),
+            If(ifs, elsif, els) => self.visit_if(buf, ifs, elsif, els, false),
+            With(ws, e, p, b) => self.visit_with(buf, *ws, e, p, b),
+            Portal(ws, e, p, b) => self.visit_portal(buf, *ws, e, p, b),
+            Unless(ifs, elsif, els) => self.visit_if(buf, ifs, elsif, els, true),
+            Defined(ws, name, args, p, b) => self.visit_defined(buf, *ws, name, args, p, b),
         }
     }
 
-    fn visit_unless(
+    /// Block of a custom helper, a loop over the blocks of its arguments for `[helpers]`
+    /// or a condition on the block of its arguments for `[conditions]`
+    fn visit_defined(
         &mut self,
         buf: &mut Vec<HIR>,
         ws: (Ws, Ws),
-        scond: &SExpr,
-        nodes: &'a [SNode],
+        name: &str,
+        sargs: &SExpr,
+        params: &BlockParams,
+        nodes: &'a [SNode<'a>],
     ) {
-        let mut cond = *scond.t().clone();
+        let (path, condition) = match (self.c.condition(name), self.c.helper(name)) {
+            (Some(path), _) => (path, true),
+            (None, Some(path)) => (path, false),
+            (None, None) => {
+                self.errors.push(ErrorMessage {
+                    message: format!("Unknown helper `{}`", name),
+                    span: *sargs.span(),
+                });
+                return;
+            }
+        };
+        let path = match parse_str::<syn::Path>(path) {
+            Ok(path) => path,
+            Err(_) => {
+                let table = if condition { "conditions" } else { "helpers" };
+                self.errors.push(ErrorMessage {
+                    message: format!("Invalid path of helper `{}` in [{}]", name, table),
+                    span: *sargs.span(),
+                });
+                return;
+            }
+        };
+        validator::scope(sargs, &mut self.errors);
+        if condition {
+            self.visit_condition(buf, ws, path, sargs, params, nodes);
+            return;
+        }
+
+        let (loop_var, last) = find_loop_var(self.c, self.ctx, self.on_path.clone(), nodes);
         self.handle_ws(ws.0);
-        self.visit_expr_mut(&mut cond);
+        self.write_buf_writable(buf);
+        let args = self.visit_sexpr(sargs);
+        let args = syn::parse2::<syn::Expr>(
+            quote!((<#path as ::yarte::CustomBlockHelper<_>>::blocks(&(#args)))),
+        )
+        .unwrap();
+
+        self.each_loop(
+            buf,
+            ws,
+            args,
+            *sargs.span(),
+            &[],
+            params,
+            nodes,
+            None,
+            (loop_var, last),
+        );
+    }
 
-        if let Some(val) = self.eval_bool(&cond) {
-            if !val {
-                self.scp.push_scope(vec![]);
-                self.handle(nodes, buf);
-                self.scp.pop();
-                self.handle_ws(ws.1);
+    /// Block of a custom helper of `[conditions]`, rendered when its arguments have a block,
+    /// with the block as its context
+    fn visit_condition(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        path: syn::Path,
+        sargs: &SExpr,
+        params: &BlockParams,
+        nodes: &'a [SNode<'a>],
+    ) {
+        self.handle_ws(ws.0);
+        self.write_buf_writable(buf);
+        let args = self.visit_sexpr(sargs);
+
+        self.on.push(On::With(self.scp.len()));
+        self.scp.push_scope(vec![]);
+        let v = self.scp.push_ident("__block_");
+        let cond = syn::parse2::<syn::Expr>(quote!(
+            let ::core::option::Option::Some(#v) =
+                <#path as ::yarte::CustomConditionHelper<_>>::block(&(#args))
+        ))
+        .unwrap();
+
+        let mut body = self.region("if", *sargs.span());
+        match (params.item.as_slice(), &params.index) {
+            ([], None) => (),
+            ([param], None) => {
+                let param = self.scp.push_ident(&param.to_string());
+                body.push(visit_coverage::local(quote!(let #param = #v;)));
             }
-        } else {
-            validator::unless(scond, &mut self.errors);
+            _ => self.errors.push(ErrorMessage {
+                message: "a condition helper takes one block parameter".to_string(),
+                span: *sargs.span(),
+            }),
+        }
+        self.handle(nodes, &mut body);
+        self.handle_ws(ws.1);
+        self.write_buf_writable(&mut body);
+        self.region_leave(&mut body);
 
-            self.write_buf_writable(buf);
-            self.scp.push_scope(vec![]);
-            let mut buf_t = vec![];
-            self.handle(nodes, &mut buf_t);
-            self.scp.pop();
+        self.on.pop();
+        self.scp.pop();
 
-            self.handle_ws(ws.1);
-            self.write_buf_writable(&mut buf_t);
-            let cond = syn::Expr::Unary(syn::ExprUnary {
-                expr: Box::new(syn::Expr::Paren(syn::ExprParen {
-                    attrs: vec![],
-                    paren_token: syn::token::Paren(Span::call_site()),
-                    expr: Box::new(cond),
-                })),
-                attrs: vec![],
-                op: syn::UnOp::Not(Token![!](Span::call_site())),
+        buf.push(HIR::IfElse(Box::new(IfElse {
+            ifs: (cond, body),
+            if_else: vec![],
+            els: None,
+        })));
+    }
+
+    /// Block of `portal`, rendered by the wasm client in the element of its selector
+    ///
+    /// The server writes it in a `<template data-yarte-portal>` where it's written, the client
+    /// moves its content, keeping its nodes in the template
+    fn visit_portal(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        sargs: &SExpr,
+        params: &BlockParams,
+        nodes: &'a [SNode<'a>],
+    ) {
+        let message = match &**sargs.t() {
+            _ if self.s.mode != Mode::WASM && self.s.mode != Mode::WASMServer => {
+                Some("`portal` is a helper of wasm applications")
+            }
+            _ if !params.item.is_empty() || params.index.is_some() => {
+                Some("`portal` takes no block parameters")
+            }
+            _ if self.on.iter().any(|x| match x {
+                On::Each(_) => true,
+                On::With(_) => false,
+            }) =>
+            {
+                Some("`portal` can't be in an `each` block")
+            }
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(selector),
+                ..
+            }) => {
+                self.handle_ws(ws.0);
+                self.buf_w.push(Writable::LitP(format!(
+                    "<template data-yarte-portal=\"{}\">",
+                    escape(&selector.value())
+                )));
+                self.handle(nodes, buf);
+                self.buf_w.push(Writable::Lit("</template>"));
+                self.handle_ws(ws.1);
+                None
+            }
+            _ => Some("`portal` takes the selector of its element, as `{{#portal \"#modal\" }}`"),
+        };
+        if let Some(message) = message {
+            self.errors.push(ErrorMessage {
+                message: message.to_string(),
+                span: *sargs.span(),
             });
-            buf.push(HIR::IfElse(Box::new(IfElse {
-                ifs: (cond, buf_t),
-                if_else: vec![],
-                els: None,
-            })));
         }
     }
 
-    fn visit_with(&mut self, buf: &mut Vec<HIR>, ws: (Ws, Ws), args: &SExpr, nodes: &'a [SNode]) {
-        validator::scope(args, &mut self.errors);
+    fn visit_with(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        sargs: &SExpr,
+        params: &BlockParams,
+        nodes: &'a [SNode],
+    ) {
+        validator::scope(sargs, &mut self.errors);
 
         self.handle_ws(ws.0);
-        let mut args = *args.t().clone();
-        self.visit_expr_mut(&mut args);
+        let args = self.visit_sexpr(sargs);
         self.on.push(On::With(self.scp.len()));
-        self.scp.push_scope(vec![args]);
+        self.scp.push_scope(vec![args.clone()]);
+        match (params.item.as_slice(), &params.index) {
+            ([], None) => (),
+            ([param], None) => {
+                self.write_buf_writable(buf);
+                let param = self.scp.push_ident(&param.to_string());
+                buf.push(visit_coverage::local(quote!(let #param = &(#args);)));
+            }
+            _ => self.errors.push(ErrorMessage {
+                message: "`with` takes one block parameter".to_string(),
+                span: *sargs.span(),
+            }),
+        }
 
         self.handle(nodes, buf);
 
@@ -294,52 +628,174 @@ impl<'a> Generator<'a> {
         buf: &mut Vec<HIR>,
         ws: (Ws, Ws),
         sargs: &'a SExpr,
+        modifiers: &[Modifier],
+        params: &BlockParams,
         nodes: &'a [SNode<'a>],
+        els: &'a Option<(Ws, Option<syn::Ident>, Vec<SNode<'a>>)>,
     ) {
-        let loop_var = find_loop_var(self.c, self.ctx, self.on_path.clone(), nodes);
-        let mut args = *sargs.t().clone();
-        self.visit_expr_mut(&mut args);
+        let (loop_var, last) = find_loop_var(self.c, self.ctx, self.on_path.clone(), nodes);
+        let args = self.visit_sexpr(sargs);
 
-        if let Some(args) = self.eval_iter(&args) {
-            self.const_iter(buf, ws, args, nodes, loop_var);
-            return;
+        if modifiers.is_empty() && params.is_empty() && els.is_none() {
+            if let Some(args) = self.eval_iter(&args) {
+                self.const_iter(buf, ws, args, nodes, loop_var, last);
+                return;
+            }
         }
 
         validator::each(sargs, &mut self.errors);
+        validator::modifiers(sargs, modifiers, &mut self.errors);
 
         self.handle_ws(ws.0);
         self.write_buf_writable(buf);
 
+        let args = if let syn::Expr::Range(..) = args {
+            args
+        } else if let Some(args) = self.consume(&args, *sargs.span()) {
+            args
+        } else {
+            syn::parse2::<syn::Expr>(quote!(((&(#args)).into_iter()))).unwrap()
+        };
+
+        self.each_loop(
+            buf,
+            ws,
+            args,
+            *sargs.span(),
+            modifiers,
+            params,
+            nodes,
+            els.as_ref(),
+            (loop_var, last),
+        );
+    }
+
+    /// Loop of an each block over the iterator `args`, with its arms
+    #[allow(clippy::too_many_arguments)]
+    fn each_loop(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        args: syn::Expr,
+        span: source_map::Span,
+        modifiers: &[Modifier],
+        params: &BlockParams,
+        nodes: &'a [SNode<'a>],
+        els: Option<&'a (Ws, Option<syn::Ident>, Vec<SNode<'a>>)>,
+        (loop_var, last): (bool, bool),
+    ) {
         let id = self.scp.len();
+        // Items until the first error, kept for the arm after the loop
+        let try_each = match els {
+            Some((_, Some(_), _)) => Some(self.scp.push_ident("__try_")),
+            _ => None,
+        };
+        let args = match &try_each {
+            Some(e) => {
+                buf.push(visit_coverage::local(
+                    quote!(let #e = ::yarte::builtins::TryEach::new();),
+                ));
+                syn::parse2::<syn::Expr>(quote!((#e.iter(#args)))).unwrap()
+            }
+            None => args,
+        };
+        let args = self.visit_modifiers(args, modifiers, id);
+        // Rendered items, after the modifiers, the empty arm is rendered without any
+        let empty_each = match els {
+            Some((_, None, _)) => Some(self.scp.push_ident("__empty_")),
+            _ => None,
+        };
+        let args = match &empty_each {
+            Some(e) => {
+                buf.push(visit_coverage::local(
+                    quote!(let #e = ::yarte::builtins::EmptyEach::new();),
+                ));
+                syn::parse2::<syn::Expr>(quote!((#e.iter(#args)))).unwrap()
+            }
+            None => args,
+        };
+
         self.scp.push_scope(vec![]);
         let v = self.scp.push_ident("__key_");
-        let (args, expr) = if loop_var {
+        let (args, expr, i) = if last {
             let i = self.scp.push_ident("__index_");
-            let args = if let syn::Expr::Range(..) = args {
-                syn::parse2::<syn::Expr>(quote!(((#args).enumerate()))).unwrap()
-            } else {
-                syn::parse2::<syn::Expr>(quote!(((&(#args)).into_iter().enumerate()))).unwrap()
-            };
-            (args, syn::parse2::<syn::Expr>(quote!((#i, #v))).unwrap())
+            let l = self.scp.push_ident("__last_");
+            let args = syn::parse2::<syn::Expr>(
+                quote!((::yarte::builtins::with_last(::core::iter::Iterator::enumerate(#args)))),
+            )
+            .unwrap();
+            (
+                args,
+                syn::parse2::<syn::Expr>(quote!((#l, (#i, #v)))).unwrap(),
+                Some(i),
+            )
+        } else if loop_var || params.index.is_some() {
+            let i = self.scp.push_ident("__index_");
+            let args = syn::parse2::<syn::Expr>(quote!((::core::iter::Iterator::enumerate(#args))))
+                .unwrap();
+            (
+                args,
+                syn::parse2::<syn::Expr>(quote!((#i, #v))).unwrap(),
+                Some(i),
+            )
         } else {
-            let args = if let syn::Expr::Range(..) = args {
-                args
-            } else {
-                syn::parse2::<syn::Expr>(quote!(((&(#args)).into_iter()))).unwrap()
-            };
-            (args, syn::parse2::<syn::Expr>(quote!(#v)).unwrap())
+            (args, syn::parse2::<syn::Expr>(quote!(#v)).unwrap(), None)
         };
         self.on.push(On::Each(id));
 
-        let mut body = Vec::new();
+        let mut body = self.region("each", span);
+        if let (Some(index), Some(i)) = (&params.index, i) {
+            let index = self.scp.push_ident(&index.to_string());
+            body.push(visit_coverage::local(quote!(let #index = #i;)));
+        }
+        if !params.is_empty() {
+            let params: Vec<_> = params
+                .item
+                .iter()
+                .map(|x| self.scp.push_ident(&x.to_string()))
+                .collect();
+            body.push(if let [param] = params.as_slice() {
+                visit_coverage::local(quote!(let #param = #v;))
+            } else {
+                visit_coverage::local(quote!(let (#(#params),*) = #v;))
+            });
+        }
+        self.once_scope();
         self.handle(nodes, &mut body);
-        self.handle_ws(ws.1);
+        self.handle_ws(els.map_or(ws.1, |(ws, _, _)| *ws));
         self.write_buf_writable(&mut body);
+        self.once_locals(&mut body);
+        self.region_leave(&mut body);
 
         self.on.pop();
         self.scp.pop();
 
-        buf.push(HIR::Each(Box::new(Each { args, body, expr })))
+        buf.push(HIR::Each(Box::new(Each { args, body, expr })));
+
+        if let Some((_, err, els)) = els {
+            self.scp.push_scope(vec![]);
+            let cond = match (err, try_each, empty_each) {
+                (Some(err), Some(e), _) => {
+                    let err = self.scp.push_ident(&err.to_string());
+                    quote!(let ::core::option::Option::Some(#err) = #e.take())
+                }
+                (None, _, Some(e)) => quote!(#e.is_empty()),
+                _ => unreachable!(),
+            };
+            let cond = syn::parse2::<syn::Expr>(cond).unwrap();
+            let mut body = self.region("else", span);
+            self.handle(els, &mut body);
+            self.handle_ws(ws.1);
+            self.write_buf_writable(&mut body);
+            self.region_leave(&mut body);
+            self.scp.pop();
+
+            buf.push(HIR::IfElse(Box::new(IfElse {
+                ifs: (cond, body),
+                if_else: vec![],
+                els: None,
+            })));
+        }
     }
 
     fn visit_if(
@@ -348,20 +804,27 @@ impl<'a> Generator<'a> {
         (pws, scond, block): &'a ((Ws, Ws), SExpr, Vec<SNode>),
         ifs: &'a [(Ws, SExpr, Vec<SNode>)],
         els: &'a Option<(Ws, Vec<SNode>)>,
+        unless: bool,
     ) {
         self.scp.push_scope(vec![]);
-        let mut cond = *scond.t().clone();
-        self.visit_expr_mut(&mut cond);
+        let cond = self.visit_sexpr(scond);
         self.handle_ws(pws.0);
         let (mut last, mut o_ifs) = if let Some(val) = self.eval_bool(&cond) {
+            let val = val != unless;
             if val {
                 self.handle(block, buf);
             }
             (val, None)
+        } else if unless {
+            validator::unless(scond, &mut self.errors);
+            self.write_buf_writable(buf);
+            let mut body = self.region("unless", *scond.span());
+            self.handle(block, &mut body);
+            (false, Some((not(cond), body)))
         } else {
             validator::ifs(scond, &mut self.errors);
             self.write_buf_writable(buf);
-            let mut body = Vec::new();
+            let mut body = self.region("if", *scond.span());
             self.handle(block, &mut body);
             (false, Some((cond, body)))
         };
@@ -383,13 +846,12 @@ impl<'a> Generator<'a> {
             }
 
             self.scp.push_scope(vec![]);
-            let mut cond = *scond.t().clone();
-            self.visit_expr_mut(&mut cond);
+            let cond = self.visit_sexpr(scond);
 
             if let Some(val) = self.eval_bool(&cond) {
                 if val {
                     if o_ifs.is_some() {
-                        let mut body = Vec::new();
+                        let mut body = self.region("else if", *scond.span());
                         self.handle(block, &mut body);
                         o_els = Some(body);
                     } else {
@@ -400,7 +862,7 @@ impl<'a> Generator<'a> {
             } else {
                 validator::ifs(scond, &mut self.errors);
 
-                let mut body = Vec::new();
+                let mut body = self.region("else if", *scond.span());
                 self.handle(block, &mut body);
                 if o_ifs.is_some() {
                     if_else.push((cond, body));
@@ -426,7 +888,7 @@ impl<'a> Generator<'a> {
             }
             if o_ifs.is_some() {
                 self.scp.push_scope(vec![]);
-                let mut body = Vec::new();
+                let mut body = self.region("else", *scond.span());
                 self.handle(els, &mut body);
                 self.scp.pop();
                 Some(body)
@@ -447,25 +909,136 @@ impl<'a> Generator<'a> {
             } else {
                 self.write_buf_writable(&mut ifs.1);
             }
+            self.region_leave(&mut ifs.1);
+            for (_, body) in &mut if_else {
+                self.region_leave(body);
+            }
+            if let Some(body) = els.as_mut() {
+                self.region_leave(body);
+            }
             buf.push(HIR::IfElse(Box::new(IfElse { ifs, if_else, els })))
         }
     }
 
-    fn visit_partial(&mut self, buf: &mut Vec<HIR>, ws: Ws, path: &str, exprs: &'a SVExpr) {
-        let p = self.c.resolve_partial(&self.on_path, path);
-        let nodes = self.ctx.get(&p).unwrap();
+    fn visit_partial(&mut self, buf: &mut Vec<HIR>, ws: Ws, path: &SStr, args: &SArgs) {
+        if *path.t() == PARTIAL_BLOCK {
+            return self.visit_block_call(buf, ws, path, args);
+        }
+
+        let source = resolve_partial(self.c, self.ctx, &self.on_path, path.t());
+        self.partial_call(buf, ws, path, args, source);
+    }
+
+    /// Partial block `{{#> partial}}..{{/partial}}`, the partial renders the block with
+    /// `{{> @partial-block}}`, without the partial the block is rendered in its place
+    fn visit_partial_block(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        path: &SStr,
+        args: &SArgs,
+        nodes: &'a [SNode<'a>],
+    ) {
+        match find_partial(self.c, self.ctx, &self.on_path, path.t()) {
+            Some(source) => {
+                let block = (self.on_path.clone(), ((ws.0).1, (ws.1).0), nodes);
+                self.partial_blocks.push(block);
+                self.partial_call(buf, ((ws.0).0, (ws.1).1), path, args, source);
+                self.partial_blocks.pop();
+            }
+            None => {
+                self.handle_ws(ws.0);
+                self.handle(nodes, buf);
+                self.handle_ws(ws.1);
+            }
+        }
+    }
+
+    /// Block of the partial block being rendered, `{{> @partial-block}}`
+    fn visit_block_call(&mut self, buf: &mut Vec<HIR>, ws: Ws, path: &SStr, args: &SArgs) {
+        // In the block, `@partial-block` is the block of the outer partial block
+        let block = match self.partial_blocks.pop() {
+            Some(block) => block,
+            None => {
+                self.errors.push(ErrorMessage {
+                    message: format!("`{}` out of the partial of a partial block", PARTIAL_BLOCK),
+                    span: *path.span(),
+                });
+                return;
+            }
+        };
+
+        self.partial_call(buf, ws, path, args, block.clone());
+        self.partial_blocks.push(block);
+    }
+
+    fn partial_call(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: Ws,
+        path: &SStr,
+        args: &SArgs,
+        source: (PathBuf, Ws, &'a [SNode<'a>]),
+    ) {
+        // A partial is inlined in itself up to `recursion` times, the next call renders nothing
+        let calls = self
+            .partials
+            .iter()
+            .filter(|x| ptr::eq(**x, source.2))
+            .count();
+        if calls > self.c.limits.recursion {
+            if self.c.limits.recursion == 0 {
+                self.errors.push(ErrorMessage {
+                    message: format!(
+                        "Recursive partial `{}`, set `recursion` of `[limits]` in yarte.toml",
+                        path.t()
+                    ),
+                    span: *path.span(),
+                });
+            }
+            self.handle_ws(ws);
+            return;
+        }
 
+        self.partials.push(source.2);
+        let id = self.regions.len();
+        let region = self.region(&format!("partial `{}`", path.t()), *path.span());
+        self.span = *args.span();
+        let args: Vec<Arg> = args.t().iter().map(|arg| arg.t().clone()).collect();
+        let start = path.span().start();
+        let site = format!("{}:{}:{}", self.rel_path(), start.line, start.column);
+        self.sites.push(site);
+        self.inline_partial(buf, ws, source, &args, region);
+        self.sites.pop();
+        self.region_end(buf, id);
+        self.partials.pop();
+    }
+
+    fn inline_partial(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: Ws,
+        (p, inner, nodes): (PathBuf, Ws, &'a [SNode<'a>]),
+        args: &[Arg],
+        region: Vec<HIR>,
+    ) {
         let p = mem::replace(&mut self.on_path, p);
 
         self.flush_ws(ws);
+        if !region.is_empty() {
+            self.write_buf_writable(buf);
+            buf.extend(region);
+        }
+        if inner.0 {
+            self.prepare_ws((false, true));
+        }
 
-        let exprs = exprs.t();
-        if exprs.is_empty() {
+        if args.is_empty() {
             self.scp.push_scope(vec![]);
             self.handle(nodes, buf);
             self.scp.pop();
         } else {
-            let (no_visited, scope) = visit_partial(&exprs);
+            let (no_visited, scope) = visit_partial(args);
             let mut cur = BTreeMap::new();
             for (k, expr) in no_visited {
                 let mut expr = expr.clone();
@@ -498,6 +1071,9 @@ impl<'a> Generator<'a> {
             }
         }
 
+        if inner.1 {
+            self.flush_ws((true, false));
+        }
         self.prepare_ws(ws);
         self.on_path = p;
     }
@@ -519,6 +1095,7 @@ impl<'a> Generator<'a> {
             Float(a) => push_some!(a),
             Bool(a) => push_some!(a),
             Str(a) if safe || self.s.mode == Mode::Text => push_some!(a),
+            Str(a) if self.s.mode == Mode::JSON => push_some!(json_escape(&a)),
             Str(a) => push_some!(escape(&a)),
             _ => None,
         })
@@ -531,6 +1108,7 @@ impl<'a> Generator<'a> {
         args: impl IntoIterator<Item = Value>,
         nodes: &'a [SNode<'a>],
         loop_var: bool,
+        last: bool,
     ) {
         macro_rules! handle {
             ($ctx:expr) => {
@@ -545,7 +1123,17 @@ impl<'a> Generator<'a> {
         let id = self.scp.len();
         self.on.push(On::Each(id));
         self.flush_ws(ws.0);
-        if loop_var {
+        if last {
+            let args: Vec<_> = args.into_iter().collect();
+            let len = args.len();
+            for (i, v) in args.into_iter().enumerate() {
+                handle!(vec![
+                    parse_str(&v.to_string()).unwrap(),
+                    parse_str(&i.to_string()).unwrap(),
+                    parse_str(&(i + 1 == len).to_string()).unwrap()
+                ]);
+            }
+        } else if loop_var {
             for (i, v) in args.into_iter().enumerate() {
                 handle!(vec![
                     parse_str(&v.to_string()).unwrap(),
@@ -755,6 +1343,15 @@ impl<'a> Generator<'a> {
         }
     }
 
+    /// Expression of the template resolved in the current scope, its span is the one of
+    /// the errors of the visit
+    fn visit_sexpr(&mut self, sexpr: &SExpr) -> syn::Expr {
+        self.span = *sexpr.span();
+        let mut expr = (**sexpr.t()).clone();
+        self.visit_expr_mut(&mut expr);
+        expr
+    }
+
     fn write_buf_writable(&mut self, buf: &mut Vec<HIR>) {
         if self.buf_w.is_empty() {
             return;
@@ -779,38 +1376,63 @@ impl<'a> Generator<'a> {
         }
     }
 
-    /* Helper methods for dealing with whitespace nodes */
+    /* Helper methods for dealing with whitespace nodes, see `whitespace` */
     fn skip_ws(&mut self) {
-        self.next_ws = None;
-        self.skip_ws = true;
+        self.ws.skip();
     }
 
-    // Based on https://github.com/djc/askama
-    // Combines `flush_ws()` and `prepare_ws()` to handle both trailing whitespace from the
-    // preceding literal and leading whitespace from the succeeding literal.
     fn handle_ws(&mut self, ws: Ws) {
-        self.flush_ws(ws);
-        self.prepare_ws(ws);
+        if let Some(lit) = self.ws.handle(ws) {
+            self.buf_w.push(Writable::Lit(lit));
+        }
     }
 
-    // If the previous literal left some trailing whitespace in `next_ws` and the
-    // prefix whitespace suppressor from the given argument, flush that whitespace.
-    // In either case, `next_ws` is reset to `None` (no trailing whitespace).
     fn flush_ws(&mut self, ws: Ws) {
-        if self.next_ws.is_some() && !ws.0 {
-            let val = self.next_ws.unwrap();
-            if !val.is_empty() {
-                self.buf_w.push(Writable::Lit(val));
-            }
+        if let Some(lit) = self.ws.flush(ws) {
+            self.buf_w.push(Writable::Lit(lit));
         }
-        self.next_ws = None;
     }
 
-    // Sets `skip_ws` to match the suffix whitespace suppressor from the given
-    // argument, to determine whether to suppress leading whitespace from the
-    // next literal.
     fn prepare_ws(&mut self, ws: Ws) {
-        self.skip_ws = ws.1;
+        self.ws.prepare(ws);
+    }
+}
+
+/// Path, whitespace of the inner edges and nodes of the partial `name` called in the
+/// template of `path`, an inline partial of the template or a partial file
+fn resolve_partial<'a>(
+    c: &Config,
+    ctx: Context<'a>,
+    path: &PathBuf,
+    name: &str,
+) -> (PathBuf, Ws, &'a [SNode<'a>]) {
+    match find_inline(&ctx[path], name) {
+        Some((ws, nodes)) => (path.clone(), ((ws.0).1, (ws.1).0), nodes),
+        None => {
+            let p = c.resolve_partial(path, name);
+            let nodes = &ctx[&p];
+            (p, (false, false), nodes)
+        }
+    }
+}
+
+/// Partial `name` of `resolve_partial` when it exists, `None` for `@partial-block`
+fn find_partial<'a>(
+    c: &Config,
+    ctx: Context<'a>,
+    path: &PathBuf,
+    name: &str,
+) -> Option<(PathBuf, Ws, &'a [SNode<'a>])> {
+    if name == PARTIAL_BLOCK {
+        return None;
+    }
+
+    match find_inline(&ctx[path], name) {
+        Some((ws, nodes)) => Some((path.clone(), ((ws.0).1, (ws.1).0), nodes)),
+        None => c.find_partial(path, name).map(|p| {
+            let nodes = &ctx[&p][..];
+            (p, (false, false), nodes)
+        }),
     }
 }
 
@@ -829,7 +1451,383 @@ fn is_super<S>(i: &Punctuated<PathSegment, S>) -> Option<(usize, String)> {
     }
 }
 
+/// Negated condition of `unless` helper
+fn not(cond: syn::Expr) -> syn::Expr {
+    syn::Expr::Unary(syn::ExprUnary {
+        expr: Box::new(syn::Expr::Paren(syn::ExprParen {
+            attrs: vec![],
+            paren_token: syn::token::Paren(Span::call_site()),
+            expr: Box::new(cond),
+        })),
+        attrs: vec![],
+        op: syn::UnOp::Not(Token![!](Span::call_site())),
+    })
+}
+
 #[inline]
 fn is_tuple_index(ident: &[u8]) -> bool {
     1 < ident.len() && ident[0] == b'_' && ident[1..].iter().all(|x| x.is_ascii_digit())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yarte_parser::{parse, source_map, Arena};
+
+    /// Attributes of the text templates
+    const TXT: &str = "ext = \"txt\"";
+
+    /// Template `src` of the struct `Test` with the attributes `attrs`, parsed for `pass`
+    fn template<T>(
+        attrs: &str,
+        src: &str,
+        config: &Config,
+        pass: impl FnOnce(&Config, &Struct, Context) -> T,
+    ) -> T {
+        let i = parse_str::<syn::DeriveInput>(&format!(
+            "#[template(src = {:?}, {})] struct Test;",
+            src, attrs
+        ))
+        .unwrap();
+        let s = visit_derive(&i, config);
+        let arena = Arena::new();
+        let mut ctx = BTreeMap::new();
+        ctx.insert(
+            &s.path,
+            parse(source_map::get_cursor(&s.path, &s.src), &arena),
+        );
+        let result = pass(config, &s, &ctx);
+        source_map::clean();
+
+        result
+    }
+
+    fn lower(attrs: &str, src: &str, config: &Config) -> Result<Vec<HIR>, Vec<ErrorMessage>> {
+        template(attrs, src, config, generate)
+    }
+
+    #[test]
+    fn test_yarte_module() {
+        let module = |src| yarte_module(&parse_str(src).unwrap()).map(ToString::to_string);
+        assert_eq!(
+            module("::yarte::builtins::Meta::new().title(&(a))").as_deref(),
+            Some("builtins")
+        );
+        assert_eq!(
+            module("::yarte::builtins::Img { src: a }").as_deref(),
+            Some("builtins")
+        );
+        assert_eq!(
+            module("::yarte::profile::leave(__yarte_profile_0)").as_deref(),
+            Some("profile")
+        );
+        assert_eq!(module("yarte::builtins::Id(a)"), None);
+        assert_eq!(module("::yarte::Scalar(&(::yarte::builtins::Id(a)))"), None);
+        assert_eq!(module("a.yarte()"), None);
+    }
+
+    #[test]
+    fn test_limits() {
+        let src = "{{#if a }}{{#if b }}{{ c }}{{/if }}{{/if }}";
+        assert!(lower(TXT, src, &Config::new("")).is_ok());
+
+        let errors = lower(TXT, src, &Config::new("[limits]\ndepth = 2")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("depth"));
+        // `{{ c }}`
+        assert_eq!(errors[0].span.hi - errors[0].span.lo, 7);
+
+        let src = "{{ a }}{{ b }}{{ c }}";
+        assert!(lower(TXT, src, &Config::new("[limits]\nexpressions = 3")).is_ok());
+        let errors = lower(TXT, src, &Config::new("[limits]\nexpressions = 2")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("expressions"));
+
+        let errors = lower(TXT, "a{{ b }}c", &Config::new("[limits]\nnodes = 2")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("nodes"));
+    }
+
+    #[test]
+    fn test_recursion() {
+        let src = "{{#*inline \"a\"}}a{{> a }}{{/inline}}{{> a }}";
+        let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.starts_with("Recursive partial `a`"));
+        // `a` of the inner `{{> a }}`
+        assert_eq!(errors[0].span.lo, 21);
+
+        // Without the region markers of `coverage` and `profile`
+        let lit: String = lower(TXT, src, &Config::new("[limits]\nrecursion = 2"))
+            .unwrap()
+            .iter()
+            .filter_map(|x| match x {
+                HIR::Lit(x) => Some(x.as_str()),
+                HIR::Local(_) => None,
+                x => panic!("unexpected {:?}", x),
+            })
+            .collect();
+        assert_eq!(lit, "aaa");
+    }
+
+    #[test]
+    fn test_inline_svg_errors() {
+        let src = "{{ @inline_svg \"missing.svg\" }}";
+        let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("can't read svg file"));
+        // `@inline_svg "missing.svg"`
+        assert_eq!(errors[0].span.hi - errors[0].span.lo, 25);
+
+        let errors = lower(TXT, "{{ @inline_svg path }}", &Config::new("")).unwrap_err();
+        assert!(errors[0].message.contains("string literal"));
+        let errors = lower(TXT, "{{ @inline_svg \"a.svg\" ids=a }}", &Config::new("")).unwrap_err();
+        assert!(errors[0].message.contains("`ids`"));
+    }
+
+    #[test]
+    fn test_img_errors() {
+        for (src, message) in &[
+            ("{{ @img src widths=[1] }}", "string literal"),
+            ("{{ @img \"a.jpg\" }}", "`widths`"),
+            ("{{ @img \"a.jpg\" widths=[a] }}", "`widths`"),
+            ("{{ @img \"a.jpg\" widths=[] }}", "can't be empty"),
+            ("{{ @img \"a.jpg\" widths=[1] }}", "asset manifest"),
+        ] {
+            let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert!(errors[0].message.contains(message), "{}", errors[0].message);
+            assert_eq!(errors[0].span.lo, 3);
+        }
+    }
+
+    #[test]
+    fn test_paginate_partial_errors() {
+        for (src, message) in &[
+            (
+                "{{ @paginate 1 2 url partial=link }}",
+                "name of an inline partial",
+            ),
+            (
+                "{{ @paginate 1 2 url partial=\"link\" }}",
+                "Unknown inline partial `link`",
+            ),
+        ] {
+            let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert!(errors[0].message.contains(message), "{}", errors[0].message);
+            assert_eq!(errors[0].span.lo, 3);
+        }
+    }
+
+    #[test]
+    fn test_chunks_size() {
+        for src in &[
+            "{{#each items chunks=0 }}{{/each }}",
+            "{{#each items chunks=n }}{{/each }}",
+        ] {
+            let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert!(errors[0].message.contains("greater than zero"));
+        }
+        assert!(lower(TXT, "{{#each items chunks=2 }}{{/each }}", &Config::new("")).is_ok());
+    }
+
+    #[test]
+    fn test_await() {
+        for src in &[
+            "{{ a.await }}",
+            "{{#if a.await }}{{/if }}",
+            "{{#each items }}{{ @cycle a.await \"b\" }}{{/each }}",
+            "{{ (async { a }) }}",
+        ] {
+            let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+            assert!(
+                errors.iter().any(|e| e.message.contains("async")),
+                "{}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn test_nth_zero() {
+        let src = "{{#each items }}{{#if @nth 0 }}|{{/if }}{{/each }}";
+        let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("greater than zero"));
+        // `@nth 0`
+        assert_eq!(errors[0].span.hi - errors[0].span.lo, 6);
+    }
+
+    #[test]
+    fn test_portal() {
+        let config = Config::new("");
+        let lower = |mode: &str, src: &str| lower(&format!("mode = {:?}", mode), src, &config);
+
+        let src = "<p>{{#portal \"#a&\\\"b\" }}<i>{{ c }}</i>{{/portal }}</p>";
+        let hir = lower("iso", src).unwrap();
+        assert_eq!(hir.len(), 3);
+        match (&hir[0], &hir[2]) {
+            (HIR::Lit(a), HIR::Lit(b)) => {
+                assert_eq!(a, "<p><template data-yarte-portal=\"#a&amp;&quot;b\"><i>");
+                assert_eq!(b, "</i></template></p>");
+            }
+            _ => panic!("{:?}", hir),
+        }
+        assert!(lower("wasm", src).is_ok());
+
+        let errors = lower("html", src).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("wasm applications"));
+        let errors = lower(
+            "iso",
+            "{{#each a }}{{#portal \"#a\" }}b{{/portal }}{{/each }}",
+        );
+        assert!(errors.unwrap_err()[0].message.contains("`each`"));
+        let errors = lower("iso", "{{#portal a }}b{{/portal }}").unwrap_err();
+        assert!(errors[0].message.contains("selector"));
+    }
+
+    #[test]
+    fn test_constants() {
+        let src = "{{! const A = 1 !}}{{! const B = -1.5 !}}{{! const C = \"c\" !}}\
+                   {{! const D: Vec<u8> = Vec::new() !}}{{! const E = 2u8 !}}{{ A }}";
+        let consts: Vec<_> = template("", src, &Config::new(""), |c, s, ctx| {
+            assert!(generate(c, s, ctx).is_ok());
+            constants(s, ctx)
+        })
+        .iter()
+        .map(|c| quote!(#c).to_string())
+        .collect();
+        assert_eq!(
+            consts,
+            [
+                "const A : usize = 1 ;",
+                "const B : f64 = - 1.5 ;",
+                "const C : & 'static str = \"c\" ;",
+                "const D : Vec < u8 > = Vec :: new () ;",
+                "const E : u8 = 2u8 ;",
+            ]
+        );
+
+        for (src, message) in &[
+            ("{{#if a }}{{! const A = 1 !}}{{/if }}", "top level"),
+            ("{{! const a = 1 !}}", "Constant `a` must be in upper case"),
+            ("{{! const A = 1 + 1 !}}", "Constant `A` needs a type"),
+            ("{{! const A = -true !}}", "Constant `A` needs a type"),
+            (
+                "{{! const A = 1 !}}{{! const A = 2 !}}",
+                "Constant `A` is already declared",
+            ),
+        ] {
+            let errors = lower(TXT, src, &Config::new("")).unwrap_err();
+            assert_eq!(errors.len(), 1, "{}", src);
+            assert!(errors[0].message.contains(message), "{}", src);
+        }
+    }
+
+    fn lower_once(src: &str) -> Result<Vec<HIR>, Vec<ErrorMessage>> {
+        lower("ext = \"txt\", once = true", src, &Config::new(""))
+    }
+
+    #[test]
+    fn test_once() {
+        assert!(lower_once("{{#each a }}{{ b }}{{ b }}{{/each }}{{#each c.d }}{{/each }}").is_ok());
+        assert!(lower_once("{{#each a }}{{/each }}{{#each b }}{{/each }}").is_ok());
+
+        let errors = lower_once("{{#each a }}{{/each }}{{ a.len() }}").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .message
+            .starts_with("field `a` is consumed by an each and used again"));
+
+        let errors = lower_once("{{#each a }}{{#each super::b }}{{/each }}{{/each }}").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .message
+            .starts_with("field `b` is consumed by an each inside a loop"));
+    }
+
+    fn branches(src: &str) -> Vec<String> {
+        template("", src, &Config::new(""), |c, s, ctx| {
+            let mut warnings = Warnings::new();
+            duplicate_branches(c, s, ctx, &mut warnings);
+            warnings.messages().to_vec()
+        })
+    }
+
+    #[test]
+    fn test_duplicate_branches() {
+        assert!(branches("{{#if a }}b{{else if c }}d{{else }}b{{/if }}").is_empty());
+        assert!(branches("{{#unless a }}b{{else if a }}c{{/unless }}").is_empty());
+        assert!(branches("{{#if a }}{{else }}{{/if }}").is_empty());
+        assert!(branches("{{#if false }}b{{else if false }}c{{/if }}").is_empty());
+
+        let messages = branches("{{#if a.b }}c{{else if d }}e{{else if a . b }}f{{/if }}");
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("duplicate condition at "));
+        assert!(messages[0].contains("Test.hbs:1:39, first used at "));
+        assert!(messages[0].ends_with("Test.hbs:1:7"));
+
+        let messages = branches(
+            "{{#each x }}{{#if a }}<p>{{ b }}</p>\n{{else }}<p>{{ b }}</p>\n{{/if }}{{/each }}",
+        );
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("identical branches at "));
+        assert!(messages[0].contains("Test.hbs:1:23 and at "));
+        assert!(messages[0].ends_with("Test.hbs:2:10"));
+
+        // Nested helpers are compared with their bodies
+        let messages = branches(
+            "{{#if a }}{{#each x }}{{ b }}{{/each }}{{else }}{{#each x }}{{ b }}{{/each }}{{/if }}",
+        );
+        assert_eq!(messages.len(), 1);
+        assert!(branches(
+            "{{#if a }}{{#each x }}{{ b }}{{/each }}{{else }}{{#each x }}{{ c }}{{/each }}{{/if }}"
+        )
+        .is_empty());
+        assert!(branches("{{#if a }}{{> p b }}{{else }}{{> p c }}{{/if }}").is_empty());
+    }
+
+    #[test]
+    fn test_warnings() {
+        let (messages, tokens) = template("", "a\n {{ b }}", &Config::new(""), |c, s, ctx| {
+            let mut warnings = Warnings::new();
+            assert!(warnings.is_empty());
+            assert!(warnings.emit(s).is_empty());
+            warnings.warn("a");
+            warnings.extend(vec!["a".to_string(), "b".to_string()]);
+            warnings.warn_at(c, *ctx[&s.path][1].span(), "c");
+            (warnings.messages().to_vec(), warnings.emit(s).to_string())
+        });
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[..2], ["a", "b"]);
+        assert!(messages[2].starts_with("c at "));
+        assert!(messages[2].ends_with("Test.hbs:2:2"));
+        assert_eq!(tokens.matches("deprecated").count(), 3);
+    }
+
+    #[test]
+    fn test_links() {
+        let src = "<a href=\"/posts?page=2\"></a><form action='/login'></form>\
+                   <a href=\"/post/{{ id }}\"></a><a href=\"https://example.com/a\"></a>\
+                   <a href=\"//cdn/a\"></a><a href=\"/admin\">{{ a }}</a>";
+        let config = Config::builder()
+            .routes(yarte_config::Routes::new("/posts\n/post/{id}"))
+            .build();
+        let errors = lower("", src, &config).unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].message,
+            "Unknown route `/login` in `action` attribute"
+        );
+        assert_eq!(
+            errors[1].message,
+            "Unknown route `/admin` in `href` attribute"
+        );
+        assert_eq!(errors[1].span.hi - errors[1].span.lo, 6);
+    }
+}
@@ -1,14 +1,22 @@
+use proc_macro2::TokenStream;
 use quote::quote;
 
+use yarte_config::Routes;
 use yarte_helpers::helpers::ErrorMessage;
-use yarte_parser::SExpr;
+use yarte_parser::{
+    source_map::{BytePos, Span},
+    Modifier, Once, SExpr, SStr,
+};
+
+use super::{visit_builtin, visit_modifier};
 
 pub(super) fn expression(e: &SExpr, out: &mut Vec<ErrorMessage>) {
     use syn::Expr::*;
     match **e.t() {
-        Binary(..) | Call(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..)
-        | Macro(..) | Lit(..) | Try(..) | Unary(..) | Unsafe(..) | If(..) | Loop(..)
-        | Match(..) => (),
+        Binary(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..) | Macro(..)
+        | Lit(..) | Try(..) | Unary(..) | Unsafe(..) | If(..) | Loop(..) | Match(..) => (),
+        Call(ref c) => call(c, e, out),
+        Verbatim(ref tokens) => builtin(tokens, e, false, out),
         _ => out.push(ErrorMessage {
             message: "Not available Rust expression in a template expression".to_string(),
             span: *e.span(),
@@ -19,9 +27,10 @@ pub(super) fn expression(e: &SExpr, out: &mut Vec<ErrorMessage>) {
 pub(super) fn ifs(e: &SExpr, out: &mut Vec<ErrorMessage>) {
     use syn::Expr::*;
     match **e.t() {
-        Binary(..) | Call(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..)
-        | Macro(..) | Lit(..) | Try(..) | Unary(..) | Unsafe(..) | If(..) | Loop(..)
-        | Match(..) | Let(..) => (),
+        Binary(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..) | Macro(..)
+        | Lit(..) | Try(..) | Unary(..) | Unsafe(..) | If(..) | Loop(..) | Match(..) | Let(..) => {}
+        Call(ref c) => call(c, e, out),
+        Verbatim(ref tokens) => builtin(tokens, e, false, out),
         _ => out.push(ErrorMessage {
             message: "Not available Rust expression in a template `if helper` arguments"
                 .to_string(),
@@ -35,6 +44,7 @@ pub(super) fn each(e: &SExpr, out: &mut Vec<ErrorMessage>) {
     match **e.t() {
         Call(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..) | Macro(..)
         | Try(..) | Unsafe(..) | If(..) | Loop(..) | Match(..) | Range(..) | Reference(..) => (),
+        Verbatim(ref tokens) => builtin(tokens, e, true, out),
         _ => out.push(ErrorMessage {
             message: "Not available Rust expression in a template `each helper` argument"
                 .to_string(),
@@ -43,6 +53,36 @@ pub(super) fn each(e: &SExpr, out: &mut Vec<ErrorMessage>) {
     }
 }
 
+pub(super) fn modifiers(e: &SExpr, modifiers: &[Modifier], out: &mut Vec<ErrorMessage>) {
+    for Modifier(ident, value) in modifiers {
+        let message = match visit_modifier::MODIFIERS.iter().find(|(x, _)| ident == x) {
+            None => format!("Unknown modifier `{}` in a template `each helper`", ident),
+            Some((_, true)) if value.is_none() => format!("Modifier `{}` needs a value", ident),
+            Some((_, false)) if value.is_some() => {
+                format!("Modifier `{}` doesn't take a value", ident)
+            }
+            Some(("chunks", _)) if !value.as_ref().map_or(false, is_positive_int) => {
+                "Modifier `chunks` needs an integer literal greater than zero".to_string()
+            }
+            _ => continue,
+        };
+        out.push(ErrorMessage {
+            message,
+            span: *e.span(),
+        });
+    }
+}
+
+fn is_positive_int(e: &syn::Expr) -> bool {
+    match e {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse::<usize>().map_or(false, |x| x != 0),
+        _ => false,
+    }
+}
+
 pub(super) fn unless(e: &SExpr, out: &mut Vec<ErrorMessage>) {
     use syn::Expr::*;
     match **e.t() {
@@ -76,6 +116,97 @@ pub(super) fn scope(e: &SExpr, out: &mut Vec<ErrorMessage>) {
     }
 }
 
+/// `@zip` iterates in lockstep, it's only available as `each` argument
+fn builtin(tokens: &TokenStream, e: &SExpr, each: bool, out: &mut Vec<ErrorMessage>) {
+    if syn::parse2::<Once>(tokens.clone()).is_ok() {
+        return;
+    }
+    let message = match visit_builtin::check(tokens) {
+        Ok(b) if (b.ident == "zip") != each => format!(
+            "Not available built-in helper `@{}` in a template {}",
+            b.ident,
+            if each {
+                "`each helper` argument"
+            } else {
+                "expression"
+            }
+        ),
+        Ok(_) => return,
+        Err(message) => message,
+    };
+    out.push(ErrorMessage {
+        message,
+        span: *e.span(),
+    })
+}
+
+/// Built-in helpers in the subexpressions of a helper call `name (@sub ..)`
+fn call(c: &syn::ExprCall, e: &SExpr, out: &mut Vec<ErrorMessage>) {
+    for arg in &c.args {
+        match arg {
+            syn::Expr::Verbatim(tokens) => builtin(tokens, e, false, out),
+            syn::Expr::Call(c) => call(c, e, out),
+            _ => (),
+        }
+    }
+}
+
+/// Static `href` and `action` with internal paths must match a route of the manifest
+pub(super) fn links(lit: &SStr, routes: &Routes, out: &mut Vec<ErrorMessage>) {
+    let src = lit.t();
+    for attr in &["href", "action"] {
+        for (start, end) in attr_values(src, attr) {
+            let path = &src[start..end];
+            if path.starts_with('/') && !path.starts_with("//") && !routes.matches(path) {
+                let lo = lit.span().lo + start as BytePos;
+                out.push(ErrorMessage {
+                    message: format!("Unknown route `{}` in `{}` attribute", path, attr),
+                    span: Span {
+                        lo,
+                        hi: lo + (end - start) as BytePos,
+                    },
+                });
+            }
+        }
+    }
+}
+
+/// Ranges of the static values of the attribute `name` in a literal
+///
+/// Values followed by an expression are dynamic and skipped
+fn attr_values(src: &str, name: &str) -> Vec<(usize, usize)> {
+    let bytes = src.as_bytes();
+    let pattern = format!("{}=", name);
+    let mut values = vec![];
+    let mut at = 0;
+    while let Some(i) = src[at..].find(&pattern) {
+        let i = at + i;
+        at = i + pattern.len();
+        if i == 0 || !bytes[i - 1].is_ascii_whitespace() {
+            continue;
+        }
+
+        let (start, end) = match bytes.get(at) {
+            Some(q @ b'"') | Some(q @ b'\'') => match src[at + 1..].find(*q as char) {
+                Some(len) => (at + 1, at + 1 + len),
+                None => continue,
+            },
+            Some(_) => match src[at..].find(|c: char| c.is_whitespace() || c == '>') {
+                Some(len) => (at, at + len),
+                None => continue,
+            },
+            None => continue,
+        };
+
+        if start < end {
+            values.push((start, end));
+        }
+        at = end;
+    }
+
+    values
+}
+
 // TODO:
 pub(super) fn partial_assign(e: &syn::Expr) {
     use syn::Expr::*;
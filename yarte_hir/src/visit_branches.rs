@@ -0,0 +1,216 @@
+use std::{mem, path::PathBuf, ptr};
+
+use quote::quote;
+
+use yarte_config::Config;
+use yarte_parser::{Helper, Node, Partial, SArgs, SExpr, SNode, Ws};
+
+use super::{diagnostics::location, find_partial, Context, Struct, Warnings};
+
+/// Find likely copy-paste errors in the conditionals of the document, partials included
+///
+/// Reports consecutive branches with identical bodies and `else if` conditions, but literals,
+/// already used in the same chain, with the locations of both.
+pub fn duplicate_branches(c: &Config, s: &Struct, ctx: Context, warnings: &mut Warnings) {
+    let mut finder = FindBranches {
+        c,
+        ctx,
+        on_path: s.path.clone(),
+        warnings,
+        partials: vec![ctx.get(&s.path).unwrap()],
+    };
+    finder.find(ctx.get(&s.path).unwrap());
+}
+
+struct FindBranches<'a> {
+    c: &'a Config<'a>,
+    ctx: Context<'a>,
+    on_path: PathBuf,
+    warnings: &'a mut Warnings,
+    partials: Vec<&'a [SNode<'a>]>,
+}
+
+impl<'a> FindBranches<'a> {
+    fn partial(&mut self, path: &str) {
+        // The block of `@partial-block` is found in the partial block
+        if let Some((p, _, nodes)) = find_partial(self.c, self.ctx, &self.on_path, path) {
+            // A recursive partial is found in its first call
+            if self.partials.iter().any(|x| ptr::eq(*x, nodes)) {
+                return;
+            }
+            let parent = mem::replace(&mut self.on_path, p);
+            self.partials.push(nodes);
+
+            self.find(nodes);
+
+            self.partials.pop();
+            self.on_path = parent;
+        }
+    }
+
+    fn find(&mut self, nodes: &'a [SNode]) {
+        for n in nodes {
+            match n.t() {
+                Node::Helper(h) => match &**h {
+                    Helper::If((_, first, block), else_if, els) => {
+                        self.chain(Some(first), block, else_if, els)
+                    }
+                    Helper::Unless((_, _, block), else_if, els) => {
+                        self.chain(None, block, else_if, els)
+                    }
+                    Helper::With(_, _, _, b)
+                    | Helper::Portal(_, _, _, b)
+                    | Helper::Each(_, _, _, _, b, None)
+                    | Helper::Defined(_, _, _, _, b) => self.find(b),
+                    Helper::Each(_, _, _, _, b, Some((_, _, els))) => {
+                        self.find(b);
+                        self.find(els);
+                    }
+                },
+                Node::Partial(Partial(_, path, _)) => self.partial(path.t()),
+                Node::PartialBlock(_, path, _, block) => {
+                    self.find(block);
+                    self.partial(path.t());
+                }
+                Node::Lit(..)
+                | Node::Raw(..)
+                | Node::Local(..)
+                | Node::Expr(..)
+                | Node::Safe(..)
+                | Node::Comment(_)
+                | Node::Inline(..)
+                | Node::Const(_)
+                | Node::Keep(_) => (),
+            }
+        }
+    }
+
+    /// Check a conditional chain, the negated condition of `unless` isn't compared
+    fn chain(
+        &mut self,
+        first: Option<&'a SExpr>,
+        block: &'a [SNode],
+        else_if: &'a [(Ws, SExpr, Vec<SNode>)],
+        els: &'a Option<(Ws, Vec<SNode>)>,
+    ) {
+        let mut conds: Vec<_> = first.into_iter().collect();
+        for (_, cond, _) in else_if {
+            // Literals are evaluated at compile time
+            if let syn::Expr::Lit(_) = **cond.t() {
+                continue;
+            }
+            let tokens = expr(cond);
+            if let Some(prev) = conds.iter().find(|x| expr(x) == tokens) {
+                let message = format!(
+                    "duplicate condition at {}, first used at {}",
+                    location(self.c, *cond.span()),
+                    location(self.c, *prev.span())
+                );
+                self.warnings.warn(message);
+            }
+            conds.push(cond);
+        }
+
+        let bodies: Vec<&[SNode]> = Some(block)
+            .into_iter()
+            .chain(else_if.iter().map(|(_, _, b)| &b[..]))
+            .chain(els.iter().map(|(_, b)| &b[..]))
+            .collect();
+        for pair in bodies.windows(2) {
+            if let (Some(a), Some(b)) = (pair[0].first(), pair[1].first()) {
+                if same(pair[0], pair[1]) {
+                    let message = format!(
+                        "identical branches at {} and at {}",
+                        location(self.c, *a.span()),
+                        location(self.c, *b.span())
+                    );
+                    self.warnings.warn(message);
+                }
+            }
+        }
+
+        for b in bodies {
+            self.find(b);
+        }
+    }
+}
+
+fn expr(e: &SExpr) -> String {
+    let e = e.t();
+    quote!(#e).to_string()
+}
+
+/// Nodes equal but their spans
+fn same(a: &[SNode], b: &[SNode]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_node(a.t(), b.t()))
+}
+
+fn same_node(a: &Node, b: &Node) -> bool {
+    use Node::*;
+    match (a, b) {
+        (Comment(a), Comment(b)) | (Keep(a), Keep(b)) => a == b,
+        (Const(a), Const(b)) => a == b,
+        (Expr(wa, a), Expr(wb, b)) | (Safe(wa, a), Safe(wb, b)) => wa == wb && a.t() == b.t(),
+        (Helper(a), Helper(b)) => same_helper(a, b),
+        (Inline(wa, na, a), Inline(wb, nb, b)) => wa == wb && na.t() == nb.t() && same(a, b),
+        (Lit(la, a, ra), Lit(lb, b, rb)) => la == lb && a.t() == b.t() && ra == rb,
+        (Local(a), Local(b)) => a.t() == b.t(),
+        (Partial(self::Partial(wa, pa, aa)), Partial(self::Partial(wb, pb, ab))) => {
+            wa == wb && pa.t() == pb.t() && same_args(aa, ab)
+        }
+        (PartialBlock(wa, pa, aa, a), PartialBlock(wb, pb, ab, b)) => {
+            wa == wb && pa.t() == pb.t() && same_args(aa, ab) && same(a, b)
+        }
+        (Raw(wa, la, a, ra), Raw(wb, lb, b, rb)) => {
+            wa == wb && la == lb && a.t() == b.t() && ra == rb
+        }
+        _ => false,
+    }
+}
+
+fn same_helper(a: &Helper, b: &Helper) -> bool {
+    use Helper::*;
+    match (a, b) {
+        (Each(wa, ea, ma, pa, a, els_a), Each(wb, eb, mb, pb, b, els_b)) => {
+            wa == wb
+                && ea.t() == eb.t()
+                && ma == mb
+                && pa == pb
+                && same(a, b)
+                && match (els_a, els_b) {
+                    (Some((wa, ia, a)), Some((wb, ib, b))) => wa == wb && ia == ib && same(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        }
+        (If(first_a, else_if_a, els_a), If(first_b, else_if_b, els_b))
+        | (Unless(first_a, else_if_a, els_a), Unless(first_b, else_if_b, els_b)) => {
+            let ((wa, ca, a), (wb, cb, b)) = (first_a, first_b);
+            wa == wb
+                && ca.t() == cb.t()
+                && same(a, b)
+                && else_if_a.len() == else_if_b.len()
+                && else_if_a
+                    .iter()
+                    .zip(else_if_b)
+                    .all(|((wa, ca, a), (wb, cb, b))| wa == wb && ca.t() == cb.t() && same(a, b))
+                && match (els_a, els_b) {
+                    (Some((wa, a)), Some((wb, b))) => wa == wb && same(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        }
+        (With(wa, ea, pa, a), With(wb, eb, pb, b))
+        | (Portal(wa, ea, pa, a), Portal(wb, eb, pb, b)) => {
+            wa == wb && ea.t() == eb.t() && pa == pb && same(a, b)
+        }
+        (Defined(wa, na, ea, pa, a), Defined(wb, nb, eb, pb, b)) => {
+            wa == wb && na == nb && ea.t() == eb.t() && pa == pb && same(a, b)
+        }
+        _ => false,
+    }
+}
+
+fn same_args(a: &SArgs, b: &SArgs) -> bool {
+    a.t().len() == b.t().len() && a.t().iter().zip(b.t()).all(|(a, b)| a.t() == b.t())
+}
@@ -0,0 +1,546 @@
+use std::{fs, path::PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse2, visit_mut::VisitMut};
+
+use v_htmlescape::escape;
+use yarte_helpers::helpers::ErrorMessage;
+use yarte_parser::{find_inline, Arg, Builtin, BuiltinArg, SExpr, Ws};
+
+use super::{visit_coverage::local, Each, Generator, IfElse, On, HIR};
+
+/// Built-in helpers: name, positional arguments range and named arguments, `*` for any name
+const BUILTINS: &[(&str, (usize, usize), &[&str])] = &[
+    ("bidi", (1, 1), &[]),
+    ("csp", (0, 0), &[]),
+    ("cycle", (1, usize::max_value()), &[]),
+    ("date", (1, 1), &[]),
+    ("dir", (1, 1), &[]),
+    ("first", (0, 0), &[]),
+    ("id", (1, 1), &[]),
+    ("img", (1, 1), &["widths", "sizes", "alt"]),
+    ("index", (0, 0), &[]),
+    ("inline_svg", (1, 1), &["ids"]),
+    ("jsonld", (1, 1), &[]),
+    ("last", (0, 0), &[]),
+    (
+        "meta",
+        (0, 0),
+        &["title", "description", "image", "url", "site_name", "kind"],
+    ),
+    ("money", (1, 1), &[]),
+    ("nth", (1, 1), &[]),
+    ("num", (1, 1), &[]),
+    ("paginate", (3, 3), &["window", "partial"]),
+    ("pathseg", (1, 1), &[]),
+    (
+        "plural",
+        (1, 1),
+        &["zero", "one", "two", "few", "many", "other"],
+    ),
+    ("query", (0, 0), &["*"]),
+    ("urlencode", (1, 1), &[]),
+    ("zip", (2, 6), &[]),
+];
+
+/// Parse and check a built-in helper call
+pub(super) fn check(tokens: &TokenStream) -> Result<Builtin, String> {
+    let builtin = parse2::<Builtin>(tokens.clone()).map_err(|e| e.to_string())?;
+    check_builtin(&builtin)?;
+
+    Ok(builtin)
+}
+
+fn check_builtin(b: &Builtin) -> Result<(), String> {
+    let name = b.ident.to_string();
+    let (_, (min, max), named) = BUILTINS
+        .iter()
+        .find(|(x, ..)| *x == name)
+        .ok_or_else(|| format!("Unknown built-in helper `@{}`", name))?;
+
+    let len = b.positional().count();
+    if len < *min || *max < len {
+        return Err(if min == max {
+            format!("`@{}` expects {} arguments, found {}", name, min, len)
+        } else if *max == usize::max_value() {
+            format!(
+                "`@{}` expects at least {} arguments, found {}",
+                name, min, len
+            )
+        } else {
+            format!(
+                "`@{}` expects between {} and {} arguments, found {}",
+                name, min, max, len
+            )
+        });
+    }
+
+    if name == "inline_svg" {
+        if !b.positional().all(|x| match x {
+            BuiltinArg::Expr(e) => is_str_lit(e),
+            _ => false,
+        }) {
+            return Err("`@inline_svg` expects the path of the file as string literal".into());
+        }
+        if !b.named("ids").map_or(true, is_str_lit) {
+            return Err("`ids` of `@inline_svg` must be string literal".into());
+        }
+    }
+
+    if name == "img" {
+        if !b.positional().all(|x| match x {
+            BuiltinArg::Expr(e) => is_str_lit(e),
+            _ => false,
+        }) {
+            return Err("`@img` expects the source of the image as string literal".into());
+        }
+        match b.named("widths") {
+            Some(syn::Expr::Array(a)) if a.elems.is_empty() => {
+                return Err("`widths` of `@img` can't be empty".into());
+            }
+            Some(syn::Expr::Array(a)) if a.elems.iter().all(|x| width(x).is_some()) => (),
+            _ => return Err("`@img` expects `widths`, an array of integer literals".into()),
+        }
+    }
+
+    if name == "paginate" && !b.named("partial").map_or(true, is_str_lit) {
+        return Err("`partial` of `@paginate` must be the name of an inline partial".into());
+    }
+
+    if name == "nth"
+        && b.positional().any(|x| match x {
+            BuiltinArg::Expr(e) => is_zero_lit(e),
+            _ => false,
+        })
+    {
+        return Err("`@nth` expects a step greater than zero".into());
+    }
+
+    for arg in &b.args {
+        match arg {
+            BuiltinArg::Named(ident, _) if !named.iter().any(|x| *x == "*" || ident == x) => {
+                return Err(format!(
+                    "Unknown argument `{}` in built-in helper `@{}`",
+                    ident, name
+                ));
+            }
+            BuiltinArg::Sub(b) => check_builtin(b)?,
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn is_zero_lit(e: &syn::Expr) -> bool {
+    match e {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse::<u64>().ok() == Some(0),
+        _ => false,
+    }
+}
+
+/// Width of `@img`, an integer literal
+fn width(e: &syn::Expr) -> Option<u32> {
+    match e {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(w),
+            ..
+        }) => w.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_str_lit(e: &syn::Expr) -> bool {
+    match e {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(_),
+            ..
+        }) => true,
+        _ => false,
+    }
+}
+
+/// Constructors of `yarte::builtins::schema` callable by name in `@jsonld`
+const SCHEMAS: &[&str] = &["article", "breadcrumb_list", "organization"];
+
+fn is_schema(ident: &syn::Ident) -> bool {
+    SCHEMAS.iter().any(|x| ident == x)
+}
+
+impl<'a> Generator<'a> {
+    /// Lower a built-in helper call to a runtime helper expression
+    ///
+    /// Returns `None` for invalid calls, which are reported by the validator
+    pub(super) fn visit_builtin(&mut self, tokens: &TokenStream) -> Option<syn::Expr> {
+        check(tokens).ok().map(|b| self.lower_builtin(b))
+    }
+
+    /// Partial overriding a built-in helper call and its named arguments as partial arguments
+    pub(super) fn builtin_partial(&self, expr: &syn::Expr) -> Option<(PathBuf, Vec<Arg>)> {
+        let tokens = match expr {
+            syn::Expr::Verbatim(tokens) => tokens,
+            _ => return None,
+        };
+        let b = check(tokens).ok()?;
+        let path = self.c.resolve_builtin(&self.s.path, &b.ident.to_string())?;
+        let args = b
+            .args
+            .into_iter()
+            .filter_map(|arg| match arg {
+                BuiltinArg::Named(ident, e) => Some(Arg::Named(ident, e)),
+                _ => None,
+            })
+            .collect();
+
+        Some((path, args))
+    }
+
+    /// `@paginate` with a `partial`, each item of the list rendered by the inline partial
+    /// with its `Link` as scope. Returns `false` for the other expressions
+    pub(super) fn paginate_partial(&mut self, buf: &mut Vec<HIR>, ws: Ws, sexpr: &SExpr) -> bool {
+        let b = match sexpr.t() {
+            syn::Expr::Verbatim(tokens) => match check(tokens) {
+                Ok(b) if b.ident == "paginate" => b,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        let name = match b.named("partial") {
+            Some(syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            })) => s.value(),
+            _ => return false,
+        };
+        let (inner, nodes) = match find_inline(&self.ctx[&self.on_path], &name) {
+            Some((ws, nodes)) => (((ws.0).1, (ws.1).0), nodes),
+            None => {
+                self.errors.push(ErrorMessage {
+                    message: format!("Unknown inline partial `{}` of `@paginate`", name),
+                    span: *sexpr.span(),
+                });
+                return true;
+            }
+        };
+
+        self.handle_ws(ws);
+        self.write_buf_writable(buf);
+        let paginate = self.lower_builtin(b);
+        let p = self.scp.push_ident("__paginate_");
+        buf.push(local(quote!(let #p = #paginate;)));
+
+        let id = self.scp.len();
+        self.scp.push_scope(vec![]);
+        let v = self.scp.push_ident("__key_");
+        let i = self.scp.push_ident("__index_");
+        self.on.push(On::Each(id));
+
+        let mut body = vec![];
+        let this = syn::parse_str("this").unwrap();
+        let source = (self.on_path.clone(), inner, nodes);
+        self.inline_partial(
+            &mut body,
+            (false, false),
+            source,
+            &[Arg::Positional(this)],
+            vec![],
+        );
+        self.write_buf_writable(&mut body);
+
+        self.on.pop();
+        self.scp.pop();
+
+        let each = Each {
+            args: syn::parse2(quote!((::core::iter::Iterator::enumerate(#p.links().iter()))))
+                .unwrap(),
+            body,
+            expr: syn::parse2(quote!((#i, #v))).unwrap(),
+        };
+        buf.push(HIR::IfElse(Box::new(IfElse {
+            ifs: (
+                syn::parse2(quote!(!#p.is_empty())).unwrap(),
+                vec![
+                    HIR::Lit("<nav><ul class=\"pagination\">".into()),
+                    HIR::Each(Box::new(each)),
+                    HIR::Lit("</ul></nav>".into()),
+                ],
+            ),
+            if_else: vec![],
+            els: None,
+        })));
+
+        true
+    }
+
+    fn lower_builtin(&mut self, b: Builtin) -> syn::Expr {
+        let mut args = vec![];
+        let mut named = vec![];
+        for arg in b.args {
+            match arg {
+                BuiltinArg::Expr(mut e) => {
+                    self.visit_expr_mut(&mut e);
+                    args.push(e);
+                }
+                BuiltinArg::Named(ident, mut e) => {
+                    self.visit_expr_mut(&mut e);
+                    named.push((ident.to_string(), e));
+                }
+                BuiltinArg::Sub(b) => args.push(self.lower_builtin(b)),
+            }
+        }
+        let named_arg = |name: &str| named.iter().find(|(x, _)| x == name).map(|(_, e)| e);
+
+        let name = b.ident.to_string();
+        let tokens = match name.as_str() {
+            "cycle" => {
+                let index = &self.scp[self.enclosing_each(&name)][1];
+                let len = args.len();
+                quote!([#(#args),*][#index % #len])
+            }
+            "csp" => quote!(::yarte::builtins::Csp::new(self)),
+            "first" => {
+                let index = &self.scp[self.enclosing_each(&name)][1];
+                quote!((#index == 0))
+            }
+            "index" => {
+                let index = &self.scp[self.enclosing_each(&name)][1];
+                quote!(#index)
+            }
+            "last" => {
+                let last = &self.scp[self.enclosing_each(&name)][2];
+                quote!(#last)
+            }
+            "nth" => {
+                let index = &self.scp[self.enclosing_each(&name)][1];
+                let n = &args[0];
+                quote!(((#index + 1) % (#n) == 0))
+            }
+            "date" | "money" => {
+                let formatter = self.formatter();
+                let hook = syn::Ident::new(&format!("fmt_{}", name), b.ident.span());
+                let value = &args[0];
+                quote!(::yarte::builtins::Formatted(
+                    &(#value),
+                    <#formatter as ::yarte::builtins::Formatter>::#hook
+                ))
+            }
+            "bidi" => {
+                let value = &args[0];
+                quote!(::yarte::builtins::Bidi(&(#value)))
+            }
+            "dir" => {
+                let value = &args[0];
+                quote!(::yarte::builtins::Dir(&(#value)))
+            }
+            "jsonld" => {
+                let mut value = args[0].clone();
+                if let syn::Expr::Call(syn::ExprCall { func, .. }) = &mut value {
+                    if let syn::Expr::Path(p) = &**func {
+                        if let Some(ident) = p.path.get_ident().filter(|x| is_schema(x)) {
+                            *func = parse2(quote!(::yarte::builtins::schema::#ident)).unwrap();
+                        }
+                    }
+                }
+                quote!(::yarte::builtins::JsonLd(&(#value)))
+            }
+            "meta" => {
+                let mut tokens = quote!(::yarte::builtins::Meta::new());
+                for (name, e) in &named {
+                    let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+                    tokens = quote!(#tokens.#name(&(#e)));
+                }
+                tokens
+            }
+            "num" => {
+                let value = &args[0];
+                quote!(::yarte::builtins::Num(
+                    &(#value),
+                    ::yarte::builtins::Localized::locale(self)
+                ))
+            }
+            "plural" => {
+                let n = &args[0];
+                let mut tokens = quote!(::yarte::builtins::Plural::new(
+                    &(#n),
+                    ::yarte::builtins::Localized::locale(self)
+                ));
+                for (name, e) in &named {
+                    let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+                    tokens = quote!(#tokens.#name(&(#e)));
+                }
+                tokens
+            }
+            "paginate" => {
+                let (current, total, url) = (&args[0], &args[1], &args[2]);
+                let mut tokens =
+                    quote!(::yarte::builtins::Paginate::new(#current, #total, &(#url)));
+                if let Some(window) = named_arg("window") {
+                    tokens = quote!(#tokens.window(#window));
+                }
+                tokens
+            }
+            "id" => {
+                let prefix = &args[0];
+                let key = self.id_key();
+                let indexes = self.on.iter().filter_map(|x| match x {
+                    On::Each(j) => Some(&self.scp[*j][1]),
+                    On::With(_) => None,
+                });
+                quote!(::yarte::builtins::Id(&(#prefix), #key, &[#(#indexes),*]))
+            }
+            "query" => {
+                let args = named.iter().map(
+                    |(name, e)| quote!((#name, ::yarte::builtins::QueryArg::query_arg(&(#e)))),
+                );
+                quote!(::yarte::builtins::Query(&[#(#args),*]))
+            }
+            "img" => {
+                // Literals checked by the validator
+                let source = match &args[0] {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) => s.value(),
+                    _ => unreachable!(),
+                };
+                let widths: Vec<u32> = match named_arg("widths") {
+                    Some(syn::Expr::Array(a)) => a.elems.iter().filter_map(width).collect(),
+                    _ => unreachable!(),
+                };
+                // Urls of the manifest are trusted, escaped as attribute values here
+                let c = self.c;
+                let urls = match &c.assets {
+                    Some(assets) => widths
+                        .iter()
+                        .map(|w| match assets.get(&source, *w) {
+                            Some(url) => escape(url).to_string(),
+                            None => {
+                                self.errors.push(ErrorMessage {
+                                    message: format!(
+                                        "image `{}` of width {} isn't in the asset manifest",
+                                        source, w
+                                    ),
+                                    span: self.span,
+                                });
+                                String::new()
+                            }
+                        })
+                        .collect(),
+                    None => {
+                        self.errors.push(ErrorMessage {
+                            message: "`@img` needs the asset manifest, `assets` of [main] \
+                                      in yarte.toml"
+                                .into(),
+                            span: self.span,
+                        });
+                        vec![String::new(); widths.len()]
+                    }
+                };
+                let srcset = urls
+                    .iter()
+                    .zip(&widths)
+                    .map(|(url, w)| format!("{} {}w", url, w))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let largest = (0..widths.len()).max_by_key(|i| widths[*i]);
+                let src = largest.map_or("", |i| &urls[i]);
+                let sizes = named_arg("sizes").map_or(quote!("100vw"), |x| quote!(#x));
+                let alt = named_arg("alt").map_or(quote!(""), |x| quote!(#x));
+                quote!(::yarte::builtins::Img {
+                    src: #src,
+                    srcset: #srcset,
+                    sizes: &(#sizes),
+                    alt: &(#alt),
+                })
+            }
+            "inline_svg" => {
+                // Literals checked by the validator
+                let path = match &args[0] {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) => self.c.get_dir().join(s.value()),
+                    _ => unreachable!(),
+                };
+                let src = fs::read_to_string(&path).unwrap_or_else(|e| {
+                    self.errors.push(ErrorMessage {
+                        message: format!("can't read svg file {:?}: {}", path, e),
+                        span: self.span,
+                    });
+                    String::new()
+                });
+                let ids = match named_arg("ids") {
+                    Some(ids) => quote!(::core::option::Option::Some(#ids)),
+                    None => quote!(::core::option::Option::None),
+                };
+                // Replaced by the sanitized svg before the code generation
+                quote!(::yarte::builtins::InlineSvg(#src, #ids))
+            }
+            "urlencode" => {
+                let value = &args[0];
+                quote!(::yarte::builtins::UrlEncode(&(#value)))
+            }
+            "pathseg" => {
+                let value = &args[0];
+                quote!(::yarte::builtins::PathSeg(&(#value)))
+            }
+            "zip" => quote!(::yarte::builtins::Zip((#(&(#args)),*))),
+            _ => unreachable!(),
+        };
+
+        parse2(tokens).expect("Correct built-in helper expression")
+    }
+
+    /// Type of the `formatter` template attribute
+    fn formatter(&self) -> syn::Path {
+        match &self.s.formatter {
+            Some(formatter) => syn::parse_str(formatter)
+                .unwrap_or_else(|_| panic!("attribute 'formatter' must be a type path")),
+            None => parse2(quote!(::yarte::builtins::DefaultFormatter)).unwrap(),
+        }
+    }
+
+    /// Key of the ids of the current template file, hash of its path in the templates directory
+    ///
+    /// Server and client renders of the same template have the same ids
+    /// Key of the template file and the include sites of its partials,
+    /// the same partial included twice has different ids
+    fn id_key(&self) -> String {
+        let mut key = self.rel_path();
+        for site in &self.sites {
+            key.push('\n');
+            key.push_str(site);
+        }
+        // FNV-1a, stable across builds
+        let hash = key.bytes().fold(0x811c_9dc5u32, |h, b| {
+            (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+        });
+
+        format!("{:08x}", hash)
+    }
+
+    /// Current file path relative to the templates directory
+    pub(super) fn rel_path(&self) -> String {
+        self.on_path
+            .strip_prefix(self.c.get_dir())
+            .unwrap_or(&self.on_path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Scope of the nearest each block, loop state helpers refer to it
+    fn enclosing_each(&self, name: &str) -> usize {
+        self.on
+            .iter()
+            .rev()
+            .find_map(|x| match x {
+                On::Each(j) => Some(*j),
+                On::With(_) => None,
+            })
+            .unwrap_or_else(|| panic!("use @{} outside of each", name))
+    }
+}
@@ -0,0 +1,101 @@
+use quote::quote;
+use syn::{parse2, Lit, UnOp};
+
+use yarte_helpers::helpers::ErrorMessage;
+use yarte_parser::{source_map::Span, Const, Node};
+
+use super::{Context, Generator, Struct};
+
+/// Constants declared at the top level of the template, `{{! const NAME = expr !}}`
+///
+/// Lowered to `const` items of the scope of the generated code, so the expressions
+/// use them by name. They're checked when the template is lowered.
+pub fn constants(s: &Struct, ctx: Context) -> Vec<syn::ItemConst> {
+    ctx.get(&s.path)
+        .unwrap()
+        .iter()
+        .filter_map(|n| match n.t() {
+            Node::Const(c) => {
+                let Const { ident, expr, .. } = &**c;
+                let ty = const_type(c)?;
+                Some(parse2(quote!(const #ident: #ty = #expr;)).expect("Correct constant"))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Type of the constant, or of its literal value
+///
+/// Integers without suffix are `usize`, or `isize` when negated, and floats are `f64`
+fn const_type(c: &Const) -> Option<syn::Type> {
+    if let Some(ty) = &c.ty {
+        return Some(ty.clone());
+    }
+
+    let (lit, neg) = match &c.expr {
+        syn::Expr::Lit(e) => (&e.lit, false),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            syn::Expr::Lit(e) => (&e.lit, true),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let ty = match lit {
+        Lit::Int(i) if !i.suffix().is_empty() => i.suffix(),
+        Lit::Int(_) if neg => "isize",
+        Lit::Int(_) => "usize",
+        Lit::Float(f) if !f.suffix().is_empty() => f.suffix(),
+        Lit::Float(_) => "f64",
+        _ if neg => return None,
+        Lit::Str(_) => "&'static str",
+        Lit::ByteStr(_) => "&'static [u8]",
+        Lit::Byte(_) => "u8",
+        Lit::Char(_) => "char",
+        Lit::Bool(_) => "bool",
+        Lit::Verbatim(_) => return None,
+    };
+
+    syn::parse_str(ty).ok()
+}
+
+impl<'a> Generator<'a> {
+    /// Check a constant of the template, its item is written by `constants`
+    pub(super) fn visit_const(&mut self, c: &Const, span: Span) {
+        let name = c.ident.to_string();
+        let message = if self.depth != 1 || self.on_path != self.s.path {
+            "Constants are declared at the top level of the template, out of helpers and partials"
+                .to_owned()
+        } else if !name.starts_with(char::is_uppercase) {
+            format!("Constant `{}` must be in upper case", name)
+        } else if const_type(c).is_none() {
+            format!(
+                "Constant `{}` needs a type, `const {}: Type = expr`, unless its value is a literal",
+                name, name
+            )
+        } else if self.is_declared(c, span) {
+            format!("Constant `{}` is already declared", name)
+        } else {
+            return;
+        };
+
+        self.errors.push(ErrorMessage { message, span });
+    }
+
+    /// Some constant with the same name before `span`
+    fn is_declared(&self, c: &Const, span: Span) -> bool {
+        self.ctx
+            .get(&self.s.path)
+            .unwrap()
+            .iter()
+            .take_while(|n| n.span().lo < span.lo)
+            .any(|n| match n.t() {
+                Node::Const(x) => x.ident == c.ident,
+                _ => false,
+            })
+    }
+}
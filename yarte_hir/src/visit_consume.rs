@@ -0,0 +1,112 @@
+use quote::quote;
+use syn::{parse2, visit::Visit};
+
+use yarte_parser::source_map::Span;
+
+use super::{ErrorMessage, Generator, On, HIR};
+
+impl<'a> Generator<'a> {
+    /// Iterator of an each over a field of a `once` template, the field is consumed
+    ///
+    /// Returns `None` for other iterators, borrowed as in the by-ref templates
+    pub(super) fn consume(&mut self, args: &syn::Expr, span: Span) -> Option<syn::Expr> {
+        if !self.s.once {
+            return None;
+        }
+        let field = root_field(args)?;
+
+        if self
+            .on
+            .iter()
+            .any(|on| if let On::Each(_) = on { true } else { false })
+        {
+            self.errors.push(ErrorMessage {
+                message: format!(
+                    "field `{}` is consumed by an each inside a loop, \
+                     `once` templates can consume a field only once",
+                    field
+                ),
+                span,
+            });
+        }
+        self.consumed.push((field, span));
+
+        Some(parse2(quote!(((#args).into_iter()))).unwrap())
+    }
+
+    /// Fail on the consumed fields used again anywhere in the template
+    pub(super) fn check_consumed(&mut self, buf: &[HIR]) {
+        for (field, span) in &self.consumed {
+            let mut count = Count { field, uses: 0 };
+            count.visit_hir(buf);
+            if 1 < count.uses {
+                self.errors.push(ErrorMessage {
+                    message: format!(
+                        "field `{}` is consumed by an each and used again, \
+                         `once` templates can use a consumed field only once",
+                        field
+                    ),
+                    span: *span,
+                });
+            }
+        }
+    }
+}
+
+/// Name of a field of the template `self.field`
+fn root_field(e: &syn::Expr) -> Option<String> {
+    match e {
+        syn::Expr::Paren(e) => root_field(&e.expr),
+        syn::Expr::Field(syn::ExprField {
+            base,
+            member: syn::Member::Named(ident),
+            ..
+        }) => match &**base {
+            syn::Expr::Path(p) if p.path.is_ident("self") => Some(ident.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Uses of a field of the template in the lowered template
+struct Count<'a> {
+    field: &'a str,
+    uses: usize,
+}
+
+impl<'a> Count<'a> {
+    fn visit_hir(&mut self, buf: &[HIR]) {
+        for i in buf {
+            match i {
+                HIR::Expr(e) | HIR::Safe(e) => self.visit_expr(e),
+                HIR::Local(l) => self.visit_local(l),
+                HIR::Each(each) => {
+                    self.visit_expr(&each.args);
+                    self.visit_hir(&each.body);
+                }
+                HIR::IfElse(if_else) => {
+                    self.visit_expr(&if_else.ifs.0);
+                    self.visit_hir(&if_else.ifs.1);
+                    for (e, body) in &if_else.if_else {
+                        self.visit_expr(e);
+                        self.visit_hir(body);
+                    }
+                    if let Some(els) = &if_else.els {
+                        self.visit_hir(els);
+                    }
+                }
+                HIR::Lit(_) => (),
+            }
+        }
+    }
+}
+
+impl<'a, 'v> Visit<'v> for Count<'a> {
+    fn visit_expr(&mut self, e: &'v syn::Expr) {
+        match root_field(e) {
+            Some(field) if field == self.field => self.uses += 1,
+            _ => syn::visit::visit_expr(self, e),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use quote::{format_ident, quote};
+use syn::parse2;
+
+use yarte_parser::source_map::Span;
+
+use super::{yarte_module, Generator, Mode, HIR};
+
+impl<'a> Generator<'a> {
+    /// Start of a block with a coverage region, empty without the `coverage` or `profile` feature
+    ///
+    /// Regions are conditional branches, each bodies and partials. With `profile`
+    /// the region is timed in debug builds until the end of its block. With `context`
+    /// the region is entered in debug builds until its `region_leave`.
+    pub(super) fn region(&mut self, kind: &str, span: Span) -> Vec<HIR> {
+        if !self.has_regions() {
+            return vec![];
+        }
+
+        let region = self.regions.len();
+        let label = format!("{} at {}", kind, self.location(span));
+        self.regions.push(label.clone());
+        let template = self.s.ident.to_string();
+
+        let mut buf = vec![];
+        if cfg!(feature = "coverage") {
+            buf.push(local(quote!(
+                let _ = ::yarte::coverage::hit(#template, #region, &[]);
+            )));
+        }
+        if cfg!(feature = "profile") {
+            let timer = timer(region);
+            buf.push(local(quote!(
+                #[cfg(debug_assertions)]
+                let #timer = ::yarte::profile::enter(#template, #region, #label);
+            )));
+        }
+        if cfg!(feature = "context") {
+            buf.push(local(quote!(
+                #[cfg(debug_assertions)]
+                let _ = ::yarte::context::enter(#region, #label);
+            )));
+        }
+        buf
+    }
+
+    /// End of the body of a region, the context of the errors is the region entered last
+    /// and not left
+    pub(super) fn region_leave(&self, body: &mut Vec<HIR>) {
+        if !cfg!(feature = "context") || !self.has_regions() {
+            return;
+        }
+
+        body.push(local(quote!(
+            #[cfg(debug_assertions)]
+            let _ = ::yarte::context::leave();
+        )));
+    }
+
+    /// End of a region not ending with its block, the partials
+    pub(super) fn region_end(&mut self, buf: &mut Vec<HIR>, region: usize) {
+        if !(cfg!(feature = "profile") || cfg!(feature = "context")) || !self.has_regions() {
+            return;
+        }
+
+        self.write_buf_writable(buf);
+        if cfg!(feature = "profile") {
+            let timer = timer(region);
+            buf.push(local(quote!(
+                #[cfg(debug_assertions)]
+                let _ = ::yarte::profile::leave(#timer);
+            )));
+        }
+        self.region_leave(buf);
+    }
+
+    /// Labels of every region in the hits, each hit registers the regions of the template
+    pub(super) fn regions(&self, buf: &mut [HIR]) {
+        if !cfg!(feature = "coverage") || self.regions.is_empty() {
+            return;
+        }
+
+        let labels = &self.regions;
+        set_labels(buf, &parse2(quote!(&[#(#labels),*])).unwrap());
+    }
+
+    fn has_regions(&self) -> bool {
+        (cfg!(feature = "coverage") || cfg!(feature = "profile") || cfg!(feature = "context"))
+            && self.s.mode != Mode::WASM
+    }
+
+    fn location(&self, span: Span) -> String {
+        let mut prefix = self.c.get_dir().clone();
+        prefix.pop();
+        let path = span.file_path();
+        let start = span.start();
+
+        format!(
+            "{}:{}",
+            path.strip_prefix(&prefix).unwrap_or(&path).display(),
+            start.line
+        )
+    }
+}
+
+fn timer(region: usize) -> syn::Ident {
+    format_ident!("__yarte_profile_{}", region)
+}
+
+fn set_labels(buf: &mut [HIR], labels: &syn::Expr) {
+    for i in buf {
+        match i {
+            HIR::Local(local) => {
+                if let Some((_, init)) = &mut local.init {
+                    if let syn::Expr::Call(call) = &mut **init {
+                        if yarte_module(&call.func).map_or(false, |x| x == "coverage") {
+                            call.args[2] = labels.clone();
+                        }
+                    }
+                }
+            }
+            HIR::Each(each) => set_labels(&mut each.body, labels),
+            HIR::IfElse(if_else) => {
+                set_labels(&mut if_else.ifs.1, labels);
+                for (_, body) in &mut if_else.if_else {
+                    set_labels(body, labels);
+                }
+                if let Some(els) = &mut if_else.els {
+                    set_labels(els, labels);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+pub(super) fn local(tokens: proc_macro2::TokenStream) -> HIR {
+    match parse2(tokens).unwrap() {
+        syn::Stmt::Local(local) => HIR::Local(Box::new(local)),
+        _ => unreachable!(),
+    }
+}
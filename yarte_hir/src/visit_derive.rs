@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{mem, path::PathBuf};
 
 use quote::quote;
 use syn::visit::Visit;
@@ -19,13 +19,37 @@ pub struct Struct<'a> {
     pub print: Print,
     pub mode: Mode,
     pub err_msg: String,
+    pub error_template: Option<String>,
     pub msgs: Option<ItemEnum>,
     pub script: Option<String>,
+    pub css: Option<PathBuf>,
+    pub formatter: Option<String>,
+    pub csp: bool,
+    pub escape: Escape,
+    pub keep_comments: bool,
+    pub once: bool,
+    pub scoped: bool,
+    pub strict_html: bool,
     pub fields: Vec<syn::Field>,
     pub ident: &'a syn::Ident,
+    /// representation of the template, when it's one of several
+    pub representation: Option<Representation>,
+    /// other representations, rendered by inherent methods
+    pub alternates: Vec<Alternate>,
+    /// fields bound by the generated `with_{fields}`, the rest are rendered later
+    pub bind: Vec<syn::Ident>,
     generics: &'a syn::Generics,
 }
 
+/// Other representation of the context of a template `#[template(html = "..", json = "..")]`
+#[derive(Debug)]
+pub struct Alternate {
+    pub representation: Representation,
+    pub path: PathBuf,
+    pub src: String,
+    pub mode: Mode,
+}
+
 impl<'a> Struct<'a> {
     pub fn implement_head(&self, t: TokenStream, body: &TokenStream) -> TokenStream {
         let Struct {
@@ -33,32 +57,94 @@ impl<'a> Struct<'a> {
         } = *self;
         let (impl_generics, orig_ty_generics, where_clause) = generics.split_for_impl();
 
-        quote!(impl#impl_generics #t for #ident #orig_ty_generics #where_clause { #body })
+        quote!(
+            #[automatically_derived]
+            impl#impl_generics #t for #ident #orig_ty_generics #where_clause { #body }
+        )
+    }
+
+    pub fn implement_inherent(&self, body: &TokenStream) -> TokenStream {
+        let Struct {
+            ident, generics, ..
+        } = *self;
+        let (impl_generics, orig_ty_generics, where_clause) = generics.split_for_impl();
+
+        quote!(impl#impl_generics #ident #orig_ty_generics #where_clause { #body })
+    }
+
+    /// Template of an alternate representation for the same struct
+    pub fn alternate(&self, a: &Alternate) -> Struct<'a> {
+        Struct {
+            src: a.src.clone(),
+            path: a.path.clone(),
+            print: Print::None,
+            mode: a.mode,
+            err_msg: self.err_msg.clone(),
+            error_template: None,
+            msgs: None,
+            script: None,
+            css: None,
+            formatter: self.formatter.clone(),
+            csp: false,
+            escape: Escape::Auto,
+            keep_comments: self.keep_comments,
+            once: false,
+            scoped: false,
+            strict_html: false,
+            fields: self.fields.clone(),
+            ident: self.ident,
+            representation: Some(a.representation),
+            alternates: vec![],
+            bind: vec![],
+            generics: self.generics,
+        }
     }
 }
 
 struct StructBuilder {
+    bind: Option<String>,
+    csp: bool,
+    css: Option<String>,
     err_msg: Option<String>,
+    error_template: Option<String>,
+    escape: Option<String>,
     ext: Option<String>,
     fields: Vec<syn::Field>,
+    formatter: Option<String>,
+    keep_comments: bool,
     mode: Option<String>,
+    once: bool,
     path: Option<String>,
     print: Option<String>,
+    representations: Vec<(Representation, String)>,
+    scoped: bool,
     script: Option<String>,
     src: Option<String>,
+    strict_html: bool,
 }
 
 impl Default for StructBuilder {
     fn default() -> Self {
         StructBuilder {
+            bind: None,
+            csp: false,
+            css: None,
             err_msg: None,
+            error_template: None,
+            escape: None,
             ext: None,
             fields: vec![],
+            formatter: None,
+            keep_comments: false,
             mode: None,
+            once: false,
             path: None,
             print: None,
+            representations: vec![],
+            scoped: false,
             script: None,
             src: None,
+            strict_html: false,
         }
     }
 }
@@ -89,6 +175,53 @@ impl StructBuilder {
 
         self.visit_data(data);
 
+        let bind = self
+            .bind
+            .iter()
+            .flat_map(|x| x.split(|c: char| c == ',' || c.is_whitespace()))
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                let field = self
+                    .fields
+                    .iter()
+                    .filter_map(|f| f.ident.as_ref())
+                    .find(|f| *f == x);
+                field.cloned().unwrap_or_else(|| {
+                    panic!("'bind' field '{}' isn't a named field of the struct", x)
+                })
+            })
+            .collect();
+
+        let mut representations = mem::take(&mut self.representations).into_iter();
+        let representation = representations.next().map(|(r, path)| {
+            if self.src.is_some() || self.path.is_some() {
+                panic!("must specify 'src' or 'path' or representations, not both");
+            }
+            self.path = Some(path);
+            r
+        });
+        let html_mode = match self.mode.as_ref().map(|m| Mode::from(m.clone())) {
+            Some(mode) if representation.is_some() => match mode {
+                Mode::HTML | Mode::HTMLMin => mode,
+                _ => {
+                    panic!("'mode' of a template with representations must be 'html' or 'html-min'")
+                }
+            },
+            _ => Mode::HTMLMin,
+        };
+        let alternates = representations
+            .map(|(representation, path)| {
+                let (path, src) = config.get_template(&path);
+                let mode = representation.mode(html_mode);
+                Alternate {
+                    representation,
+                    path,
+                    src,
+                    mode,
+                }
+            })
+            .collect();
+
         let (path, src) = match (self.src, self.ext) {
             (Some(src), ext) => (
                 config.get_dir().join(
@@ -101,7 +234,11 @@ impl StructBuilder {
             (None, Some(_)) => panic!("'ext' attribute cannot be used with 'path' attribute"),
         };
 
-        let mode = self.mode.map(Into::into).unwrap_or_else(|| {
+        let mode = match representation {
+            Some(r) => Some(r.mode(html_mode)),
+            None => self.mode.map(Into::into),
+        };
+        let mode = mode.unwrap_or_else(|| {
             if let Some(e) = path.extension() {
                 if HTML_EXTENSIONS.contains(&e.to_str().unwrap()) {
                     return Mode::HTMLMin;
@@ -112,18 +249,30 @@ impl StructBuilder {
         });
 
         Struct {
+            csp: self.csp,
+            css: self.css.map(|css| config.get_dir().join(css)),
             err_msg: self
                 .err_msg
                 .unwrap_or_else(|| "Template parsing error".into()),
+            error_template: self.error_template,
+            escape: self.escape.into(),
             fields: self.fields,
+            formatter: self.formatter,
             generics,
             ident,
+            keep_comments: self.keep_comments,
             mode,
             msgs,
+            once: self.once,
             path,
             print: self.print.into(),
+            representation,
+            alternates,
+            scoped: self.scoped,
             script: self.script,
             src,
+            strict_html: self.strict_html,
+            bind,
         }
     }
 }
@@ -165,6 +314,21 @@ impl<'a> Visit<'a> for StructBuilder {
             } else {
                 panic!("attribute 'src' must be string literal");
             }
+        } else if let Some(r) = REPRESENTATIONS.iter().find(|r| path.is_ident(r.name())) {
+            if let syn::Lit::Str(ref s) = lit {
+                if self.representations.iter().any(|(x, _)| x == r) {
+                    panic!("representation '{}' is specified twice", r.name());
+                }
+                self.representations.push((*r, s.value()));
+            } else {
+                panic!("attribute '{}' must be string literal", r.name());
+            }
+        } else if path.is_ident("bind") {
+            if let syn::Lit::Str(ref s) = lit {
+                self.bind = Some(s.value());
+            } else {
+                panic!("attribute 'bind' must be string literal");
+            }
         } else if path.is_ident("print") {
             if let syn::Lit::Str(ref s) = lit {
                 self.print = Some(s.value());
@@ -189,14 +353,68 @@ impl<'a> Visit<'a> for StructBuilder {
             } else {
                 panic!("attribute 'script' must be string literal");
             }
+        } else if path.is_ident("css") {
+            if let syn::Lit::Str(ref s) = lit {
+                self.css = Some(s.value());
+            } else {
+                panic!("attribute 'css' must be string literal");
+            }
+        } else if path.is_ident("escape") {
+            if let syn::Lit::Str(ref s) = lit {
+                self.escape = Some(s.value());
+            } else {
+                panic!("attribute 'escape' must be string literal");
+            }
+        } else if path.is_ident("formatter") {
+            if let syn::Lit::Str(ref s) = lit {
+                self.formatter = Some(s.value());
+            } else {
+                panic!("attribute 'formatter' must be string literal");
+            }
+        } else if path.is_ident("csp") {
+            if let syn::Lit::Bool(ref b) = lit {
+                self.csp = b.value;
+            } else {
+                panic!("attribute 'csp' must be boolean literal");
+            }
+        } else if path.is_ident("once") {
+            if let syn::Lit::Bool(ref b) = lit {
+                self.once = b.value;
+            } else {
+                panic!("attribute 'once' must be boolean literal");
+            }
+        } else if path.is_ident("scoped") {
+            if let syn::Lit::Bool(ref b) = lit {
+                self.scoped = b.value;
+            } else {
+                panic!("attribute 'scoped' must be boolean literal");
+            }
+        } else if path.is_ident("keep_comments") {
+            if let syn::Lit::Bool(ref b) = lit {
+                self.keep_comments = b.value;
+            } else {
+                panic!("attribute 'keep_comments' must be boolean literal");
+            }
+        } else if path.is_ident("strict_html") {
+            if let syn::Lit::Bool(ref b) = lit {
+                self.strict_html = b.value;
+            } else {
+                panic!("attribute 'strict_html' must be boolean literal");
+            }
         } else if cfg!(feature = "actix-web") && path.is_ident("err") {
             if let syn::Lit::Str(ref s) = lit {
                 self.err_msg = Some(s.value());
             } else {
                 panic!("attribute 'err' must be string literal");
             }
+        } else if cfg!(feature = "actix-web") && path.is_ident("error_template") {
+            if let syn::Lit::Str(ref s) = lit {
+                self.error_template = Some(s.value());
+            } else {
+                panic!("attribute 'error_template' must be string literal");
+            }
         } else {
-            panic!("invalid attribute '{:?}'", path.get_ident());
+            panic!("invalid attribute '{}'", quote!(#path));
         }
     }
 }
@@ -223,13 +441,46 @@ impl From<Option<String>> for Print {
     }
 }
 
-#[derive(PartialEq, Debug)]
+/// Escape of the expressions of html templates `#[template(escape = "..")]`
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Escape {
+    /// Formatter with simd escape, the method `call` without formatter
+    Auto,
+    /// Every render through the formatter, with simd escape when it's available
+    Simd,
+    /// Every render without simd escape
+    Scalar,
+}
+
+impl From<Option<String>> for Escape {
+    fn from(s: Option<String>) -> Escape {
+        match s {
+            Some(s) => match s.as_ref() {
+                "simd" => Escape::Simd,
+                "scalar" => Escape::Scalar,
+                v => panic!("invalid value for escape attribute: {}", v),
+            },
+            None => Escape::Auto,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Mode {
     Text,
     HTML,
     HTMLMin,
+    Email,
     WASM,
     WASMServer,
+    JSON,
+}
+
+impl Mode {
+    /// Output without markup, the html passes don't apply
+    pub fn is_text(self) -> bool {
+        self == Mode::Text || self == Mode::JSON
+    }
 }
 
 impl From<String> for Mode {
@@ -238,13 +489,57 @@ impl From<String> for Mode {
             "text" => Mode::Text,
             "html" => Mode::HTML,
             "html-min" => Mode::HTMLMin,
+            "email" => Mode::Email,
             "wasm" | "client" | "front" => Mode::WASM,
             "wasm-server" | "iso" | "server" | "back" => Mode::WASMServer,
+            "json" => Mode::JSON,
             v => panic!("invalid value for mode attribute: {}", v),
         }
     }
 }
 
+/// Representation of the context of a template, for the content negotiation
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Representation {
+    HTML,
+    JSON,
+    Text,
+}
+
+static REPRESENTATIONS: [Representation; 3] = [
+    Representation::HTML,
+    Representation::JSON,
+    Representation::Text,
+];
+
+impl Representation {
+    /// Name of its attribute and of its method `render_{name}`
+    pub fn name(self) -> &'static str {
+        match self {
+            Representation::HTML => "html",
+            Representation::JSON => "json",
+            Representation::Text => "text",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Representation::HTML => "text/html",
+            Representation::JSON => "application/json",
+            Representation::Text => "text/plain",
+        }
+    }
+
+    /// Mode of its template
+    fn mode(self, html: Mode) -> Mode {
+        match self {
+            Representation::HTML => html,
+            Representation::JSON => Mode::JSON,
+            Representation::Text => Mode::Text,
+        }
+    }
+}
+
 static DEFAULT_EXTENSION: &str = "hbs";
 static HTML_EXTENSIONS: [&str; 6] = [
     DEFAULT_EXTENSION,
@@ -1,23 +1,35 @@
-use std::{mem, path::PathBuf};
+use std::{mem, path::PathBuf, ptr};
 
-use syn::visit::Visit;
+use syn::visit::{self, Visit};
 
 use yarte_config::Config;
-use yarte_parser::{Helper, Node, Partial, SNode};
+use yarte_parser::{Arg, Builtin, BuiltinArg, Helper, Node, Once, Partial, SArgs, SNode};
 
-use super::{is_super, Context};
+use super::{find_partial, is_super, Context};
 
-pub(super) fn find_loop_var(c: &Config, ctx: Context, path: PathBuf, nodes: &[SNode]) -> bool {
-    FindEach::new(c, ctx, path).find(nodes)
+/// Find the loop variables used in an each block: index and is last
+pub(super) fn find_loop_var(
+    c: &Config,
+    ctx: Context,
+    path: PathBuf,
+    nodes: &[SNode],
+) -> (bool, bool) {
+    let mut finder = FindEach::new(c, ctx, path);
+    finder.find(nodes);
+    (finder.loop_var, finder.last)
 }
 
-// Find {{ index }} {{ index0 }} {{ first }} {{ _index_[0-9] }}
+// Find {{ index }} {{ index0 }} {{ first }} {{ _index_[0-9] }} {{ @cycle .. }} {{ @first }}
+// {{ @index }}, {{ @nth .. }}, {{ @last }} and {{ @id .. }}
 struct FindEach<'a> {
     loop_var: bool,
+    last: bool,
     c: &'a Config<'a>,
     ctx: Context<'a>,
     on_path: PathBuf,
     on_: usize,
+    each_: usize,
+    partials: Vec<&'a [SNode<'a>]>,
 }
 
 impl<'a> FindEach<'a> {
@@ -27,11 +39,52 @@ impl<'a> FindEach<'a> {
             ctx,
             on_path,
             loop_var: false,
+            last: false,
             on_: 0,
+            each_: 0,
+            partials: vec![],
         }
     }
 
-    pub fn find(&mut self, nodes: &'a [SNode]) -> bool {
+    fn done(&self) -> bool {
+        self.loop_var && self.last
+    }
+
+    /// Find in the arguments and the nodes of a partial, `true` when it's scoped
+    fn partial(&mut self, path: &str, args: &'a SArgs) -> bool {
+        let mut scoped = false;
+        for arg in args.t() {
+            match arg.t() {
+                Arg::Named(_, e) => self.visit_expr(e),
+                Arg::Positional(_) => scoped = true,
+            }
+            if self.done() {
+                break;
+            }
+        }
+        if scoped {
+            return true;
+        }
+
+        // The block of `@partial-block` is found in the partial block
+        if let Some((p, _, nodes)) = find_partial(self.c, self.ctx, &self.on_path, path) {
+            // A recursive partial is found in its first call
+            if self.partials.iter().any(|x| ptr::eq(*x, nodes)) {
+                return false;
+            }
+            let parent = mem::replace(&mut self.on_path, p);
+            self.partials.push(nodes);
+
+            self.find(nodes);
+
+            self.partials.pop();
+            self.on_path = parent;
+        }
+
+        false
+    }
+
+    pub fn find(&mut self, nodes: &'a [SNode]) {
         for n in nodes {
             match n.t() {
                 Node::Local(expr) => self.visit_local(expr.t()),
@@ -39,98 +92,142 @@ impl<'a> FindEach<'a> {
                 Node::Helper(h) => {
                     let h: &Helper = &*h;
                     match h {
-                        Helper::If((_, first, block), else_if, els) => {
+                        Helper::If((_, first, block), else_if, els)
+                        | Helper::Unless((_, first, block), else_if, els) => {
                             self.visit_expr(first.t());
-                            if self.loop_var {
+                            if self.done() {
                                 break;
                             }
                             self.find(block);
                             for (_, e, b) in else_if {
-                                if self.loop_var {
+                                if self.done() {
                                     break;
                                 }
 
                                 self.visit_expr(e.t());
-                                if self.loop_var {
+                                if self.done() {
                                     break;
                                 }
 
                                 self.find(b);
                             }
-                            if self.loop_var {
+                            if self.done() {
                                 break;
                             }
                             if let Some((_, els)) = els {
                                 self.find(els);
                             }
                         }
-                        Helper::With(_, e, b) => {
+                        Helper::With(_, e, _, b) => {
                             self.visit_expr(e.t());
-                            if self.loop_var {
+                            if self.done() {
                                 break;
                             }
                             self.on_ += 1;
                             self.find(b);
                             self.on_ -= 1;
                         }
-                        Helper::Unless(_, expr, block) => {
+                        Helper::Portal(_, e, _, b) => {
+                            self.visit_expr(e.t());
+                            if self.done() {
+                                break;
+                            }
+                            self.find(b);
+                        }
+                        Helper::Each(_, expr, _, _, block, els) => {
                             self.visit_expr(expr.t());
-                            if self.loop_var {
+                            if self.done() {
                                 break;
                             }
+                            self.on_ += 1;
+                            self.each_ += 1;
                             self.find(block);
+                            self.each_ -= 1;
+                            self.on_ -= 1;
+                            // The error arm is out of the loop
+                            if let Some((_, _, els)) = els {
+                                self.find(els);
+                            }
                         }
-                        Helper::Each(_, expr, block) => {
+                        Helper::Defined(_, name, expr, _, block) => {
                             self.visit_expr(expr.t());
-                            if self.loop_var {
+                            if self.done() {
                                 break;
                             }
+                            // A condition is not a loop
+                            let each = self.c.condition(name).is_none() as usize;
                             self.on_ += 1;
+                            self.each_ += each;
                             self.find(block);
+                            self.each_ -= each;
                             self.on_ -= 1;
                         }
-                        Helper::Defined(..) => unimplemented!(),
                     }
                 }
-                Node::Partial(Partial(_, path, expr)) => {
-                    let p = self.c.resolve_partial(&self.on_path, path.t());
-                    let nodes = self.ctx.get(&p).unwrap();
-                    let expr = expr.t();
-                    if !expr.is_empty() {
-                        let at = if let syn::Expr::Assign(_) = expr[0] {
-                            0
-                        } else {
-                            1
-                        };
-                        for e in &expr[at..] {
-                            self.visit_expr(e);
-                            if self.loop_var {
-                                break;
-                            }
-                        }
-                        if at == 1 {
-                            break;
-                        }
+                Node::Partial(Partial(_, path, args)) => {
+                    if self.partial(path.t(), args) {
+                        break;
+                    }
+                }
+                Node::PartialBlock(_, path, args, block) => {
+                    self.find(block);
+                    if self.partial(path.t(), args) {
+                        break;
                     }
-
-                    let parent = mem::replace(&mut self.on_path, p);
-
-                    self.find(nodes);
-
-                    self.on_path = parent;
                 }
-                Node::Raw(..) | Node::Lit(..) | Node::Comment(_) => (),
+                Node::Raw(..)
+                | Node::Lit(..)
+                | Node::Comment(_)
+                | Node::Inline(..)
+                | Node::Const(_)
+                | Node::Keep(_) => (),
             }
-            if self.loop_var {
+            if self.done() {
                 break;
             }
         }
-        self.loop_var
     }
 }
 
-impl<'a> Visit<'a> for FindEach<'a> {
-    fn visit_expr_path(&mut self, i: &'a syn::ExprPath) {
+impl<'a> FindEach<'a> {
+    fn visit_builtin(&mut self, b: &Builtin) {
+        // Ids have the indexes of all the enclosing loops
+        if b.ident == "id" {
+            self.loop_var = true;
+        }
+        if self.each_ == 0 {
+            match b.ident.to_string().as_str() {
+                "cycle" | "first" | "index" | "nth" => self.loop_var = true,
+                "last" => {
+                    self.loop_var = true;
+                    self.last = true;
+                }
+                _ => (),
+            }
+        }
+        for arg in &b.args {
+            match arg {
+                BuiltinArg::Expr(e) | BuiltinArg::Named(_, e) => self.visit_expr(e),
+                BuiltinArg::Sub(b) => self.visit_builtin(b),
+            }
+        }
+    }
+}
+
+impl<'a, 'v> Visit<'v> for FindEach<'a> {
+    fn visit_expr(&mut self, i: &'v syn::Expr) {
+        if let syn::Expr::Verbatim(tokens) = i {
+            if let Ok(b) = syn::parse2::<Builtin>(tokens.clone()) {
+                self.visit_builtin(&b);
+            } else if let Ok(Once(e)) = syn::parse2(tokens.clone()) {
+                self.visit_expr(&e);
+            }
+        } else {
+            visit::visit_expr(self, i);
+        }
+    }
+
+    fn visit_expr_path(&mut self, i: &'v syn::ExprPath) {
         macro_rules! search {
             ($ident:expr) => {
                 match $ident.as_ref() {
@@ -0,0 +1,75 @@
+use quote::quote;
+use syn::{parse2, visit_mut::VisitMut};
+
+use yarte_parser::Modifier;
+
+use super::{Generator, On};
+
+/// Each helper modifiers and if they need a value
+pub(super) const MODIFIERS: &[(&str, bool)] = &[
+    ("chunks", true),
+    ("group_by", true),
+    ("limit", true),
+    ("reverse", false),
+    ("skip", true),
+    ("sort_by", true),
+];
+
+impl<'a> Generator<'a> {
+    /// Lower each helper modifiers to iterator adapters over `iter`
+    pub(super) fn visit_modifiers(
+        &mut self,
+        mut iter: syn::Expr,
+        modifiers: &[Modifier],
+        id: usize,
+    ) -> syn::Expr {
+        for Modifier(ident, value) in modifiers {
+            let tokens = match (ident.to_string().as_str(), value) {
+                ("chunks", Some(size)) => {
+                    let mut size = size.clone();
+                    self.visit_expr_mut(&mut size);
+                    quote!(::yarte::builtins::chunks(#iter, #size))
+                }
+                ("group_by", Some(key)) => {
+                    let key = self.item_closure(key, id);
+                    quote!(::yarte::builtins::group_by(#iter, #key))
+                }
+                ("limit", Some(n)) => {
+                    let mut n = n.clone();
+                    self.visit_expr_mut(&mut n);
+                    quote!(::core::iter::Iterator::take(#iter, #n))
+                }
+                ("reverse", None) => quote!(::core::iter::Iterator::rev(#iter)),
+                ("skip", Some(n)) => {
+                    let mut n = n.clone();
+                    self.visit_expr_mut(&mut n);
+                    quote!(::core::iter::Iterator::skip(#iter, #n))
+                }
+                ("sort_by", Some(key)) => {
+                    let key = self.item_closure(key, id);
+                    quote!(::yarte::builtins::sort_by(#iter, #key))
+                }
+                // Reported by validator
+                _ => continue,
+            };
+            iter = parse2(tokens).expect("Correct each modifier");
+        }
+
+        iter
+    }
+
+    /// Closure over the loop item `|item| &(expr)`, with `expr` resolved in the scope of the item
+    fn item_closure(&mut self, expr: &syn::Expr, id: usize) -> syn::Expr {
+        self.scp.push_scope(vec![]);
+        let item = self.scp.push_ident("__item_");
+        self.on.push(On::Each(id));
+
+        let mut expr = expr.clone();
+        self.visit_expr_mut(&mut expr);
+
+        self.on.pop();
+        self.scp.pop();
+
+        parse2(quote!(|#item| &(#expr))).expect("Correct closure")
+    }
+}
@@ -0,0 +1,45 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse2, visit_mut::VisitMut};
+
+use yarte_parser::Once;
+
+use super::{visit_coverage::local, Generator, HIR};
+
+impl<'a> Generator<'a> {
+    /// Scope of the `#once` expressions, the render or a loop body
+    pub(super) fn once_scope(&mut self) {
+        self.once.push(vec![]);
+    }
+
+    /// Declare the values of the `#once` expressions of the scope at the start of `buf`
+    pub(super) fn once_locals(&mut self, buf: &mut Vec<HIR>) {
+        let scope = self.once.pop().expect("some once scope");
+        let locals = scope
+            .into_iter()
+            .map(|(_, ident)| local(quote!(let #ident = ::yarte::builtins::Once::new();)));
+        buf.splice(0..0, locals);
+    }
+
+    /// Lower a `#once` expression to the value of its scope, the same expressions share it
+    ///
+    /// Returns `None` for other verbatim expressions
+    pub(super) fn visit_once(&mut self, tokens: &TokenStream) -> Option<syn::Expr> {
+        let Once(mut expr) = parse2(tokens.clone()).ok()?;
+        self.visit_expr_mut(&mut expr);
+
+        let key = quote!(#expr).to_string();
+        let depth = self.once.len() - 1;
+        let scope = self.once.last_mut().expect("some once scope");
+        let ident = match scope.iter().find(|(x, _)| *x == key) {
+            Some((_, ident)) => ident.clone(),
+            None => {
+                let ident = format_ident!("__once_{}_{}", depth, scope.len());
+                scope.push((key, ident.clone()));
+                ident
+            }
+        };
+
+        Some(parse2(quote!((*#ident.get_or_init(|| #expr)))).unwrap())
+    }
+}
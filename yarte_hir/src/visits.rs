@@ -4,7 +4,7 @@ use syn::{
     visit_mut::{self, VisitMut},
 };
 
-use super::Generator;
+use super::{ErrorMessage, Generator};
 
 impl<'a> VisitMut for Generator<'a> {
     fn visit_arm_mut(
@@ -37,6 +37,14 @@ impl<'a> VisitMut for Generator<'a> {
                     .resolve_path(&i)
                     .expect("Correct resolve path expression");
             }
+            Verbatim(tokens) => {
+                if let Some(e) = self
+                    .visit_once(tokens)
+                    .or_else(|| self.visit_builtin(tokens))
+                {
+                    *expr = e;
+                }
+            }
             a => visit_mut::visit_expr_mut(self, a),
         };
     }
@@ -63,7 +71,19 @@ impl<'a> VisitMut for Generator<'a> {
     }
 
     fn visit_expr_async_mut(&mut self, _i: &mut syn::ExprAsync) {
-        panic!("Not available async in a template expression");
+        self.errors.push(ErrorMessage {
+            message: "Not available async in a template expression".to_string(),
+            span: self.span,
+        });
+    }
+
+    fn visit_expr_await_mut(&mut self, _i: &mut syn::ExprAwait) {
+        self.errors.push(ErrorMessage {
+            message: "Not available await in a template expression, templates aren't rendered \
+                      async"
+                .to_string(),
+            span: self.span,
+        });
     }
 
     fn visit_expr_call_mut(&mut self, syn::ExprCall { func, args, .. }: &mut syn::ExprCall) {
@@ -82,8 +102,12 @@ impl<'a> VisitMut for Generator<'a> {
             ..
         }: &mut syn::ExprClosure,
     ) {
-        if let Some(..) = asyncness {
-            panic!("Not available async in template expression");
+        if asyncness.is_some() {
+            self.errors.push(ErrorMessage {
+                message: "Not available async in a template expression".to_string(),
+                span: self.span,
+            });
+            return;
         };
 
         self.scp.push_scope(vec![]);
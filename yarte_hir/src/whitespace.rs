@@ -0,0 +1,145 @@
+//! Whitespace of the literals around the template nodes, adapted from
+//! [`askama`](https://github.com/djc/askama)
+//!
+//! The parser splits each literal in its leading whitespace, its text and its trailing
+//! whitespace, and reads the `~` of each node as a `Ws(left, right)` pair. Lowering is
+//! the same in every mode:
+//!
+//! - the trailing whitespace of a literal is pending until the next node, a `~` at the
+//!   left of that node drops it
+//! - a `~` at the right of a node drops the leading whitespace of the next literal
+//! - comments and locals drop the whitespace on both sides
+//! - a literal of only whitespace is pending as a whole, its leading and trailing
+//!   whitespace are the same
+//!
+//! Text and `html` modes write the rest as is. `html-min` and the other modes with html
+//! passes minify the resulting html afterwards, by the serializer of `yarte_dom`: the
+//! whitespace at the edges of the elements is dropped, the whitespace between text,
+//! expressions and inline elements and inside `<pre>` is kept, so a `~` only changes
+//! the output there. The golden tests of each mode are in `yarte/tests/whitespace.rs`.
+use yarte_parser::Ws;
+
+/// Pending whitespace of the lowering of a template
+#[derive(Debug, Default)]
+pub(crate) struct Whitespace<'a> {
+    /// Trailing whitespace of the previous literal
+    next: Option<&'a str>,
+    /// Leading whitespace of the next literal is dropped
+    skip: bool,
+}
+
+impl<'a> Whitespace<'a> {
+    /// Without pending whitespace
+    pub(crate) fn is_empty(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Literal split by the parser, returns the leading whitespace to write before it
+    pub(crate) fn lit(&mut self, lws: &'a str, lit: &'a str, rws: &'a str) -> Option<&'a str> {
+        debug_assert!(self.is_empty());
+        let mut write = None;
+        if !lws.is_empty() {
+            if self.skip {
+                self.skip = false;
+            } else if lit.is_empty() {
+                debug_assert!(rws.is_empty());
+                self.next = Some(lws);
+            } else {
+                write = Some(lws);
+            }
+        }
+
+        if !rws.is_empty() {
+            self.next = Some(rws);
+        }
+
+        write
+    }
+
+    /// Drop the whitespace on both sides, of comments and locals
+    pub(crate) fn skip(&mut self) {
+        self.next = None;
+        self.skip = true;
+    }
+
+    /// Node with `~` in `ws`, returns the pending whitespace to write before it
+    pub(crate) fn handle(&mut self, ws: Ws) -> Option<&'a str> {
+        let write = self.flush(ws);
+        self.prepare(ws);
+        write
+    }
+
+    /// Pending whitespace to write, unless the node has `~` at its left
+    pub(crate) fn flush(&mut self, ws: Ws) -> Option<&'a str> {
+        self.next.take().filter(|x| !ws.0 && !x.is_empty())
+    }
+
+    /// Drop the leading whitespace of the next literal when the node has `~` at its right
+    pub(crate) fn prepare(&mut self, ws: Ws) {
+        self.skip = ws.1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Written parts of the literals, `|` for each node with its `Ws`
+    fn lower(parts: &[Result<(&'static str, &'static str, &'static str), Ws>]) -> String {
+        let mut ws = Whitespace::default();
+        let mut out = String::new();
+        for part in parts {
+            match *part {
+                Ok((l, lit, r)) => {
+                    out.extend(ws.lit(l, lit, r));
+                    out.push_str(lit);
+                }
+                Err(w) => {
+                    out.extend(ws.handle(w));
+                    out.push('|');
+                }
+            }
+        }
+        out.extend(ws.flush((false, false)));
+
+        out
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let lit = |l, lit, r| Ok((l, lit, r));
+        let a = lit(" ", "a", " ");
+        let b = lit(" ", "b", " ");
+        let node = |l, r| Err((l, r));
+        let cases = [
+            ((false, false), " a | b "),
+            ((true, false), " a| b "),
+            ((false, true), " a |b "),
+            ((true, true), " a|b "),
+        ];
+        for (w, expected) in cases.iter() {
+            assert_eq!(lower(&[a.clone(), node(w.0, w.1), b.clone()]), *expected);
+        }
+
+        let blank = lit("\n  ", "", "");
+        assert_eq!(
+            lower(&[node(false, false), blank.clone(), node(false, false)]),
+            "|\n  |"
+        );
+        assert_eq!(
+            lower(&[node(false, true), blank.clone(), node(false, false)]),
+            "||"
+        );
+        assert_eq!(lower(&[node(false, false), blank, node(true, false)]), "||");
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut ws = Whitespace::default();
+        assert_eq!(ws.lit(" ", "a", " "), Some(" "));
+        ws.skip();
+        assert!(ws.is_empty());
+        assert_eq!(ws.lit(" ", "b", ""), None);
+        assert_eq!(ws.lit(" ", "c", ""), Some(" "));
+    }
+}
@@ -0,0 +1,233 @@
+use syn::{
+    parse::{ParseStream, Parser},
+    Ident, Result, Token,
+};
+
+use yarte_ast::{Arg, SArg};
+
+use crate::{
+    source_map::{BytePos, Span, S},
+    trim,
+};
+
+/// Argument of a partial `expr` or `name = expr`
+fn arg(input: ParseStream) -> Result<Arg> {
+    if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+        let ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(Arg::Named(ident, input.parse()?))
+    } else {
+        Ok(Arg::Positional(input.parse()?))
+    }
+}
+
+/// Parse a comma separated list of arguments, `lo` is the offset of `src` in the source map
+///
+/// The list is split at the commas out of literals and brackets, and the pieces
+/// are joined while they aren't an argument, as in a turbofish `::<A, B>`.
+/// A trailing comma is allowed.
+pub fn parse_args(src: &str, lo: BytePos) -> Result<Vec<SArg>> {
+    let (pieces, _) = scan(src);
+    let len = pieces.len();
+    let mut pieces = pieces.into_iter().enumerate();
+    let mut args = vec![];
+    while let Some((i, (start, mut end))) = pieces.next() {
+        let mut first = None;
+        loop {
+            let (l, s, _) = trim(&src[start..end]);
+            if s.is_empty() && i + 1 == len && first.is_none() {
+                break;
+            }
+            match arg.parse_str(s) {
+                Ok(arg) => {
+                    let lo = lo + (start + l.len()) as BytePos;
+                    let hi = lo + s.len() as BytePos;
+                    args.push(S(arg, Span { lo, hi }));
+                    break;
+                }
+                Err(e) => {
+                    let e = first.get_or_insert(e).clone();
+                    match pieces.next() {
+                        Some((_, (_, next))) => end = next,
+                        None => return Err(e),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+/// Whether `src` ends inside a string or char literal
+pub(crate) fn is_open(src: &str) -> bool {
+    scan(src).1
+}
+
+/// Byte ranges between the commas out of literals and brackets,
+/// and whether the last literal isn't closed
+fn scan(src: &str) -> (Vec<(usize, usize)>, bool) {
+    let b = src.as_bytes();
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut i = 0;
+    let mut open = false;
+    while i < b.len() {
+        let end = match b[i] {
+            b'"' => end_str(b, i + 1, 0),
+            b'r' if is_raw(b, i) => {
+                let hashes = b[i + 1..].iter().take_while(|x| **x == b'#').count();
+                end_str(b, i + hashes + 2, hashes)
+            }
+            b'\'' => end_char(src, i + 1),
+            b'(' | b'[' | b'{' => {
+                depth += 1;
+                Some(i)
+            }
+            b')' | b']' | b'}' => {
+                depth = depth.saturating_sub(1);
+                Some(i)
+            }
+            b',' if depth == 0 => {
+                pieces.push((start, i));
+                start = i + 1;
+                Some(i)
+            }
+            _ => Some(i),
+        };
+        match end {
+            Some(j) => i = j + 1,
+            None => {
+                open = true;
+                break;
+            }
+        }
+    }
+    pieces.push((start, b.len()));
+
+    (pieces, open)
+}
+
+/// Raw string `r"..."`, `r#"..."#` or `br"..."`
+fn is_raw(b: &[u8], i: usize) -> bool {
+    let ident = |x: u8| x == b'_' || x.is_ascii_alphanumeric();
+    let prefix = i == 0 || !ident(b[i - 1]) || (b[i - 1] == b'b' && (i == 1 || !ident(b[i - 2])));
+    prefix && b[i + 1..].iter().find(|x| **x != b'#') == Some(&b'"')
+}
+
+/// Position of the closing quote of a string followed by `hashes` `#`
+fn end_str(b: &[u8], mut i: usize, hashes: usize) -> Option<usize> {
+    while i < b.len() {
+        match b[i] {
+            b'\\' if hashes == 0 => i += 1,
+            b'"' if i + hashes < b.len() && b[i + 1..=i + hashes].iter().all(|x| *x == b'#') => {
+                return Some(i + hashes);
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Position of the closing quote of a char literal, a lifetime hasn't it
+fn end_char(src: &str, i: usize) -> Option<usize> {
+    let mut chars = src[i..].char_indices();
+    match chars.next() {
+        Some((_, '\\')) => src[i + 1..].find('\'').map(|j| {
+            // escaped quote `'\''`
+            if j == 0 {
+                i + 2
+            } else {
+                i + 1 + j
+            }
+        }),
+        Some(_) => match chars.next() {
+            Some((j, '\'')) => Some(i + j),
+            _ => Some(i - 1),
+        },
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+
+    fn args(src: &str) -> Vec<(Arg, (u32, u32))> {
+        parse_args(src, 0)
+            .unwrap()
+            .into_iter()
+            .map(|a| {
+                let span = a.span();
+                (a.t().clone(), (span.lo, span.hi))
+            })
+            .collect()
+    }
+
+    fn named(ident: &str, e: &str) -> Arg {
+        Arg::Named(parse_str(ident).unwrap(), parse_str(e).unwrap())
+    }
+
+    #[test]
+    fn test() {
+        let src = "bar, foo = \"bar,\"\n, fuu = 1  , goo = true,    ";
+        let expected = vec![
+            (Arg::Positional(parse_str("bar").unwrap()), (0, 3)),
+            (named("foo", "\"bar,\""), (5, 17)),
+            (named("fuu", "1"), (20, 27)),
+            (named("goo", "true"), (31, 41)),
+        ];
+
+        assert_eq!(args(src), expected);
+        assert_eq!(args(src.trim_end().trim_end_matches(',')), expected);
+        assert_eq!(args(""), vec![]);
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            args(r##"a = 'a', b = ',', c = '\'', d = r#",}}"#, e = b',' "##)
+                .into_iter()
+                .map(|(a, _)| a)
+                .collect::<Vec<_>>(),
+            vec![
+                named("a", "'a'"),
+                named("b", "','"),
+                named("c", r"'\''"),
+                named("d", r###"r#",}}"#"###),
+                named("e", "b','"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_turbofish() {
+        assert_eq!(
+            args("foo = bar.into_iter().collect::<HashMap<K, V>>(), b = a == 1"),
+            vec![
+                (
+                    named("foo", "bar.into_iter().collect::<HashMap<K, V>>()"),
+                    (0, 48)
+                ),
+                (named("b", "a == 1"), (50, 60)),
+            ]
+        );
+        assert_eq!(
+            args("bar == 1"),
+            vec![(Arg::Positional(parse_str("bar == 1").unwrap()), (0, 8))]
+        );
+    }
+
+    #[test]
+    fn test_fail() {
+        assert!(parse_args("a,, b", 0).is_err());
+        assert!(parse_args(",", 0).is_err());
+        assert!(parse_args("a = \"b", 0).is_err());
+        assert!(is_open("a = \"b }}"));
+        assert!(!is_open("a = \"b }}\""));
+    }
+}
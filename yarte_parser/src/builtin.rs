@@ -0,0 +1,225 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream, Parser},
+    token, Expr, Ident, Result, Token,
+};
+
+use crate::call;
+
+/// Built-in helper call `@name arg0 arg1 key=value (@sub ..)`
+///
+/// Arguments are separated by whitespace or commas, complex expressions must be
+/// wrapped in parentheses. It's carried by the `syn::Expr::Verbatim` variant.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Builtin {
+    pub ident: Ident,
+    pub args: Vec<BuiltinArg>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum BuiltinArg {
+    Expr(Expr),
+    Named(Ident, Expr),
+    Sub(Builtin),
+}
+
+impl Builtin {
+    /// Positional arguments
+    pub fn positional(&self) -> impl Iterator<Item = &BuiltinArg> {
+        self.args.iter().filter(|x| match x {
+            BuiltinArg::Named(..) => false,
+            _ => true,
+        })
+    }
+
+    /// Named argument by name
+    pub fn named(&self, name: &str) -> Option<&Expr> {
+        self.args.iter().find_map(|x| match x {
+            BuiltinArg::Named(ident, e) if ident == name => Some(e),
+            _ => None,
+        })
+    }
+}
+
+impl Parse for Builtin {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![@]>()?;
+        let ident = input.parse()?;
+        let mut args = vec![];
+        while !input.is_empty() {
+            if is_sub(input) {
+                let content;
+                parenthesized!(content in input);
+                args.push(BuiltinArg::Sub(content.parse()?));
+            } else {
+                let segment = input.step(|cursor| {
+                    let mut rest = *cursor;
+                    let mut tokens = TokenStream::new();
+                    while let Some((tt, next)) = rest.token_tree() {
+                        if is_sub_tree(&tt) {
+                            break;
+                        }
+                        tokens.extend(Some(tt));
+                        rest = next;
+                    }
+                    Ok((tokens, rest))
+                })?;
+                args.extend(arguments.parse2(segment)?);
+            }
+        }
+
+        Ok(Builtin { ident, args })
+    }
+}
+
+fn arguments(input: ParseStream) -> Result<Vec<BuiltinArg>> {
+    let mut args = vec![];
+    while !input.is_empty() {
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        } else if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+            let ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            args.push(BuiltinArg::Named(ident, input.parse()?));
+        } else {
+            args.push(BuiltinArg::Expr(input.call(call::argument)?));
+        }
+    }
+
+    Ok(args)
+}
+
+/// Separate with a comma each parenthesized argument preceded by whitespace,
+/// so `@name arg0 (arg1)` isn't parsed as the call `arg0(arg1)`, also in the
+/// subexpressions `(name arg0 (arg1))`
+pub(super) fn separate(src: &str) -> String {
+    let mut out = String::with_capacity(src.len() + 4);
+    let mut chars = src.chars();
+    // whether each open bracket is a subexpression
+    let mut subs = vec![];
+    let mut last = ' ';
+    let mut ws = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    if c == '\\' {
+                        out.extend(chars.next());
+                    } else if c == '"' {
+                        break;
+                    }
+                }
+            }
+            '(' | '[' | '{' => {
+                if c == '(' && *subs.last().unwrap_or(&true) && ws && is_operand_end(last) {
+                    out.push(',');
+                }
+                subs.push(c == '(' && call::is_call_src(chars.as_str()));
+                out.push(c);
+            }
+            ')' | ']' | '}' => {
+                subs.pop();
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        ws = c.is_whitespace();
+        if !ws {
+            last = c;
+        }
+    }
+
+    out
+}
+
+fn is_operand_end(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == ')' || c == ']' || c == '"' || c == '\''
+}
+
+fn is_sub(input: ParseStream) -> bool {
+    input.peek(token::Paren)
+        && input
+            .cursor()
+            .token_tree()
+            .map_or(false, |(tt, _)| is_sub_tree(&tt))
+}
+
+pub(crate) fn is_sub_tree(tt: &TokenTree) -> bool {
+    match tt {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
+            match g.stream().into_iter().next() {
+                Some(TokenTree::Punct(p)) => p.as_char() == '@',
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+
+    #[test]
+    fn test() {
+        let src = "@paginate current total url_fn(1) window = 3 (@len items) (a == b)";
+        let expected = Builtin {
+            ident: parse_str("paginate").unwrap(),
+            args: vec![
+                BuiltinArg::Expr(parse_str("current").unwrap()),
+                BuiltinArg::Expr(parse_str("total").unwrap()),
+                BuiltinArg::Expr(parse_str("url_fn(1)").unwrap()),
+                BuiltinArg::Named(parse_str("window").unwrap(), parse_str("3").unwrap()),
+                BuiltinArg::Sub(Builtin {
+                    ident: parse_str("len").unwrap(),
+                    args: vec![BuiltinArg::Expr(parse_str("items").unwrap())],
+                }),
+                BuiltinArg::Expr(parse_str("(a == b)").unwrap()),
+            ],
+        };
+
+        assert_eq!(parse_str::<Builtin>(src).unwrap(), expected);
+        assert_eq!(expected.positional().count(), 5);
+        assert_eq!(expected.named("window"), Some(&parse_str("3").unwrap()));
+        assert!(expected.named("foo").is_none());
+    }
+
+    #[test]
+    fn test_separate() {
+        assert_eq!(separate("@foo a (b) c(d)"), "@foo a ,(b) c(d)");
+        assert_eq!(separate("@foo (a) = (b)"), "@foo ,(a) = (b)");
+        assert_eq!(
+            separate("@foo \"(\\\" (\" (|a| (a))"),
+            "@foo \"(\\\" (\" ,(|a| (a))"
+        );
+        assert_eq!(separate("@foo [a (b)] + (c)"), "@foo [a (b)] + (c)");
+
+        let expected = Builtin {
+            ident: parse_str("foo").unwrap(),
+            args: vec![
+                BuiltinArg::Expr(parse_str("a").unwrap()),
+                BuiltinArg::Expr(parse_str("(|b| b)").unwrap()),
+                BuiltinArg::Expr(parse_str("c").unwrap()),
+            ],
+        };
+        assert_eq!(
+            parse_str::<Builtin>(&separate("@foo a (|b| b), c")).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let expected = Builtin {
+            ident: parse_str("foo").unwrap(),
+            args: vec![],
+        };
+        assert_eq!(parse_str::<Builtin>("@foo").unwrap(), expected);
+        assert!(parse_str::<Builtin>("foo").is_err());
+        assert!(parse_str::<Builtin>("@foo (@)").is_err());
+    }
+}
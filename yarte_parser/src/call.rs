@@ -0,0 +1,143 @@
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream, Parser},
+    parse2, token, Expr, ExprCall, ExprPath, Ident, Lit, Path, Result, Token,
+};
+
+use crate::builtin::{is_sub_tree, Builtin};
+
+/// Helper call with subexpressions `name arg0 (inner arg1) (@name ..)`
+///
+/// Arguments are separated by whitespace or commas as in the built-in helpers,
+/// and the call is lowered to the nested Rust calls `name(arg0, inner(arg1), ..)`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Call {
+    pub path: Path,
+    pub args: Vec<Expr>,
+}
+
+impl Parse for Call {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path = input.call(Path::parse_mod_style)?;
+        let mut args = vec![];
+        while !input.is_empty() {
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            } else {
+                args.push(input.call(argument)?);
+            }
+        }
+
+        Ok(Call { path, args })
+    }
+}
+
+impl From<Call> for Expr {
+    fn from(Call { path, args }: Call) -> Self {
+        Expr::Call(ExprCall {
+            attrs: vec![],
+            func: Box::new(Expr::Path(ExprPath {
+                attrs: vec![],
+                qself: None,
+                path,
+            })),
+            paren_token: token::Paren::default(),
+            args: args.into_iter().collect(),
+        })
+    }
+}
+
+/// Argument of a helper call, a subexpression or a Rust expression
+pub(crate) fn argument(input: ParseStream) -> Result<Expr> {
+    if input.peek(token::Paren) {
+        let sub = input.cursor().token_tree().and_then(|(tt, _)| match tt {
+            tt if is_sub_tree(&tt) => Some(true),
+            TokenTree::Group(g) if is_call(g.stream()) => Some(false),
+            _ => None,
+        });
+        if let Some(builtin) = sub {
+            let content;
+            parenthesized!(content in input);
+            return if builtin {
+                let tokens: TokenStream = content.parse()?;
+                parse2::<Builtin>(tokens.clone())?;
+                Ok(Expr::Verbatim(tokens))
+            } else {
+                content.parse::<Call>().map(Into::into)
+            };
+        }
+    }
+
+    input.parse()
+}
+
+/// Parenthesized helper call `(name arg ..)`, a path followed by an argument
+/// which can't continue a Rust expression
+fn is_call(tokens: TokenStream) -> bool {
+    let call = |input: ParseStream| {
+        input.call(Path::parse_mod_style)?;
+        let is = input.peek(Token![,])
+            || input.peek(Lit)
+            || input.peek(Ident)
+            || input.peek(Token![self])
+            || input.peek(token::Bracket);
+        input.parse::<TokenStream>()?;
+        Ok(is)
+    };
+
+    call.parse2(tokens).unwrap_or(false)
+}
+
+/// Whether a group starting at `rest` is a helper call, with arguments separated by whitespace
+pub(crate) fn is_call_src(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    if rest.starts_with('@') {
+        return true;
+    }
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or_else(|| rest.len());
+    0 < end
+        && rest[end..].starts_with(char::is_whitespace)
+        && syn::parse_str::<Path>(&rest[..end]).is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::builtin::separate;
+    use quote::quote;
+    use syn::parse_str;
+
+    fn call(src: &str) -> Expr {
+        parse_str::<Call>(&separate(src)).unwrap().into()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            call("helper (inner arg) other"),
+            parse_str::<Expr>("helper(inner(arg), other)").unwrap()
+        );
+        assert_eq!(
+            call("a::helper (inner (deep x) \"y\") [1, 2]"),
+            parse_str::<Expr>("a::helper(inner(deep(x), \"y\"), [1, 2])").unwrap()
+        );
+        assert_eq!(
+            call("helper (a + b) (f(c)) (g (c)) (h c.d)"),
+            parse_str::<Expr>("helper((a + b), (f(c)), g((c)), h(c.d))").unwrap()
+        );
+        let e = call("upper (@len items)");
+        assert_eq!(
+            quote!(#e).to_string(),
+            quote!(upper(@len items)).to_string()
+        );
+    }
+
+    #[test]
+    fn test_fail() {
+        assert!(parse_str::<Call>(&separate("helper (inner +)")).is_err());
+        assert!(parse_str::<Call>(&separate("helper (@)")).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use syn::{parse::ParseStream, Result, Token};
+
+use yarte_ast::Const;
+
+/// Constant of a template `{{! const NAME: Type = expr !}}`
+pub(crate) fn constant(input: ParseStream) -> Result<Const> {
+    input.parse::<Token![const]>()?;
+    let ident = input.parse()?;
+    let ty = if input.peek(Token![:]) {
+        input.parse::<Token![:]>()?;
+        Some(input.parse()?)
+    } else {
+        None
+    };
+    input.parse::<Token![=]>()?;
+    let expr = input.parse()?;
+    if input.peek(Token![;]) {
+        input.parse::<Token![;]>()?;
+    }
+
+    Ok(Const { ident, ty, expr })
+}
@@ -0,0 +1,184 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream, Parser},
+    Expr, Ident, Result, Token,
+};
+
+use yarte_ast::{BlockParams, Modifier};
+
+/// Each helper arguments `expr modifier* (as |param, .. index|)?`
+///
+/// The expression `(zip expr expr ..)` iterates the collections in lockstep, it's carried
+/// as the built-in helper `@zip` by the `syn::Expr::Verbatim` variant.
+pub(super) struct EachArgs {
+    expr: Expr,
+    modifiers: Vec<Modifier>,
+    params: BlockParams,
+}
+
+impl Parse for EachArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (args, params) = split_params(input)?;
+        let (expr, modifiers) = expr_modifiers.parse2(args)?;
+
+        Ok(EachArgs {
+            expr,
+            modifiers,
+            params,
+        })
+    }
+}
+
+/// Arguments of a block helper and its block parameters `args (as |param, .. index|)?`
+pub(super) fn split_params(input: ParseStream) -> Result<(TokenStream, BlockParams)> {
+    let args = input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = TokenStream::new();
+        while let Some((tt, next)) = rest.token_tree() {
+            if is_as(&tt) && next.punct().map_or(false, |(p, _)| p.as_char() == '|') {
+                break;
+            }
+            tokens.extend(Some(tt));
+            rest = next;
+        }
+        Ok((tokens, rest))
+    })?;
+
+    let mut params = BlockParams::default();
+    if !input.is_empty() {
+        input.parse::<Token![as]>()?;
+        input.parse::<Token![|]>()?;
+        if input.peek(Token![|]) {
+            return Err(input.error("expected block parameters"));
+        }
+        while !input.peek(Token![|]) {
+            params.item.push(input.parse()?);
+            if input.peek(Ident) {
+                // The name after the item separated by whitespace
+                params.index = Some(input.parse()?);
+                break;
+            } else if !input.peek(Token![|]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        input.parse::<Token![|]>()?;
+    }
+
+    Ok((args, params))
+}
+
+fn is_as(tt: &TokenTree) -> bool {
+    match tt {
+        TokenTree::Ident(ident) => ident == "as",
+        _ => false,
+    }
+}
+
+fn expr_modifiers(input: ParseStream) -> Result<(Expr, Vec<Modifier>)> {
+    let expr = zip(input)?.map_or_else(|| input.parse(), Ok)?;
+    let mut modifiers = vec![];
+    while !input.is_empty() {
+        let ident = input.parse()?;
+        let value = if input.peek(Token![=]) && !input.peek(Token![==]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        modifiers.push(Modifier(ident, value));
+    }
+
+    Ok((expr, modifiers))
+}
+
+/// Parenthesized `zip` followed by its arguments, `(zip(a))` is a call
+fn zip(input: ParseStream) -> Result<Option<Expr>> {
+    input.step(|cursor| {
+        if let Some((content, _, next)) = cursor.group(proc_macro2::Delimiter::Parenthesis) {
+            if let Some((TokenTree::Ident(ident), rest)) = content.token_tree() {
+                if ident == "zip" && rest.ident().is_some() {
+                    let args = rest.token_stream();
+                    return Ok((Some(Expr::Verbatim(quote!(@zip #args))), next));
+                }
+            }
+        }
+        Ok((None, *cursor))
+    })
+}
+
+impl From<EachArgs> for (Expr, Vec<Modifier>, BlockParams) {
+    fn from(args: EachArgs) -> Self {
+        (args.expr, args.modifiers, args.params)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+
+    #[test]
+    fn test() {
+        let src = "self.items.iter() chunks = 3 reverse group_by=a.b limit=n + 1";
+        let (expr, modifiers, params) = parse_str::<EachArgs>(src).unwrap().into();
+
+        assert_eq!(expr, parse_str::<Expr>("self.items.iter()").unwrap());
+        assert_eq!(
+            modifiers,
+            vec![
+                Modifier(parse_str("chunks").unwrap(), Some(parse_str("3").unwrap())),
+                Modifier(parse_str("reverse").unwrap(), None),
+                Modifier(
+                    parse_str("group_by").unwrap(),
+                    Some(parse_str("a.b").unwrap())
+                ),
+                Modifier(
+                    parse_str("limit").unwrap(),
+                    Some(parse_str("n + 1").unwrap())
+                ),
+            ]
+        );
+
+        assert!(params.is_empty());
+
+        assert!(parse_str::<EachArgs>("items 3").is_err());
+    }
+
+    #[test]
+    fn test_params() {
+        let src = "(zip names self.ages) reverse as |name, age|";
+        let (expr, modifiers, params) = parse_str::<EachArgs>(src).unwrap().into();
+
+        assert_eq!(expr, Expr::Verbatim(quote!(@zip names self.ages)));
+        assert_eq!(
+            modifiers,
+            vec![Modifier(parse_str("reverse").unwrap(), None)]
+        );
+        assert_eq!(
+            params.item,
+            vec![
+                parse_str::<Ident>("name").unwrap(),
+                parse_str::<Ident>("age").unwrap()
+            ]
+        );
+        assert_eq!(params.index, None);
+
+        let (expr, _, params) = parse_str::<EachArgs>("(zip(a)) as |x|").unwrap().into();
+        assert_eq!(expr, parse_str::<Expr>("(zip(a))").unwrap());
+        assert_eq!(params.item, vec![parse_str::<Ident>("x").unwrap()]);
+
+        let (_, _, params) = parse_str::<EachArgs>("items as |item idx|").unwrap().into();
+        assert_eq!(params.item, vec![parse_str::<Ident>("item").unwrap()]);
+        assert_eq!(params.index, Some(parse_str("idx").unwrap()));
+
+        let (_, _, params) = parse_str::<EachArgs>("pairs as |a, b i|").unwrap().into();
+        assert_eq!(params.item.len(), 2);
+        assert_eq!(params.index, Some(parse_str("i").unwrap()));
+
+        assert!(parse_str::<EachArgs>("items as ||").is_err());
+        assert!(parse_str::<EachArgs>("items as |a b c|").is_err());
+        assert!(parse_str::<EachArgs>("items as |a b, c|").is_err());
+        assert!(parse_str::<EachArgs>("items as |a| b").is_err());
+    }
+}
@@ -2,66 +2,49 @@
 
 use std::str;
 
-use syn::{parse_str, Expr, Local};
+use syn::{parse::Parser, parse_str, Expr, Ident, Local};
 use unicode_xid::UnicodeXID;
 
 #[cfg(test)]
 mod test;
 
-mod expr_list;
+mod args;
+mod builtin;
+mod call;
+mod constant;
+mod each_args;
+mod once;
 mod pre_partials;
 pub mod source_map;
 mod stmt_local;
 #[macro_use]
 mod strnom;
+mod whitespace;
 
-pub use self::pre_partials::parse_partials;
+pub use yarte_ast::{
+    Arena, Arg, BlockParams, Const, Helper, Modifier, Node, Partial, SArg, SArgs, SExpr, SLocal,
+    SNode, SStr, Ws, PARTIAL_BLOCK,
+};
+
+pub use self::{
+    args::parse_args,
+    builtin::{Builtin, BuiltinArg},
+    call::Call,
+    once::Once,
+    pre_partials::parse_partials,
+    whitespace::{is_lit_ws, set_unicode_whitespace, trim_lit},
+};
 use crate::{
-    expr_list::ExprList,
-    source_map::{spanned, Span, S},
+    constant::constant,
+    each_args::{split_params, EachArgs},
+    source_map::{from_cursor, from_len, spanned, BytePos, Span, S},
     stmt_local::StmtLocal,
     strnom::{is_ws, skip_ws, ws, Cursor, LexError, PResult},
 };
 
-pub type Ws = (bool, bool);
-
-pub type SExpr = S<Box<Expr>>;
-pub type SLocal = S<Box<Local>>;
-pub type SNode<'a> = S<Node<'a>>;
-pub type SStr<'a> = S<&'a str>;
-pub type SVExpr = S<Vec<Expr>>;
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct Partial<'a>(pub Ws, pub SStr<'a>, pub SVExpr);
-
-#[derive(Debug, PartialEq, Clone)]
-pub enum Node<'a> {
-    Comment(&'a str),
-    Expr(Ws, SExpr),
-    Helper(Box<Helper<'a>>),
-    Lit(&'a str, SStr<'a>, &'a str),
-    Local(SLocal),
-    Partial(Partial<'a>),
-    Raw((Ws, Ws), &'a str, SStr<'a>, &'a str),
-    Safe(Ws, SExpr),
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub enum Helper<'a> {
-    Each((Ws, Ws), SExpr, Vec<SNode<'a>>),
-    If(
-        ((Ws, Ws), SExpr, Vec<SNode<'a>>),
-        Vec<(Ws, SExpr, Vec<SNode<'a>>)>,
-        Option<(Ws, Vec<SNode<'a>>)>,
-    ),
-    With((Ws, Ws), SExpr, Vec<SNode<'a>>),
-    Unless((Ws, Ws), SExpr, Vec<SNode<'a>>),
-    // TODO:
-    Defined((Ws, Ws), &'a str, SExpr, Vec<SNode<'a>>),
-}
-
-pub fn parse(c: Cursor) -> Vec<SNode> {
-    match eat(c) {
+/// Nodes of a template, their helpers, constants, expressions and locals allocated in `arena`
+pub fn parse<'a>(c: Cursor<'a>, arena: &'a Arena<'a>) -> Vec<SNode<'a>> {
+    match eat(c, arena) {
         Ok((l, res)) => {
             if l.is_empty() {
                 return res;
@@ -81,7 +64,7 @@ macro_rules! try_eat {
         match $($t)+ {
             Ok((c, n)) => {
                 eat_lit(&mut $nodes, $i, $at + $j);
-                $nodes.push(S(n, Span::from_cursor($i.adv($at + $j), c)));
+                $nodes.push(S(n, from_cursor($i.adv($at + $j), c)));
                 $i = c;
                 0
             },
@@ -104,7 +87,7 @@ macro_rules! kill {
 /// $callback: macro for special expressions like {{ else if }}
 macro_rules! make_eater {
     ($name:ident, $callback:ident) => {
-        fn $name(mut i: Cursor) -> PResult<Vec<SNode>> {
+        fn $name<'a>(mut i: Cursor<'a>, a: &'a Arena<'a>) -> PResult<'a, Vec<SNode<'a>>> {
             let mut buf = vec![];
             let mut at = 0;
 
@@ -113,15 +96,21 @@ macro_rules! make_eater {
                     macro_rules! _switch {
                         ($n:expr, $t:expr, $ws:expr) => {
                             match $n {
-                                b'{' => try_eat!(buf, i, at, j, safe(i.adv(at + j + 3 + $t), $ws)),
-                                b'!' => try_eat!(buf, i, at, j, comment(i.adv(at + j + 3 + $t))),
-                                b'#' => try_eat!(buf, i, at, j, hel(i.adv(at + j + 3 + $t), $ws)),
+                                b'{' => {
+                                    try_eat!(buf, i, at, j, safe(i.adv(at + j + 3 + $t), $ws, a))
+                                }
+                                b'!' => {
+                                    try_eat!(buf, i, at, j, comment(i.adv(at + j + 3 + $t), a))
+                                }
+                                b'#' => {
+                                    try_eat!(buf, i, at, j, hel(i.adv(at + j + 3 + $t), $ws, a))
+                                }
                                 b'>' => try_eat!(buf, i, at, j, par(i.adv(at + j + 3 + $t), $ws)),
                                 b'R' => try_eat!(buf, i, at, j, raw(i.adv(at + j + 3 + $t), $ws)),
                                 b'/' => kill!(buf, i.adv(at + j + 2), i, at + j),
                                 _ => {
                                     $callback!(buf, i, at, j, $t);
-                                    try_eat!(buf, i, at, j, expr(i.adv(at + j + 2 + $t), $ws))
+                                    try_eat!(buf, i, at, j, expr(i.adv(at + j + 2 + $t), $ws, a))
                                 }
                             }
                         };
@@ -158,8 +147,15 @@ macro_rules! non {
 // Main eater
 make_eater!(eat, non);
 
+const EACH: &str = "each";
 const IF: &str = "if";
+const UNLESS: &str = "unless";
 const ELSE: &str = "else";
+const KEEP: &str = "keep";
+const CONST: &str = "const";
+const INLINE: &str = "*inline";
+const WITH: &str = "with";
+const PORTAL: &str = "portal";
 
 // Test special expression `{{ else ..` and kill eater at next brackets
 macro_rules! is_else {
@@ -173,25 +169,58 @@ macro_rules! is_else {
 // If else branch eater
 make_eater!(eat_if, is_else);
 
+// Test special expression `{{ else }}` or `{{ else err }}` of each, `else` isn't the start of an identifier
+macro_rules! is_each_else {
+    ($n:ident, $i:ident, $at:ident, $j:ident, $t:expr) => {
+        let c = skip_ws($i.adv($at + $j + 2 + $t));
+        if c.starts_with(ELSE)
+            && !c
+                .adv(ELSE.len())
+                .chars()
+                .next()
+                .map_or(false, is_ident_continue)
+        {
+            kill!($n, $i.adv($at + $j + 2), $i, $at + $j);
+        }
+    };
+}
+
+// Each empty or error arm eater
+make_eater!(eat_each_block, is_each_else);
+
 /// Push literal at cursor with length
 fn eat_lit<'a>(nodes: &mut Vec<SNode<'a>>, i: Cursor<'a>, len: usize) {
     let lit = &i.rest[..len];
     if !lit.is_empty() {
-        let (l, lit, r) = trim(lit);
+        let (l, lit, r) = trim_lit(lit);
         let ins = Span {
-            lo: i.off + (l.len() as u32),
-            hi: i.off + ((len - r.len()) as u32),
+            lo: i.off + (l.len() as BytePos),
+            hi: i.off + ((len - r.len()) as BytePos),
         };
         let out = Span {
             lo: i.off,
-            hi: i.off + (len as u32),
+            hi: i.off + (len as BytePos),
         };
         nodes.push(S(Node::Lit(l, S(lit, ins), r), out));
     }
 }
 
 /// Eat comment
-fn comment(c: Cursor) -> PResult<Node> {
+fn comment<'a>(c: Cursor<'a>, a: &'a Arena<'a>) -> PResult<'a, Node<'a>> {
+    let (c, text) = comment_text(c)?;
+    let node = match keep(text) {
+        Some(text) => Node::Keep(text),
+        None if is_const(text) => {
+            Node::Const(a.constant(constant.parse_str(text).map_err(|_| LexError::Fail)?))
+        }
+        None => Node::Comment(text),
+    };
+
+    Ok((c, node))
+}
+
+/// Eat the text of a comment and its end
+fn comment_text(c: Cursor) -> PResult<&str> {
     let (c, expected) = if c.starts_with("--") {
         (c.adv(2), "--!}}")
     } else {
@@ -204,10 +233,7 @@ fn comment(c: Cursor) -> PResult<Node> {
     loop {
         if let Some(j) = c.adv_find(at, ch) {
             if c.adv_starts_with(at + j + 1, rest) {
-                break Ok((
-                    c.adv(at + j + expected.len()),
-                    Node::Comment(&c.rest[..at + j]),
-                ));
+                break Ok((c.adv(at + j + expected.len()), &c.rest[..at + j]));
             } else {
                 at += j + 1;
             }
@@ -217,6 +243,46 @@ fn comment(c: Cursor) -> PResult<Node> {
     }
 }
 
+/// Text of a comment kept in the output `{{!keep ... !}}`
+fn keep(text: &str) -> Option<&str> {
+    if text.starts_with(KEEP) {
+        let rest = &text[KEEP.len()..];
+        if rest.is_empty() || rest.starts_with(is_ws) {
+            return Some(rest.trim_matches(is_ws));
+        }
+    }
+
+    None
+}
+
+/// Comment declaring a constant `{{! const NAME = expr !}}` or `{{! const NAME: Type = expr !}}`
+fn is_const(text: &str) -> bool {
+    let text = text.trim_start_matches(is_ws);
+    if !text.starts_with(CONST) {
+        return false;
+    }
+    let rest = &text[CONST.len()..];
+    let name = rest.trim_start_matches(is_ws);
+    if name.len() == rest.len() {
+        return false;
+    }
+    let after = name
+        .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_')
+        .trim_start_matches(is_ws);
+
+    after.len() < name.len() && (after.starts_with(':') || after.starts_with('='))
+}
+
+/// HTML comment of a kept comment, `--` is split up so the text can't close it
+pub fn html_comment(text: &str) -> String {
+    let mut text = text.to_owned();
+    while text.contains("--") {
+        text = text.replace("--", "- -");
+    }
+
+    format!("<!-- {} -->", text)
+}
+
 /// Wrap Partial into the Node
 #[inline]
 fn par(i: Cursor, lws: bool) -> PResult<Node> {
@@ -235,40 +301,50 @@ fn partial(i: Cursor, lws: bool) -> PResult<Partial> {
 }
 
 /// Eat helper Node
-fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
+fn hel<'a>(i: Cursor<'a>, a_lws: bool, a: &'a Arena<'a>) -> PResult<'a, Node<'a>> {
+    if skip_ws(i).starts_with(INLINE) {
+        return inline(i, a_lws, a);
+    }
+    if skip_ws(i).starts_with(">") {
+        return partial_block(i, a_lws, a);
+    }
+
     let (i, (above_ws, ident, args)) = do_parse!(
         i,
         ws >> ident: call!(spanned, identifier)
-            >> args: arguments
+            >> args: call!(helper_arguments, ident.0, a)
             >> rws: end_expr
             >> (((a_lws, rws), ident, args))
     )?;
+    let (args, modifiers, params) = args;
 
-    if ident.0.eq("if") {
-        return if_else(above_ws, i, args);
+    if ident.0.eq(IF) || ident.0.eq(UNLESS) {
+        return if_else(ident.0, above_ws, i, args, a);
     }
 
-    let (c, (below_ws, block, c_ident)) = do_parse!(
+    let (i, (block, els)) = if ident.0.eq(EACH) {
+        each_block(i, a)?
+    } else {
+        eat(i, a).map(|(c, block)| (c, (block, None)))?
+    };
+    let (c, (below_ws, c_ident)) = do_parse!(
         i,
-        block: eat
-            >> lws: opt!(tag!("~"))
+        lws: opt!(tag!("~"))
             >> tag!("/")
             >> ws
             >> c_ident: call!(spanned, identifier)
             >> rws: end_expr
-            >> (((lws.is_some(), rws), block, c_ident))
+            >> (((lws.is_some(), rws), c_ident))
     )?;
 
     if ident.0.eq(c_ident.0) {
         Ok((
             c,
-            Node::Helper(Box::new({
-                match ident.0 {
-                    "each" => Helper::Each((above_ws, below_ws), args, block),
-                    "with" => Helper::With((above_ws, below_ws), args, block),
-                    "unless" => Helper::Unless((above_ws, below_ws), args, block),
-                    defined => Helper::Defined((above_ws, below_ws), defined, args, block),
-                }
+            Node::Helper(a.helper(match ident.0 {
+                EACH => Helper::Each((above_ws, below_ws), args, modifiers, params, block, els),
+                WITH => Helper::With((above_ws, below_ws), args, params, block),
+                PORTAL => Helper::Portal((above_ws, below_ws), args, params, block),
+                defined => Helper::Defined((above_ws, below_ws), defined, args, params, block),
             })),
         ))
     } else {
@@ -276,13 +352,150 @@ fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
     }
 }
 
-/// Eat if else Node
+/// Eat partial block `{{#> partial args}}..{{/partial}}`
+fn partial_block<'a>(i: Cursor<'a>, a_lws: bool, a: &'a Arena<'a>) -> PResult<'a, Node<'a>> {
+    let (i, Partial(above_ws, ident, args)) = partial(skip_ws(i).adv(1), a_lws)?;
+    let (i, block) = eat(i, a)?;
+    let (c, (below_ws, c_ident)) = do_parse!(
+        i,
+        lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> c_ident: partial_close
+            >> rws: end_expr
+            >> (((lws.is_some(), rws), c_ident))
+    )?;
+
+    if *ident.t() == c_ident {
+        Ok((
+            c,
+            Node::PartialBlock((above_ws, below_ws), ident, args, block),
+        ))
+    } else {
+        Err(LexError::Fail)
+    }
+}
+
+/// Eat the path of the closing tag of a partial block
+fn partial_close(i: Cursor) -> PResult<&str> {
+    take_while!(i, |c| !is_ws(c) && c != '}' && c != '~')
+}
+
+/// Eat inline partial definition `{{#*inline "name"}}..{{/inline}}`
+fn inline<'a>(i: Cursor<'a>, a_lws: bool, a: &'a Arena<'a>) -> PResult<'a, Node<'a>> {
+    let (i, (above_ws, name)) = do_parse!(
+        i,
+        ws >> tag!(INLINE)
+            >> ws
+            >> name: call!(spanned, inline_name)
+            >> rws: end_expr
+            >> (((a_lws, rws), name))
+    )?;
+    let (i, block) = eat(i, a)?;
+    let (c, below_ws) = do_parse!(
+        i,
+        lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(&INLINE[1..])
+            >> rws: end_expr
+            >> ((lws.is_some(), rws))
+    )
+    .map_err(|_| LexError::Fail)?;
+
+    Ok((c, Node::Inline((above_ws, below_ws), name, block)))
+}
+
+/// Eat the quoted name of an inline partial
+fn inline_name(i: Cursor) -> PResult<&str> {
+    if !i.starts_with("\"") {
+        return Err(LexError::Fail);
+    }
+    let i = i.adv(1);
+    match i.find('"') {
+        Some(0) | None => Err(LexError::Fail),
+        Some(j) => Ok((i.adv(j + 1), &i.rest[..j])),
+    }
+}
+
+/// Inline partial `name` defined in `nodes`, with the whitespace of its tags
+pub fn find_inline<'a>(nodes: &'a [SNode<'a>], name: &str) -> Option<((Ws, Ws), &'a [SNode<'a>])> {
+    nodes.iter().find_map(|n| match n.t() {
+        Node::Inline(ws, n, block) if *n.t() == name => Some((*ws, block.as_slice())),
+        Node::Inline(_, _, block) | Node::PartialBlock(_, _, _, block) => find_inline(block, name),
+        Node::Helper(h) => match &**h {
+            Helper::Each(_, _, _, _, block, els) => find_inline(block, name)
+                .or_else(|| els.as_ref().and_then(|(_, _, els)| find_inline(els, name))),
+            Helper::If((_, _, block), ifs, els) | Helper::Unless((_, _, block), ifs, els) => {
+                find_inline(block, name)
+                    .or_else(|| ifs.iter().find_map(|(_, _, b)| find_inline(b, name)))
+                    .or_else(|| els.as_ref().and_then(|(_, els)| find_inline(els, name)))
+            }
+            Helper::With(_, _, _, block)
+            | Helper::Portal(_, _, _, block)
+            | Helper::Defined(_, _, _, _, block) => find_inline(block, name),
+        },
+        _ => None,
+    })
+}
+
+/// Eat the block of each helper and its empty arm `{{else}}` or its error arm `{{else err}}`
+#[allow(clippy::type_complexity)]
+fn each_block<'a>(
+    i: Cursor<'a>,
+    a: &'a Arena<'a>,
+) -> PResult<'a, (Vec<SNode<'a>>, Option<(Ws, Option<Ident>, Vec<SNode<'a>>)>)> {
+    let (i, block) = eat_each_block(i, a)?;
+    if let Ok((c, lws)) = do_parse!(
+        i,
+        lws: opt!(tag!("~")) >> ws >> tag!(ELSE) >> (lws.is_some())
+    ) {
+        let (c, (rws, name, els)) = map_fail!(do_parse!(
+            c,
+            ws >> name: opt!(identifier)
+                >> rws: end_expr
+                >> els: call!(eat, a)
+                >> ((rws, name, els))
+        ))?;
+        let ident = match name {
+            Some(name) => Some(parse_str::<Ident>(name).map_err(|_| LexError::Fail)?),
+            None => None,
+        };
+
+        Ok((c, (block, Some(((lws, rws), ident, els)))))
+    } else {
+        Ok((i, (block, None)))
+    }
+}
+
+/// Eat helper arguments, `each` helper accepts modifiers and block parameters
+fn helper_arguments<'a>(
+    i: Cursor<'a>,
+    ident: &str,
+    a: &'a Arena<'a>,
+) -> PResult<'a, (SExpr<'a>, Vec<Modifier>, BlockParams)> {
+    if ident == EACH {
+        each_arguments(i, a).map(|(c, S((e, m, p), span))| (c, (S(e, span), m, p)))
+    } else if ident == IF || ident == UNLESS {
+        arguments(i, a).map(|(c, e)| (c, (e, vec![], BlockParams::default())))
+    } else {
+        block_arguments(i, a).map(|(c, S((e, p), span))| (c, (S(e, span), vec![], p)))
+    }
+}
+
+/// Eat if else Node, `unless` helper has the same else chain
 #[inline]
-fn if_else(abode_ws: Ws, i: Cursor, args: SExpr) -> PResult<Node> {
+fn if_else<'a>(
+    helper: &str,
+    abode_ws: Ws,
+    i: Cursor<'a>,
+    args: SExpr<'a>,
+    a: &'a Arena<'a>,
+) -> PResult<'a, Node<'a>> {
     let mut nodes = vec![];
     let mut tail = None;
 
-    let (mut i, first) = eat_if(i)?;
+    let (mut i, first) = eat_if(i, a)?;
 
     loop {
         if let Ok((c, lws)) = do_parse!(
@@ -292,9 +505,9 @@ fn if_else(abode_ws: Ws, i: Cursor, args: SExpr) -> PResult<Node> {
             if let Ok((c, _)) = tag!(skip_ws(c), IF) {
                 let (c, b) = map_fail!(do_parse!(
                     c,
-                    ws >> args: arguments
+                    ws >> args: call!(arguments, a)
                         >> rws: end_expr
-                        >> block: eat_if
+                        >> block: call!(eat_if, a)
                         >> (((lws, rws), args, block))
                 ))?;
                 nodes.push(b);
@@ -302,24 +515,25 @@ fn if_else(abode_ws: Ws, i: Cursor, args: SExpr) -> PResult<Node> {
             } else {
                 let (c, b) = map_fail!(do_parse!(
                     c,
-                    rws: end_expr >> block: eat >> (((lws, rws), block))
+                    rws: end_expr >> block: call!(eat, a) >> (((lws, rws), block))
                 ))?;
                 tail = Some(b);
                 i = c;
             }
         } else if let Ok((c, lws)) = do_parse!(
             i,
-            lws: opt!(tag!("~")) >> tag!("/") >> ws >> tag!(IF) >> (lws.is_some())
+            lws: opt!(tag!("~")) >> tag!("/") >> ws >> tag!(helper) >> (lws.is_some())
         ) {
             let (c, rws) = end_expr(c)?;
+            let ifs = ((abode_ws, (lws, rws)), args, first);
 
             break Ok((
                 c,
-                Node::Helper(Box::new(Helper::If(
-                    ((abode_ws, (lws, rws)), args, first),
-                    nodes,
-                    tail,
-                ))),
+                Node::Helper(a.helper(if helper == IF {
+                    Helper::If(ifs, nodes, tail)
+                } else {
+                    Helper::Unless(ifs, nodes, tail)
+                })),
             ));
         } else {
             break Err(LexError::Fail);
@@ -352,9 +566,9 @@ fn raw(i: Cursor, a_lws: bool) -> PResult<Node> {
         }
     };
 
-    let (l, v, r) = trim(j);
-    let lo = i.off + (l.len() as u32);
-    let hi = lo + (v.len() as u32);
+    let (l, v, r) = trim_lit(j);
+    let lo = i.off + (l.len() as BytePos);
+    let hi = lo + (v.len() as BytePos);
     Ok((
         c,
         Node::Raw(((a_lws, a_rws), b_ws), l, S(v, Span { lo, hi }), r),
@@ -364,19 +578,18 @@ fn raw(i: Cursor, a_lws: bool) -> PResult<Node> {
 /// Arguments builder
 macro_rules! make_argument {
     ($name:ident, $fun:ident, $ret:ty) => {
-        fn $name(i: Cursor) -> $ret {
+        fn $name<'a>(i: Cursor<'a>, a: &'a Arena<'a>) -> $ret {
             let mut at = 0;
             loop {
                 if let Some(j) = i.adv_find(at, '}') {
                     if 0 < j && i.adv_starts_with(at + j - 1, "~}}") {
                         let (_, s, _) = trim(&i.rest[..j - 1]);
-                        break $fun(s).map(|e| {
-                            (i.adv(at + j - 1), S(e, Span::from_len(skip_ws(i), s.len())))
-                        });
+                        break $fun(s, a)
+                            .map(|e| (i.adv(at + j - 1), S(e, from_len(skip_ws(i), s.len()))));
                     } else if i.adv_starts_with(j + 1, "}") {
                         let (_, s, _) = trim(&i.rest[..j]);
-                        break $fun(s)
-                            .map(|e| (i.adv(at + j), S(e, Span::from_len(skip_ws(i), s.len()))));
+                        break $fun(s, a)
+                            .map(|e| (i.adv(at + j), S(e, from_len(skip_ws(i), s.len()))));
                     }
 
                     at += j + 1;
@@ -389,13 +602,48 @@ macro_rules! make_argument {
 }
 
 // Eat arguments at helpers
-make_argument!(arguments, eat_expr, PResult<SExpr>);
+make_argument!(arguments, eat_expr, PResult<'a, SExpr<'a>>);
+
+// Eat arguments at each helper
+make_argument!(
+    each_arguments,
+    eat_each,
+    PResult<'a, S<(&'a Expr, Vec<Modifier>, BlockParams)>>
+);
+
+// Eat arguments at block helpers
+make_argument!(
+    block_arguments,
+    eat_block,
+    PResult<'a, S<(&'a Expr, BlockParams)>>
+);
+
+/// Eat arguments at partials, a `}}` inside of a literal isn't the end of them
+fn args_list(i: Cursor) -> PResult<SArgs> {
+    let mut at = 0;
+    loop {
+        let j = at + i.adv_find(at, '}').ok_or(LexError::Next)?;
+        if i.adv_starts_with(j, "}}") {
+            let end = if 0 < j && i.adv_starts_with(j - 1, "~}}") {
+                j - 1
+            } else {
+                j
+            };
+            let (l, s, _) = trim(&i.rest[..end]);
+            if !args::is_open(s) {
+                let lo = i.adv(l.len());
+                break map_fail!(
+                    parse_args(s, lo.off).map(|args| (i.adv(end), S(args, from_len(lo, s.len()))))
+                );
+            }
+        }
 
-// Eat arguments at partials
-make_argument!(args_list, eat_expr_list, PResult<SVExpr>);
+        at = j + 1;
+    }
+}
 
 /// Eat safe Node
-fn safe(i: Cursor, lws: bool) -> PResult<Node> {
+fn safe<'a>(i: Cursor<'a>, lws: bool, a: &'a Arena<'a>) -> PResult<'a, Node<'a>> {
     let mut at = 0;
     let (c, rws, s) = loop {
         if let Some(j) = i.adv_find(at, '}') {
@@ -413,16 +661,16 @@ fn safe(i: Cursor, lws: bool) -> PResult<Node> {
     };
 
     let (_, s, _) = trim(s);
-    eat_expr(s).map(|e| {
+    eat_expr(s, a).map(|e| {
         (
             c,
-            Node::Safe((lws, rws), S(e, Span::from_len(skip_ws(i), s.len()))),
+            Node::Safe((lws, rws), S(e, from_len(skip_ws(i), s.len()))),
         )
     })
 }
 
 /// Eat expression Node
-fn expr(i: Cursor, lws: bool) -> PResult<Node> {
+fn expr<'a>(i: Cursor<'a>, lws: bool, a: &'a Arena<'a>) -> PResult<'a, Node<'a>> {
     let mut at = 0;
     let (c, rws, s) = loop {
         if let Some(j) = i.adv_find(at, '}') {
@@ -440,33 +688,64 @@ fn expr(i: Cursor, lws: bool) -> PResult<Node> {
 
     let (_, s, _) = trim(s);
     if s.starts_with("let ") {
-        eat_local(s).map(|e| (c, Node::Local(S(e, Span::from_len(skip_ws(i), s.len())))))
+        eat_local(s, a).map(|e| (c, Node::Local(S(e, from_len(skip_ws(i), s.len())))))
     } else {
         Err(LexError::Next)
     }
     .or_else(|_| {
-        eat_expr(s).map(|e| {
+        eat_expr(s, a).map(|e| {
             (
                 c,
-                Node::Expr((lws, rws), S(e, Span::from_len(skip_ws(i), s.len()))),
+                Node::Expr((lws, rws), S(e, from_len(skip_ws(i), s.len()))),
             )
         })
     })
 }
 
 /// Parse syn expression
-fn eat_expr(i: &str) -> Result<Box<Expr>, LexError> {
-    map_fail!(parse_str::<Expr>(i).map(Box::new))
+fn eat_expr<'a>(i: &str, a: &'a Arena<'a>) -> Result<&'a Expr, LexError> {
+    if i.trim_start().starts_with('@') {
+        let i = &builtin::separate(i);
+        return map_fail!(parse_str::<Builtin>(i)
+            .and(parse_str(i))
+            .map(|tokens| a.expr(Expr::Verbatim(tokens))));
+    }
+    if i.trim_start().starts_with('#') {
+        return map_fail!(parse_str::<Once>(i)
+            .and(parse_str(i))
+            .map(|tokens| a.expr(Expr::Verbatim(tokens))));
+    }
+    map_fail!(parse_str::<Expr>(i)
+        .or_else(|e| parse_str::<Call>(&builtin::separate(i))
+            .map_err(|_| e)
+            .map(Into::into))
+        .map(|e| a.expr(e)))
 }
 
-/// Parse syn local
-fn eat_local(i: &str) -> Result<Box<Local>, LexError> {
-    map_fail!(parse_str::<StmtLocal>(i).map(Into::into).map(Box::new))
+/// Parse syn expression followed by each helper modifiers and block parameters
+fn eat_each<'a>(
+    i: &str,
+    a: &'a Arena<'a>,
+) -> Result<(&'a Expr, Vec<Modifier>, BlockParams), LexError> {
+    map_fail!(parse_str::<EachArgs>(i).map(|args| {
+        let (e, modifiers, params) = args.into();
+        (a.expr(e), modifiers, params)
+    }))
 }
 
-/// Parse syn expression comma separated list
-fn eat_expr_list(i: &str) -> Result<Vec<Expr>, LexError> {
-    map_fail!(parse_str::<ExprList>(i).map(Into::into))
+/// Parse syn expression followed by block parameters
+fn eat_block<'a>(i: &str, a: &'a Arena<'a>) -> Result<(&'a Expr, BlockParams), LexError> {
+    match split_params.parse_str(i) {
+        Ok((args, params)) if !params.is_empty() => {
+            eat_expr(&args.to_string(), a).map(|e| (e, params))
+        }
+        _ => eat_expr(i, a).map(|e| (e, BlockParams::default())),
+    }
+}
+
+/// Parse syn local
+fn eat_local<'a>(i: &str, a: &'a Arena<'a>) -> Result<&'a Local, LexError> {
+    map_fail!(parse_str::<StmtLocal>(i).map(|local| a.local(local.into())))
 }
 
 /// Eat whitespace flag in end of expressions `.. }}` or `.. ~}}`
@@ -527,27 +806,7 @@ fn path(i: Cursor) -> PResult<&str> {
     })
 }
 
+/// Split in the leading whitespace, the rest and the trailing whitespace, as the tags
 pub fn trim(i: &str) -> (&str, &str, &str) {
-    if i.is_empty() {
-        return ("", "", "");
-    }
-
-    let b = i.as_bytes();
-
-    if let Some(ln) = b.iter().position(|x| !is_ws((*x).into())) {
-        let rn = b.iter().rposition(|x| !is_ws((*x).into())).unwrap();
-        (
-            safe_utf8(&b[..ln]),
-            safe_utf8(&b[ln..=rn]),
-            safe_utf8(&b[rn + 1..]),
-        )
-    } else {
-        (i, "", "")
-    }
-}
-
-/// Convert from bytes to str
-/// Use when previous check bytes it's valid utf8
-fn safe_utf8(s: &[u8]) -> &str {
-    unsafe { ::std::str::from_utf8_unchecked(s) }
+    whitespace::trim_by(i, is_ws)
 }
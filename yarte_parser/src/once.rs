@@ -0,0 +1,22 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Expr, Ident, Result, Token,
+};
+
+/// Memoized expression `#once expr`
+///
+/// It's carried by the `syn::Expr::Verbatim` variant, as the built-in helpers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Once(pub Expr);
+
+impl Parse for Once {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![#]>()?;
+        let ident: Ident = input.parse()?;
+        if ident != "once" {
+            return Err(Error::new(ident.span(), "expected `#once`"));
+        }
+
+        Ok(Once(input.parse()?))
+    }
+}
@@ -1,7 +1,7 @@
 use super::{
-    comment, partial, raw,
-    strnom::{Cursor, LexError, PResult},
-    Partial,
+    comment_text, inline_name, partial, raw,
+    strnom::{skip_ws, Cursor, LexError, PResult},
+    Partial, INLINE, PARTIAL_BLOCK,
 };
 
 pub fn parse_partials(rest: &str) -> Vec<Partial> {
@@ -21,8 +21,10 @@ pub fn parse_partials(rest: &str) -> Vec<Partial> {
     }
 }
 
+/// Partials of the template and of its partial blocks, without its inline partials
 fn eat_partials(mut i: Cursor) -> PResult<Vec<Partial>> {
     let mut nodes = vec![];
+    let mut inlines = vec![];
 
     loop {
         if let Some(j) = i.find('{') {
@@ -48,9 +50,30 @@ fn eat_partials(mut i: Cursor) -> PResult<Vec<Partial>> {
                                 Err(LexError::Next) => i,
                             }
                         }
+                        b'#' if skip_ws(i.adv(j + 3 + $t)).starts_with(">") => {
+                            let i = skip_ws(i.adv(j + 3 + $t)).adv(1);
+                            match partial(i, $ws) {
+                                Ok((i, n)) => {
+                                    nodes.push(n);
+                                    i
+                                }
+                                Err(LexError::Fail) => break Err(LexError::Fail),
+                                Err(LexError::Next) => i,
+                            }
+                        }
+                        b'#' if skip_ws(i.adv(j + 3 + $t)).starts_with(INLINE) => {
+                            let i = skip_ws(skip_ws(i.adv(j + 3 + $t)).adv(INLINE.len()));
+                            match inline_name(i) {
+                                Ok((i, name)) => {
+                                    inlines.push(name);
+                                    i
+                                }
+                                Err(_) => i,
+                            }
+                        }
                         b'!' => {
                             let i = i.adv(j + 3);
-                            match comment(i) {
+                            match comment_text(i) {
                                 Ok((i, _)) => i,
                                 Err(_) => i,
                             }
@@ -71,6 +94,9 @@ fn eat_partials(mut i: Cursor) -> PResult<Vec<Partial>> {
                 i.adv(j + 1)
             };
         } else {
+            nodes.retain(|Partial(_, path, _)| {
+                !inlines.contains(path.t()) && *path.t() != PARTIAL_BLOCK
+            });
             break Ok((i.adv(i.len()), nodes));
         }
     }
@@ -98,5 +124,9 @@ mod tests {
         assert_eq!(parse_partials(src), vec![]);
         let src = r#"{{R}} {{> foo }} {{/R}}"#;
         assert_eq!(parse_partials(src), vec![]);
+        let src = r#"{{> foo }}{{#*inline "foo"}}{{/inline}}"#;
+        assert_eq!(parse_partials(src), vec![]);
+        let src = r#"{{> @partial-block }}"#;
+        assert_eq!(parse_partials(src), vec![]);
     }
 }
@@ -2,17 +2,19 @@
 
 use std::str::Chars;
 
+use crate::source_map::BytePos;
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Cursor<'a> {
     pub rest: &'a str,
-    pub off: u32,
+    pub off: BytePos,
 }
 
 impl<'a> Cursor<'a> {
     pub fn adv(&self, amt: usize) -> Cursor<'a> {
         Cursor {
             rest: &self.rest[amt..],
-            off: self.off + (amt as u32),
+            off: self.off + (amt as BytePos),
         }
     }
 
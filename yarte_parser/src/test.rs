@@ -3,8 +3,17 @@ use syn::{parse_str, Expr, Stmt};
 
 const WS: Ws = (false, false);
 
+/// Expected nodes, leaked as the parsed ones in the arena of the test
+fn leak<'a, T: 'a>(x: T) -> &'a T {
+    Box::leak(Box::new(x))
+}
+
+fn arena<'a>() -> &'a Arena<'a> {
+    leak(Arena::new())
+}
+
 fn parse(rest: &str) -> Vec<SNode> {
-    _parse(Cursor { rest, off: 0 })
+    _parse(Cursor { rest, off: 0 }, arena())
 }
 
 #[test]
@@ -38,16 +47,45 @@ fn test_1() {
     assert_eq!(
         parse(src),
         vec![S(
-            Node::Helper(Box::new(Helper::Unless(
-                ((false, false), (false, false)),
-                S(Box::new(expr), Span { lo: 11, hi: 15 }),
-                vec![S(Lit("", S("{", span), ""), span)]
+            Node::Helper(leak(Helper::Unless(
+                (
+                    ((false, false), (false, false)),
+                    S(leak(expr), Span { lo: 11, hi: 15 }),
+                    vec![S(Lit("", S("{", span), ""), span)]
+                ),
+                vec![],
+                None
             ))),
             Span { lo: 0, hi: 30 }
         )]
     );
 }
 
+#[test]
+fn test_unless_else() {
+    let src = "{{#unless a}}b{{else if c}}d{{else}}e{{/unless}}";
+    let expr = |x: &str, lo, hi| S(leak(parse_str::<Expr>(x).unwrap()), Span { lo, hi });
+    let lit = |x, lo, hi| vec![S(Lit("", S(x, Span { lo, hi }), ""), Span { lo, hi })];
+    assert_eq!(
+        parse(src),
+        vec![S(
+            Node::Helper(leak(Helper::Unless(
+                (
+                    ((false, false), (false, false)),
+                    expr("a", 10, 11),
+                    lit("b", 13, 14)
+                ),
+                vec![((false, false), expr("c", 24, 25), lit("d", 27, 28))],
+                Some(((false, false), lit("e", 36, 37)))
+            ))),
+            Span {
+                lo: 0,
+                hi: src.len() as u32
+            }
+        )]
+    );
+}
+
 #[test]
 fn test_eat_comment() {
     let src = r#"{{! Commentary !}}"#;
@@ -79,6 +117,68 @@ fn test_eat_comment() {
     );
 }
 
+#[test]
+fn test_eat_keep() {
+    let src = r#"{{!keep build 42 !}}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(parse(src), vec![S(Keep("build 42"), span)]);
+    let src = r#"{{!--keep -- !}} --!}}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(parse(src), vec![S(Keep("-- !}}"), span)]);
+    let src = r#"{{!keeper !}}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(parse(src), vec![S(Comment("keeper "), span)]);
+}
+
+#[test]
+fn test_eat_const() {
+    let src = r#"{{! const PAGE_SIZE = 20 !}}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    let expected = crate::Const {
+        ident: parse_str("PAGE_SIZE").unwrap(),
+        ty: None,
+        expr: parse_str("20").unwrap(),
+    };
+    assert_eq!(parse(src), vec![S(Node::Const(leak(expected)), span)]);
+    let src = r#"{{!-- const TITLE: &str = concat!("a", "b"); --!}}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    let expected = crate::Const {
+        ident: parse_str("TITLE").unwrap(),
+        ty: Some(parse_str("&str").unwrap()),
+        expr: parse_str("concat!(\"a\", \"b\")").unwrap(),
+    };
+    assert_eq!(parse(src), vec![S(Node::Const(leak(expected)), span)]);
+    let src = r#"{{! constant values !}}{{! const values are below !}}"#;
+    assert_eq!(
+        parse(src),
+        vec![
+            S(Comment(" constant values "), Span { lo: 0, hi: 23 }),
+            S(Comment(" const values are below "), Span { lo: 23, hi: 53 })
+        ]
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_eat_const_invalid() {
+    parse(r#"{{! const PAGE_SIZE = !}}"#);
+}
+
 #[test]
 fn test_eat_expr() {
     let src = r#"{{ var }}"#;
@@ -92,7 +192,7 @@ fn test_eat_expr() {
             Expr(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("var").unwrap()),
+                    leak(parse_str::<Expr>("var").unwrap()),
                     Span { lo: 3, hi: 6 },
                 ),
             ),
@@ -111,7 +211,7 @@ fn test_eat_expr() {
             Expr(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("fun()").unwrap()),
+                    leak(parse_str::<Expr>("fun()").unwrap()),
                     Span { lo: 3, hi: 8 },
                 ),
             ),
@@ -130,7 +230,7 @@ fn test_eat_expr() {
             Expr(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("fun(|a| a)").unwrap()),
+                    leak(parse_str::<Expr>("fun(|a| a)").unwrap()),
                     Span { lo: 3, hi: 13 },
                 ),
             ),
@@ -153,7 +253,7 @@ fn test_eat_expr() {
             Expr(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("fun(|a| {{a}})").unwrap()),
+                    leak(parse_str::<Expr>("fun(|a| {{a}})").unwrap()),
                     Span { lo: 15, hi: 61 },
                 ),
             ),
@@ -189,7 +289,7 @@ fn test_eat_safe() {
             Safe(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("var").unwrap()),
+                    leak(parse_str::<Expr>("var").unwrap()),
                     Span { lo: 4, hi: 7 },
                 ),
             ),
@@ -208,7 +308,7 @@ fn test_eat_safe() {
             Safe(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("fun()").unwrap()),
+                    leak(parse_str::<Expr>("fun()").unwrap()),
                     Span { lo: 4, hi: 9 },
                 ),
             ),
@@ -227,7 +327,7 @@ fn test_eat_safe() {
             Safe(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("fun(|a| a)").unwrap()),
+                    leak(parse_str::<Expr>("fun(|a| a)").unwrap()),
                     Span { lo: 4, hi: 14 },
                 ),
             ),
@@ -250,7 +350,7 @@ fn test_eat_safe() {
             Safe(
                 WS,
                 S(
-                    Box::new(parse_str::<Expr>("fun(|a| {{{a}}})").unwrap()),
+                    leak(parse_str::<Expr>("fun(|a| {{{a}}})").unwrap()),
                     Span { lo: 16, hi: 64 },
                 ),
             ),
@@ -259,6 +359,66 @@ fn test_eat_safe() {
     );
 }
 
+#[test]
+fn test_eat_builtin() {
+    let src = r#"{{ @paginate current total (url) }}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(
+        parse(src),
+        vec![S(
+            Expr(
+                WS,
+                S(
+                    leak(Expr::Verbatim(
+                        parse_str("@paginate current total, (url)").unwrap()
+                    )),
+                    Span { lo: 3, hi: 32 },
+                ),
+            ),
+            span,
+        )]
+    );
+}
+
+#[should_panic]
+#[test]
+fn test_eat_builtin_panic() {
+    let src = r#"{{ @paginate (@) }}"#;
+    parse(src);
+}
+
+#[test]
+fn test_eat_once() {
+    let src = r#"{{ #once self.total() }}"#;
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(
+        parse(src),
+        vec![S(
+            Expr(
+                WS,
+                S(
+                    leak(Expr::Verbatim(parse_str("#once self.total()").unwrap())),
+                    Span { lo: 3, hi: 21 },
+                ),
+            ),
+            span,
+        )]
+    );
+}
+
+#[should_panic]
+#[test]
+fn test_eat_once_panic() {
+    let src = r#"{{ #twice a }}"#;
+    parse(src);
+}
+
 #[should_panic]
 #[test]
 fn test_eat_safe_panic() {
@@ -284,7 +444,7 @@ fn test_eat_if() {
     let rest = r#"foo{{ else }}"#;
     let result = " else }}";
     assert_eq!(
-        eat_if(Cursor { rest, off: 0 }).unwrap(),
+        eat_if(Cursor { rest, off: 0 }, arena()).unwrap(),
         (
             Cursor {
                 rest: result,
@@ -299,7 +459,7 @@ fn test_eat_if() {
     let rest = r#"{{foo}}{{else}}"#;
     let result = "else}}";
     assert_eq!(
-        eat_if(Cursor { rest, off: 0 }).unwrap(),
+        eat_if(Cursor { rest, off: 0 }, arena()).unwrap(),
         (
             Cursor {
                 rest: result,
@@ -309,7 +469,7 @@ fn test_eat_if() {
                 Expr(
                     WS,
                     S(
-                        Box::new(parse_str::<Expr>("foo").unwrap()),
+                        leak(parse_str::<Expr>("foo").unwrap()),
                         Span { lo: 2, hi: 5 },
                     ),
                 ),
@@ -325,14 +485,14 @@ fn test_eat_if() {
     };
     let result = "else if cond}}{{else}}";
     assert_eq!(
-        eat_if(Cursor { rest, off: 0 }).unwrap(),
+        eat_if(Cursor { rest, off: 0 }, arena()).unwrap(),
         (
             Cursor {
                 rest: result,
                 off: (rest.len() - result.len()) as u32,
             },
             vec![S(
-                Local(S(Box::new(local), Span { lo: 3, hi: 14 })),
+                Local(S(leak(local), Span { lo: 3, hi: 14 })),
                 Span { lo: 0, hi: 17 },
             )]
         )
@@ -343,24 +503,26 @@ fn test_eat_if() {
 fn test_helpers() {
     let rest = "each name }}{{first}} {{last}}{{/each}}";
     assert_eq!(
-        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        hel(Cursor { rest, off: 0 }, false, arena()).unwrap(),
         (
             Cursor {
                 rest: "",
                 off: rest.len() as u32,
             },
-            Helper(Box::new(Helper::Each(
+            Helper(leak(Helper::Each(
                 (WS, WS),
                 S(
-                    Box::new(parse_str::<Expr>("name").unwrap()),
+                    leak(parse_str::<Expr>("name").unwrap()),
                     Span { lo: 5, hi: 9 },
                 ),
+                vec![],
+                BlockParams::default(),
                 vec![
                     S(
                         Expr(
                             WS,
                             S(
-                                Box::new(parse_str::<Expr>("first").unwrap()),
+                                leak(parse_str::<Expr>("first").unwrap()),
                                 Span { lo: 14, hi: 19 },
                             ),
                         ),
@@ -374,34 +536,139 @@ fn test_helpers() {
                         Expr(
                             WS,
                             S(
-                                Box::new(parse_str::<Expr>("last").unwrap()),
+                                leak(parse_str::<Expr>("last").unwrap()),
                                 Span { lo: 24, hi: 28 },
                             ),
                         ),
                         Span { lo: 22, hi: 30 },
                     ),
                 ],
+                None,
             )))
         )
     );
 }
 
+#[test]
+fn test_each_modifiers() {
+    let rest = "each items chunks=3 reverse }}{{/each}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false, arena()).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Helper(leak(Helper::Each(
+                (WS, WS),
+                S(
+                    leak(parse_str::<Expr>("items").unwrap()),
+                    Span { lo: 5, hi: 27 },
+                ),
+                vec![
+                    Modifier(parse_str("chunks").unwrap(), Some(parse_str("3").unwrap())),
+                    Modifier(parse_str("reverse").unwrap(), None),
+                ],
+                BlockParams::default(),
+                vec![],
+                None,
+            )))
+        )
+    );
+}
+
+#[test]
+fn test_each_else() {
+    let rest = "each items }}{{ this }}{{~ else err }}{{ err }}{{/each}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false, arena()).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Helper(leak(Helper::Each(
+                (WS, WS),
+                S(
+                    leak(parse_str::<Expr>("items").unwrap()),
+                    Span { lo: 5, hi: 10 },
+                ),
+                vec![],
+                BlockParams::default(),
+                vec![S(
+                    Expr(
+                        WS,
+                        S(
+                            leak(parse_str::<Expr>("this").unwrap()),
+                            Span { lo: 16, hi: 20 },
+                        ),
+                    ),
+                    Span { lo: 13, hi: 23 },
+                )],
+                Some((
+                    (true, false),
+                    Some(parse_str("err").unwrap()),
+                    vec![S(
+                        Expr(
+                            WS,
+                            S(
+                                leak(parse_str::<Expr>("err").unwrap()),
+                                Span { lo: 41, hi: 44 },
+                            ),
+                        ),
+                        Span { lo: 38, hi: 47 },
+                    )],
+                )),
+            )))
+        )
+    );
+
+    // `else` starting an identifier is an expression
+    let rest = "each items }}{{ elsewhere }}{{/each}}";
+    match hel(Cursor { rest, off: 0 }, false, arena()).unwrap().1 {
+        Helper(h) => match h {
+            Helper::Each(_, _, _, _, block, None) => assert_eq!(block.len(), 1),
+            h => panic!("{:?}", h),
+        },
+        n => panic!("{:?}", n),
+    }
+
+    // Empty arm
+    let rest = "each items }}{{ this }}{{ else ~}} empty {{/each}}";
+    match hel(Cursor { rest, off: 0 }, false, arena()).unwrap().1 {
+        Helper(h) => match h {
+            Helper::Each(_, _, _, _, block, Some(((false, true), None, els))) => {
+                assert_eq!(block.len(), 1);
+                assert_eq!(
+                    *els,
+                    vec![S(
+                        Lit(" ", S("empty", Span { lo: 35, hi: 40 }), " "),
+                        Span { lo: 34, hi: 41 },
+                    )]
+                );
+            }
+            h => panic!("{:?}", h),
+        },
+        n => panic!("{:?}", n),
+    }
+}
+
 #[test]
 fn test_if_else() {
     let rest = "foo{{/if}}";
     let args = S(
-        Box::new(parse_str::<Expr>("bar").unwrap()),
+        leak(parse_str::<Expr>("bar").unwrap()),
         Span { lo: 0, hi: 0 },
     );
 
     assert_eq!(
-        if_else(WS, Cursor { rest, off: 0 }, args.clone()).unwrap(),
+        if_else(IF, WS, Cursor { rest, off: 0 }, args.clone(), arena()).unwrap(),
         (
             Cursor {
                 rest: "",
                 off: rest.len() as u32,
             },
-            Helper(Box::new(Helper::If(
+            Helper(leak(Helper::If(
                 (
                     (WS, WS),
                     args,
@@ -418,18 +685,18 @@ fn test_if_else() {
 
     let rest = "foo{{else}}bar{{/if}}";
     let args = S(
-        Box::new(parse_str::<Expr>("bar").unwrap()),
+        leak(parse_str::<Expr>("bar").unwrap()),
         Span { lo: 0, hi: 0 },
     );
 
     assert_eq!(
-        if_else(WS, Cursor { rest, off: 0 }, args.clone()).unwrap(),
+        if_else(IF, WS, Cursor { rest, off: 0 }, args.clone(), arena()).unwrap(),
         (
             Cursor {
                 rest: "",
                 off: rest.len() as u32,
             },
-            Helper(Box::new(Helper::If(
+            Helper(leak(Helper::If(
                 (
                     (WS, WS),
                     args,
@@ -455,18 +722,18 @@ fn test_if_else() {
 fn test_else_if() {
     let rest = "foo{{else if cond }}bar{{else}}foO{{/if}}";
     let args = S(
-        Box::new(parse_str::<Expr>("bar").unwrap()),
+        leak(parse_str::<Expr>("bar").unwrap()),
         Span { lo: 0, hi: 0 },
     );
 
     assert_eq!(
-        if_else(WS, Cursor { rest, off: 0 }, args.clone()).unwrap(),
+        if_else(IF, WS, Cursor { rest, off: 0 }, args.clone(), arena()).unwrap(),
         (
             Cursor {
                 rest: "",
                 off: rest.len() as u32,
             },
-            Helper(Box::new(Helper::If(
+            Helper(leak(Helper::If(
                 (
                     (WS, WS),
                     args,
@@ -478,7 +745,7 @@ fn test_else_if() {
                 vec![(
                     WS,
                     S(
-                        Box::new(parse_str::<Expr>("cond").unwrap()),
+                        leak(parse_str::<Expr>("cond").unwrap()),
                         Span { lo: 13, hi: 17 },
                     ),
                     vec![S(
@@ -509,13 +776,14 @@ fn test_defined() {
     assert_eq!(
         parse(src),
         vec![S(
-            Helper(Box::new(Helper::Defined(
+            Helper(leak(Helper::Defined(
                 (WS, WS),
                 "foo",
                 S(
-                    Box::new(parse_str::<Expr>("bar").unwrap()),
+                    leak(parse_str::<Expr>("bar").unwrap()),
                     Span { lo: 7, hi: 10 },
                 ),
+                BlockParams::default(),
                 vec![S(
                     Lit("", S("hello", Span { lo: 12, hi: 17 }), ""),
                     Span { lo: 12, hi: 17 },
@@ -526,6 +794,66 @@ fn test_defined() {
     );
 }
 
+#[test]
+fn test_defined_params() {
+    let src = "{{#foo bar as |x i|}}hello{{/foo}}";
+    assert_eq!(&src[21..26], "hello");
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(
+        parse(src),
+        vec![S(
+            Helper(leak(Helper::Defined(
+                (WS, WS),
+                "foo",
+                S(
+                    leak(parse_str::<Expr>("bar").unwrap()),
+                    Span { lo: 7, hi: 19 },
+                ),
+                BlockParams {
+                    item: vec![parse_str("x").unwrap()],
+                    index: Some(parse_str("i").unwrap()),
+                },
+                vec![S(
+                    Lit("", S("hello", Span { lo: 21, hi: 26 }), ""),
+                    Span { lo: 21, hi: 26 },
+                )],
+            ))),
+            span,
+        )]
+    );
+}
+
+#[test]
+fn test_portal() {
+    let src = "{{#portal \"#a\"}}hello{{/portal}}";
+    assert_eq!(&src[16..21], "hello");
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
+    assert_eq!(
+        parse(src),
+        vec![S(
+            Helper(leak(Helper::Portal(
+                (WS, WS),
+                S(
+                    leak(parse_str::<Expr>("\"#a\"").unwrap()),
+                    Span { lo: 10, hi: 14 },
+                ),
+                BlockParams::default(),
+                vec![S(
+                    Lit("", S("hello", Span { lo: 16, hi: 21 }), ""),
+                    Span { lo: 16, hi: 21 },
+                )],
+            ))),
+            span,
+        )]
+    );
+}
+
 #[test]
 fn test_ws_expr() {
     let src = "{{~foo~}}";
@@ -539,7 +867,7 @@ fn test_ws_expr() {
             Expr(
                 (true, true),
                 S(
-                    Box::new(parse_str::<Expr>("foo").unwrap()),
+                    leak(parse_str::<Expr>("foo").unwrap()),
                     Span { lo: 3, hi: 6 },
                 ),
             ),
@@ -557,7 +885,7 @@ fn test_ws_expr() {
             Expr(
                 (true, true),
                 S(
-                    Box::new(parse_str::<Expr>("foo").unwrap()),
+                    leak(parse_str::<Expr>("foo").unwrap()),
                     Span { lo: 4, hi: 7 },
                 ),
             ),
@@ -575,7 +903,7 @@ fn test_ws_expr() {
             Expr(
                 (true, false),
                 S(
-                    Box::new(parse_str::<Expr>("foo").unwrap()),
+                    leak(parse_str::<Expr>("foo").unwrap()),
                     Span { lo: 4, hi: 7 },
                 ),
             ),
@@ -593,7 +921,7 @@ fn test_ws_expr() {
             Expr(
                 (false, true),
                 S(
-                    Box::new(parse_str::<Expr>("foo").unwrap()),
+                    leak(parse_str::<Expr>("foo").unwrap()),
                     Span { lo: 2, hi: 5 },
                 ),
             ),
@@ -611,7 +939,7 @@ fn test_ws_expr() {
             Safe(
                 (true, true),
                 S(
-                    Box::new(parse_str::<Expr>("foo").unwrap()),
+                    leak(parse_str::<Expr>("foo").unwrap()),
                     Span { lo: 4, hi: 7 },
                 ),
             ),
@@ -629,7 +957,7 @@ fn test_ws_expr() {
             Safe(
                 (false, true),
                 S(
-                    Box::new(parse_str::<Expr>("foo").unwrap()),
+                    leak(parse_str::<Expr>("foo").unwrap()),
                     Span { lo: 3, hi: 6 },
                 ),
             ),
@@ -648,13 +976,16 @@ fn test_ws_each() {
     assert_eq!(
         parse(src),
         vec![S(
-            Helper(Box::new(Helper::Each(
+            Helper(leak(Helper::Each(
                 ((true, true), (true, true)),
                 S(
-                    Box::new(parse_str::<Expr>("bar").unwrap()),
+                    leak(parse_str::<Expr>("bar").unwrap()),
                     Span { lo: 9, hi: 12 },
                 ),
                 vec![],
+                BlockParams::default(),
+                vec![],
+                None,
             ))),
             span,
         )]
@@ -671,11 +1002,11 @@ fn test_ws_if() {
     assert_eq!(
         parse(src),
         vec![S(
-            Helper(Box::new(Helper::If(
+            Helper(leak(Helper::If(
                 (
                     ((true, true), (true, true)),
                     S(
-                        Box::new(parse_str::<Expr>("bar").unwrap()),
+                        leak(parse_str::<Expr>("bar").unwrap()),
                         Span { lo: 7, hi: 10 },
                     ),
                     vec![],
@@ -698,11 +1029,11 @@ fn test_ws_if_else() {
     assert_eq!(
         parse(src),
         vec![S(
-            Helper(Box::new(Helper::If(
+            Helper(leak(Helper::If(
                 (
                     ((true, true), (true, true)),
                     S(
-                        Box::new(parse_str::<Expr>("bar").unwrap()),
+                        leak(parse_str::<Expr>("bar").unwrap()),
                         Span { lo: 7, hi: 10 },
                     ),
                     vec![],
@@ -725,11 +1056,11 @@ fn test_ws_if_else_if() {
     assert_eq!(
         parse(src),
         vec![S(
-            Helper(Box::new(Helper::If(
+            Helper(leak(Helper::If(
                 (
                     ((true, true), (true, true)),
                     S(
-                        Box::new(parse_str::<Expr>("bar").unwrap()),
+                        leak(parse_str::<Expr>("bar").unwrap()),
                         Span { lo: 7, hi: 10 },
                     ),
                     vec![],
@@ -737,7 +1068,7 @@ fn test_ws_if_else_if() {
                 vec![(
                     (true, true),
                     S(
-                        Box::new(parse_str::<Expr>("bar").unwrap()),
+                        leak(parse_str::<Expr>("bar").unwrap()),
                         Span { lo: 24, hi: 27 },
                     ),
                     vec![],
@@ -818,7 +1149,10 @@ fn test_partial_ws() {
                 WS,
                 S("partial", Span { lo: 4, hi: 11 }),
                 S(
-                    vec![parse_str::<Expr>("scope").unwrap()],
+                    vec![S(
+                        Arg::Positional(parse_str("scope").unwrap()),
+                        Span { lo: 12, hi: 17 },
+                    )],
                     Span { lo: 12, hi: 17 },
                 ),
             )),
@@ -837,7 +1171,10 @@ fn test_partial_ws() {
                 (false, true),
                 S("partial", Span { lo: 4, hi: 11 }),
                 S(
-                    vec![parse_str::<Expr>("scope").unwrap()],
+                    vec![S(
+                        Arg::Positional(parse_str("scope").unwrap()),
+                        Span { lo: 12, hi: 17 },
+                    )],
                     Span { lo: 12, hi: 17 },
                 ),
             )),
@@ -876,7 +1213,10 @@ fn test_partial() {
                 WS,
                 S("partial", Span { lo: 4, hi: 11 }),
                 S(
-                    vec![parse_str::<Expr>("scope").unwrap()],
+                    vec![S(
+                        Arg::Positional(parse_str("scope").unwrap()),
+                        Span { lo: 12, hi: 17 },
+                    )],
                     Span { lo: 12, hi: 17 },
                 ),
             )),
@@ -885,6 +1225,77 @@ fn test_partial() {
     );
 }
 
+#[test]
+fn test_inline() {
+    let src = "{{#*inline \"row\" ~}}a{{/inline}}{{> row }}";
+    let nodes = parse(src);
+    assert_eq!(
+        nodes[0],
+        S(
+            Inline(
+                ((false, true), WS),
+                S("row", Span { lo: 11, hi: 16 }),
+                vec![S(
+                    Lit("", S("a", Span { lo: 20, hi: 21 }), ""),
+                    Span { lo: 20, hi: 21 },
+                )],
+            ),
+            Span { lo: 0, hi: 32 },
+        )
+    );
+    assert_eq!(
+        find_inline(&nodes, "row"),
+        Some((
+            ((false, true), WS),
+            &[S(
+                Lit("", S("a", Span { lo: 20, hi: 21 }), ""),
+                Span { lo: 20, hi: 21 },
+            )][..]
+        ))
+    );
+    assert_eq!(find_inline(&nodes, "col"), None);
+}
+
+#[test]
+#[should_panic]
+fn test_inline_unnamed() {
+    parse("{{#*inline row }}a{{/inline}}");
+}
+
+#[test]
+fn test_partial_block() {
+    let src = "{{#> layout title = \"a\" }}b{{~/layout}}";
+    let nodes = parse(src);
+    match nodes[0].t() {
+        PartialBlock(ws, path, args, block) => {
+            assert_eq!(*ws, (WS, (true, false)));
+            assert_eq!(path, &S("layout", Span { lo: 5, hi: 11 }));
+            assert_eq!(args.t().len(), 1);
+            assert_eq!(
+                block,
+                &vec![S(
+                    Lit("", S("b", Span { lo: 26, hi: 27 }), ""),
+                    Span { lo: 26, hi: 27 },
+                )]
+            );
+        }
+        n => panic!("{:?}", n),
+    }
+    assert_eq!(
+        nodes[0].span(),
+        &Span {
+            lo: 0,
+            hi: src.len() as u32
+        }
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_partial_block_unclosed() {
+    parse("{{#> layout }}b{{/header}}");
+}
+
 #[test]
 fn test_raw() {
     let src = "{{R}}{{#some }}{{/some}}{{/R}}";
@@ -907,15 +1318,37 @@ fn test_raw() {
 }
 
 #[test]
-fn test_expr_list() {
-    let src = "bar, foo = \"bar\"\n, fuu = 1  , goo = true,    ";
+fn test_partial_args() {
+    let src = "{{> partial scope, a = \"}}\", b = c::<A, B>() }}";
+    let span = Span {
+        lo: 0,
+        hi: src.len() as u32,
+    };
     assert_eq!(
-        eat_expr_list(src).unwrap(),
-        vec![
-            parse_str::<Expr>("bar").unwrap(),
-            parse_str::<Expr>("foo=\"bar\"").unwrap(),
-            parse_str::<Expr>("fuu=1").unwrap(),
-            parse_str::<Expr>("goo=true").unwrap(),
-        ]
+        parse(src),
+        vec![S(
+            Node::Partial(Partial(
+                WS,
+                S("partial", Span { lo: 4, hi: 11 }),
+                S(
+                    vec![
+                        S(
+                            Arg::Positional(parse_str("scope").unwrap()),
+                            Span { lo: 12, hi: 17 },
+                        ),
+                        S(
+                            Arg::Named(parse_str("a").unwrap(), parse_str("\"}}\"").unwrap()),
+                            Span { lo: 19, hi: 27 },
+                        ),
+                        S(
+                            Arg::Named(parse_str("b").unwrap(), parse_str("c::<A, B>()").unwrap()),
+                            Span { lo: 29, hi: 44 },
+                        ),
+                    ],
+                    Span { lo: 12, hi: 44 },
+                ),
+            )),
+            span,
+        )]
     );
 }
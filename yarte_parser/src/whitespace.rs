@@ -0,0 +1,60 @@
+//! Whitespace of the literals, split by the parser for `~` and collapsed by the html passes
+//!
+//! It's the ASCII whitespace of the html spec, so a no-break space of `&nbsp;` is text.
+//! With the unicode mode, it's every char with the `White_Space` property.
+use std::cell::Cell;
+
+thread_local! {
+    static UNICODE: Cell<bool> = Cell::new(false);
+}
+
+/// Set the whitespace mode of the literals, ASCII or unicode
+///
+/// Use in the same thread, before parsing
+pub fn set_unicode_whitespace(unicode: bool) {
+    UNICODE.with(|x| x.set(unicode));
+}
+
+/// Whether `c` is whitespace of a literal in the current mode
+pub fn is_lit_ws(c: char) -> bool {
+    if UNICODE.with(Cell::get) {
+        c.is_whitespace()
+    } else {
+        c.is_ascii_whitespace()
+    }
+}
+
+/// Split a literal in its leading whitespace, its text and its trailing whitespace
+pub fn trim_lit(i: &str) -> (&str, &str, &str) {
+    trim_by(i, is_lit_ws)
+}
+
+pub(crate) fn trim_by(i: &str, f: impl Fn(char) -> bool) -> (&str, &str, &str) {
+    let start = i.len() - i.trim_start_matches(&f).len();
+    if start == i.len() {
+        return (i, "", "");
+    }
+    let end = i.trim_end_matches(&f).len();
+
+    (&i[..start], &i[start..end], &i[end..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_trim_lit() {
+        assert_eq!(trim_lit(""), ("", "", ""));
+        assert_eq!(trim_lit(" \n "), (" \n ", "", ""));
+        assert_eq!(trim_lit(" a b\t"), (" ", "a b", "\t"));
+        assert_eq!(trim_lit("\u{a0}a\u{a0} "), ("", "\u{a0}a\u{a0}", " "));
+        // continuation bytes of `Å` and `à` aren't whitespace
+        assert_eq!(trim_lit("Å à"), ("", "Å à", ""));
+
+        set_unicode_whitespace(true);
+        assert_eq!(trim_lit("\u{a0}a\u{2003} "), ("\u{a0}", "a", "\u{2003} "));
+        assert_eq!(trim_lit("Å\u{85}"), ("", "Å", "\u{85}"));
+        set_unicode_whitespace(false);
+    }
+}
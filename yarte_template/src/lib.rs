@@ -1,6 +1,36 @@
-use std::fmt::{self, Write};
+#![no_std]
+#![forbid(unsafe_code)]
 
-use yarte_helpers::Result;
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{
+    any,
+    cell::Cell,
+    fmt::{self, Write},
+    str,
+};
+
+#[cfg(feature = "async")]
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{self, Poll},
+};
+
+#[cfg(feature = "bytes-buf")]
+use bytes::{Bytes, BytesMut};
+#[cfg(feature = "async")]
+use futures_io::AsyncWrite;
+#[cfg(feature = "context")]
+use yarte_helpers::context;
+
+use yarte_helpers::{
+    helpers::{Buffer, BytesWriter},
+    Context, RenderError, Result,
+};
 
 /// Template trait, will implement by derive like `Display` or `actix_web::Responder` (with feature)
 ///
@@ -11,6 +41,134 @@ pub trait Template: fmt::Display {
         write!(buf, "{}", self).map(|_| buf)
     }
 
+    /// which will write this template, the error has the type name of the template in
+    /// debug builds and, with the `context` feature, the region that failed
+    ///
+    /// ```ignore
+    /// if let Err(e) = template.try_render() {
+    ///     log::error!("{}", e);
+    /// }
+    /// ```
+    fn try_render(&self) -> core::result::Result<String, RenderError> {
+        #[cfg(feature = "context")]
+        context::reset();
+        self.call().map_err(|_| within::<Self>(RenderError::Fmt))
+    }
+
+    /// which will write this template, borrowed without allocation when it's constant
+    fn call_cow(&self) -> Result<Cow<'static, str>> {
+        match Self::constant() {
+            Some(s) => Ok(Cow::Borrowed(s)),
+            None => self.call().map(Cow::Owned),
+        }
+    }
+
+    /// which will write this template in bytes
+    fn call_bytes(&self) -> Result<Vec<u8>>
+    where
+        Self: Sized,
+    {
+        let mut buf = Vec::with_capacity(Self::size_hint());
+        self.write_bytes(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// which will append this template to the buffer, a `Vec<u8>` or a `BytesMut`, json and
+    /// html templates write them without formatter. The buffer can be reused between renders
+    ///
+    /// ```ignore
+    /// buf.clear();
+    /// template.write_bytes(&mut buf)?;
+    /// ```
+    fn write_bytes<B: Buffer>(&self, buf: &mut B) -> Result<()>
+    where
+        Self: Sized,
+    {
+        write!(BytesWriter(buf), "{}", self)
+    }
+
+    /// which will write this template in the buffer without allocation,
+    /// fails when it doesn't fit in the buffer
+    ///
+    /// ```ignore
+    /// let mut buf = [0; 64];
+    /// let badge = template.write_to(&mut buf)?;
+    /// ```
+    fn write_to<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str> {
+        let mut writer = SliceWriter { buf, len: 0 };
+        write!(writer, "{}", self)?;
+        let SliceWriter { buf, len } = writer;
+        str::from_utf8(&buf[..len]).map_err(|_| fmt::Error)
+    }
+
+    /// which will write this template in the writer as it's rendered, in chunks of 8 KiB,
+    /// without holding the whole output in memory
+    ///
+    /// With the feature `async`, `render_into_async` writes it in an `AsyncWrite`.
+    ///
+    /// ```ignore
+    /// let file = File::create("feed.xml")?;
+    /// template.render_into(file)?;
+    /// ```
+    #[cfg(feature = "std")]
+    fn render_into<W: std::io::Write>(&self, writer: W) -> core::result::Result<(), RenderError>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "context")]
+        context::reset();
+        let mut writer = IoWriter {
+            writer,
+            buf: Vec::with_capacity(CHUNK),
+            error: None,
+        };
+        let result = write!(writer, "{}", self).and_then(|_| writer.flush());
+        match (result, writer.error) {
+            (Ok(()), _) => writer
+                .writer
+                .flush()
+                .map_err(|e| within::<Self>(RenderError::Io(e))),
+            (Err(_), Some(e)) => Err(within::<Self>(RenderError::Io(e))),
+            (Err(_), None) => Err(within::<Self>(RenderError::Fmt)),
+        }
+    }
+
+    /// which will write this template in the async writer of `futures-io`, in chunks of 8 KiB
+    /// awaiting the writer between them, and flush it
+    ///
+    /// The template is rendered by the synchronous `Display`, so the output is written in a
+    /// buffer at the first poll and only its writing is async. Tokio writers are adapted
+    /// with the `compat` of `tokio-util`.
+    ///
+    /// ```ignore
+    /// template.render_into_async(&mut socket).await?;
+    /// ```
+    #[cfg(feature = "async")]
+    fn render_into_async<W: AsyncWrite + Unpin>(&self, writer: W) -> RenderInto<'_, Self, W>
+    where
+        Self: Sized,
+    {
+        RenderInto {
+            template: self,
+            writer,
+            buf: None,
+            pos: 0,
+        }
+    }
+
+    /// Returns a `Display` of this template that writes it lazily where it's formatted,
+    /// without an intermediate `String`.
+    ///
+    /// ```ignore
+    /// log::info!("rendered {}", template.render_display());
+    /// ```
+    fn render_display(&self) -> Displayable<'_, Self>
+    where
+        Self: Sized,
+    {
+        Displayable(self)
+    }
+
     /// https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types
     #[cfg(feature = "mime")]
     fn mime() -> &'static str
@@ -20,4 +178,185 @@ pub trait Template: fmt::Display {
     /// Approximation of output size used in method `call`.
     /// Yarte implements an heuristic algorithm of allocation.
     fn size_hint() -> usize;
+
+    /// Output of a template without expressions, used in method `call_cow`.
+    fn constant() -> Option<&'static str> {
+        None
+    }
+}
+
+/// Template written in a `BytesMut`, implemented for every template with the feature
+/// `bytes-buf`
+///
+/// With `Template` in scope, `call_bytes` is called through the trait:
+///
+/// ```ignore
+/// let body = TemplateBytes::call_bytes(&page, 16 * 1024)?;
+/// ```
+#[cfg(feature = "bytes-buf")]
+pub trait TemplateBytes: Template + Sized {
+    /// which will write this template in a `BytesMut` with the capacity, frozen in `Bytes`
+    fn call_bytes(&self, capacity: usize) -> Result<Bytes> {
+        let mut buf = BytesMut::with_capacity(capacity);
+        self.write_bytes(&mut buf)?;
+        Ok(buf.freeze())
+    }
+}
+
+#[cfg(feature = "bytes-buf")]
+impl<T: Template> TemplateBytes for T {}
+
+/// Error of the render of `T`, with its name and region in debug builds
+fn within<T: ?Sized>(e: RenderError) -> RenderError {
+    if cfg!(debug_assertions) {
+        #[cfg(feature = "context")]
+        let region = context::current();
+        #[cfg(not(feature = "context"))]
+        let region = None;
+        e.within(Context {
+            template: any::type_name::<T>(),
+            region,
+        })
+    } else {
+        e
+    }
+}
+
+/// Size of the chunks written by `render_into` and `render_into_async`
+#[cfg(feature = "std")]
+const CHUNK: usize = 8 * 1024;
+
+/// Writer in chunks of an `io::Write`, keeps its error
+#[cfg(feature = "std")]
+struct IoWriter<W: std::io::Write> {
+    writer: W,
+    buf: Vec<u8>,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> IoWriter<W> {
+    fn flush(&mut self) -> fmt::Result {
+        match self.writer.write_all(&self.buf) {
+            Ok(()) => {
+                self.buf.clear();
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(e);
+                Err(fmt::Error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.extend_from_slice(s.as_bytes());
+        if CHUNK <= self.buf.len() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Future of `render_into_async`
+#[cfg(feature = "async")]
+pub struct RenderInto<'a, T: Template, W> {
+    template: &'a T,
+    writer: W,
+    buf: Option<Vec<u8>>,
+    pos: usize,
+}
+
+#[cfg(feature = "async")]
+impl<'a, T: Template, W: AsyncWrite + Unpin> Future for RenderInto<'a, T, W> {
+    type Output = core::result::Result<(), RenderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.buf.is_none() {
+            #[cfg(feature = "context")]
+            context::reset();
+            let mut buf = Vec::with_capacity(T::size_hint());
+            if this.template.write_bytes(&mut buf).is_err() {
+                return Poll::Ready(Err(within::<T>(RenderError::Fmt)));
+            }
+            this.buf = Some(buf);
+        }
+        let buf = this.buf.as_ref().expect("rendered");
+        while this.pos < buf.len() {
+            let end = buf.len().min(this.pos + CHUNK);
+            match Pin::new(&mut this.writer).poll_write(cx, &buf[this.pos..end]) {
+                Poll::Ready(Ok(0)) => {
+                    let e = std::io::Error::new(std::io::ErrorKind::WriteZero, "write zero");
+                    return Poll::Ready(Err(within::<T>(RenderError::Io(e))));
+                }
+                Poll::Ready(Ok(n)) => this.pos += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(within::<T>(RenderError::Io(e)))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Pin::new(&mut this.writer)
+            .poll_flush(cx)
+            .map_err(|e| within::<T>(RenderError::Io(e)))
+    }
+}
+
+/// Template written when it's formatted, by `format!`, `write!` or logging macros
+pub struct Displayable<'a, T: Template>(&'a T);
+
+impl<'a, T: Template> fmt::Display for Displayable<'a, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.0, f)
+    }
+}
+
+/// Writer in a byte slice, fails when it's full
+struct SliceWriter<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> Write for SliceWriter<'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if self.buf.len() < end {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Template consuming its context, will implement by derive with the attribute `once = true`
+///
+/// For contexts holding iterators or buffers that can only be consumed once
+pub trait OnceRender: Sized {
+    /// which will write this template consuming it
+    fn render_once(self) -> Result<String> {
+        let mut buf = String::with_capacity(Self::size_hint());
+        write!(buf, "{}", Once(Cell::new(Some(self)))).map(|_| buf)
+    }
+
+    /// Write this template to the formatter consuming it
+    fn fmt_once(self, f: &mut fmt::Formatter) -> fmt::Result;
+
+    /// Approximation of output size used in method `render_once`.
+    fn size_hint() -> usize;
+}
+
+/// Display of a template consuming it, written once
+struct Once<T>(Cell<Option<T>>);
+
+impl<T: OnceRender> fmt::Display for Once<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.take() {
+            Some(t) => t.fmt_once(f),
+            None => Err(fmt::Error),
+        }
+    }
 }
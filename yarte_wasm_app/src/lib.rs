@@ -1,6 +1,7 @@
 use std::{
     cell::{Cell, RefCell},
     default::Default,
+    mem,
     rc::Rc,
 };
 
@@ -51,6 +52,24 @@ pub trait App: Default + Sized + Unpin + 'static {
     }
 }
 
+thread_local!(
+    static AFTER_RENDER: RefCell<Vec<Box<dyn FnOnce()>>> = RefCell::new(vec![]);
+);
+
+/// Schedule `f` after the render of the pending messages, when the DOM is patched
+///
+/// For effects on the DOM from the update code, like the focus of an element
+pub fn after_render<F: FnOnce() + 'static>(f: F) {
+    AFTER_RENDER.with(|q| q.borrow_mut().push(Box::new(f)));
+}
+
+fn run_after_render() {
+    let tasks = AFTER_RENDER.with(|q| mem::take(&mut *q.borrow_mut()));
+    for f in tasks {
+        f();
+    }
+}
+
 /// The address of App
 pub struct Addr<A: App>(Rc<Context<A>>);
 
@@ -74,13 +93,35 @@ impl<A: App> Addr<A> {
         if self.0.ready.get() {
             self.0.ready.replace(false);
             while let Some(msg) = self.0.q.pop() {
-                self.0.app.borrow_mut().__dispatch(msg, &self);
+                self.with_app(|app| app.__dispatch(msg, &self));
                 while let Some(msg) = self.0.q.pop() {
-                    self.0.app.borrow_mut().__dispatch(msg, &self);
+                    self.with_app(|app| app.__dispatch(msg, &self));
                 }
-                self.0.app.borrow_mut().__render(&self);
+                self.with_app(|app| app.__render(&self));
             }
             self.0.ready.replace(true);
+            // Ready, the tasks can send messages
+            run_after_render();
+        }
+    }
+
+    /// Call `f` with the mutable app
+    ///
+    /// The app is never borrowed twice since messages are queued
+    #[cfg(not(feature = "no-panic"))]
+    #[inline]
+    fn with_app<F: FnOnce(&mut A)>(&self, f: F) {
+        f(&mut self.0.app.borrow_mut())
+    }
+
+    /// Call `f` with the mutable app, without the panic of `borrow_mut`
+    #[cfg(feature = "no-panic")]
+    #[inline]
+    fn with_app<F: FnOnce(&mut A)>(&self, f: F) {
+        let app = self.0.app.try_borrow_mut();
+        debug_assert!(app.is_ok());
+        if let Ok(mut app) = app {
+            f(&mut app)
         }
     }
 
@@ -88,8 +129,11 @@ impl<A: App> Addr<A> {
     ///
     /// Link events and get nodes
     pub fn hydrate(&self) {
+        #[cfg(not(feature = "no-panic"))]
         assert!(!self.0.ready.get());
-        self.0.app.borrow_mut().__hydrate(&self);
+        #[cfg(feature = "no-panic")]
+        debug_assert!(!self.0.ready.get());
+        self.with_app(|app| app.__hydrate(&self));
         self.0.ready.replace(true);
         self.update();
     }
@@ -348,6 +392,31 @@ mod test {
         });
     }
 
+    #[wasm_bindgen_test]
+    fn test_after_render() {
+        let c = Rc::new(Cell::new(0));
+        let c2 = Rc::clone(&c);
+        let c3 = Rc::clone(&c);
+        let app = Test {
+            c,
+            ..Default::default()
+        };
+        let addr = app.__start();
+        addr.hydrate();
+
+        let seen = Rc::new(Cell::new(0));
+        let seen2 = Rc::clone(&seen);
+        let addr2 = addr.clone();
+        after_render(move || {
+            seen2.set(c2.get());
+            addr2.send(Msg::Msg(3));
+        });
+        assert_eq!(seen.get(), 0);
+        addr.send(Msg::Msg(2));
+        assert_eq!(seen.get(), 2);
+        assert_eq!(c3.get(), 3);
+    }
+
     #[wasm_bindgen_test]
     fn test() {
         let c = Rc::new(Cell::new(0));
@@ -61,9 +61,9 @@ impl<T> Queue<T> {
                 *self.tail.get() = next;
                 debug_assert!((*tail).value.is_none());
                 debug_assert!((*next).value.is_some());
-                let ret = (*next).value.take().unwrap();
+                let ret = (*next).value.take();
                 drop(Box::from_raw(tail));
-                Some(ret)
+                ret
             }
         }
     }